@@ -0,0 +1,838 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use dnsmessage::{Message as DnsMessage, Opcode, Rcode, FLAG_AA};
+use dnsname::Name;
+use dnsquestion::{QuestionType, CLASS_IN, CLASS_ANY};
+use dnsrecord::{Record, ResourceRecord};
+use catalog::{ZoneCatalog, ZONE_CHANGED};
+use edns::{EdnsOption, ExtendedErrorCode};
+use network::Network;
+use secondary::RefreshTrigger;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use transfer::{axfr_messages, ixfr_messages};
+use tsig::{KeyStore, TsigError, TsigSession};
+use update::apply_update;
+use udp::{ContinueState, Handler, Message, Responder, Transport};
+use wire::WireReader;
+use zone::{Lookup, Zone};
+
+/*----------------------------------------------------------------------------*/
+
+/// Max number of CNAMEs followed when answering a query
+const MAX_CNAME_CHAIN_LEN : usize = 8;
+
+const HEADER_LEN : usize = 12;
+
+/// Times an update is applied again if the zone changed meanwhile
+const MAX_UPDATE_ATTEMPTS : usize = 3;
+
+/*----------------------------------------------------------------------------*/
+
+/// Answers queries from the zones it is authoritative for
+pub struct AuthoritativeHandler {
+
+    catalog : Arc<ZoneCatalog>,
+    /// Keys requests might be signed with
+    keys : KeyStore,
+    /// Clients allowed to transfer zones
+    transfer_acl : Vec<Network>,
+    /// Keys allowing to transfer zones regardless of the client address
+    transfer_keys : Vec<Name>,
+    /// Primaries NOTIFY is accepted from, along with the zone, the key
+    /// NOTIFY must be signed with, if any, and the secondary to trigger
+    notify_sources : Vec<(Name, IpAddr, Option<Name>, RefreshTrigger)>,
+    /// Clients allowed to update zones
+    update_acl : Vec<Network>,
+    /// Keys allowing to update zones regardless of the client address
+    update_keys : Vec<Name>,
+    /// Serializes updates, each one changing the latest version of a zone
+    update_lock : Mutex<()>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl AuthoritativeHandler {
+
+    pub fn new() -> AuthoritativeHandler {
+
+        AuthoritativeHandler::with_catalog(Arc::new(ZoneCatalog::new()))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Serve the zones of `catalog`, which might be changed while serving
+    pub fn with_catalog(catalog : Arc<ZoneCatalog>) -> AuthoritativeHandler {
+
+        AuthoritativeHandler {
+            catalog,
+            keys : KeyStore::new(),
+            transfer_acl : Vec::new(),
+            transfer_keys : Vec::new(),
+            notify_sources : Vec::new(),
+            update_acl : Vec::new(),
+            update_keys : Vec::new(),
+            update_lock : Mutex::new(()),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn catalog(&self) -> &Arc<ZoneCatalog> {
+        &self.catalog
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Serve `zone` with apex `origin`. The zone requires an SOA record
+    /// at its apex.
+    pub fn add_zone(&mut self, origin : Name, zone : Zone) -> Result<(), &'static str> {
+
+        self.catalog.add(origin, zone)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Verify signed requests (RFC 8945) with `keys` and sign the
+    /// responses. Requests signed with unknown keys are rejected.
+    pub fn set_keys(&mut self, keys : KeyStore) {
+
+        self.keys = keys;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Allow clients within `network` to transfer zones.
+    /// By default, transfers are refused.
+    pub fn allow_transfer(&mut self, network : Network) {
+
+        self.transfer_acl.push(network);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Allow requests signed with key `key` to transfer zones
+    pub fn allow_transfer_key(&mut self, key : Name) {
+
+        self.transfer_keys.push(key);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Allow clients within `network` to update zones.
+    /// By default, updates are refused.
+    pub fn allow_update(&mut self, network : Network) {
+
+        self.update_acl.push(network);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Allow requests signed with key `key` to update zones
+    pub fn allow_update_key(&mut self, key : Name) {
+
+        self.update_keys.push(key);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Accept NOTIFY for zone `origin` from `primary`, making the
+    /// secondary of the zone check for a new version via `trigger`.
+    /// If `key` is given, NOTIFY must be signed with that TSIG key.
+    pub fn accept_notify(&mut self, origin : Name, primary : IpAddr, key : Option<Name>,
+                         trigger : RefreshTrigger) {
+
+        self.notify_sources.push((origin, primary, key, trigger));
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Create the response to the NOTIFY `query` received from `client`
+    /// (RFC 1996). `key` is the name of the TSIG key the query was
+    /// verified with, if any.
+    /// Returns None if the message must not be answered at all.
+    pub fn notified(&self, query : &DnsMessage, client : &IpAddr, key : Option<&Name>)
+        -> Option<DnsMessage> {
+
+        if query.is_response() {
+            return None;
+        }
+
+        let mut response = DnsMessage::response_to(query);
+
+        if 1 != query.questions.len() {
+            response.set_rcode(Rcode::FormErr);
+            return Some(response);
+        }
+
+        let question = &query.questions[0];
+
+        if QuestionType::Soa != question.qtype {
+            response.set_rcode(Rcode::NotImp);
+            return Some(response);
+        }
+
+        let source = self.notify_sources.iter()
+            .find(|(origin, primary, _, _)| *origin == question.name && primary == client);
+
+        match source {
+            Some((_, _, Some(required), _)) if Some(required) != key => {
+                println!("Refusing unsigned NOTIFY for {} from {}", question.name, client);
+                reject(&mut response, Rcode::NotAuth, ExtendedErrorCode::Prohibited,
+                    "NOTIFY must be signed");
+            },
+            Some((_, _, _, trigger)) => trigger.trigger(),
+            None => {
+                println!("Refusing NOTIFY for {} from {}", question.name, client);
+                reject(&mut response, Rcode::Refused, ExtendedErrorCode::Prohibited,
+                    "NOTIFY not allowed");
+            },
+        };
+
+        Some(response)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Apply the dynamic UPDATE `query` received from `client` (RFC 2136)
+    /// and create the response. `key` is the name of the TSIG key the
+    /// query was verified with, if any.
+    /// Returns None if the message must not be answered at all.
+    pub fn update(&self, query : &DnsMessage, client : &IpAddr, key : Option<&Name>)
+        -> Option<DnsMessage> {
+
+        if query.is_response() {
+            return None;
+        }
+
+        let mut response = DnsMessage::response_to(query);
+
+        if 1 != query.questions.len() || QuestionType::Soa != query.questions[0].qtype {
+            response.set_rcode(Rcode::FormErr);
+            return Some(response);
+        }
+
+        if ! is_allowed(&self.update_acl, &self.update_keys, client, key) {
+            println!("Refusing update from {}", client);
+            reject(&mut response, Rcode::Refused, ExtendedErrorCode::Prohibited,
+                "Update not allowed");
+            return Some(response);
+        }
+
+        let origin = &query.questions[0].name;
+        let _guard = self.update_lock.lock().unwrap();
+
+        // The zone might be reloaded or transferred while we update it
+        for _ in 0 .. MAX_UPDATE_ATTEMPTS {
+
+            let zone = match self.catalog.get(origin) {
+                Some(z) if CLASS_IN == query.questions[0].qclass => z,
+                _ => {
+                    reject(&mut response, Rcode::NotAuth, ExtendedErrorCode::NotAuthoritative,
+                        "Zone not served");
+                    return Some(response);
+                }
+            };
+
+            let updated = match apply_update(query, origin, &zone) {
+                Ok(Some(updated)) => updated,
+                Ok(None) => return Some(response),
+                Err(rcode) => {
+                    response.set_rcode(rcode);
+                    return Some(response);
+                },
+            };
+
+            match self.catalog.replace_if(origin.clone(), updated, Some(&zone)) {
+                Ok(_) => return Some(response),
+                Err(ZONE_CHANGED) => continue,
+                Err(_) => break,
+            };
+
+        }
+
+        reject(&mut response, Rcode::ServFail, ExtendedErrorCode::Other,
+            "Zone could not be updated");
+        Some(response)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Create the responses to the AXFR or IXFR `query` received from
+    /// `client`. `key` is the name of the TSIG key the query was verified
+    /// with, if any.
+    /// Returns None if the message must not be answered at all.
+    pub fn transfer(&self, query : &DnsMessage, client : &IpAddr, key : Option<&Name>)
+        -> Option<Vec<DnsMessage>> {
+
+        if query.is_response() {
+            return None;
+        }
+
+        let mut response = DnsMessage::response_to(query);
+
+        if ! is_allowed(&self.transfer_acl, &self.transfer_keys, client, key) {
+            println!("Refusing zone transfer to {}", client);
+            reject(&mut response, Rcode::Refused, ExtendedErrorCode::Prohibited,
+                "Zone transfer not allowed");
+            return Some(vec![response]);
+        }
+
+        let question = &query.questions[0];
+        let origin = &question.name;
+
+        let zone = match self.catalog.get(origin) {
+            Some(z) => z,
+            None => {
+                reject(&mut response, Rcode::NotAuth, ExtendedErrorCode::NotAuthoritative,
+                    "Zone not served");
+                return Some(vec![response]);
+            }
+        };
+
+        let messages = match question.qtype {
+            QuestionType::Ixfr => {
+                let client_soa = query.authority.iter().find_map(|rr| match rr.record {
+                    Record::SOA(ref soa) => Some(soa),
+                    _ => None,
+                });
+                match client_soa {
+                    Some(soa) => ixfr_messages(query, origin, &zone, soa),
+                    None => {
+                        response.set_rcode(Rcode::FormErr);
+                        return Some(vec![response]);
+                    },
+                }
+            },
+            _ => axfr_messages(query, origin, &zone),
+        };
+
+        match messages {
+            Some(messages) => Some(messages),
+            None => {
+                reject(&mut response, Rcode::ServFail, ExtendedErrorCode::Other,
+                    "Zone could not be transferred");
+                Some(vec![response])
+            },
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Verify the TSIG of `query`, received as `bytes` (RFC 8945, 5.2).
+    /// Returns the session to sign the responses with, None if `query`
+    /// is not signed, or the encoded error response to send.
+    fn verify(&self, query : &DnsMessage, bytes : &[u8])
+        -> Result<Option<TsigSession>, Vec<u8>> {
+
+        let tsig = match query.tsig {
+            Some(ref tsig) => tsig,
+            None => return Ok(None),
+        };
+
+        let mut session = self.keys.session_for(tsig)
+            .map_err(|e| e.response_to(query, None).to_vec())?;
+
+        match session.verify(bytes) {
+            Ok(()) => Ok(Some(session)),
+            Err(TsigError::BadTime) =>
+                Err(TsigError::BadTime.response_to(query, Some(&mut session)).to_vec()),
+            Err(e) => Err(e.response_to(query, None).to_vec()),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Create the response to `query`.
+    /// Returns None if the message must not be answered at all.
+    pub fn answer(&self, query : &DnsMessage) -> Option<DnsMessage> {
+
+        if query.is_response() {
+            return None;
+        }
+
+        let mut response = DnsMessage::response_to(query);
+
+        if let Some(ref edns) = query.edns {
+            if 0 != edns.version {
+                response.set_rcode(Rcode::BadVers);
+                return Some(response);
+            }
+        }
+
+        if Opcode::Query as u16 != query.opcode() {
+            response.set_rcode(Rcode::NotImp);
+            return Some(response);
+        }
+
+        if 1 != query.questions.len() {
+            response.set_rcode(Rcode::FormErr);
+            return Some(response);
+        }
+
+        let question = &query.questions[0];
+
+        if CLASS_IN != question.qclass && CLASS_ANY != question.qclass {
+            reject(&mut response, Rcode::Refused, ExtendedErrorCode::NotSupported,
+                "Class not supported");
+            return Some(response);
+        }
+
+        match question.qtype {
+            QuestionType::Axfr | QuestionType::Ixfr | QuestionType::Opt => {
+                response.set_rcode(Rcode::NotImp);
+                return Some(response);
+            },
+            _ => {},
+        };
+
+        if self.catalog.find(&question.name).is_none() {
+            reject(&mut response, Rcode::Refused, ExtendedErrorCode::NotAuthoritative,
+                "Name not in any zone served");
+            return Some(response);
+        }
+
+        let scope = self.resolve(query, &mut response);
+
+        if ! query.dnssec_ok() {
+            response.remove_dnssec_records();
+        }
+
+        let subnet = query.edns.as_ref().and_then(|e| e.client_subnet());
+
+        if let (Some(subnet), Some(edns)) = (subnet, response.edns.as_mut()) {
+            edns.set_option(EdnsOption::ClientSubnet(subnet.response(scope)));
+        }
+
+        Some(response)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Fill in the sections of `response`, following CNAMEs within our zones.
+    /// Clients setting the DO bit get the signatures and proofs of
+    /// non-existence of signed zones as well (RFC 4035, 3.1).
+    /// Returns the ECS scope prefix length of the answer.
+    fn resolve(&self, query : &DnsMessage, response : &mut DnsMessage) -> u8 {
+
+        let question = &query.questions[0];
+        let qtype = question.qtype.code();
+        let dnssec = query.dnssec_ok();
+
+        // Subnet variants are not signed, clients asking for signatures
+        // get the records the signatures cover
+        let subnet = match dnssec {
+            true => None,
+            false => query.edns.as_ref().and_then(|e| e.client_subnet()),
+        };
+
+        let mut name = question.name.clone();
+
+        response.set_flag(FLAG_AA, true);
+
+        for _ in 0 .. MAX_CNAME_CHAIN_LEN {
+
+            let (origin, zone) = match self.catalog.find(&name) {
+                Some(z) => z,
+                // Alias pointing outside of our zones
+                None => return 0,
+            };
+
+            let (origin, zone) = (&origin, &*zone);
+
+            // Answers synthesized from a wildcard come with its signatures
+            // and the proof that the name itself does not exist
+            let (lookup, wildcard) = match zone.find(origin, &name, qtype, subnet) {
+                Lookup::Wildcard(wildcard, lookup) => (*lookup, Some(wildcard)),
+                lookup => (lookup, None),
+            };
+
+            let source = wildcard.as_ref().unwrap_or(&name);
+
+            let expansion = |nodata : bool| match (dnssec, wildcard.as_ref()) {
+                (true, Some(w)) => zone.wildcard_denial(origin, &name, w, nodata),
+                _ => Vec::new(),
+            };
+
+            match lookup {
+
+                Lookup::Answer(records, scope) => {
+                    response.answers.extend(records);
+                    if dnssec {
+                        response.answers.extend(signatures(zone, source, &name, qtype));
+                        response.authority.extend(expansion(false));
+                    }
+                    return scope;
+                },
+
+                Lookup::Alias(rr) => {
+                    let target = match rr.record {
+                        Record::CNAME(ref target) => target.clone(),
+                        _ => return 0,
+                    };
+                    response.answers.push(rr);
+                    if dnssec {
+                        response.answers.extend(
+                            signatures(zone, source, &name, QuestionType::Cname.code()));
+                        response.authority.extend(expansion(false));
+                    }
+                    name = target;
+                },
+
+                Lookup::Referral(ns) => {
+                    if response.answers.is_empty() {
+                        response.set_flag(FLAG_AA, false);
+                    }
+                    response.additional.extend(glue(zone, origin, &ns));
+                    if dnssec {
+                        let cut = ns[0].name.clone();
+                        response.authority.extend(ns);
+                        response.authority.extend(secure_delegation(zone, origin, &cut));
+                    } else {
+                        response.authority.extend(ns);
+                    }
+                    return 0;
+                },
+
+                Lookup::NoData => {
+                    response.authority.extend(negative_soa(zone, origin));
+                    if dnssec {
+                        response.authority.extend(
+                            zone.signatures(origin, QuestionType::Soa.code()));
+                        match wildcard {
+                            Some(_) => response.authority.extend(expansion(true)),
+                            None => response.authority.extend(
+                                zone.denial(origin, &name, false)),
+                        };
+                    }
+                    return 0;
+                },
+
+                Lookup::NxDomain => {
+                    response.set_rcode(Rcode::NxDomain);
+                    response.authority.extend(negative_soa(zone, origin));
+                    if dnssec {
+                        response.authority.extend(
+                            zone.signatures(origin, QuestionType::Soa.code()));
+                        response.authority.extend(zone.denial(origin, &name, true));
+                    }
+                    return 0;
+                },
+
+                // Wildcards do not expand to wildcards
+                Lookup::Wildcard(..) => return 0,
+
+            };
+
+        }
+
+        0
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for AuthoritativeHandler {
+
+    fn default() -> Self {
+        AuthoritativeHandler::new()
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Handler for AuthoritativeHandler {
+
+    fn handle (&self, msg : Message, responder : Responder) -> ContinueState {
+
+        let bytes = &msg.buffer[.. msg.num_bytes];
+
+        let query = match DnsMessage::read(&mut WireReader::new(bytes)) {
+            Ok(q) => q,
+            Err(_) => {
+                if let Some(response) = format_error(bytes) {
+                    responder.respond(response);
+                }
+                return ContinueState::Continue;
+            }
+        };
+
+        let client = msg.addr.ip();
+
+        let mut session = match self.verify(&query, bytes) {
+            Ok(session) => session,
+            Err(response) => {
+                println!("Rejecting signed request from {}", client);
+                responder.respond(response);
+                return ContinueState::Continue;
+            }
+        };
+
+        let key = session.as_ref().map(|s| s.key().name.clone());
+
+        if Transport::Tcp == msg.transport && is_transfer(&query) {
+
+            let mut responses = self.transfer(&query, &client, key.as_ref()).unwrap_or_default();
+
+            if let Some(ref mut session) = session {
+                responses.iter_mut().for_each(|r| session.sign(r));
+            }
+
+            responder.respond_all(responses.iter().map(|r| r.to_vec()).collect());
+
+            return ContinueState::Continue;
+
+        }
+
+        let response = match Opcode::from_u16(query.opcode()) {
+            Some(Opcode::Notify) => self.notified(&query, &client, key.as_ref()),
+            Some(Opcode::Update) => self.update(&query, &client, key.as_ref()),
+            _ => self.answer(&query),
+        };
+
+        if let Some(mut response) = response {
+
+            // Padding and TSIG must cover the cookie as well
+            if let Some(cookie) = msg.cookie {
+                response.set_cookie(cookie);
+            }
+
+            let max_len = match msg.transport {
+                Transport::Udp => msg.max_response_len
+                    .unwrap_or_else(|| query.max_udp_response_len()),
+                Transport::Tcp => usize::MAX,
+            };
+
+            responder.respond(encode(response, &query, max_len, session.as_mut()));
+
+        }
+
+        ContinueState::Continue
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Whether `client` or the `key` a request was signed with is allowed
+/// by the networks `acl` or the key names `keys`
+fn is_allowed(acl : &[Network], keys : &[Name], client : &IpAddr, key : Option<&Name>)
+    -> bool {
+
+    acl.iter().any(|n| n.contains(client)) ||
+        key.map(|k| keys.contains(k)).unwrap_or(false)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Set `rcode` on `response` and explain it by an Extended DNS Error
+/// (RFC 8914). The EDE is only attached if the query came with EDNS,
+/// since OPT must not be sent to clients not supporting it.
+fn reject(response : &mut DnsMessage, rcode : Rcode, code : ExtendedErrorCode, text : &str) {
+
+    response.set_rcode(rcode);
+
+    if response.edns.is_some() {
+        response.add_extended_error(code, text);
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Serialize `response` to `query`, truncating it if it exceeds `max_len`.
+/// Padding and the TSIG, if `session` is given, are added after
+/// truncating, for them to cover the message actually sent.
+fn encode(mut response : DnsMessage, query : &DnsMessage, max_len : usize,
+          session : Option<&mut TsigSession>) -> Vec<u8> {
+
+    let mut complete = response.clone();
+    complete.pad_response(query);
+
+    if let Some(ref session) = session {
+        (**session).clone().sign(&mut complete);
+    }
+
+    if complete.to_vec().len() > max_len {
+        response = response.truncated();
+    }
+
+    response.pad_response(query);
+
+    if let Some(session) = session {
+        session.sign(&mut response);
+    }
+
+    response.to_vec()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Signatures at `source` covering `type_code`, for the records served
+/// as owned by `name`. Signatures of a wildcard keep their label count,
+/// telling the expansion apart (RFC 4035, 3.1.3.3).
+fn signatures(zone : &Zone, source : &Name, name : &Name, type_code : u16)
+    -> Vec<ResourceRecord> {
+
+    let mut rrsigs = zone.signatures(source, type_code);
+    rrsigs.iter_mut().for_each(|rr| rr.name = name.clone());
+
+    rrsigs
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// SOA record to put into the authority section of negative answers.
+/// Its TTL is the negative caching TTL (RFC 2308).
+fn negative_soa(zone : &Zone, origin : &Name) -> Option<ResourceRecord> {
+
+    let mut soa = zone.soa(origin)?;
+
+    if let Record::SOA(ref data) = soa.record {
+        soa.ttl = soa.ttl.min(data.minimum);
+    }
+
+    Some(soa)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// DS records of the delegation at `cut` along with their signatures,
+/// or the proof there are none (RFC 4035, 3.1.4)
+fn secure_delegation(zone : &Zone, origin : &Name, cut : &Name) -> Vec<ResourceRecord> {
+
+    let ds = zone.rrset(cut, QuestionType::Ds.code());
+
+    if ds.is_empty() {
+        return zone.denial(origin, cut, false);
+    }
+
+    let signatures = zone.signatures(cut, QuestionType::Ds.code());
+    ds.into_iter().chain(signatures).collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Whether `query` is a well-formed AXFR or IXFR query.
+/// Others are left to `answer` to deal with.
+fn is_transfer(query : &DnsMessage) -> bool {
+
+    Opcode::Query as u16 == query.opcode() &&
+        query.edns.as_ref().map(|e| 0 == e.version).unwrap_or(true) &&
+        1 == query.questions.len() &&
+        (QuestionType::Axfr == query.questions[0].qtype ||
+         QuestionType::Ixfr == query.questions[0].qtype) &&
+        (CLASS_IN == query.questions[0].qclass || CLASS_ANY == query.questions[0].qclass)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Addresses of name servers of a delegation that lie within the zone
+fn glue(zone : &Zone, origin : &Name, ns : &[ResourceRecord]) -> Vec<ResourceRecord> {
+
+    let mut glue = Vec::new();
+
+    for rr in ns {
+
+        let target = match rr.record {
+            Record::NS(ref target) if target.is_subdomain_of(origin) => target,
+            _ => continue,
+        };
+
+        glue.extend(zone.rrset(target, QuestionType::A.code()));
+        glue.extend(zone.rrset(target, QuestionType::Aaaa.code()));
+
+    }
+
+    glue
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// FORMERR response for a query that could not be parsed,
+/// provided at least its header is intact
+pub(crate) fn format_error(query : &[u8]) -> Option<Vec<u8>> {
+
+    if HEADER_LEN > query.len() {
+        return None;
+    }
+
+    let mut reader = WireReader::new(query);
+
+    let id = reader.read_u16().ok()?;
+    let flags = reader.read_u16().ok()?;
+
+    let mut response = DnsMessage::new(id);
+    response.flags = flags;
+
+    if response.is_response() {
+        return None;
+    }
+
+    response = DnsMessage::response_to(&response);
+    response.set_rcode(Rcode::FormErr);
+
+    Some(response.to_vec())
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,735 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ::std::collections::{BTreeMap, HashMap};
+use ::std::sync::Mutex;
+use ::std::time::{Duration, Instant};
+use dnsmessage::{Message as DnsMessage, Rcode, FLAG_TC};
+use dnsname::Name;
+use dnsquestion::{QuestionType, CLASS_IN};
+use dnsrecord::{Record, ResourceRecord};
+use wire::WireWriter;
+
+/*----------------------------------------------------------------------------*/
+
+/// Memory the cache might use at most, in octets
+pub const DEFAULT_CACHE_SIZE : usize = 16 * 1024 * 1024;
+
+/// TTL records are kept for at most, in seconds
+pub const DEFAULT_MAX_TTL : u32 = 86400;
+
+/// TTL negative answers are kept for at most (RFC 2308, 5)
+pub const DEFAULT_MAX_NEGATIVE_TTL : u32 = 3600;
+
+/// Type code the non-existence of a name is kept under, as it holds
+/// for every type
+const NXDOMAIN_TYPE : u16 = 0;
+
+/// Estimated memory used by an entry apart from its records
+const ENTRY_OVERHEAD : usize = 96;
+
+const MAX_CNAME_CHAIN_LEN : usize = 8;
+
+/// TTL of stale records served, in seconds (RFC 8767, 4)
+const STALE_TTL : u32 = 30;
+
+/// Uses an entry needs to see before it is considered worth prefetching
+const PREFETCH_MIN_HITS : u32 = 2;
+
+/// Entries are prefetched once less than 1/PREFETCH_RATIO of their TTL
+/// is left
+const PREFETCH_RATIO : u32 = 10;
+
+/*----------------------------------------------------------------------------*/
+
+/// Counters of a cache
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheStats {
+
+    /// Lookups answered from the cache
+    pub hits : u64,
+    /// Lookups the cache could not answer
+    pub misses : u64,
+    /// Hits answered with expired records
+    pub stale_hits : u64,
+    /// Hits on entries that should be refreshed before they expire
+    pub prefetches : u64,
+    /// Entries dropped to make room for others
+    pub evictions : u64,
+    pub entries : usize,
+    /// Estimated memory used by the entries
+    pub size : usize,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Cache of responses, as used by resolvers and forwarders: RRsets are
+/// kept by name, type and class until their TTL expires, and served
+/// with their TTL decreased by the time passed since.
+/// NXDOMAIN and NODATA are kept according to the SOA record that came
+/// along (RFC 2308). If the cache grows too large, the entries used least
+/// recently are dropped.
+///
+/// Expired entries might be kept for a while longer, to answer with if
+/// they cannot be refreshed (RFC 8767).
+///
+/// The cache might be shared between threads.
+pub struct Cache {
+
+    inner : Mutex<CacheInner>,
+    max_size : usize,
+    min_ttl : u32,
+    max_ttl : u32,
+    max_negative_ttl : u32,
+    max_stale : Duration,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Response found in the cache
+#[derive(Clone, Debug)]
+pub struct CacheAnswer {
+
+    pub response : DnsMessage,
+    /// Some records have expired already
+    pub stale : bool,
+    /// Records are used often and about to expire, thus should be
+    /// refreshed. Only set for the first lookup that finds them so.
+    pub prefetch : bool,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+
+    name : Name,
+    rtype : u16,
+    class : u16,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+enum CacheData {
+
+    Records(Vec<ResourceRecord>),
+    /// Name does not exist - SOA record of the zone
+    NxDomain(ResourceRecord),
+    /// Name has no records of the type - SOA record of the zone
+    NoData(ResourceRecord),
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+struct CacheEntry {
+
+    data : CacheData,
+    expires : Instant,
+    /// TTL the entry was inserted with
+    ttl : u32,
+    size : usize,
+    /// Time of last use, to find the entry used least recently
+    tick : u64,
+    hits : u32,
+    /// Entry has been reported for prefetching already
+    prefetching : bool,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// How entries are looked up
+#[derive(Clone, Copy)]
+struct Policy {
+
+    now : Instant,
+    /// Time expired entries are kept for
+    max_stale : Duration,
+    /// Whether to answer with expired entries
+    serve_stale : bool,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+struct CacheInner {
+
+    entries : HashMap<CacheKey, CacheEntry>,
+    /// Keys of `entries` by their time of last use
+    lru : BTreeMap<u64, CacheKey>,
+    tick : u64,
+    stats : CacheStats,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Cache {
+
+    /// Cache using about `max_size` octets of memory at most
+    pub fn new(max_size : usize) -> Cache {
+
+        Cache {
+            inner : Mutex::new(CacheInner {
+                entries : HashMap::new(),
+                lru : BTreeMap::new(),
+                tick : 0,
+                stats : CacheStats::default(),
+            }),
+            max_size,
+            min_ttl : 0,
+            max_ttl : DEFAULT_MAX_TTL,
+            max_negative_ttl : DEFAULT_MAX_NEGATIVE_TTL,
+            max_stale : Duration::from_secs(0),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Keep records for at least `min_ttl`, but at most `max_ttl`
+    /// seconds, regardless of their TTL
+    pub fn set_ttl_limits(&mut self, min_ttl : u32, max_ttl : u32) {
+
+        self.min_ttl = min_ttl;
+        self.max_ttl = max_ttl.max(min_ttl);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn set_max_negative_ttl(&mut self, ttl : u32) {
+
+        self.max_negative_ttl = ttl;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Keep entries for `max_stale` after they expired, to be served if
+    /// they cannot be refreshed. Zero, the default, disables serving
+    /// stale records.
+    pub fn set_max_stale(&mut self, max_stale : Duration) {
+
+        self.max_stale = max_stale;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Keep the records of `response` to the query of a single question.
+    /// NXDOMAIN and NODATA are kept if the response carries an SOA record,
+    /// for the name the CNAMEs lead to.
+    /// Failures and truncated responses are ignored.
+    pub fn insert(&self, response : &DnsMessage) {
+
+        self.insert_at(response, Instant::now())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Like `insert`, as if it was `now`
+    pub fn insert_at(&self, response : &DnsMessage, now : Instant) {
+
+        let nxdomain = Rcode::NxDomain as u16 == response.rcode();
+
+        let cacheable = 1 == response.questions.len() && ! response.flag(FLAG_TC) &&
+            (nxdomain || Rcode::NoError as u16 == response.rcode());
+
+        if ! cacheable {
+            return;
+        }
+
+        let question = &response.questions[0];
+        let mut inner = self.inner.lock().unwrap();
+
+        for rrset in rrsets(&response.answers) {
+
+            let ttl = rrset.iter().map(|rr| rr.ttl).min().unwrap_or(0)
+                .clamp(self.min_ttl, self.max_ttl);
+
+            let key = CacheKey::new(&rrset[0].name, rrset[0].type_code(), rrset[0].class);
+
+            // The name exists after all
+            inner.remove(&CacheKey::new(&key.name, NXDOMAIN_TYPE, key.class));
+            inner.insert(key, CacheData::Records(rrset), ttl, now, self.max_size);
+
+        }
+
+        let (last, complete) = follow_chain(&response.answers, &question.name,
+                                            question.qtype.code());
+
+        if complete {
+            return;
+        }
+
+        let soa = response.authority.iter()
+            .find(|rr| QuestionType::Soa.code() == rr.type_code());
+
+        let (soa, ttl) = match soa {
+            Some(rr) => match rr.record {
+                Record::SOA(ref data) => (rr.clone(), rr.ttl.min(data.minimum)),
+                _ => return,
+            },
+            // Negative answers without SOA are not to be cached
+            None => return,
+        };
+
+        let ttl = ttl.min(self.max_negative_ttl);
+
+        let (rtype, data) = match nxdomain {
+            true => (NXDOMAIN_TYPE, CacheData::NxDomain(soa)),
+            false => (question.qtype.code(), CacheData::NoData(soa)),
+        };
+
+        inner.insert(CacheKey::new(&last, rtype, question.qclass), data, ttl, now,
+                     self.max_size);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Response to a query for `name` and `qtype`, if the cache can
+    /// answer it, following cached CNAMEs. The TTLs are those left.
+    pub fn lookup(&self, name : &Name, qtype : QuestionType) -> Option<DnsMessage> {
+
+        self.lookup_at(name, qtype, Instant::now())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Like `lookup`, as if it was `now`
+    pub fn lookup_at(&self, name : &Name, qtype : QuestionType, now : Instant)
+        -> Option<DnsMessage> {
+
+        self.find_at(name, qtype, false, now).map(|answer| answer.response)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Like `lookup`, but tells whether the records should be refreshed.
+    /// If `serve_stale` is set, expired records are used as well, with
+    /// a TTL of 30 seconds.
+    pub fn find(&self, name : &Name, qtype : QuestionType, serve_stale : bool)
+        -> Option<CacheAnswer> {
+
+        self.find_at(name, qtype, serve_stale, Instant::now())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Like `find`, as if it was `now`
+    pub fn find_at(&self, name : &Name, qtype : QuestionType, serve_stale : bool,
+                   now : Instant) -> Option<CacheAnswer> {
+
+        let policy = Policy {
+            now,
+            max_stale : self.max_stale,
+            serve_stale,
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+
+        let answer = inner.answer(name, qtype, policy);
+
+        match answer {
+            Some(ref a) => {
+                inner.stats.hits += 1;
+                inner.stats.stale_hits += a.stale as u64;
+                inner.stats.prefetches += a.prefetch as u64;
+            },
+            None => inner.stats.misses += 1,
+        };
+
+        answer
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn stats(&self) -> CacheStats {
+
+        self.inner.lock().unwrap().stats
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Number of entries, each an RRset or a negative answer
+    pub fn len(&self) -> usize {
+
+        self.inner.lock().unwrap().entries.len()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn is_empty(&self) -> bool {
+
+        0 == self.len()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn clear(&self) {
+
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.entries.clear();
+        inner.lru.clear();
+        inner.stats.entries = 0;
+        inner.stats.size = 0;
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for Cache {
+
+    fn default() -> Self {
+        Cache::new(DEFAULT_CACHE_SIZE)
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl CacheKey {
+
+    fn new(name : &Name, rtype : u16, class : u16) -> CacheKey {
+
+        CacheKey {
+            name : name.to_lowercase(),
+            rtype,
+            class,
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl CacheInner {
+
+    fn answer(&mut self, name : &Name, qtype : QuestionType, policy : Policy)
+        -> Option<CacheAnswer> {
+
+        let mut answer = CacheAnswer {
+            response : DnsMessage::response_to(&DnsMessage::query(0, name.clone(), qtype)),
+            stale : false,
+            prefetch : false,
+        };
+
+        let mut current = name.clone();
+
+        for _ in 0 .. MAX_CNAME_CHAIN_LEN {
+
+            let nxdomain = self.data(&current, NXDOMAIN_TYPE, policy, &mut answer);
+
+            if let Some(CacheData::NxDomain(soa)) = nxdomain {
+                answer.response.set_rcode(Rcode::NxDomain);
+                answer.response.authority.push(soa);
+                return Some(answer);
+            }
+
+            match self.data(&current, qtype.code(), policy, &mut answer) {
+                Some(CacheData::Records(records)) => {
+                    answer.response.answers.extend(records);
+                    return Some(answer);
+                },
+                Some(CacheData::NxDomain(soa)) | Some(CacheData::NoData(soa)) => {
+                    answer.response.authority.push(soa);
+                    return Some(answer);
+                },
+                None => {},
+            };
+
+            if QuestionType::Cname == qtype {
+                return None;
+            }
+
+            let cname = match self.data(&current, QuestionType::Cname.code(), policy,
+                                        &mut answer)? {
+                CacheData::Records(records) => records,
+                _ => return None,
+            };
+
+            current = match cname.first()?.record {
+                Record::CNAME(ref target) => target.clone(),
+                _ => return None,
+            };
+
+            answer.response.answers.extend(cname);
+
+        }
+
+        None
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// What is known about records at `name` of type `rtype`, with the
+    /// TTLs decreased
+    fn data(&mut self, name : &Name, rtype : u16, policy : Policy,
+            answer : &mut CacheAnswer) -> Option<CacheData> {
+
+        let (entry, ttl) = self.get(&CacheKey::new(name, rtype, CLASS_IN), policy, answer)?;
+
+        let decayed = |rr : &ResourceRecord|
+            ResourceRecord::new(rr.name.clone(), ttl, rr.record.clone());
+
+        Some(match entry.data {
+            CacheData::Records(ref records) =>
+                CacheData::Records(records.iter().map(decayed).collect()),
+            CacheData::NxDomain(ref soa) => CacheData::NxDomain(decayed(soa)),
+            CacheData::NoData(ref soa) => CacheData::NoData(decayed(soa)),
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Entry for `key`, marked as used, along with the TTL to serve it
+    /// with. Expired entries are dropped once they are older than
+    /// `max_stale`, and only returned if stale entries are to be served.
+    /// Flags `answer` as stale or to prefetch, as the entry requires.
+    fn get(&mut self, key : &CacheKey, policy : Policy, answer : &mut CacheAnswer)
+        -> Option<(&CacheEntry, u32)> {
+
+        let expires = self.entries.get(key)?.expires;
+
+        if expires + policy.max_stale <= policy.now {
+            self.remove(key);
+            return None;
+        }
+
+        let stale = expires <= policy.now;
+
+        if stale && ! policy.serve_stale {
+            return None;
+        }
+
+        self.tick += 1;
+        let tick = self.tick;
+
+        let entry = self.entries.get_mut(key)?;
+        self.lru.remove(&entry.tick);
+        self.lru.insert(tick, key.clone());
+        entry.tick = tick;
+        entry.hits = entry.hits.saturating_add(1);
+
+        if stale {
+            answer.stale = true;
+            return Some((entry, STALE_TTL));
+        }
+
+        let ttl = remaining(expires, policy.now);
+
+        let popular = PREFETCH_MIN_HITS <= entry.hits;
+
+        if popular && ! entry.prefetching && ttl.saturating_mul(PREFETCH_RATIO) <= entry.ttl {
+            entry.prefetching = true;
+            answer.prefetch = true;
+        }
+
+        Some((entry, ttl))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Add entry, dropping the ones used least recently while the cache
+    /// is too large
+    fn insert(&mut self, key : CacheKey, data : CacheData, ttl : u32, now : Instant,
+              max_size : usize) {
+
+        self.remove(&key);
+
+        if 0 == ttl {
+            return;
+        }
+
+        let size = ENTRY_OVERHEAD + data.size();
+
+        while self.stats.size + size > max_size {
+
+            let oldest = match self.lru.keys().next() {
+                None => return,
+                Some(tick) => *tick,
+            };
+
+            if let Some(key) = self.lru.get(&oldest).cloned() {
+                self.remove(&key);
+                self.stats.evictions += 1;
+            }
+
+        }
+
+        self.tick += 1;
+        self.lru.insert(self.tick, key.clone());
+
+        self.entries.insert(key, CacheEntry {
+            data,
+            expires : now + Duration::from_secs(ttl as u64),
+            ttl,
+            size,
+            tick : self.tick,
+            hits : 0,
+            prefetching : false,
+        });
+
+        self.stats.entries += 1;
+        self.stats.size += size;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn remove(&mut self, key : &CacheKey) {
+
+        if let Some(entry) = self.entries.remove(key) {
+            self.lru.remove(&entry.tick);
+            self.stats.entries -= 1;
+            self.stats.size -= entry.size;
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl CacheData {
+
+    /// Estimated memory used by the records
+    fn size(&self) -> usize {
+
+        let records = match self {
+            CacheData::Records(records) => records.as_slice(),
+            CacheData::NxDomain(soa) | CacheData::NoData(soa) => ::std::slice::from_ref(soa),
+        };
+
+        records.iter()
+            .map(|rr| {
+                let mut writer = WireWriter::uncompressed();
+                rr.write(&mut writer);
+                writer.len()
+            })
+            .sum()
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Seconds left until `expires`
+fn remaining(expires : Instant, now : Instant) -> u32 {
+
+    expires.saturating_duration_since(now).as_secs() as u32
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// `records` grouped into RRsets, in the order they appear
+fn rrsets(records : &[ResourceRecord]) -> Vec<Vec<ResourceRecord>> {
+
+    let mut sets : Vec<Vec<ResourceRecord>> = Vec::new();
+
+    for rr in records {
+
+        let set = sets.iter_mut().find(|s| s[0].name == rr.name &&
+            s[0].type_code() == rr.type_code() && s[0].class == rr.class);
+
+        match set {
+            Some(s) => s.push(rr.clone()),
+            None => sets.push(vec![rr.clone()]),
+        };
+
+    }
+
+    sets
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Name the CNAMEs among `answers` lead to from `name`, and whether
+/// records of type `rtype` are found there
+fn follow_chain(answers : &[ResourceRecord], name : &Name, rtype : u16) -> (Name, bool) {
+
+    let mut current = name.clone();
+
+    for _ in 0 .. MAX_CNAME_CHAIN_LEN {
+
+        if answers.iter().any(|rr| rr.name == current && rtype == rr.type_code()) {
+            return (current, true);
+        }
+
+        let target = answers.iter()
+            .filter(|rr| rr.name == current)
+            .find_map(|rr| match rr.record {
+                Record::CNAME(ref target) => Some(target.clone()),
+                _ => None,
+            });
+
+        match target {
+            Some(t) => current = t,
+            None => break,
+        };
+
+    }
+
+    (current, false)
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,379 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::collections::HashMap;
+use ::std::sync::{Arc, Mutex, RwLock};
+use ::std::thread::{sleep, spawn, JoinHandle};
+use ::std::time::Duration;
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::Record;
+use signer::{now, Signer};
+use zone::Zone;
+
+/*----------------------------------------------------------------------------*/
+
+/// Error of replacing a zone that is not the expected one anymore
+pub(crate) const ZONE_CHANGED : &str = "Zone changed meanwhile";
+
+/*----------------------------------------------------------------------------*/
+
+/// Told whenever a zone of a catalog was replaced by a new version
+pub trait ZoneObserver {
+
+    fn zone_changed(&self, origin : &Name, zone : &Zone);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Zones served by one process, keyed by their apex.
+/// Zones might be added, replaced or removed while queries are answered:
+/// Lookups hand out references to the zone they found, thus a zone being
+/// replaced keeps serving queries that are already in progress.
+/// Zones with a signer are signed whenever they are added or replaced.
+pub struct ZoneCatalog {
+
+    zones : RwLock<HashMap<Name, Arc<Zone>>>,
+    /// Held while zones are added, replaced or removed, so changes are
+    /// checked and journaled without blocking lookups
+    install_lock : Mutex<()>,
+    observers : RwLock<Vec<Arc<dyn ZoneObserver + Send + Sync>>>,
+    signers : RwLock<HashMap<Name, Arc<Signer>>>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ZoneCatalog {
+
+    pub fn new() -> ZoneCatalog {
+
+        ZoneCatalog {
+            zones : RwLock::new(HashMap::new()),
+            install_lock : Mutex::new(()),
+            observers : RwLock::new(Vec::new()),
+            signers : RwLock::new(HashMap::new()),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Add `zone` with apex `origin`. The zone requires an SOA record
+    /// at its apex.
+    pub fn add(&self, origin : Name, zone : Zone) -> Result<(), &'static str> {
+
+        check_apex(&origin, &zone)?;
+
+        let zone = self.sign(&origin, zone, None)?;
+        zone.build_denial_chain();
+
+        let _guard = self.install_lock.lock().unwrap();
+        let mut zones = self.zones.write().unwrap();
+
+        if zones.contains_key(&origin) {
+            return Err("Zone exists already");
+        }
+
+        zones.insert(origin, Arc::new(zone));
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Add `zone` with apex `origin` or replace the zone there.
+    /// The changes are recorded in the journal of the replaced zone, which
+    /// `zone` takes over.
+    /// Fails if the zone is replaced by someone else meanwhile.
+    /// Returns the replaced zone.
+    pub fn replace(&self, origin : Name, zone : Zone)
+        -> Result<Option<Arc<Zone>>, &'static str> {
+
+        let previous = self.get(&origin);
+        self.replace_if(origin, zone, previous.as_ref())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Like `replace`, but only if the zone at `origin` still is `expected`,
+    /// the version `zone` was derived from - None if there was no zone.
+    pub fn replace_if(&self, origin : Name, zone : Zone, expected : Option<&Arc<Zone>>)
+        -> Result<Option<Arc<Zone>>, &'static str> {
+
+        check_apex(&origin, &zone)?;
+
+        let zone = self.sign(&origin, zone, expected.map(|z| z.as_ref()))?;
+
+        self.install(origin, zone, expected)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Sign zone `origin` with `signer` whenever it is added or replaced
+    /// from now on
+    pub fn set_signer(&self, origin : Name, signer : Signer) {
+
+        self.signers.write().unwrap().insert(origin, Arc::new(signer));
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Sign zones again whose signatures are about to expire, increasing
+    /// their serial.
+    /// Returns the number of zones signed.
+    pub fn refresh_signatures(&self) -> usize {
+
+        let signers : Vec<(Name, Arc<Signer>)> = self.signers.read().unwrap().iter()
+            .map(|(origin, signer)| (origin.clone(), signer.clone()))
+            .collect();
+
+        let now = now();
+        let mut refreshed = 0;
+
+        for (origin, signer) in signers {
+
+            let current = match self.get(&origin) {
+                Some(z) if signer.needs_refresh(&z, now) => z,
+                _ => continue,
+            };
+
+            let mut zone = (*current).clone();
+
+            let result = increase_serial(&mut zone, &origin)
+                .and_then(|_| signer.sign_at(&origin, &zone, Some(&current), now))
+                .and_then(|zone| self.install(origin.clone(), zone, Some(&current)));
+
+            match result {
+                Ok(_) => refreshed += 1,
+                Err(e) => println!("Could not refresh signatures of {}: {}", origin, e),
+            };
+
+        }
+
+        refreshed
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Refresh signatures in a background thread every `interval`
+    pub fn spawn_refresh(catalog : Arc<ZoneCatalog>, interval : Duration) -> JoinHandle<()> {
+
+        spawn(move || loop {
+            sleep(interval);
+            catalog.refresh_signatures();
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Add `zone` with apex `origin` or replace the zone there, unless the
+    /// zone there is not `expected`
+    fn install(&self, origin : Name, mut zone : Zone, expected : Option<&Arc<Zone>>)
+        -> Result<Option<Arc<Zone>>, &'static str> {
+
+        // Comparing versions takes a while - do not hold up others meanwhile
+        let changes = match expected {
+            Some(previous) => zone.changes_since(&origin, previous)?,
+            None => None,
+        };
+
+        zone.build_denial_chain();
+
+        let (zone, previous) = {
+
+            let _guard = self.install_lock.lock().unwrap();
+
+            let changed = match (expected, self.get(&origin)) {
+                (Some(e), Some(current)) => ! Arc::ptr_eq(e, &current),
+                (None, None) => false,
+                _ => true,
+            };
+
+            if changed {
+                return Err(ZONE_CHANGED);
+            }
+
+            if let Some(previous) = expected {
+                zone.succeed(previous, changes)?;
+            }
+
+            let zone = Arc::new(zone);
+            let previous = self.zones.write().unwrap().insert(origin.clone(), zone.clone());
+
+            (zone, previous)
+
+        };
+
+        for observer in self.observers.read().unwrap().iter() {
+            observer.zone_changed(&origin, &zone);
+        }
+
+        Ok(previous)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Tell `observer` about every zone replaced from now on
+    pub fn add_observer(&self, observer : Arc<dyn ZoneObserver + Send + Sync>) {
+
+        self.observers.write().unwrap().push(observer);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn remove(&self, origin : &Name) -> Option<Arc<Zone>> {
+
+        let _guard = self.install_lock.lock().unwrap();
+        self.zones.write().unwrap().remove(origin)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Zone with apex `origin`
+    pub fn get(&self, origin : &Name) -> Option<Arc<Zone>> {
+
+        self.zones.read().unwrap().get(origin).cloned()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Find the most specific zone enclosing `name`.
+    /// Returns the apex of the zone along with the zone.
+    pub fn find(&self, name : &Name) -> Option<(Name, Arc<Zone>)> {
+
+        let zones = self.zones.read().unwrap();
+        let mut candidate = Some(name.clone());
+
+        // Walk up from `name` itself - the first apex hit is the longest match
+        while let Some(origin) = candidate {
+            if let Some(zone) = zones.get(&origin) {
+                return Some((origin, zone.clone()));
+            }
+            candidate = origin.parent();
+        }
+
+        None
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn origins(&self) -> Vec<Name> {
+
+        self.zones.read().unwrap().keys().cloned().collect()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn len(&self) -> usize {
+        self.zones.read().unwrap().len()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.read().unwrap().is_empty()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Signed version of `zone` if there is a signer for `origin`.
+    /// Signatures are taken over from `previous` or `zone` itself.
+    fn sign(&self, origin : &Name, zone : Zone, previous : Option<&Zone>)
+        -> Result<Zone, &'static str> {
+
+        let signer = self.signers.read().unwrap().get(origin).cloned();
+
+        match signer {
+            None => Ok(zone),
+            Some(signer) => signer.sign(origin, &zone, Some(previous.unwrap_or(&zone))),
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for ZoneCatalog {
+
+    fn default() -> Self {
+        ZoneCatalog::new()
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+fn check_apex(origin : &Name, zone : &Zone) -> Result<(), &'static str> {
+
+    match zone.soa(origin) {
+        None => Err("Zone requires SOA record at its apex"),
+        Some(_) => Ok(()),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn increase_serial(zone : &mut Zone, origin : &Name) -> Result<(), &'static str> {
+
+    let soa = zone.soa(origin).ok_or("Zone requires SOA record at its apex")?;
+
+    let mut data = match soa.record {
+        Record::SOA(ref data) => data.clone(),
+        _ => return Err("Zone requires SOA record at its apex"),
+    };
+
+    data.serial = data.serial.wrapping_add(1);
+
+    zone.remove_rrset(origin, QuestionType::Soa.code());
+    zone.add_with_ttl(origin.clone(), soa.ttl, Record::SOA(data))
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,558 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ::std::io::{Read, Write};
+use ::std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket};
+use ::std::path::Path;
+use ::std::str::FromStr;
+use ::std::sync::atomic::{AtomicUsize, Ordering};
+use ::std::time::{Duration, Instant};
+use dnsmessage::{Message as DnsMessage, Rcode, FLAG_CD, FLAG_RD, FLAG_TC};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::ResourceRecord;
+use edns::Edns;
+use hosts::{Hosts, HOSTS_PATH};
+use resolvconf::{ResolvConf, RESOLV_CONF_PATH};
+use udp::MAX_UDP_PAYLOAD_LEN;
+use validator::QuerySource;
+use wire::WireReader;
+
+/*----------------------------------------------------------------------------*/
+
+/// Number of times a query is sent to every server again if not answered
+pub const DEFAULT_CLIENT_RETRIES : usize = 2;
+
+/// Time to wait for an answer before asking the next server
+pub const DEFAULT_CLIENT_TIMEOUT_SECS : u64 = 2;
+
+/// UDP payload size announced via EDNS, avoiding fragmentation
+/// (DNS flag day 2020)
+const CLIENT_UDP_PAYLOAD_LEN : u16 = 1232;
+
+const NXDOMAIN : &str = "Name does not exist";
+const SERVER_FAILURE : &str = "Server failed to answer";
+
+/// Attempts to bind to a random source port before leaving the choice
+/// to the operating system
+const SOURCE_PORT_ATTEMPTS : usize = 10;
+
+/*----------------------------------------------------------------------------*/
+
+/// Stub resolver: Sends queries to the configured servers and waits for
+/// their answers. Queries go via UDP from a random source port with a
+/// random ID, and are repeated via TCP if the answer was truncated.
+/// Servers not answering in time, or failing, are skipped.
+///
+/// `resolve` additionally consults a hosts table and expands names via
+/// a search list, the way the C library does.
+pub struct Client {
+
+    servers : Vec<SocketAddr>,
+    retries : usize,
+    timeout : Duration,
+    recursion_desired : bool,
+    dnssec_ok : bool,
+    edns : bool,
+    rotate : bool,
+    /// Server to ask first if rotating
+    next_server : AtomicUsize,
+    search : Vec<Name>,
+    ndots : usize,
+    hosts : Hosts,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Client {
+
+    pub fn new() -> Client {
+
+        Client {
+            servers : Vec::new(),
+            retries : DEFAULT_CLIENT_RETRIES,
+            timeout : Duration::from_secs(DEFAULT_CLIENT_TIMEOUT_SECS),
+            recursion_desired : true,
+            dnssec_ok : false,
+            edns : true,
+            rotate : false,
+            next_server : AtomicUsize::new(0),
+            search : Vec::new(),
+            ndots : 1,
+            hosts : Hosts::new(),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Client configured like the C library via `/etc/resolv.conf` and
+    /// `/etc/hosts`
+    pub fn system() -> Result<Client, &'static str> {
+
+        Client::from_files(RESOLV_CONF_PATH, HOSTS_PATH)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Client configured via the resolver configuration at `resolv_conf`
+    /// and the hosts table at `hosts`. Missing files are taken as empty.
+    pub fn from_files<P : AsRef<Path>, Q : AsRef<Path>>(resolv_conf : P, hosts : Q)
+        -> Result<Client, &'static str> {
+
+        let mut client = Client::from_resolv_conf(&ResolvConf::load(resolv_conf)?);
+        client.set_hosts(Hosts::load(hosts)?);
+
+        Ok(client)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn from_resolv_conf(conf : &ResolvConf) -> Client {
+
+        let mut client = Client::new();
+
+        for server in &conf.nameservers {
+            client.add_server(*server);
+        }
+
+        client.set_retries(conf.attempts.saturating_sub(1), conf.timeout);
+        client.set_search(conf.search.clone(), conf.ndots);
+        client.set_rotate(conf.rotate);
+        client.set_edns(conf.edns0);
+
+        client
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Servers are asked in the order they were added
+    pub fn add_server(&mut self, server : SocketAddr) {
+
+        self.servers.push(server);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn servers(&self) -> &[SocketAddr] {
+        &self.servers
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Ask every server up to `retries` times more, waiting `timeout`
+    /// for the answer each time
+    pub fn set_retries(&mut self, retries : usize, timeout : Duration) {
+
+        self.retries = retries;
+        self.timeout = timeout;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether servers are asked to resolve queries recursively
+    pub fn set_recursion_desired(&mut self, value : bool) {
+
+        self.recursion_desired = value;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Ask for DNSSEC records along with the answers
+    pub fn set_dnssec_ok(&mut self, value : bool) {
+
+        self.dnssec_ok = value;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Announce EDNS support in queries. Asking for DNSSEC records
+    /// implies EDNS regardless.
+    pub fn set_edns(&mut self, value : bool) {
+
+        self.edns = value;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Ask the servers in turn rather than starting with the first one
+    /// each time
+    pub fn set_rotate(&mut self, value : bool) {
+
+        self.rotate = value;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Domains to try appending by `resolve` to names with less than
+    /// `ndots` dots
+    pub fn set_search(&mut self, search : Vec<Name>, ndots : usize) {
+
+        self.search = search;
+        self.ndots = ndots;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Table of names `resolve` answers without asking any server
+    pub fn set_hosts(&mut self, hosts : Hosts) {
+
+        self.hosts = hosts;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Records of type `qtype` for `name` as given by a user: Names in
+    /// the hosts table are answered from there. Otherwise the names from
+    /// `search_names` are looked up until one has records.
+    /// An empty list means names exist, but have no such records.
+    pub fn resolve(&self, name : &str, qtype : QuestionType)
+        -> Result<Vec<ResourceRecord>, &'static str> {
+
+        let local = self.hosts.lookup(&Name::from_str(name)?, qtype);
+
+        if ! local.is_empty() {
+            return Ok(local);
+        }
+
+        let candidates = self.search_names(name)?;
+
+        let mut nodata = false;
+        let mut server_failure = false;
+
+        // NXDOMAIN and SERVFAIL move on to the next name, anything else
+        // but an answer stops the search
+        for candidate in &candidates {
+
+            match self.lookup(candidate, qtype) {
+                Ok(ref records) if records.is_empty() => nodata = true,
+                Ok(records) => return Ok(records),
+                Err(NXDOMAIN) => (),
+                Err(SERVER_FAILURE) => server_failure = true,
+                Err(msg) => return Err(msg),
+            };
+
+        }
+
+        match (nodata, server_failure) {
+            (true, _) => Ok(Vec::new()),
+            (false, true) => Err(SERVER_FAILURE),
+            (false, false) => Err(NXDOMAIN),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Names `name` might refer to, in the order to try them (see
+    /// resolv.conf(5)): A name ending in a dot is taken as is. One
+    /// with at least `ndots` dots is tried as is before appending the
+    /// search domains, others after.
+    pub fn search_names(&self, name : &str) -> Result<Vec<Name>, &'static str> {
+
+        let absolute = Name::from_str(name)?;
+
+        if name.ends_with('.') {
+            return Ok(vec![absolute]);
+        }
+
+        let mut names : Vec<Name> = self.search.iter()
+            .map(|domain| absolute.join(domain))
+            .collect();
+
+        match name.matches('.').count() < self.ndots {
+            true => names.push(absolute),
+            false => names.insert(0, absolute),
+        };
+
+        Ok(names)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Records of type `qtype` owned by `name`, along with the CNAME
+    /// records leading there. An empty list means there are none.
+    pub fn lookup(&self, name : &Name, qtype : QuestionType)
+        -> Result<Vec<ResourceRecord>, &'static str> {
+
+        let response = self.query(name, qtype)?;
+
+        match Rcode::NxDomain as u16 == response.rcode() {
+            true => Err(NXDOMAIN),
+            false => Ok(response.answers),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Response to a query for `name` and `qtype`
+    pub fn query(&self, name : &Name, qtype : QuestionType) -> Result<DnsMessage, &'static str> {
+
+        let query = self.build_query(name, qtype, self.dnssec_ok)?;
+        self.send(&query)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Send `query` and return the first response matching it.
+    /// Responses indicating neither success nor NXDOMAIN make the next
+    /// server be asked.
+    pub fn send(&self, query : &DnsMessage) -> Result<DnsMessage, &'static str> {
+
+        if self.servers.is_empty() {
+            return Err("No server to ask");
+        }
+
+        let bytes = query.to_vec();
+        let mut error = "No answer";
+
+        let first = match self.rotate {
+            true => self.next_server.fetch_add(1, Ordering::Relaxed) % self.servers.len(),
+            false => 0,
+        };
+
+        let servers = self.servers[first ..].iter().chain(&self.servers[.. first]);
+
+        for _ in 0 ..= self.retries {
+
+            for server in servers.clone() {
+
+                let response = match self.exchange(server, query, &bytes) {
+                    Ok(r) => r,
+                    Err(msg) => {
+                        error = msg;
+                        continue;
+                    },
+                };
+
+                match response.rcode() {
+                    r if Rcode::NoError as u16 == r || Rcode::NxDomain as u16 == r =>
+                        return Ok(response),
+                    _ => error = SERVER_FAILURE,
+                };
+
+            }
+
+        }
+
+        Err(error)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn build_query(&self, name : &Name, qtype : QuestionType, dnssec_ok : bool)
+        -> Result<DnsMessage, &'static str> {
+
+        let mut query = DnsMessage::query(DnsMessage::random_id()?, name.clone(), qtype);
+        query.set_flag(FLAG_RD, self.recursion_desired);
+
+        if self.edns || dnssec_ok {
+            query.edns = Some(Edns::new(CLIENT_UDP_PAYLOAD_LEN));
+            query.set_dnssec_ok(dnssec_ok);
+        }
+
+        Ok(query)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Ask `server` via UDP, and via TCP if the answer got truncated
+    fn exchange(&self, server : &SocketAddr, query : &DnsMessage, bytes : &[u8])
+        -> Result<DnsMessage, &'static str> {
+
+        let response = self.exchange_udp(server, query, bytes)?;
+
+        match response.flag(FLAG_TC) {
+            true => self.exchange_tcp(server, query, bytes),
+            false => Ok(response),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn exchange_udp(&self, server : &SocketAddr, query : &DnsMessage, bytes : &[u8])
+        -> Result<DnsMessage, &'static str> {
+
+        let socket = bind_random_port(server)?;
+
+        socket.connect(server)
+            .and_then(|_| socket.send(bytes))
+            .map_err(|_| "Could not send query")?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut buffer = [0u8; MAX_UDP_PAYLOAD_LEN];
+
+        // Skip everything but the answer until timed out
+        loop {
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Err("No answer");
+            }
+
+            socket.set_read_timeout(Some(deadline - now))
+                .map_err(|_| "Could not set timeout")?;
+
+            let len = socket.recv(&mut buffer).map_err(|_| "No answer")?;
+
+            if let Ok(response) = DnsMessage::read(&mut WireReader::new(&buffer[.. len])) {
+                if answers(query, &response) {
+                    return Ok(response);
+                }
+            }
+
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn exchange_tcp(&self, server : &SocketAddr, query : &DnsMessage, bytes : &[u8])
+        -> Result<DnsMessage, &'static str> {
+
+        let mut stream = TcpStream::connect_timeout(server, self.timeout)
+            .map_err(|_| "Could not connect to server")?;
+
+        stream.set_read_timeout(Some(self.timeout))
+            .and_then(|_| stream.set_write_timeout(Some(self.timeout)))
+            .map_err(|_| "Could not set timeout")?;
+
+        stream.write_all(&(bytes.len() as u16).to_be_bytes())
+            .and_then(|_| stream.write_all(bytes))
+            .map_err(|_| "Could not send query")?;
+
+        let mut len = [0u8; 2];
+        stream.read_exact(&mut len).map_err(|_| "Could not receive response")?;
+
+        let mut frame = vec![0u8; u16::from_be_bytes(len) as usize];
+        stream.read_exact(&mut frame).map_err(|_| "Could not receive response")?;
+
+        let response = DnsMessage::read(&mut WireReader::new(&frame))?;
+
+        match answers(query, &response) {
+            true => Ok(response),
+            false => Err("Response does not match query"),
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for Client {
+
+    fn default() -> Self {
+        Client::new()
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl QuerySource for Client {
+
+    /// Ask with DO and CD set, leaving validation to the caller
+    fn query(&self, name : &Name, qtype : QuestionType) -> Result<DnsMessage, &'static str> {
+
+        let mut query = self.build_query(name, qtype, true)?;
+        query.set_flag(FLAG_CD, true);
+
+        self.send(&query)
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Whether `response` answers `query`: Same ID and question
+fn answers(query : &DnsMessage, response : &DnsMessage) -> bool {
+
+    response.is_response() && query.id == response.id &&
+        query.questions == response.questions
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// UDP socket to talk to `server` from, bound to a random port
+/// (RFC 5452, 9.2)
+fn bind_random_port(server : &SocketAddr) -> Result<UdpSocket, &'static str> {
+
+    let ip = match server {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+
+    for _ in 0 .. SOURCE_PORT_ATTEMPTS {
+
+        let mut port = [0u8; 2];
+        ::getrandom::getrandom(&mut port).map_err(|_| "Could not gather random data")?;
+
+        // Leave out the well known ports
+        let port = 1024 + u16::from_be_bytes(port) % (u16::MAX - 1024);
+
+        if let Ok(socket) = UdpSocket::bind(SocketAddr::new(ip, port)) {
+            return Ok(socket);
+        }
+
+    }
+
+    UdpSocket::bind(SocketAddr::new(ip, 0)).map_err(|_| "Could not bind socket")
+
+}
+
+/*----------------------------------------------------------------------------*/
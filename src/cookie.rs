@@ -0,0 +1,425 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::collections::HashMap;
+use ::std::hash::Hasher;
+use ::std::net::{IpAddr, SocketAddr};
+use ::std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use siphasher::sip::SipHasher24;
+use dnsmessage::{Message, Rcode};
+use dnstraits::AsBytes;
+use edns::{Cookie, CLIENT_COOKIE_LEN};
+use udp::MAX_SAFE_UDP_PAYLOAD_LEN;
+
+/*----------------------------------------------------------------------------*/
+
+const SECRET_LEN : usize = 16;
+const SERVER_COOKIE_LEN : usize = 16;
+const SERVER_COOKIE_VERSION : u8 = 1;
+
+/// Server cookies older than this are rejected (RFC 9018, section 4.3)
+const MAX_COOKIE_AGE_SECS : u32 = 3600;
+/// Server cookies with timestamps up to this far in the future are accepted
+const MAX_CLOCK_SKEW_SECS : u32 = 300;
+
+const MAX_PENDING_QUERIES : usize = 1024;
+const PENDING_QUERY_TIMEOUT_SECS : u64 = 5;
+
+/*----------------------------------------------------------------------------*/
+
+/// How a listener treats DNS Cookies (RFC 7873)
+#[derive(Clone, Debug)]
+pub struct CookieConfig {
+
+    /// Answer queries carrying an invalid server cookie with BADCOOKIE
+    /// instead of processing them
+    pub enforce : bool,
+    /// Responses to queries without valid server cookie exceeding
+    /// this size are truncated to force the client to TCP
+    pub max_cookieless_response_len : usize,
+    /// Interval to rotate the server secret in
+    pub secret_lifetime : Duration,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CookieStatus {
+
+    /// Query did not contain a COOKIE option
+    Missing,
+    /// Only a client cookie was sent
+    ClientOnly,
+    /// Client sent a server cookie we issued
+    Valid,
+    /// Client sent a server cookie we cannot verify
+    Invalid,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Generates and verifies server cookies as described in RFC 9018.
+/// The previous secret is kept after rotation, so cookies issued
+/// shortly before remain valid.
+pub struct ServerCookies {
+
+    current : [u8; SECRET_LEN],
+    previous : Option<[u8; SECRET_LEN]>,
+    rotated_at : Instant,
+    lifetime : Duration,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+struct PendingQuery {
+
+    /// Max size of the response
+    max_len : usize,
+    received_at : Instant,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Applies cookie handling to the datagrams of a listener:
+/// Checks cookies of queries and provides the cookies for the handler to
+/// add to the responses, along with the size to truncate them to.
+pub struct CookieFilter {
+
+    config : CookieConfig,
+    cookies : ServerCookies,
+    pending : HashMap<(SocketAddr, u16), PendingQuery>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for CookieConfig {
+
+    fn default() -> Self {
+
+        CookieConfig {
+            enforce : true,
+            max_cookieless_response_len : MAX_SAFE_UDP_PAYLOAD_LEN,
+            secret_lifetime : Duration::from_secs(3600),
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ServerCookies {
+
+    pub fn new(lifetime : Duration) -> Result<ServerCookies, &'static str> {
+
+        Ok(ServerCookies::with_secret(random_secret()?, lifetime))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn with_secret(secret : [u8; SECRET_LEN], lifetime : Duration) -> ServerCookies {
+
+        ServerCookies {
+            current : secret,
+            previous : None,
+            rotated_at : Instant::now(),
+            lifetime,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Replace the secret. Cookies generated with the old one stay valid
+    /// until the next rotation.
+    pub fn rotate(&mut self, secret : [u8; SECRET_LEN]) {
+
+        self.previous = Some(self.current);
+        self.current = secret;
+        self.rotated_at = Instant::now();
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn rotate_if_due(&mut self) -> Result<(), &'static str> {
+
+        if self.rotated_at.elapsed() >= self.lifetime {
+            self.rotate(random_secret()?);
+        }
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Server cookie for client, issued at `timestamp` (seconds since epoch)
+    pub fn generate(&self, client_cookie : &[u8; CLIENT_COOKIE_LEN],
+                    client_ip : &IpAddr, timestamp : u32) -> Vec<u8> {
+
+        int_generate(&self.current, client_cookie, client_ip, timestamp)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Check the cookies found in a query at time `now` (seconds since epoch)
+    pub fn check(&self, cookie : Option<&Cookie>, client_ip : &IpAddr, now : u32)
+        -> CookieStatus {
+
+        let cookie = match cookie {
+            None => return CookieStatus::Missing,
+            Some(c) => c,
+        };
+
+        if cookie.server.is_empty() {
+            return CookieStatus::ClientOnly;
+        }
+
+        if SERVER_COOKIE_LEN != cookie.server.len() ||
+            SERVER_COOKIE_VERSION != cookie.server[0] {
+            return CookieStatus::Invalid;
+        }
+
+        let timestamp = u32::from_be_bytes(
+            [cookie.server[4], cookie.server[5], cookie.server[6], cookie.server[7]]);
+
+        // Serial number arithmetic, the timestamp wraps in 2106
+        let age = now.wrapping_sub(timestamp);
+        let too_old = age > MAX_COOKIE_AGE_SECS && age < u32::MAX / 2;
+        let too_new = timestamp.wrapping_sub(now) > MAX_CLOCK_SKEW_SECS
+            && age >= u32::MAX / 2;
+
+        if too_old || too_new {
+            return CookieStatus::Invalid;
+        }
+
+        let matches = |secret : &[u8; SECRET_LEN]|
+            int_generate(secret, &cookie.client, client_ip, timestamp) == cookie.server;
+
+        if matches(&self.current) || self.previous.as_ref().is_some_and(matches) {
+            CookieStatus::Valid
+        } else {
+            CookieStatus::Invalid
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl CookieFilter {
+
+    pub fn new(config : CookieConfig) -> Result<CookieFilter, &'static str> {
+
+        let cookies = ServerCookies::new(config.secret_lifetime)?;
+
+        Ok(CookieFilter {
+            config,
+            cookies,
+            pending : HashMap::new(),
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Check the cookie of an incoming query and return the cookie
+    /// to add to its response, if any, along with the max size of the
+    /// response. Clients that did not prove to own their source address
+    /// get small responses only. The handler must add the cookie and
+    /// truncate before padding or signing the response.
+    /// If the query must not be processed, the response to send
+    /// instead is returned as error.
+    pub fn check_query(&mut self, addr : &SocketAddr, bytes : &[u8])
+        -> Result<(Option<Cookie>, usize), Vec<u8>> {
+
+        let cookieless_len = self.config.max_cookieless_response_len;
+
+        // Malformed queries are left to the handler
+        let query = match Message::from_bytes(bytes) {
+            Ok(q) => q,
+            Err(_) => return Ok((None, cookieless_len)),
+        };
+
+        if query.is_response() {
+            return Ok((None, cookieless_len));
+        }
+
+        if self.cookies.rotate_if_due().is_err() {
+            println!("Could not rotate cookie secret");
+        }
+
+        let cookie = query.edns.as_ref().and_then(|e| e.cookie());
+        let status = self.cookies.check(cookie, &addr.ip(), unix_time());
+
+        if CookieStatus::Invalid == status && self.config.enforce {
+
+            let mut response = Message::response_to(&query);
+            response.set_rcode(Rcode::BadCookie);
+
+            if let Some(cookie) = self.server_cookie(cookie, addr) {
+                response.set_cookie(cookie);
+            }
+
+            return Err(response.to_udp_bytes(query.max_udp_response_len()));
+
+        }
+
+        let max_len = match status {
+            CookieStatus::Valid => query.max_udp_response_len(),
+            _ => query.max_udp_response_len().min(cookieless_len),
+        };
+
+        self.expire_pending();
+
+        self.pending.insert((*addr, query.id), PendingQuery {
+            max_len,
+            received_at : Instant::now(),
+        });
+
+        Ok((self.server_cookie(cookie, addr), max_len))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether an outgoing response stays within the size returned
+    /// by `check_query` for its query.
+    /// Queries received too long ago are not considered, the ID might
+    /// have been reused by the client meanwhile.
+    pub fn check_response(&mut self, addr : &SocketAddr, bytes : &[u8]) -> bool {
+
+        if bytes.len() < 2 {
+            return true;
+        }
+
+        let id = u16::from_be_bytes([bytes[0], bytes[1]]);
+
+        match self.pending.remove(&(*addr, id)) {
+            Some(ref pending) if ! is_expired(pending) => bytes.len() <= pending.max_len,
+            _ => true,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Cookie to answer the query carrying `cookie` from `addr` with
+    fn server_cookie(&self, cookie : Option<&Cookie>, addr : &SocketAddr) -> Option<Cookie> {
+
+        let client = cookie?.client;
+        let server = self.cookies.generate(&client, &addr.ip(), unix_time());
+
+        Some(Cookie { client, server })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn expire_pending(&mut self) {
+
+        if self.pending.len() < MAX_PENDING_QUERIES {
+            return;
+        }
+
+        self.pending.retain(|_, p| ! is_expired(p));
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+fn is_expired(pending : &PendingQuery) -> bool {
+
+    pending.received_at.elapsed() >= Duration::from_secs(PENDING_QUERY_TIMEOUT_SECS)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn int_generate(secret : &[u8; SECRET_LEN], client_cookie : &[u8; CLIENT_COOKIE_LEN],
+                client_ip : &IpAddr, timestamp : u32) -> Vec<u8> {
+
+    let mut cookie = Vec::with_capacity(SERVER_COOKIE_LEN);
+    cookie.push(SERVER_COOKIE_VERSION);
+    cookie.extend_from_slice(&[0, 0, 0]);
+    cookie.extend_from_slice(&timestamp.to_be_bytes());
+
+    let mut hasher = SipHasher24::new_with_key(secret);
+    hasher.write(client_cookie);
+    hasher.write(&cookie);
+
+    match client_ip {
+        IpAddr::V4(a) => hasher.write(&a.octets()),
+        IpAddr::V6(a) => hasher.write(&a.octets()),
+    };
+
+    // RFC 9018 test vectors require the SipHash reference byte order
+    cookie.extend_from_slice(&hasher.finish().to_le_bytes());
+    cookie
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn random_secret() -> Result<[u8; SECRET_LEN], &'static str> {
+
+    let mut secret = [0u8; SECRET_LEN];
+    ::getrandom::getrandom(&mut secret).map_err(|_| "Could not gather random data")?;
+    Ok(secret)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn unix_time() -> u32 {
+
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or(0)
+
+}
+
+/*----------------------------------------------------------------------------*/
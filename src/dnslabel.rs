@@ -126,15 +126,10 @@ impl fmt::Display for Label {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 
         for u in self.data[1..].iter() {
-            let result = match u {
-                &0 => break,
-                &other => write!(f, "{}", other as char),
+            match *u {
+                0 => break,
+                other => write!(f, "{}", other as char)?,
             };
-
-            if result.is_err() {
-                return result;
-            }
-
         }
 
         Ok(())
@@ -164,8 +159,8 @@ impl cmp::PartialEq for Label {
             return false;
         }
 
-        self.into_normalized_iter().zip(
-            other.into_normalized_iter()).all(|(a,b)| a == b)
+        self.normalized_iter().zip(
+            other.normalized_iter()).all(|(a,b)| a == b)
 
     }
 
@@ -183,10 +178,35 @@ impl Label {
         self.data[0] as usize
     }
 
-    fn into_normalized_iter<'a>(&'a self) -> Map<slice::Iter<'a, u8>, fn(&u8) -> u8> {
+    pub fn is_empty(&self) -> bool {
+        0 == self.len()
+    }
+
+    /// Copy of this label with all ASCII letters in lower case
+    pub fn to_lowercase(&self) -> Label {
+
+        let mut label = *self;
+        let len = self.len();
+        label.data[1 .. 1 + len].make_ascii_lowercase();
+        label
+
+    }
+
+    /// Compare as octet strings with all ASCII letters in lower case
+    /// (RFC 4034, section 6.1)
+    pub fn canonical_cmp(&self, other : &Label) -> cmp::Ordering {
+
+        let a = self.to_lowercase();
+        let b = other.to_lowercase();
+
+        a.data[1 .. 1 + a.len()].cmp(&b.data[1 .. 1 + b.len()])
+
+    }
+
+    fn normalized_iter(&self) -> Map<slice::Iter<'_, u8>, fn(&u8) -> u8> {
 
         let len = self.len();
-        self.data[1 .. 1 + len].into_iter().map(_to_ascii_uppercase)
+        self.data[1 .. 1 + len].iter().map(_to_ascii_uppercase)
 
     }
 }
@@ -197,7 +217,7 @@ impl Hash for Label {
 
     fn hash<H: Hasher>(&self, state : &mut H) {
 
-        for octet in self.into_normalized_iter() {
+        for octet in self.normalized_iter() {
             octet.hash(state);
         }
 
@@ -31,18 +31,582 @@
  * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+use ::std::fmt;
+use dnstraits::AsBytes;
+use dnsquestion::{Question, QuestionType};
+use dnsrecord::ResourceRecord;
+use dnsname::Name;
+use edns::{Cookie, Edns, EdnsOption, ExtendedError, ExtendedErrorCode, OPTION_PADDING};
+use tsig::Tsig;
+use udp::{MAX_SAFE_UDP_PAYLOAD_LEN, MAX_UDP_PAYLOAD_LEN};
+use wire::{WireReader, WireWriter};
 
+/*----------------------------------------------------------------------------*/
+
+pub const FLAG_QR : u16 = 0x8000;
+pub const FLAG_AA : u16 = 0x0400;
+pub const FLAG_TC : u16 = 0x0200;
+pub const FLAG_RD : u16 = 0x0100;
+pub const FLAG_RA : u16 = 0x0080;
+pub const FLAG_AD : u16 = 0x0020;
+pub const FLAG_CD : u16 = 0x0010;
+
+const OPCODE_SHIFT : u16 = 11;
+const OPCODE_MASK : u16 = 0x7800;
+const RCODE_MASK : u16 = 0x000F;
+
+const HEADER_LEN : usize = 12;
+
+/// Block sizes recommended by RFC 8467, section 4.1
+pub const QUERY_PADDING_BLOCK : usize = 128;
+pub const RESPONSE_PADDING_BLOCK : usize = 468;
+
+const OPTION_HEADER_LEN : usize = 4;
 
 /*----------------------------------------------------------------------------*/
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opcode {
+
+    Query = 0,
+    Status = 2,
+    Notify = 4,
+    Update = 5,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Response codes, including extended ones that require EDNS
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rcode {
+
+    NoError = 0,
+    FormErr = 1,
+    ServFail = 2,
+    NxDomain = 3,
+    NotImp = 4,
+    Refused = 5,
+    YxDomain = 6,
+    YxRrset = 7,
+    NxRrset = 8,
+    NotAuth = 9,
+    NotZone = 10,
+    BadVers = 16,
+    BadCookie = 23,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// A DNS message (RFC 1035, section 4)
+#[derive(Clone, Debug, PartialEq)]
 pub struct Message {
 
-    id : u16,
-    flags : u16,
-    questions : Vector<Question>,
-    answerts : Vector <Record>,
-    authoritative : Vector<Record>,
-    additional : Vector<Record>,
+    pub id : u16,
+    /// Header flags including OPCODE and the lower 4 bits of the RCODE
+    pub flags : u16,
+    pub questions : Vec<Question>,
+    pub answers : Vec<ResourceRecord>,
+    pub authority : Vec<ResourceRecord>,
+    pub additional : Vec<ResourceRecord>,
+    pub edns : Option<Edns>,
+    /// Transaction signature, always the last record of the message
+    pub tsig : Option<Tsig>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Opcode {
+
+    pub fn from_u16(value : u16) -> Option<Opcode> {
+
+        match value {
+            0 => Some(Opcode::Query),
+            2 => Some(Opcode::Status),
+            4 => Some(Opcode::Notify),
+            5 => Some(Opcode::Update),
+            _ => None,
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Message {
+
+    pub fn new(id : u16) -> Message {
+
+        Message {
+            id,
+            flags : 0,
+            questions : Vec::new(),
+            answers : Vec::new(),
+            authority : Vec::new(),
+            additional : Vec::new(),
+            edns : None,
+            tsig : None,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Unpredictable message ID for queries we send
+    pub fn random_id() -> Result<u16, &'static str> {
+
+        let mut id = [0u8; 2];
+        ::getrandom::getrandom(&mut id).map_err(|_| "Could not gather random data")?;
+
+        Ok(u16::from_be_bytes(id))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn query(id : u16, name : Name, qtype : QuestionType) -> Message {
+
+        let mut msg = Message::new(id);
+        msg.set_flag(FLAG_RD, true);
+        msg.questions.push(Question::new(name, qtype));
+        msg
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Create an empty response to `query`.
+    /// ID, OPCODE, RD, CD and the question section are copied,
+    /// if the query used EDNS, the response will as well.
+    pub fn response_to(query : &Message) -> Message {
+
+        let mut msg = Message::new(query.id);
+
+        msg.flags = FLAG_QR | (query.flags & (OPCODE_MASK | FLAG_RD | FLAG_CD));
+        msg.questions = query.questions.clone();
+
+        if let Some(ref edns) = query.edns {
+            let mut response_edns = Edns::new(MAX_UDP_PAYLOAD_LEN as u16);
+            response_edns.dnssec_ok = edns.dnssec_ok;
+            msg.edns = Some(response_edns);
+        }
+
+        msg
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn flag(&self, flag : u16) -> bool {
+        0 != self.flags & flag
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn set_flag(&mut self, flag : u16, value : bool) {
+
+        if value {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn is_response(&self) -> bool {
+        self.flag(FLAG_QR)
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn opcode(&self) -> u16 {
+        (self.flags & OPCODE_MASK) >> OPCODE_SHIFT
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn set_opcode(&mut self, opcode : Opcode) {
+
+        self.flags &= !OPCODE_MASK;
+        self.flags |= (opcode as u16) << OPCODE_SHIFT;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Full 12 bit RCODE - the upper 8 bits are taken from EDNS, if present
+    pub fn rcode(&self) -> u16 {
+
+        let extended = match self.edns {
+            Some(ref edns) => edns.extended_rcode as u16,
+            None => 0,
+        };
+
+        (extended << 4) | (self.flags & RCODE_MASK)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Set RCODE. Extended RCODEs will add EDNS to the message if required.
+    pub fn set_rcode(&mut self, rcode : Rcode) {
+
+        let rcode = rcode as u16;
+
+        self.flags &= !RCODE_MASK;
+        self.flags |= rcode & RCODE_MASK;
+
+        let extended = (rcode >> 4) as u8;
+
+        if 0 != extended && self.edns.is_none() {
+            self.edns = Some(Edns::new(MAX_UDP_PAYLOAD_LEN as u16));
+        }
+
+        if let Some(ref mut edns) = self.edns {
+            edns.extended_rcode = extended;
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether the sender asks for DNSSEC records via the DO bit (RFC 3225)
+    pub fn dnssec_ok(&self) -> bool {
+        self.edns.as_ref().map(|e| e.dnssec_ok).unwrap_or(false)
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Set the DO bit, adding EDNS if required
+    pub fn set_dnssec_ok(&mut self, value : bool) {
+
+        if ! value && self.edns.is_none() {
+            return;
+        }
+
+        self.edns
+            .get_or_insert_with(|| Edns::new(MAX_UDP_PAYLOAD_LEN as u16))
+            .dnssec_ok = value;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Remove RRSIG, NSEC and NSEC3 records, for clients that did not ask
+    /// for DNSSEC records (RFC 4035, 3.2.1). Records of the type asked for
+    /// are kept.
+    pub fn remove_dnssec_records(&mut self) {
+
+        let qtype = self.questions.first().map(|q| q.qtype.code());
+
+        let is_dnssec = |rr : &ResourceRecord| {
+            let t = rr.type_code();
+            [QuestionType::Rrsig, QuestionType::Nsec, QuestionType::Nsec3]
+                .iter().any(|d| d.code() == t)
+        };
+
+        self.answers.retain(|rr| ! is_dnssec(rr) || Some(rr.type_code()) == qtype);
+        self.authority.retain(|rr| ! is_dnssec(rr));
+        self.additional.retain(|rr| ! is_dnssec(rr));
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Max size of a UDP response to this query.
+    /// Without EDNS, this is 512 bytes, otherwise the size announced by the
+    /// client, but not more than we are willing to send.
+    pub fn max_udp_response_len(&self) -> usize {
+
+        match self.edns {
+            None => MAX_SAFE_UDP_PAYLOAD_LEN,
+            Some(ref edns) => {
+                let requested = edns.udp_payload_size as usize;
+                requested.clamp(MAX_SAFE_UDP_PAYLOAD_LEN, MAX_UDP_PAYLOAD_LEN)
+            }
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Attach an Extended DNS Error (RFC 8914), adding EDNS if required.
+    /// Several errors might be attached to one message.
+    pub fn add_extended_error(&mut self, code : ExtendedErrorCode, extra_text : &str) {
+
+        self.edns
+            .get_or_insert_with(|| Edns::new(MAX_UDP_PAYLOAD_LEN as u16))
+            .options.push(EdnsOption::ExtendedError(ExtendedError::new(code, extra_text)));
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Attach the DNS Cookie `cookie` (RFC 7873), replacing any other
+    /// and adding EDNS if required
+    pub fn set_cookie(&mut self, cookie : Cookie) {
+
+        self.edns
+            .get_or_insert_with(|| Edns::new(MAX_UDP_PAYLOAD_LEN as u16))
+            .set_option(EdnsOption::Cookie(cookie));
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Add EDNS padding (RFC 7830) such that the serialized message size
+    /// is a multiple of `block_size`. Any previous padding is replaced.
+    pub fn pad_to_block(&mut self, block_size : usize) {
+
+        if 0 == block_size {
+            return;
+        }
+
+        self.edns
+            .get_or_insert_with(|| Edns::new(MAX_UDP_PAYLOAD_LEN as u16))
+            .remove_option(OPTION_PADDING);
+
+        let unpadded_len = self.to_vec().len() + OPTION_HEADER_LEN;
+        let padding = (block_size - unpadded_len % block_size) % block_size;
+
+        if let Some(ref mut edns) = self.edns {
+            edns.options.push(EdnsOption::Padding(padding as u16));
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Apply the padding policy of RFC 8467 to this response:
+    /// Pad only if the query was padded.
+    pub fn pad_response(&mut self, query : &Message) {
+
+        let query_padded = query.edns.as_ref()
+            .and_then(|e| e.padding()).is_some();
+
+        if query_padded {
+            self.pad_to_block(RESPONSE_PADDING_BLOCK);
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn to_vec(&self) -> Vec<u8> {
+
+        let mut writer = WireWriter::new();
+        self.write(&mut writer);
+        writer.into_bytes()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Serialize for sending via UDP.
+    /// If the message exceeds `max_len`, all records are stripped
+    /// and the TC flag is set to make the client retry via TCP.
+    pub fn to_udp_bytes(&self, max_len : usize) -> Vec<u8> {
+
+        let bytes = self.to_vec();
+
+        if bytes.len() <= max_len {
+            return bytes;
+        }
+
+        self.truncated().to_vec()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Copy of this message without any records and the TC flag set.
+    /// The TSIG is dropped as well, since it does not cover the copy.
+    pub fn truncated(&self) -> Message {
+
+        let mut truncated = Message::new(self.id);
+        truncated.flags = self.flags | FLAG_TC;
+        truncated.questions = self.questions.clone();
+        truncated.edns = self.edns.clone();
+
+        truncated
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        let additional_count = self.additional.len()
+            + if self.edns.is_some() { 1 } else { 0 }
+            + if self.tsig.is_some() { 1 } else { 0 };
+
+        writer.write_u16(self.id);
+        writer.write_u16(self.flags);
+        writer.write_u16(self.questions.len() as u16);
+        writer.write_u16(self.answers.len() as u16);
+        writer.write_u16(self.authority.len() as u16);
+        writer.write_u16(additional_count as u16);
+
+        for question in &self.questions {
+            question.write(writer);
+        }
+
+        for rr in self.answers.iter().chain(&self.authority).chain(&self.additional) {
+            rr.write(writer);
+        }
+
+        if let Some(ref edns) = self.edns {
+            edns.to_record().write(writer);
+        }
+
+        if let Some(ref tsig) = self.tsig {
+            tsig.to_record().write(writer);
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read(reader : &mut WireReader) -> Result<Message, &'static str> {
+
+        let mut msg = Message::new(reader.read_u16()?);
+        msg.flags = reader.read_u16()?;
+
+        let qdcount = reader.read_u16()?;
+        let ancount = reader.read_u16()?;
+        let nscount = reader.read_u16()?;
+        let arcount = reader.read_u16()?;
+
+        for _ in 0 .. qdcount {
+            msg.questions.push(Question::read(reader)?);
+        }
+
+        msg.answers = read_records(reader, ancount)?;
+        msg.authority = read_records(reader, nscount)?;
+
+        let additional = read_records(reader, arcount)?;
+        let last = additional.len().saturating_sub(1);
+
+        for (i, rr) in additional.into_iter().enumerate() {
+
+            if QuestionType::Tsig.code() == rr.type_code() {
+
+                if i != last {
+                    return Err("TSIG record not last in message");
+                }
+
+                msg.tsig = Some(Tsig::from_record(&rr)?);
+                continue;
+
+            }
+
+            if QuestionType::Opt.code() != rr.type_code() {
+                msg.additional.push(rr);
+                continue;
+            }
+
+            if msg.edns.is_some() {
+                return Err("More than one OPT record");
+            }
+
+            msg.edns = Some(Edns::from_record(&rr)?);
+
+        }
+
+        Ok(msg)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl AsBytes for Message {
+
+    fn to_bytes(&self, target: &mut [u8]) -> Result<usize, &'static str> {
+
+        let bytes = self.to_vec();
+
+        if target.len() < bytes.len() {
+            return Err("Target buffer too small");
+        }
+
+        target[.. bytes.len()].copy_from_slice(&bytes);
+        Ok(bytes.len())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+
+        if bytes.len() < HEADER_LEN {
+            return Err("too few bytes");
+        }
+
+        Message::read(&mut WireReader::new(bytes))
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Message {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        writeln!(f, "ID {} FLAGS {:04x} RCODE {}", self.id, self.flags, self.rcode())?;
+
+        if let Some(ref edns) = self.edns {
+            writeln!(f, "{}", edns)?;
+        }
+
+        for q in &self.questions {
+            writeln!(f, "QUESTION {}", q)?;
+        }
+
+        for rr in &self.answers {
+            writeln!(f, "ANSWER {}", rr)?;
+        }
+
+        for rr in &self.authority {
+            writeln!(f, "AUTHORITY {}", rr)?;
+        }
+
+        for rr in &self.additional {
+            writeln!(f, "ADDITIONAL {}", rr)?;
+        }
+
+        if let Some(ref tsig) = self.tsig {
+            writeln!(f, "TSIG {}", tsig)?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+fn read_records(reader : &mut WireReader, count : u16)
+    -> Result<Vec<ResourceRecord>, &'static str> {
+
+    let mut records = Vec::with_capacity(count as usize);
+
+    for _ in 0 .. count {
+        records.push(ResourceRecord::read(reader)?);
+    }
+
+    Ok(records)
 
 }
 
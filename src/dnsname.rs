@@ -30,6 +30,7 @@ use ::std::str;
 use ::std::str::FromStr;
 use ::std::fmt;
 use ::std::cmp;
+use ::std::hash::{Hash, Hasher};
 use ::std::iter::IntoIterator;
 use dnstraits::{AsBytes, DnsEntity};
 use dnslabel::Label;
@@ -38,7 +39,7 @@ use dnslabel::Label;
  *                                             TYPE
  ******************************************************************************/
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Name {
     /* Last Label MUST always be empty ! */
     data : Vec<Label>,
@@ -51,8 +52,15 @@ impl FromStr for Name {
     type Err = &'static str;
 
     /// Parse a string into a DNS Name
+    /// A single trailing dot denoting the root is accepted.
     fn from_str(string : &str) -> Result<Self, &'static str> {
 
+        if "." == string {
+            return Ok(Name::root());
+        }
+
+        let string = string.strip_suffix('.').unwrap_or(string);
+
         let mut v = Vec::<Label>::new();
 
         for label_str in string.split(".") {
@@ -122,7 +130,7 @@ impl AsBytes for Name {
 
             let l = Label::from_bytes(&bytes[offset ..])?;
 
-            len = l.len() as usize;
+            len = l.len();
             v.push(l);
 
             offset = offset + len + 1;
@@ -158,6 +166,7 @@ impl cmp::PartialEq for Name {
 
     fn eq(&self, other: &Name) -> bool {
 
+        (self.data.len() == other.data.len()) &&
         self.data.iter().zip(other.data.iter()).all(
             |(a,b)| a.eq(b))
 
@@ -167,13 +176,31 @@ impl cmp::PartialEq for Name {
 
 /*----------------------------------------------------------------------------*/
 
+impl cmp::Eq for Name {}
+
+/*----------------------------------------------------------------------------*/
+
+impl Hash for Name {
+
+    fn hash<H: Hasher>(&self, state : &mut H) {
+
+        for label in &self.data {
+            label.hash(state);
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
 impl<'a> IntoIterator for &'a Name {
 
     type Item = &'a Label;
     type IntoIter = ::std::slice::Iter<'a, Label>;
 
     fn into_iter(self) -> Self::IntoIter {
-        (&self.data).into_iter()
+        self.data.iter()
     }
 
 }
@@ -182,6 +209,153 @@ impl<'a> IntoIterator for &'a Name {
 
 impl Name {
 
+    /// Create name from labels, ordered root first.
+    /// The terminal empty label is appended if missing.
+    pub fn from_labels(mut labels : Vec<Label>) -> Name {
+
+        let tail_empty = match labels.last() {
+            None => false,
+            Some(l) => l.is_empty(),
+        };
+
+        if ! tail_empty {
+            labels.push(Label::from_str("").unwrap());
+        }
+
+        Name { data : labels }
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    pub fn root() -> Name {
+
+        Name::from_labels(Vec::new())
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    pub fn is_root(&self) -> bool {
+
+        self.data.iter().all(|l| l.is_empty())
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// Parse a name as found in zone files: `@` denotes `origin`,
+    /// names not terminated by a dot are relative to `origin`.
+    pub fn from_str_relative(string : &str, origin : &Name)
+        -> Result<Name, &'static str> {
+
+        if "@" == string {
+            return Ok(origin.clone());
+        }
+
+        if string.ends_with('.') {
+            return Name::from_str(string);
+        }
+
+        Ok(Name::from_str(string)?.join(origin))
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// Append `suffix` to this name, e.g. `www` joined with `ubeer.org`
+    /// gives `www.ubeer.org`
+    pub fn join(&self, suffix : &Name) -> Name {
+
+        let mut labels = suffix.non_root_labels().to_vec();
+        labels.extend_from_slice(self.non_root_labels());
+        Name::from_labels(labels)
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// True if this name equals `other` or is below it
+    pub fn is_subdomain_of(&self, other : &Name) -> bool {
+
+        let own = self.non_root_labels();
+        let other = other.non_root_labels();
+
+        (other.len() <= own.len()) &&
+            own.iter().zip(other.iter()).all(|(a, b)| a == b)
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// Number of labels, not counting the root
+    pub fn label_count(&self) -> usize {
+
+        self.non_root_labels().len()
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// Labels without the terminal empty label, ordered root first
+    pub fn non_root_labels(&self) -> &[Label] {
+
+        let len = self.data.iter().take_while(|l| ! l.is_empty()).count();
+        &self.data[.. len]
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// Canonical form of this name, i.e. all ASCII letters in lower case
+    /// (RFC 4034, section 6.2)
+    pub fn to_lowercase(&self) -> Name {
+
+        Name { data : self.data.iter().map(|l| l.to_lowercase()).collect() }
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// Canonical DNS name order (RFC 4034, section 6.1): Names are sorted
+    /// by their labels, starting with the rightmost one
+    pub fn canonical_cmp(&self, other : &Name) -> cmp::Ordering {
+
+        let own = self.non_root_labels();
+        let other = other.non_root_labels();
+
+        own.iter().zip(other.iter())
+            .map(|(a, b)| a.canonical_cmp(b))
+            .find(|o| cmp::Ordering::Equal != *o)
+            .unwrap_or_else(|| own.len().cmp(&other.len()))
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// Whether the leftmost label is `*` (RFC 4592)
+    pub fn is_wildcard(&self) -> bool {
+
+        self.non_root_labels().last().map(|l| "*" == l.to_string()).unwrap_or(false)
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
+    /// Name without its leftmost label, None for the root
+    pub fn parent(&self) -> Option<Name> {
+
+        let labels = self.non_root_labels();
+
+        match labels.is_empty() {
+            true => None,
+            false => Some(Name::from_labels(labels[.. labels.len() - 1].to_vec())),
+        }
+
+    }
+
+     /*-----------------------------------------------------------------------*/
+
     pub fn to_slice(&self) -> &[Label] {
 
         &self.data
@@ -33,20 +33,46 @@ use ::std::str::FromStr;
 use dnstraits::AsBytes;
 use ::std::fmt;
 use dnsname::Name;
+use wire::{WireReader, WireWriter};
 
 /*----------------------------------------------------------------------------*/
 
-#[derive(Clone, Copy)]
+pub const CLASS_IN : u16 = 1;
+pub const CLASS_CH : u16 = 3;
+pub const CLASS_NONE : u16 = 254;
+pub const CLASS_ANY : u16 = 255;
+
+/*----------------------------------------------------------------------------*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u16)]
 pub enum QuestionType {
 
     A = 1,
     Ns = 2,
     Cname = 5,
+    Soa = 6,
     Ptr = 12,
     Hinfo = 13,
     Mx = 15,
+    Txt = 16,
+    Aaaa = 28,
+    Srv = 33,
+    Opt = 41,
+    Ds = 43,
+    Rrsig = 46,
+    Nsec = 47,
+    Dnskey = 48,
+    Nsec3 = 50,
+    Nsec3param = 51,
+    Cds = 59,
+    Cdnskey = 60,
+    Tsig = 250,
+    Ixfr = 251,
     Axfr = 252,
-    Any = 255
+    Any = 255,
+    /// Any type not listed, kept by its code (RFC 3597)
+    Other(u16)
 
 }
 
@@ -63,12 +89,30 @@ impl FromStr for QuestionType {
             "A" => QuestionType::A,
             "NS" => QuestionType::Ns,
             "CNAME" => QuestionType::Cname,
+            "SOA" => QuestionType::Soa,
             "PTR" => QuestionType::Ptr,
             "HINFO" => QuestionType::Hinfo,
             "MX" => QuestionType::Mx,
+            "TXT" => QuestionType::Txt,
+            "AAAA" => QuestionType::Aaaa,
+            "SRV" => QuestionType::Srv,
+            "OPT" => QuestionType::Opt,
+            "DS" => QuestionType::Ds,
+            "RRSIG" => QuestionType::Rrsig,
+            "NSEC" => QuestionType::Nsec,
+            "DNSKEY" => QuestionType::Dnskey,
+            "NSEC3" => QuestionType::Nsec3,
+            "NSEC3PARAM" => QuestionType::Nsec3param,
+            "CDS" => QuestionType::Cds,
+            "CDNSKEY" => QuestionType::Cdnskey,
+            "TSIG" => QuestionType::Tsig,
+            "IXFR" => QuestionType::Ixfr,
             "AXFR" => QuestionType::Axfr,
             "ANY" => QuestionType::Any,
-            _ => return Err("Unknown Question Type")
+            _ => return string.strip_prefix("TYPE")
+                .and_then(|code| u16::from_str(code).ok())
+                .map(QuestionType::from_u16)
+                .ok_or("Unknown Question Type")
         };
 
         Ok(qt)
@@ -91,7 +135,7 @@ impl AsBytes for QuestionType {
 
         }
 
-        let qt_u16 = *self as u16;
+        let qt_u16 = self.code();
         let qt_u16_be = u16::to_be_bytes(qt_u16);
 
         let len = qt_u16_be.len();
@@ -117,20 +161,7 @@ impl AsBytes for QuestionType {
 
         let qtype_u16= u16::from_be_bytes([bytes[0], bytes[1]]);
 
-        let qtype = match qtype_u16 {
-
-            1 => QuestionType::A,
-            2 => QuestionType::Ns,
-            5 => QuestionType::Cname,
-            12 => QuestionType::Ptr,
-            13 => QuestionType::Hinfo,
-            15 => QuestionType::Mx,
-            252 => QuestionType::Axfr,
-            255 => QuestionType::Any,
-            _ => return Err("Invalid question type")
-        };
-
-        Ok(qtype)
+        Ok(QuestionType::from_u16(qtype_u16))
 
     }
 
@@ -144,7 +175,7 @@ impl fmt::Display for QuestionType {
 
         // This one is going to be really expensive, isn't it???
 
-        write!(f, "{}", QuestionType::to_string(&self)).ok();
+        write!(f, "{}", QuestionType::to_string(self)).ok();
         Ok(())
     }
 
@@ -152,24 +183,77 @@ impl fmt::Display for QuestionType {
 
 /*----------------------------------------------------------------------------*/
 
-impl PartialEq for QuestionType {
-
-    fn eq(&self, other : &Self) -> bool {
+impl QuestionType {
 
-        let me = *self as u16;
-        let you = *other as u16;
+    pub fn from_u16(value : u16) -> QuestionType {
 
-        print!("Comparing {} with {}: {}", &me, &you, me == you);
+        match value {
 
-        me == you
+            1 => QuestionType::A,
+            2 => QuestionType::Ns,
+            5 => QuestionType::Cname,
+            6 => QuestionType::Soa,
+            12 => QuestionType::Ptr,
+            13 => QuestionType::Hinfo,
+            15 => QuestionType::Mx,
+            16 => QuestionType::Txt,
+            28 => QuestionType::Aaaa,
+            33 => QuestionType::Srv,
+            41 => QuestionType::Opt,
+            43 => QuestionType::Ds,
+            46 => QuestionType::Rrsig,
+            47 => QuestionType::Nsec,
+            48 => QuestionType::Dnskey,
+            50 => QuestionType::Nsec3,
+            51 => QuestionType::Nsec3param,
+            59 => QuestionType::Cds,
+            60 => QuestionType::Cdnskey,
+            250 => QuestionType::Tsig,
+            251 => QuestionType::Ixfr,
+            252 => QuestionType::Axfr,
+            255 => QuestionType::Any,
+            _ => QuestionType::Other(value)
+        }
 
     }
 
-}
+    /*------------------------------------------------------------------------*/
+
+    /// Type code as on the wire
+    pub fn code(&self) -> u16 {
+
+        match *self {
+
+            QuestionType::A => 1,
+            QuestionType::Ns => 2,
+            QuestionType::Cname => 5,
+            QuestionType::Soa => 6,
+            QuestionType::Ptr => 12,
+            QuestionType::Hinfo => 13,
+            QuestionType::Mx => 15,
+            QuestionType::Txt => 16,
+            QuestionType::Aaaa => 28,
+            QuestionType::Srv => 33,
+            QuestionType::Opt => 41,
+            QuestionType::Ds => 43,
+            QuestionType::Rrsig => 46,
+            QuestionType::Nsec => 47,
+            QuestionType::Dnskey => 48,
+            QuestionType::Nsec3 => 50,
+            QuestionType::Nsec3param => 51,
+            QuestionType::Cds => 59,
+            QuestionType::Cdnskey => 60,
+            QuestionType::Tsig => 250,
+            QuestionType::Ixfr => 251,
+            QuestionType::Axfr => 252,
+            QuestionType::Any => 255,
+            QuestionType::Other(code) => code
 
-/*----------------------------------------------------------------------------*/
+        }
 
-impl QuestionType {
+    }
+
+    /*------------------------------------------------------------------------*/
 
     fn to_string(qt: &QuestionType) -> String {
 
@@ -178,11 +262,27 @@ impl QuestionType {
             QuestionType::A => "A".to_string(),
             QuestionType::Ns => "NS".to_string(),
             QuestionType::Cname => "CNAME".to_string(),
+            QuestionType::Soa => "SOA".to_string(),
             QuestionType::Ptr => "PTR".to_string(),
             QuestionType::Hinfo => "HINFO".to_string(),
             QuestionType::Mx => "MX".to_string(),
+            QuestionType::Txt => "TXT".to_string(),
+            QuestionType::Aaaa => "AAAA".to_string(),
+            QuestionType::Srv => "SRV".to_string(),
+            QuestionType::Opt => "OPT".to_string(),
+            QuestionType::Ds => "DS".to_string(),
+            QuestionType::Rrsig => "RRSIG".to_string(),
+            QuestionType::Nsec => "NSEC".to_string(),
+            QuestionType::Dnskey => "DNSKEY".to_string(),
+            QuestionType::Nsec3 => "NSEC3".to_string(),
+            QuestionType::Nsec3param => "NSEC3PARAM".to_string(),
+            QuestionType::Cds => "CDS".to_string(),
+            QuestionType::Cdnskey => "CDNSKEY".to_string(),
+            QuestionType::Tsig => "TSIG".to_string(),
+            QuestionType::Ixfr => "IXFR".to_string(),
             QuestionType::Axfr => "AXFR".to_string(),
-            QuestionType::Any => "ANY".to_string()
+            QuestionType::Any => "ANY".to_string(),
+            QuestionType::Other(code) => format!("TYPE{}", code)
 
         }
 
@@ -192,11 +292,59 @@ impl QuestionType {
 
 /*----------------------------------------------------------------------------*/
 
+/// Entry of the question section of a DNS message
+#[derive(Clone, Debug, PartialEq)]
 pub struct Question {
 
-    name : Vec<Name>,
-    qtype : QuestionType,
-    qclass : u16
+    pub name : Name,
+    pub qtype : QuestionType,
+    pub qclass : u16
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Question {
+
+    pub fn new(name : Name, qtype : QuestionType) -> Question {
+
+        Question { name, qtype, qclass : CLASS_IN }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        writer.write_name(&self.name);
+        writer.write_u16(self.qtype.code());
+        writer.write_u16(self.qclass);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read(reader : &mut WireReader) -> Result<Question, &'static str> {
+
+        let name = reader.read_name()?;
+        let qtype = QuestionType::from_u16(reader.read_u16()?);
+        let qclass = reader.read_u16()?;
+
+        Ok(Question { name, qtype, qclass })
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Question {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {}", self.name, self.qtype)
+
+    }
 
 }
 
@@ -28,22 +28,77 @@
 //
 use dnstraits::{AsBytes};
 use ::std::str::FromStr;
-use ::std::net::Ipv4Addr;
+use ::std::net::{Ipv4Addr, Ipv6Addr};
 use dnsname::Name;
 use ::std::fmt;
 use ::std::cmp::PartialEq;
+use dnsquestion::{QuestionType, CLASS_ANY, CLASS_IN, CLASS_NONE};
+use dnssec::{Dnskey, Ds, Nsec, Nsec3, Nsec3Param, Rrsig};
+use wire::{WireReader, WireWriter};
 
 /******************************************************************************
  *                                             TYPE
  ******************************************************************************/
 
 /// Representation of a DNS Resource Record - Data portion
-#[derive(PartialEq)]
-#[derive(Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
 pub enum Record {
 
     A(Ipv4Addr),
-    PTR(Name)
+    NS(Name),
+    CNAME(Name),
+    SOA(Soa),
+    PTR(Name),
+    /// Preference and mail exchange
+    MX(u16, Name),
+    /// One or more character strings
+    TXT(Vec<Vec<u8>>),
+    AAAA(Ipv6Addr),
+    DS(Ds),
+    RRSIG(Rrsig),
+    NSEC(Nsec),
+    DNSKEY(Dnskey),
+    NSEC3(Nsec3),
+    NSEC3PARAM(Nsec3Param),
+    /// Child copy of a DS record (RFC 7344)
+    CDS(Ds),
+    /// Child copy of a DNSKEY record (RFC 7344)
+    CDNSKEY(Dnskey),
+    /// Record of a type not known to us - type code and raw RDATA
+    Unknown(u16, Vec<u8>),
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// RDATA of a Start Of Authority record
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
+pub struct Soa {
+
+    pub mname : Name,
+    pub rname : Name,
+    pub serial : u32,
+    pub refresh : u32,
+    pub retry : u32,
+    pub expire : u32,
+    /// TTL of negative answers (RFC 2308)
+    pub minimum : u32,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Full DNS Resource Record as found in the sections of a DNS message
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
+pub struct ResourceRecord {
+
+    pub name : Name,
+    pub class : u16,
+    pub ttl : u32,
+    pub record : Record,
 
 }
 
@@ -63,32 +118,13 @@ impl FromStr for Record {
 
     fn from_str(s : &str) -> Result<Record, &'static str> {
 
-        let s = s.to_string();
-        let mut splitter = s.splitn(2, " ");
-        let kind = splitter.next();
-        if kind.is_none() {
-            return Err("Malformed record: Missing whitespace?");
-        }
+        let fields = tokenize(s)?;
 
-        let kind = kind.unwrap();
-
-        let remainder = splitter.next();
-
-        if remainder.is_none() {
+        if fields.len() < 2 {
             return Err("Malformed record: Missing whitespace?");
         }
 
-        match kind {
-            "A" => match Ipv4Addr::from_str(remainder.unwrap()) {
-                Ok(addr) => Ok(Record::A(addr)),
-                Err(_) => Err("Could not parse IPv4 address")
-            },
-            "PTR" => match Name::from_str(remainder.unwrap()) {
-                Ok(name) => Ok(Record::PTR(name)),
-                Err(_) => Err("Could not parse DNS name")
-            }
-            &_ => Err("Unknown DNS type")
-        }
+        Record::from_fields(&fields[0], &fields[1 ..], &Name::root())
 
     }
 
@@ -144,8 +180,31 @@ impl fmt::Display for Record {
 
         let (t, d) =
             match self {
-                Record::A(ref addr) => ("A", addr.to_string()),
-                Record::PTR(ref name) => ("PTR", name.to_string()),
+                Record::A(ref addr) => ("A".to_string(), addr.to_string()),
+                Record::NS(ref name) => ("NS".to_string(), name.to_string()),
+                Record::CNAME(ref name) => ("CNAME".to_string(), name.to_string()),
+                Record::SOA(ref soa) => ("SOA".to_string(), format!(
+                    "{} {} {} {} {} {} {}",
+                    soa.mname, soa.rname, soa.serial, soa.refresh,
+                    soa.retry, soa.expire, soa.minimum)),
+                Record::PTR(ref name) => ("PTR".to_string(), name.to_string()),
+                Record::MX(preference, ref name) =>
+                    ("MX".to_string(), format!("{} {}", preference, name)),
+                Record::TXT(ref strings) => ("TXT".to_string(), strings.iter()
+                    .map(|s| quote(s))
+                    .collect::<Vec<String>>().join(" ")),
+                Record::AAAA(ref addr) => ("AAAA".to_string(), addr.to_string()),
+                Record::DS(ref ds) => ("DS".to_string(), ds.to_string()),
+                Record::RRSIG(ref rrsig) => ("RRSIG".to_string(), rrsig.to_string()),
+                Record::NSEC(ref nsec) => ("NSEC".to_string(), nsec.to_string()),
+                Record::DNSKEY(ref key) => ("DNSKEY".to_string(), key.to_string()),
+                Record::NSEC3(ref nsec3) => ("NSEC3".to_string(), nsec3.to_string()),
+                Record::NSEC3PARAM(ref param) => ("NSEC3PARAM".to_string(), param.to_string()),
+                Record::CDS(ref ds) => ("CDS".to_string(), ds.to_string()),
+                Record::CDNSKEY(ref key) => ("CDNSKEY".to_string(), key.to_string()),
+                Record::Unknown(code, ref data) => (
+                    format!("TYPE{}", code),
+                    format!("\\# {} {}", data.len(), bytes_to_hex(data))),
             };
         write!(f, "{} {}", t, d).ok();
 
@@ -157,6 +216,571 @@ impl fmt::Display for Record {
 
 /*----------------------------------------------------------------------------*/
 
+impl Record {
+
+    /// Parse record from its type `kind` and RDATA fields as found
+    /// in zone files. Names not terminated by a dot are relative to `origin`.
+    pub fn from_fields(kind : &str, fields : &[String], origin : &Name)
+        -> Result<Record, &'static str> {
+
+        let name = |s : &String| Name::from_str_relative(s, origin)
+            .map_err(|_| "Could not parse DNS name");
+
+        let number = |s : &String| u32::from_str(s)
+            .map_err(|_| "Could not parse number");
+
+        let expected_len = match kind {
+            "A" | "NS" | "CNAME" | "PTR" | "AAAA" => 1,
+            "MX" => 2,
+            "SOA" => 7,
+            _ => fields.len(),
+        };
+
+        if expected_len != fields.len() || fields.is_empty() {
+            return Err("Malformed record: Wrong number of fields");
+        }
+
+        let record = match kind {
+            "A" => match Ipv4Addr::from_str(&fields[0]) {
+                Ok(addr) => Record::A(addr),
+                Err(_) => return Err("Could not parse IPv4 address")
+            },
+            "NS" => Record::NS(name(&fields[0])?),
+            "CNAME" => Record::CNAME(name(&fields[0])?),
+            "SOA" => Record::SOA(Soa {
+                mname : name(&fields[0])?,
+                rname : name(&fields[1])?,
+                serial : number(&fields[2])?,
+                refresh : number(&fields[3])?,
+                retry : number(&fields[4])?,
+                expire : number(&fields[5])?,
+                minimum : number(&fields[6])?,
+            }),
+            "PTR" => Record::PTR(name(&fields[0])?),
+            "MX" => match u16::from_str(&fields[0]) {
+                Ok(preference) => Record::MX(preference, name(&fields[1])?),
+                Err(_) => return Err("Could not parse MX preference")
+            },
+            "TXT" => {
+                let strings = fields.iter()
+                    .map(|s| unescape(s))
+                    .collect::<Result<Vec<Vec<u8>>, &'static str>>()?;
+                if strings.iter().any(|s| 255 < s.len()) {
+                    return Err("TXT string longer than 255 octets");
+                }
+                Record::TXT(strings)
+            },
+            "AAAA" => match Ipv6Addr::from_str(&fields[0]) {
+                Ok(addr) => Record::AAAA(addr),
+                Err(_) => return Err("Could not parse IPv6 address")
+            },
+            "DS" => Record::DS(Ds::from_fields(fields)?),
+            "RRSIG" => Record::RRSIG(Rrsig::from_fields(fields, origin)?),
+            "NSEC" => Record::NSEC(Nsec::from_fields(fields, origin)?),
+            "DNSKEY" => Record::DNSKEY(Dnskey::from_fields(fields)?),
+            "NSEC3" => Record::NSEC3(Nsec3::from_fields(fields)?),
+            "NSEC3PARAM" => Record::NSEC3PARAM(Nsec3Param::from_fields(fields)?),
+            "CDS" => Record::CDS(Ds::from_fields(fields)?),
+            "CDNSKEY" => Record::CDNSKEY(Dnskey::from_fields(fields)?),
+            _ => return Record::unknown_from_fields(kind, fields),
+        };
+
+        Ok(record)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn type_code(&self) -> u16 {
+
+        match self {
+            Record::A(_) => QuestionType::A.code(),
+            Record::NS(_) => QuestionType::Ns.code(),
+            Record::CNAME(_) => QuestionType::Cname.code(),
+            Record::SOA(_) => QuestionType::Soa.code(),
+            Record::PTR(_) => QuestionType::Ptr.code(),
+            Record::MX(_, _) => QuestionType::Mx.code(),
+            Record::TXT(_) => QuestionType::Txt.code(),
+            Record::AAAA(_) => QuestionType::Aaaa.code(),
+            Record::DS(_) => QuestionType::Ds.code(),
+            Record::RRSIG(_) => QuestionType::Rrsig.code(),
+            Record::NSEC(_) => QuestionType::Nsec.code(),
+            Record::DNSKEY(_) => QuestionType::Dnskey.code(),
+            Record::NSEC3(_) => QuestionType::Nsec3.code(),
+            Record::NSEC3PARAM(_) => QuestionType::Nsec3param.code(),
+            Record::CDS(_) => QuestionType::Cds.code(),
+            Record::CDNSKEY(_) => QuestionType::Cdnskey.code(),
+            Record::Unknown(code, _) => *code,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Write RDATA including the preceding RDLENGTH field
+    pub fn write_rdata(&self, writer : &mut WireWriter) {
+
+        let len_offset = writer.len();
+        writer.write_u16(0);
+
+        match self {
+            Record::A(ref addr) => writer.write_bytes(&addr.octets()),
+            Record::NS(ref name) => writer.write_name(name),
+            Record::CNAME(ref name) => writer.write_name(name),
+            Record::SOA(ref soa) => {
+                writer.write_name(&soa.mname);
+                writer.write_name(&soa.rname);
+                writer.write_u32(soa.serial);
+                writer.write_u32(soa.refresh);
+                writer.write_u32(soa.retry);
+                writer.write_u32(soa.expire);
+                writer.write_u32(soa.minimum);
+            },
+            Record::PTR(ref name) => writer.write_name(name),
+            Record::MX(preference, ref name) => {
+                writer.write_u16(*preference);
+                writer.write_name(name);
+            },
+            Record::TXT(ref strings) => for string in strings {
+                writer.write_u8(string.len() as u8);
+                writer.write_bytes(string);
+            },
+            Record::AAAA(ref addr) => writer.write_bytes(&addr.octets()),
+            Record::DS(ref ds) => ds.write(writer),
+            Record::RRSIG(ref rrsig) => rrsig.write(writer),
+            Record::NSEC(ref nsec) => nsec.write(writer),
+            Record::DNSKEY(ref key) => key.write(writer),
+            Record::NSEC3(ref nsec3) => nsec3.write(writer),
+            Record::NSEC3PARAM(ref param) => param.write(writer),
+            Record::CDS(ref ds) => ds.write(writer),
+            Record::CDNSKEY(ref key) => key.write(writer),
+            Record::Unknown(_, ref data) => writer.write_bytes(data),
+        };
+
+        let rdlength = writer.len() - len_offset - 2;
+        writer.patch_u16(len_offset, rdlength as u16);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// RDATA in canonical form (RFC 4034, 6.2), without RDLENGTH:
+    /// Names are not compressed, and those of the types listed there
+    /// are in lower case.
+    pub fn canonical_rdata(&self) -> Vec<u8> {
+
+        let canonical = match self {
+            Record::NS(ref name) => Record::NS(name.to_lowercase()),
+            Record::CNAME(ref name) => Record::CNAME(name.to_lowercase()),
+            Record::SOA(ref soa) => Record::SOA(Soa {
+                mname : soa.mname.to_lowercase(),
+                rname : soa.rname.to_lowercase(),
+                ..soa.clone()
+            }),
+            Record::PTR(ref name) => Record::PTR(name.to_lowercase()),
+            Record::MX(preference, ref name) => Record::MX(*preference, name.to_lowercase()),
+            Record::RRSIG(ref rrsig) => Record::RRSIG(Rrsig {
+                signer : rrsig.signer.to_lowercase(),
+                ..rrsig.clone()
+            }),
+            _ => self.clone(),
+        };
+
+        let mut writer = WireWriter::uncompressed();
+        canonical.write_rdata(&mut writer);
+
+        writer.as_slice()[2 ..].to_vec()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read RDATA of length `rdlength` for record type `type_code`.
+    pub fn read_rdata(type_code : u16, rdlength : usize, reader : &mut WireReader)
+        -> Result<Record, &'static str> {
+
+        let end = reader.offset() + rdlength;
+
+        if reader.remaining() < rdlength {
+            return Err("RDATA exceeds message");
+        }
+
+        let record = match QuestionType::from_u16(type_code) {
+            QuestionType::A =>
+                Record::A(Ipv4Addr::from_bytes(reader.read_bytes(rdlength)?)?),
+            QuestionType::Ns => Record::NS(reader.read_name()?),
+            QuestionType::Cname => Record::CNAME(reader.read_name()?),
+            QuestionType::Soa => Record::SOA(Soa {
+                mname : reader.read_name()?,
+                rname : reader.read_name()?,
+                serial : reader.read_u32()?,
+                refresh : reader.read_u32()?,
+                retry : reader.read_u32()?,
+                expire : reader.read_u32()?,
+                minimum : reader.read_u32()?,
+            }),
+            QuestionType::Ptr => Record::PTR(reader.read_name()?),
+            QuestionType::Mx =>
+                Record::MX(reader.read_u16()?, reader.read_name()?),
+            QuestionType::Txt => {
+                let mut strings = Vec::new();
+                while reader.offset() < end {
+                    let len = reader.read_u8()? as usize;
+                    strings.push(reader.read_bytes(len)?.to_vec());
+                }
+                Record::TXT(strings)
+            },
+            QuestionType::Aaaa => {
+                if 16 != rdlength {
+                    return Err("Require exactly 16 octets");
+                }
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(reader.read_bytes(16)?);
+                Record::AAAA(Ipv6Addr::from(octets))
+            },
+            QuestionType::Ds => Record::DS(Ds::read(reader, end)?),
+            QuestionType::Rrsig => Record::RRSIG(Rrsig::read(reader, end)?),
+            QuestionType::Nsec => Record::NSEC(Nsec::read(reader, end)?),
+            QuestionType::Dnskey => Record::DNSKEY(Dnskey::read(reader, end)?),
+            QuestionType::Nsec3 => Record::NSEC3(Nsec3::read(reader, end)?),
+            QuestionType::Nsec3param => Record::NSEC3PARAM(Nsec3Param::read(reader)?),
+            QuestionType::Cds => Record::CDS(Ds::read(reader, end)?),
+            QuestionType::Cdnskey => Record::CDNSKEY(Dnskey::read(reader, end)?),
+            _ => Record::Unknown(type_code, reader.read_bytes(rdlength)?.to_vec()),
+        };
+
+        if reader.offset() != end {
+            return Err("RDATA length mismatch");
+        }
+
+        Ok(record)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Parse the generic representation `TYPEn \# len hex` (RFC 3597)
+    fn unknown_from_fields(kind : &str, fields : &[String])
+        -> Result<Record, &'static str> {
+
+        let code = kind.strip_prefix("TYPE")
+            .and_then(|c| u16::from_str(c).ok())
+            .ok_or("Unknown DNS type")?;
+
+        if fields.len() < 2 || "\\#" != fields[0] {
+            return Err("Malformed record: Expected \\# for unknown type");
+        }
+
+        let len = usize::from_str(&fields[1]).map_err(|_| "Could not parse RDATA length")?;
+        let data = hex_to_bytes(&fields[2 ..].concat())?;
+
+        if len != data.len() {
+            return Err("RDATA length mismatch");
+        }
+
+        Ok(Record::Unknown(code, data))
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Soa {
+
+    /// Compare serials using serial number arithmetic (RFC 1982)
+    pub fn is_newer_than(&self, other : &Soa) -> bool {
+
+        let difference = self.serial.wrapping_sub(other.serial);
+        0 != difference && difference < (1 << 31)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ResourceRecord {
+
+    pub fn new(name : Name, ttl : u32, record : Record) -> ResourceRecord {
+
+        ResourceRecord { name, class : CLASS_IN, ttl, record }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn type_code(&self) -> u16 {
+        self.record.type_code()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        writer.write_name(&self.name);
+        writer.write_u16(self.record.type_code());
+        writer.write_u16(self.class);
+        writer.write_u32(self.ttl);
+        self.record.write_rdata(writer);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read(reader : &mut WireReader) -> Result<ResourceRecord, &'static str> {
+
+        let name = reader.read_name()?;
+        let type_code = reader.read_u16()?;
+        let class = reader.read_u16()?;
+        let ttl = reader.read_u32()?;
+        let rdlength = reader.read_u16()? as usize;
+
+        // Dynamic updates (RFC 2136) refer to RRsets by records lacking RDATA
+        let record = match (rdlength, class) {
+            (0, CLASS_ANY) | (0, CLASS_NONE) => Record::Unknown(type_code, Vec::new()),
+            _ => Record::read_rdata(type_code, rdlength, reader)?,
+        };
+
+        Ok(ResourceRecord { name, class, ttl, record })
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for ResourceRecord {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {} {}", self.name, self.ttl, self.record)
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+pub fn bytes_to_hex(bytes : &[u8]) -> String {
+
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join("")
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+pub fn hex_to_bytes(hex : &str) -> Result<Vec<u8>, &'static str> {
+
+    if ! hex.len().is_multiple_of(2) || ! hex.is_ascii() {
+        return Err("Invalid hex string");
+    }
+
+    (0 .. hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i .. i + 2], 16)
+             .map_err(|_| "Invalid hex string"))
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+const BASE64_ALPHABET : &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Base64 encoding with padding (RFC 4648, section 4)
+pub fn bytes_to_base64(bytes : &[u8]) -> String {
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0 .. 4 {
+            match i <= chunk.len() {
+                true => encoded.push(BASE64_ALPHABET[(group >> (18 - 6 * i)) as usize & 0x3f] as char),
+                false => encoded.push('='),
+            }
+        }
+
+    }
+
+    encoded
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Decode base64 with padding, whitespace is ignored
+pub fn base64_to_bytes(base64 : &str) -> Result<Vec<u8>, &'static str> {
+
+    let digits : Vec<u8> = base64.bytes().filter(|b| ! b.is_ascii_whitespace()).collect();
+
+    if ! digits.len().is_multiple_of(4) {
+        return Err("Invalid base64 string");
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() / 4 * 3);
+
+    for (n, chunk) in digits.chunks(4).enumerate() {
+
+        let padding = chunk.iter().rev().take_while(|&&b| b'=' == b).count();
+        let last_chunk = n + 1 == digits.len() / 4;
+
+        if 2 < padding || (0 < padding && ! last_chunk) {
+            return Err("Invalid base64 string");
+        }
+
+        let mut group = 0u32;
+
+        for &digit in &chunk[.. 4 - padding] {
+            let value = BASE64_ALPHABET.iter().position(|&b| b == digit)
+                .ok_or("Invalid base64 string")?;
+            group = group << 6 | value as u32;
+        }
+
+        group <<= 6 * padding as u32;
+
+        bytes.extend_from_slice(&group.to_be_bytes()[1 .. 4 - padding]);
+
+    }
+
+    Ok(bytes)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Split a line of a zone file into fields.
+/// Quoted strings form one field, `(` and `)` are returned as
+/// fields of their own. Anything following `;` is a comment.
+pub fn tokenize(line : &str) -> Result<Vec<String>, &'static str> {
+
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+
+        while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            chars.next();
+        }
+
+        match chars.peek() {
+            None | Some(';') => break,
+            Some('(') | Some(')') => {
+                fields.push(chars.next().unwrap().to_string());
+                continue;
+            },
+            _ => {},
+        };
+
+        let mut field = String::new();
+
+        if Some(&'"') == chars.peek() {
+
+            chars.next();
+
+            loop {
+                match chars.next() {
+                    None => return Err("Unterminated string"),
+                    Some('"') => break,
+                    // Escapes are resolved by `unescape`
+                    Some('\\') => {
+                        field.push('\\');
+                        field.push(chars.next().ok_or("Unterminated string")?);
+                    },
+                    Some(c) => field.push(c),
+                };
+            }
+
+        } else {
+
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || ";()\"".contains(c) {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+
+        }
+
+        fields.push(field);
+
+    }
+
+    Ok(fields)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Resolve escapes `\X` and `\DDD` of a character string
+fn unescape(field : &str) -> Result<Vec<u8>, &'static str> {
+
+    let mut bytes = Vec::new();
+    let mut chars = field.chars();
+
+    while let Some(c) = chars.next() {
+
+        if '\\' != c {
+            let mut buffer = [0u8; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+
+        let escaped = chars.next().ok_or("Incomplete escape sequence")?;
+
+        if ! escaped.is_ascii_digit() {
+            let mut buffer = [0u8; 4];
+            bytes.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+
+        let digits : String = ::std::iter::once(escaped)
+            .chain(chars.by_ref().take(2))
+            .collect();
+
+        match u8::from_str(&digits) {
+            Ok(b) if 3 == digits.len() => bytes.push(b),
+            _ => return Err("Invalid escape sequence"),
+        };
+
+    }
+
+    Ok(bytes)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Quote a character string for presentation
+fn quote(bytes : &[u8]) -> String {
+
+    let mut quoted = String::from("\"");
+
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => {
+                quoted.push('\\');
+                quoted.push(b as char);
+            },
+            0x20 ..= 0x7e => quoted.push(b as char),
+            _ => quoted.push_str(&format!("\\{:03}", b)),
+        };
+    }
+
+    quoted.push('"');
+    quoted
+
+}
+
+/*----------------------------------------------------------------------------*/
+
 // impl AsBytes for Record {
 //
 //     fn to_bytes(&self, mut target: &[u8]) -> Result<(), &'static str> {
@@ -0,0 +1,1023 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::cmp::Ordering;
+use ::std::fmt;
+use ::std::str::FromStr;
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{base64_to_bytes, bytes_to_base64, bytes_to_hex, hex_to_bytes, ResourceRecord};
+use ring::digest;
+use wire::{WireReader, WireWriter};
+
+/*----------------------------------------------------------------------------*/
+
+/// DNSKEY flags (RFC 4034, 2.1.1)
+pub const DNSKEY_FLAG_ZONE : u16 = 0x0100;
+pub const DNSKEY_FLAG_SEP : u16 = 0x0001;
+
+pub const DNSKEY_PROTOCOL : u8 = 3;
+
+/// NSEC3 flags (RFC 5155, 3.1.2)
+pub const NSEC3_FLAG_OPT_OUT : u8 = 0x01;
+
+/// Security algorithm numbers (RFC 8624)
+pub const ALGORITHM_RSASHA1 : u8 = 5;
+pub const ALGORITHM_RSASHA1_NSEC3_SHA1 : u8 = 7;
+pub const ALGORITHM_RSASHA256 : u8 = 8;
+pub const ALGORITHM_RSASHA512 : u8 = 10;
+pub const ALGORITHM_ECDSAP256SHA256 : u8 = 13;
+pub const ALGORITHM_ECDSAP384SHA384 : u8 = 14;
+pub const ALGORITHM_ED25519 : u8 = 15;
+
+/// DS digest types
+pub const DIGEST_SHA1 : u8 = 1;
+pub const DIGEST_SHA256 : u8 = 2;
+pub const DIGEST_SHA384 : u8 = 4;
+
+/// The only NSEC3 hash algorithm defined (RFC 5155, 11)
+pub const NSEC3_HASH_SHA1 : u8 = 1;
+
+const BASE32HEX_ALPHABET : &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+/*----------------------------------------------------------------------------*/
+
+/// RDATA of a DNSKEY record (RFC 4034, 2)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Dnskey {
+
+    pub flags : u16,
+    pub protocol : u8,
+    pub algorithm : u8,
+    pub public_key : Vec<u8>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// RDATA of an RRSIG record (RFC 4034, 3)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rrsig {
+
+    pub type_covered : u16,
+    pub algorithm : u8,
+    /// Number of labels of the owner name, not counting a leading wildcard
+    pub labels : u8,
+    pub original_ttl : u32,
+    /// Seconds since the epoch, in serial number arithmetic
+    pub expiration : u32,
+    pub inception : u32,
+    pub key_tag : u16,
+    pub signer : Name,
+    pub signature : Vec<u8>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// RDATA of a DS record (RFC 4034, 5)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Ds {
+
+    pub key_tag : u16,
+    pub algorithm : u8,
+    pub digest_type : u8,
+    pub digest : Vec<u8>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// RDATA of an NSEC record (RFC 4034, 4)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Nsec {
+
+    pub next : Name,
+    /// Types present at the owner name, ascending
+    pub types : Vec<u16>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// RDATA of an NSEC3 record (RFC 5155, 3)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Nsec3 {
+
+    pub hash_algorithm : u8,
+    pub flags : u8,
+    pub iterations : u16,
+    pub salt : Vec<u8>,
+    pub next_hashed : Vec<u8>,
+    /// Types present at the original owner name, ascending
+    pub types : Vec<u16>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// RDATA of an NSEC3PARAM record (RFC 5155, 4)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Nsec3Param {
+
+    pub hash_algorithm : u8,
+    pub flags : u8,
+    pub iterations : u16,
+    pub salt : Vec<u8>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Dnskey {
+
+    /// Parse `FLAGS PROTOCOL ALGORITHM KEY`, the key in base64,
+    /// possibly split into several fields
+    pub fn from_fields(fields : &[String]) -> Result<Dnskey, &'static str> {
+
+        if fields.len() < 4 {
+            return Err("Malformed record: Wrong number of fields");
+        }
+
+        Ok(Dnskey {
+            flags : number(&fields[0])?,
+            protocol : number(&fields[1])?,
+            algorithm : number(&fields[2])?,
+            public_key : base64_to_bytes(&fields[3 ..].concat())?,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        writer.write_u16(self.flags);
+        writer.write_u8(self.protocol);
+        writer.write_u8(self.algorithm);
+        writer.write_bytes(&self.public_key);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read RDATA ending at offset `end`
+    pub fn read(reader : &mut WireReader, end : usize) -> Result<Dnskey, &'static str> {
+
+        let flags = reader.read_u16()?;
+        let protocol = reader.read_u8()?;
+        let algorithm = reader.read_u8()?;
+        let public_key = read_rest(reader, end)?;
+
+        Ok(Dnskey { flags, protocol, algorithm, public_key })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Key tag identifying this key in RRSIG and DS records
+    /// (RFC 4034, appendix B)
+    pub fn key_tag(&self) -> u16 {
+
+        let mut writer = WireWriter::new();
+        self.write(&mut writer);
+
+        let mut sum = writer.as_slice().iter().enumerate()
+            .map(|(i, b)| if 0 == i % 2 { (*b as u32) << 8 } else { *b as u32 })
+            .sum::<u32>();
+
+        sum += (sum >> 16) & 0xffff;
+
+        (sum & 0xffff) as u16
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether the key is meant to sign the DNSKEY RRset only
+    pub fn is_sep(&self) -> bool {
+        0 != self.flags & DNSKEY_FLAG_SEP
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Dnskey {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {} {} {}", self.flags, self.protocol, self.algorithm,
+               bytes_to_base64(&self.public_key))
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Rrsig {
+
+    /// Parse `TYPE ALGORITHM LABELS TTL EXPIRATION INCEPTION TAG SIGNER
+    /// SIGNATURE`, the signature in base64, possibly split into several
+    /// fields. Signer names not terminated by a dot are relative to `origin`.
+    pub fn from_fields(fields : &[String], origin : &Name) -> Result<Rrsig, &'static str> {
+
+        if fields.len() < 9 {
+            return Err("Malformed record: Wrong number of fields");
+        }
+
+        Ok(Rrsig {
+            type_covered : type_from_str(&fields[0])?,
+            algorithm : number(&fields[1])?,
+            labels : number(&fields[2])?,
+            original_ttl : number(&fields[3])?,
+            expiration : parse_time(&fields[4])?,
+            inception : parse_time(&fields[5])?,
+            key_tag : number(&fields[6])?,
+            signer : Name::from_str_relative(&fields[7], origin)
+                .map_err(|_| "Could not parse DNS name")?,
+            signature : base64_to_bytes(&fields[8 ..].concat())?,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Write RDATA. The signer name is never compressed (RFC 4034, 3.1.7).
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        self.write_without_signature(writer);
+        writer.write_bytes(&self.signature);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Write RDATA up to, but not including the signature, as covered by
+    /// the signature itself (RFC 4034, 3.1.8.1)
+    pub fn write_without_signature(&self, writer : &mut WireWriter) {
+
+        writer.write_u16(self.type_covered);
+        writer.write_u8(self.algorithm);
+        writer.write_u8(self.labels);
+        writer.write_u32(self.original_ttl);
+        writer.write_u32(self.expiration);
+        writer.write_u32(self.inception);
+        writer.write_u16(self.key_tag);
+        writer.write_name_uncompressed(&self.signer);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Data the signature is calculated over (RFC 4034, 3.1.8.1):
+    /// This RDATA without signature, followed by the records of `rrset`
+    /// in canonical form and order.
+    /// Owners of wildcard expansions are replaced by the wildcard.
+    pub fn signed_data(&self, rrset : &[ResourceRecord]) -> Vec<u8> {
+
+        let mut writer = WireWriter::uncompressed();
+
+        Rrsig {
+            signer : self.signer.to_lowercase(),
+            signature : Vec::new(),
+            ..self.clone()
+        }.write_without_signature(&mut writer);
+
+        let (owner, class) = match rrset.first() {
+            None => return writer.into_bytes(),
+            Some(rr) => (rr.name.to_lowercase(), rr.class),
+        };
+
+        let labels = owner.non_root_labels();
+
+        let owner = match (self.labels as usize) < labels.len() {
+            false => owner.clone(),
+            true => Name::from_str("*").unwrap()
+                .join(&Name::from_labels(labels[.. self.labels as usize].to_vec())),
+        };
+
+        let mut rdatas : Vec<Vec<u8>> = rrset.iter()
+            .map(|rr| rr.record.canonical_rdata())
+            .collect();
+
+        rdatas.sort();
+        rdatas.dedup();
+
+        for rdata in rdatas {
+            writer.write_name(&owner);
+            writer.write_u16(self.type_covered);
+            writer.write_u16(class);
+            writer.write_u32(self.original_ttl);
+            writer.write_u16(rdata.len() as u16);
+            writer.write_bytes(&rdata);
+        }
+
+        writer.into_bytes()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read RDATA ending at offset `end`
+    pub fn read(reader : &mut WireReader, end : usize) -> Result<Rrsig, &'static str> {
+
+        Ok(Rrsig {
+            type_covered : reader.read_u16()?,
+            algorithm : reader.read_u8()?,
+            labels : reader.read_u8()?,
+            original_ttl : reader.read_u32()?,
+            expiration : reader.read_u32()?,
+            inception : reader.read_u32()?,
+            key_tag : reader.read_u16()?,
+            signer : reader.read_name()?,
+            signature : read_rest(reader, end)?,
+        })
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Rrsig {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {} {} {} {} {} {} {} {}",
+               type_to_string(self.type_covered), self.algorithm, self.labels,
+               self.original_ttl, format_time(self.expiration),
+               format_time(self.inception), self.key_tag, self.signer,
+               bytes_to_base64(&self.signature))
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Ds {
+
+    /// Parse `TAG ALGORITHM DIGEST_TYPE DIGEST`, the digest in hex,
+    /// possibly split into several fields
+    pub fn from_fields(fields : &[String]) -> Result<Ds, &'static str> {
+
+        if fields.len() < 4 {
+            return Err("Malformed record: Wrong number of fields");
+        }
+
+        Ok(Ds {
+            key_tag : number(&fields[0])?,
+            algorithm : number(&fields[1])?,
+            digest_type : number(&fields[2])?,
+            digest : hex_to_bytes(&fields[3 ..].concat())?,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        writer.write_u16(self.key_tag);
+        writer.write_u8(self.algorithm);
+        writer.write_u8(self.digest_type);
+        writer.write_bytes(&self.digest);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read RDATA ending at offset `end`
+    pub fn read(reader : &mut WireReader, end : usize) -> Result<Ds, &'static str> {
+
+        Ok(Ds {
+            key_tag : reader.read_u16()?,
+            algorithm : reader.read_u8()?,
+            digest_type : reader.read_u8()?,
+            digest : read_rest(reader, end)?,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// DS record referring to `key` of the zone with apex `owner`
+    /// (RFC 4034, 5.1.4)
+    pub fn from_dnskey(owner : &Name, key : &Dnskey, digest_type : u8)
+        -> Result<Ds, &'static str> {
+
+        let mut writer = WireWriter::uncompressed();
+        writer.write_name(&owner.to_lowercase());
+        key.write(&mut writer);
+
+        Ok(Ds {
+            key_tag : key.key_tag(),
+            algorithm : key.algorithm,
+            digest_type,
+            digest : ds_digest(digest_type, writer.as_slice())?,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether this DS refers to `key` of the zone with apex `owner`
+    pub fn matches(&self, owner : &Name, key : &Dnskey) -> bool {
+
+        self.key_tag == key.key_tag() && self.algorithm == key.algorithm &&
+            Ds::from_dnskey(owner, key, self.digest_type)
+                .map(|ds| ds.digest == self.digest)
+                .unwrap_or(false)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Ds {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {} {} {}", self.key_tag, self.algorithm, self.digest_type,
+               bytes_to_hex(&self.digest).to_uppercase())
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Nsec {
+
+    /// Parse `NEXT TYPE...`. Names not terminated by a dot are relative
+    /// to `origin`.
+    pub fn from_fields(fields : &[String], origin : &Name) -> Result<Nsec, &'static str> {
+
+        if fields.is_empty() {
+            return Err("Malformed record: Wrong number of fields");
+        }
+
+        Ok(Nsec {
+            next : Name::from_str_relative(&fields[0], origin)
+                .map_err(|_| "Could not parse DNS name")?,
+            types : types_from_fields(&fields[1 ..])?,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Write RDATA. The next name is never compressed (RFC 4034, 4.1.1).
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        writer.write_name_uncompressed(&self.next);
+        write_type_bitmap(writer, &self.types);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read RDATA ending at offset `end`
+    pub fn read(reader : &mut WireReader, end : usize) -> Result<Nsec, &'static str> {
+
+        let next = reader.read_name()?;
+        let types = read_type_bitmap(reader, end)?;
+
+        Ok(Nsec { next, types })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether this NSEC record owned by `owner` proves that `name`
+    /// does not exist, i.e. `name` lies between `owner` and the next name
+    pub fn covers(&self, owner : &Name, name : &Name) -> bool {
+
+        let after_owner = Ordering::Greater == name.canonical_cmp(owner);
+        let before_next = Ordering::Less == name.canonical_cmp(&self.next);
+
+        match owner.canonical_cmp(&self.next) {
+            Ordering::Less => after_owner && before_next,
+            // Last NSEC of the zone, pointing back to the apex
+            _ => after_owner,
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Nsec {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{}", self.next)?;
+
+        for t in &self.types {
+            write!(f, " {}", type_to_string(*t))?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Nsec3 {
+
+    /// Parse `ALGORITHM FLAGS ITERATIONS SALT NEXT TYPE...`, the salt in hex
+    /// or `-` if empty, the next hashed owner name in base32hex
+    pub fn from_fields(fields : &[String]) -> Result<Nsec3, &'static str> {
+
+        if fields.len() < 5 {
+            return Err("Malformed record: Wrong number of fields");
+        }
+
+        Ok(Nsec3 {
+            hash_algorithm : number(&fields[0])?,
+            flags : number(&fields[1])?,
+            iterations : number(&fields[2])?,
+            salt : salt_from_str(&fields[3])?,
+            next_hashed : base32hex_to_bytes(&fields[4])?,
+            types : types_from_fields(&fields[5 ..])?,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        writer.write_u8(self.hash_algorithm);
+        writer.write_u8(self.flags);
+        writer.write_u16(self.iterations);
+        writer.write_u8(self.salt.len() as u8);
+        writer.write_bytes(&self.salt);
+        writer.write_u8(self.next_hashed.len() as u8);
+        writer.write_bytes(&self.next_hashed);
+        write_type_bitmap(writer, &self.types);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read RDATA ending at offset `end`
+    pub fn read(reader : &mut WireReader, end : usize) -> Result<Nsec3, &'static str> {
+
+        let hash_algorithm = reader.read_u8()?;
+        let flags = reader.read_u8()?;
+        let iterations = reader.read_u16()?;
+        let salt_len = reader.read_u8()? as usize;
+        let salt = reader.read_bytes(salt_len)?.to_vec();
+        let hash_len = reader.read_u8()? as usize;
+        let next_hashed = reader.read_bytes(hash_len)?.to_vec();
+        let types = read_type_bitmap(reader, end)?;
+
+        Ok(Nsec3 { hash_algorithm, flags, iterations, salt, next_hashed, types })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn opt_out(&self) -> bool {
+        0 != self.flags & NSEC3_FLAG_OPT_OUT
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether this NSEC3 record, whose owner is the hash `owner_hash`,
+    /// covers the hash `hash`, i.e. `hash` lies between both hashes
+    pub fn covers(&self, owner_hash : &[u8], hash : &[u8]) -> bool {
+
+        match owner_hash < &self.next_hashed[..] {
+            true => owner_hash < hash && hash < &self.next_hashed[..],
+            // Last NSEC3 of the chain
+            false => owner_hash < hash || hash < &self.next_hashed[..],
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Hash of `name` as NSEC3 owner with the parameters of this record
+    pub fn hash(&self, name : &Name) -> Vec<u8> {
+        nsec3_hash(name, &self.salt, self.iterations)
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Nsec3 {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {} {} {} {}", self.hash_algorithm, self.flags, self.iterations,
+               salt_to_string(&self.salt), bytes_to_base32hex(&self.next_hashed))?;
+
+        for t in &self.types {
+            write!(f, " {}", type_to_string(*t))?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Nsec3Param {
+
+    /// Parse `ALGORITHM FLAGS ITERATIONS SALT`, the salt in hex or `-`
+    pub fn from_fields(fields : &[String]) -> Result<Nsec3Param, &'static str> {
+
+        if 4 != fields.len() {
+            return Err("Malformed record: Wrong number of fields");
+        }
+
+        Ok(Nsec3Param {
+            hash_algorithm : number(&fields[0])?,
+            flags : number(&fields[1])?,
+            iterations : number(&fields[2])?,
+            salt : salt_from_str(&fields[3])?,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        writer.write_u8(self.hash_algorithm);
+        writer.write_u8(self.flags);
+        writer.write_u16(self.iterations);
+        writer.write_u8(self.salt.len() as u8);
+        writer.write_bytes(&self.salt);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read(reader : &mut WireReader) -> Result<Nsec3Param, &'static str> {
+
+        let hash_algorithm = reader.read_u8()?;
+        let flags = reader.read_u8()?;
+        let iterations = reader.read_u16()?;
+        let salt_len = reader.read_u8()? as usize;
+        let salt = reader.read_bytes(salt_len)?.to_vec();
+
+        Ok(Nsec3Param { hash_algorithm, flags, iterations, salt })
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Nsec3Param {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {} {} {}", self.hash_algorithm, self.flags, self.iterations,
+               salt_to_string(&self.salt))
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Iterated, salted SHA-1 hash of `name` (RFC 5155, 5)
+pub fn nsec3_hash(name : &Name, salt : &[u8], iterations : u16) -> Vec<u8> {
+
+    let mut writer = WireWriter::uncompressed();
+    writer.write_name(&name.to_lowercase());
+    writer.write_bytes(salt);
+
+    let mut hash = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, writer.as_slice());
+
+    for _ in 0 .. iterations {
+        let mut data = hash.as_ref().to_vec();
+        data.extend_from_slice(salt);
+        hash = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, &data);
+    }
+
+    hash.as_ref().to_vec()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Mnemonic of the record type `code`, `TYPEn` for unknown ones (RFC 3597)
+pub fn type_to_string(code : u16) -> String {
+
+    QuestionType::from_u16(code).to_string()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Parse a record type given as mnemonic or as `TYPEn`
+pub fn type_from_str(s : &str) -> Result<u16, &'static str> {
+
+    QuestionType::from_str(&s.to_uppercase())
+        .map(|t| t.code())
+        .map_err(|_| "Unknown DNS type")
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Format seconds since the epoch as `YYYYMMDDHHmmSS` (RFC 4034, 3.2)
+pub fn format_time(time : u32) -> String {
+
+    let days = (time / 86400) as i64;
+    let seconds = time % 86400;
+
+    // Civil date from days since the epoch, see
+    // http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}{:02}{:02}{:02}{:02}{:02}", year, month, day,
+            seconds / 3600, seconds / 60 % 60, seconds % 60)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Parse a time given as `YYYYMMDDHHmmSS` or as seconds since the epoch.
+/// Times beyond 2106 wrap around, as expected by serial number arithmetic.
+pub fn parse_time(s : &str) -> Result<u32, &'static str> {
+
+    if ! s.chars().all(|c| c.is_ascii_digit()) {
+        return Err("Could not parse time");
+    }
+
+    if 14 != s.len() {
+        return u32::from_str(s).map_err(|_| "Could not parse time");
+    }
+
+    let field = |range : ::std::ops::Range<usize>| i64::from_str(&s[range]).unwrap();
+
+    let (year, month, day) = (field(0 .. 4), field(4 .. 6), field(6 .. 8));
+    let (hour, minute, second) = (field(8 .. 10), field(10 .. 12), field(12 .. 14));
+
+    if ! (1 ..= 12).contains(&month) || ! (1 ..= 31).contains(&day) ||
+        23 < hour || 59 < minute || 59 < second {
+        return Err("Could not parse time");
+    }
+
+    // Days since the epoch from civil date, inverse of `format_time`
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = if 2 < month { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    Ok((days * 86400 + hour * 3600 + minute * 60 + second) as u32)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Base32 encoding with extended hex alphabet, without padding (RFC 4648, 7)
+pub fn bytes_to_base32hex(bytes : &[u8]) -> String {
+
+    let mut encoded = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for &b in bytes {
+
+        buffer = buffer << 8 | b as u32;
+        bits += 8;
+
+        while 5 <= bits {
+            bits -= 5;
+            encoded.push(BASE32HEX_ALPHABET[(buffer >> bits) as usize & 0x1f] as char);
+        }
+
+    }
+
+    if 0 < bits {
+        encoded.push(BASE32HEX_ALPHABET[(buffer << (5 - bits)) as usize & 0x1f] as char);
+    }
+
+    encoded
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Decode base32hex, ignoring case and padding
+pub fn base32hex_to_bytes(s : &str) -> Result<Vec<u8>, &'static str> {
+
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in s.trim_end_matches('=').bytes() {
+
+        let value = BASE32HEX_ALPHABET.iter()
+            .position(|&d| d == c.to_ascii_lowercase())
+            .ok_or("Invalid base32hex string")?;
+
+        buffer = buffer << 5 | value as u32;
+        bits += 5;
+
+        if 8 <= bits {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+
+    }
+
+    Ok(bytes)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Write the types present at a name as type bitmap (RFC 4034, 4.1.2)
+pub fn write_type_bitmap(writer : &mut WireWriter, types : &[u16]) {
+
+    let mut types = types.to_vec();
+    types.sort_unstable();
+    types.dedup();
+
+    let mut i = 0;
+
+    while i < types.len() {
+
+        let window = types[i] >> 8;
+        let mut bitmap = [0u8; 32];
+        let mut len = 0;
+
+        while i < types.len() && window == types[i] >> 8 {
+            let low = (types[i] & 0xff) as usize;
+            bitmap[low / 8] |= 0x80 >> (low % 8);
+            len = low / 8 + 1;
+            i += 1;
+        }
+
+        writer.write_u8(window as u8);
+        writer.write_u8(len as u8);
+        writer.write_bytes(&bitmap[.. len]);
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Read a type bitmap ending at offset `end`
+pub fn read_type_bitmap(reader : &mut WireReader, end : usize)
+    -> Result<Vec<u16>, &'static str> {
+
+    let mut types = Vec::new();
+    let mut last_window = None;
+
+    while reader.offset() < end {
+
+        let window = reader.read_u8()? as u16;
+        let len = reader.read_u8()? as usize;
+
+        if last_window.map(|w| window <= w).unwrap_or(false) || 0 == len || 32 < len {
+            return Err("Malformed type bitmap");
+        }
+
+        last_window = Some(window);
+
+        for (octet, bits) in reader.read_bytes(len)?.iter().enumerate() {
+            for bit in 0 .. 8 {
+                if 0 != bits & (0x80 >> bit) {
+                    types.push(window << 8 | (octet * 8 + bit) as u16);
+                }
+            }
+        }
+
+    }
+
+    Ok(types)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn types_from_fields(fields : &[String]) -> Result<Vec<u16>, &'static str> {
+
+    let mut types = fields.iter()
+        .map(|f| type_from_str(f))
+        .collect::<Result<Vec<u16>, &'static str>>()?;
+
+    types.sort_unstable();
+    types.dedup();
+
+    Ok(types)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn salt_from_str(s : &str) -> Result<Vec<u8>, &'static str> {
+
+    match s {
+        "-" => Ok(Vec::new()),
+        _ => hex_to_bytes(s),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn salt_to_string(salt : &[u8]) -> String {
+
+    match salt.is_empty() {
+        true => "-".to_string(),
+        false => bytes_to_hex(salt).to_uppercase(),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn ds_digest(digest_type : u8, data : &[u8]) -> Result<Vec<u8>, &'static str> {
+
+    let algorithm = match digest_type {
+        DIGEST_SHA1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+        DIGEST_SHA256 => &digest::SHA256,
+        DIGEST_SHA384 => &digest::SHA384,
+        _ => return Err("Unsupported digest type"),
+    };
+
+    Ok(digest::digest(algorithm, data).as_ref().to_vec())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn number<T : FromStr>(s : &str) -> Result<T, &'static str> {
+
+    T::from_str(s).map_err(|_| "Could not parse number")
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn read_rest(reader : &mut WireReader, end : usize) -> Result<Vec<u8>, &'static str> {
+
+    let len = end.checked_sub(reader.offset()).ok_or("RDATA length mismatch")?;
+    Ok(reader.read_bytes(len)?.to_vec())
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,663 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::fmt;
+use ::std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{Record, ResourceRecord, bytes_to_hex};
+use network::{Network, mask_address, max_prefix_len};
+use wire::{WireReader, WireWriter};
+
+/*----------------------------------------------------------------------------*/
+
+pub const EDNS_VERSION : u8 = 0;
+
+const DO_FLAG : u16 = 0x8000;
+
+const OPTION_NSID : u16 = 3;
+const OPTION_CLIENT_SUBNET : u16 = 8;
+const OPTION_COOKIE : u16 = 10;
+pub const OPTION_PADDING : u16 = 12;
+const OPTION_EXTENDED_ERROR : u16 = 15;
+
+pub const CLIENT_COOKIE_LEN : usize = 8;
+const MIN_SERVER_COOKIE_LEN : usize = 8;
+const MAX_SERVER_COOKIE_LEN : usize = 32;
+
+const FAMILY_IPV4 : u16 = 1;
+const FAMILY_IPV6 : u16 = 2;
+
+/*----------------------------------------------------------------------------*/
+
+/// Option carried in the RDATA of an OPT pseudo-record
+#[derive(Debug, Clone, PartialEq)]
+pub enum EdnsOption {
+
+    /// Name Server Identifier, RFC 5001
+    Nsid(Vec<u8>),
+    /// EDNS Client Subnet, RFC 7871
+    ClientSubnet(ClientSubnet),
+    /// DNS Cookie, RFC 7873
+    Cookie(Cookie),
+    /// Padding, RFC 7830 - number of padding octets
+    Padding(u16),
+    /// Extended DNS Error, RFC 8914
+    ExtendedError(ExtendedError),
+    /// Any option we do not know - option code and raw data
+    Unknown(u16, Vec<u8>),
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// EDNS Client Subnet option (RFC 7871).
+/// The address is kept masked to the source prefix length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientSubnet {
+
+    pub source_prefix_len : u8,
+    pub scope_prefix_len : u8,
+    pub address : IpAddr,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// DNS Cookie option (RFC 7873).
+/// The server cookie is empty if the client does not know one yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+
+    pub client : [u8; CLIENT_COOKIE_LEN],
+    pub server : Vec<u8>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// INFO-CODEs of Extended DNS Errors (RFC 8914, section 4)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtendedErrorCode {
+
+    Other = 0,
+    UnsupportedDnskeyAlgorithm = 1,
+    UnsupportedDsDigestType = 2,
+    StaleAnswer = 3,
+    ForgedAnswer = 4,
+    DnssecIndeterminate = 5,
+    DnssecBogus = 6,
+    SignatureExpired = 7,
+    SignatureNotYetValid = 8,
+    DnskeyMissing = 9,
+    RrsigsMissing = 10,
+    NoZoneKeyBitSet = 11,
+    NsecMissing = 12,
+    CachedError = 13,
+    NotReady = 14,
+    Blocked = 15,
+    Censored = 16,
+    Filtered = 17,
+    Prohibited = 18,
+    StaleNxdomainAnswer = 19,
+    NotAuthoritative = 20,
+    NotSupported = 21,
+    NoReachableAuthority = 22,
+    NetworkError = 23,
+    InvalidData = 24,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Extended DNS Error option (RFC 8914).
+/// INFO-CODE is kept as number since new codes might be registered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtendedError {
+
+    pub info_code : u16,
+    pub extra_text : String,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Content of an OPT pseudo-record (EDNS(0), RFC 6891).
+/// The OPT record is not kept in the additional section of a message,
+/// but extracted into an `Edns`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edns {
+
+    /// Max size of UDP payload the sender is able to receive
+    pub udp_payload_size : u16,
+    /// Upper 8 bits of the 12 bit RCODE
+    pub extended_rcode : u8,
+    pub version : u8,
+    /// DNSSEC OK
+    pub dnssec_ok : bool,
+    pub options : Vec<EdnsOption>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl EdnsOption {
+
+    pub fn code(&self) -> u16 {
+
+        match self {
+            EdnsOption::Nsid(_) => OPTION_NSID,
+            EdnsOption::ClientSubnet(_) => OPTION_CLIENT_SUBNET,
+            EdnsOption::Cookie(_) => OPTION_COOKIE,
+            EdnsOption::Padding(_) => OPTION_PADDING,
+            EdnsOption::ExtendedError(_) => OPTION_EXTENDED_ERROR,
+            EdnsOption::Unknown(code, _) => *code,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write(&self, writer : &mut WireWriter) {
+
+        writer.write_u16(self.code());
+
+        let len_offset = writer.len();
+        writer.write_u16(0);
+
+        match self {
+            EdnsOption::Nsid(ref data) => writer.write_bytes(data),
+            EdnsOption::ClientSubnet(ref subnet) => subnet.write(writer),
+            EdnsOption::Cookie(ref cookie) => {
+                writer.write_bytes(&cookie.client);
+                writer.write_bytes(&cookie.server);
+            },
+            EdnsOption::Padding(len) => writer.write_bytes(&vec![0u8; *len as usize]),
+            EdnsOption::ExtendedError(ref error) => {
+                writer.write_u16(error.info_code);
+                writer.write_bytes(error.extra_text.as_bytes());
+            },
+            EdnsOption::Unknown(_, ref data) => writer.write_bytes(data),
+        };
+
+        let len = writer.len() - len_offset - 2;
+        writer.patch_u16(len_offset, len as u16);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read(reader : &mut WireReader) -> Result<EdnsOption, &'static str> {
+
+        let code = reader.read_u16()?;
+        let len = reader.read_u16()? as usize;
+        let data = reader.read_bytes(len)?;
+
+        let option = match code {
+            OPTION_NSID => EdnsOption::Nsid(data.to_vec()),
+            OPTION_CLIENT_SUBNET =>
+                EdnsOption::ClientSubnet(ClientSubnet::read(data)?),
+            OPTION_COOKIE => EdnsOption::Cookie(Cookie::read(data)?),
+            // Content of padding is to be ignored
+            OPTION_PADDING => EdnsOption::Padding(len as u16),
+            OPTION_EXTENDED_ERROR =>
+                EdnsOption::ExtendedError(ExtendedError::read(data)?),
+            _ => EdnsOption::Unknown(code, data.to_vec()),
+        };
+
+        Ok(option)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for EdnsOption {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        match self {
+            EdnsOption::Nsid(ref data) => write!(f, "NSID {}", bytes_to_hex(data)),
+            EdnsOption::ClientSubnet(ref subnet) => write!(f, "ECS {}", subnet),
+            EdnsOption::Cookie(ref cookie) => write!(f, "COOKIE {}{}",
+                bytes_to_hex(&cookie.client), bytes_to_hex(&cookie.server)),
+            EdnsOption::Padding(len) => write!(f, "PADDING {}", len),
+            EdnsOption::ExtendedError(ref error) => write!(f, "EDE {}", error),
+            EdnsOption::Unknown(code, ref data) =>
+                write!(f, "OPT{} {}", code, bytes_to_hex(data)),
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ClientSubnet {
+
+    pub fn new(address : IpAddr, source_prefix_len : u8)
+        -> Result<ClientSubnet, &'static str> {
+
+        let network = Network::new(address, source_prefix_len)?;
+
+        Ok(ClientSubnet {
+            source_prefix_len,
+            scope_prefix_len : 0,
+            address : network.address(),
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// The network of the client as announced by source prefix length
+    pub fn network(&self) -> Network {
+
+        Network::new(self.address, self.source_prefix_len)
+            .expect("ClientSubnet with invalid prefix length")
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Option to echo back to the client within the response
+    pub fn response(&self, scope_prefix_len : u8) -> ClientSubnet {
+
+        let mut subnet = self.clone();
+        subnet.scope_prefix_len = scope_prefix_len;
+        subnet
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn write(&self, writer : &mut WireWriter) {
+
+        let (family, octets) = match self.address {
+            IpAddr::V4(a) => (FAMILY_IPV4, a.octets().to_vec()),
+            IpAddr::V6(a) => (FAMILY_IPV6, a.octets().to_vec()),
+        };
+
+        let len = (self.source_prefix_len as usize).div_ceil(8);
+
+        writer.write_u16(family);
+        writer.write_u8(self.source_prefix_len);
+        writer.write_u8(self.scope_prefix_len);
+        writer.write_bytes(&octets[.. len]);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn read(data : &[u8]) -> Result<ClientSubnet, &'static str> {
+
+        let mut reader = WireReader::new(data);
+
+        let family = reader.read_u16()?;
+        let source_prefix_len = reader.read_u8()?;
+        let scope_prefix_len = reader.read_u8()?;
+
+        let mut octets = [0u8; 16];
+
+        let address = match family {
+            FAMILY_IPV4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            FAMILY_IPV6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            _ => return Err("Unknown address family in client subnet"),
+        };
+
+        let max_len = max_prefix_len(&address);
+
+        if source_prefix_len > max_len || scope_prefix_len > max_len {
+            return Err("Client subnet prefix too long");
+        }
+
+        let len = reader.remaining();
+
+        if len != (source_prefix_len as usize).div_ceil(8) {
+            return Err("Client subnet address length mismatch");
+        }
+
+        octets[.. len].copy_from_slice(reader.read_bytes(len)?);
+
+        let address = match address {
+            IpAddr::V4(_) => IpAddr::V4(
+                Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3])),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(octets)),
+        };
+
+        if address != mask_address(&address, source_prefix_len) {
+            return Err("Client subnet address bits beyond prefix set");
+        }
+
+        Ok(ClientSubnet { source_prefix_len, scope_prefix_len, address })
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for ClientSubnet {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{}/{}/{}",
+               self.address, self.source_prefix_len, self.scope_prefix_len)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Cookie {
+
+    fn read(data : &[u8]) -> Result<Cookie, &'static str> {
+
+        let server_len = data.len().saturating_sub(CLIENT_COOKIE_LEN);
+
+        if data.len() < CLIENT_COOKIE_LEN ||
+            (0 < server_len && server_len < MIN_SERVER_COOKIE_LEN) ||
+            server_len > MAX_SERVER_COOKIE_LEN {
+            return Err("Invalid cookie length");
+        }
+
+        let mut client = [0u8; CLIENT_COOKIE_LEN];
+        client.copy_from_slice(&data[.. CLIENT_COOKIE_LEN]);
+
+        Ok(Cookie {
+            client,
+            server : data[CLIENT_COOKIE_LEN ..].to_vec(),
+        })
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ExtendedErrorCode {
+
+    pub fn from_u16(value : u16) -> Option<ExtendedErrorCode> {
+
+        use self::ExtendedErrorCode::*;
+
+        let codes = [
+            Other, UnsupportedDnskeyAlgorithm, UnsupportedDsDigestType,
+            StaleAnswer, ForgedAnswer, DnssecIndeterminate, DnssecBogus,
+            SignatureExpired, SignatureNotYetValid, DnskeyMissing,
+            RrsigsMissing, NoZoneKeyBitSet, NsecMissing, CachedError,
+            NotReady, Blocked, Censored, Filtered, Prohibited,
+            StaleNxdomainAnswer, NotAuthoritative, NotSupported,
+            NoReachableAuthority, NetworkError, InvalidData];
+
+        codes.get(value as usize).copied()
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ExtendedError {
+
+    pub fn new(code : ExtendedErrorCode, extra_text : &str) -> ExtendedError {
+
+        ExtendedError {
+            info_code : code as u16,
+            extra_text : extra_text.to_string(),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn code(&self) -> Option<ExtendedErrorCode> {
+        ExtendedErrorCode::from_u16(self.info_code)
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn read(data : &[u8]) -> Result<ExtendedError, &'static str> {
+
+        if data.len() < 2 {
+            return Err("Extended DNS error too short");
+        }
+
+        // EXTRA-TEXT should be UTF-8, but is informational only
+        let text = String::from_utf8_lossy(&data[2 ..]);
+
+        Ok(ExtendedError {
+            info_code : u16::from_be_bytes([data[0], data[1]]),
+            extra_text : text.trim_end_matches('\0').to_string(),
+        })
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for ExtendedError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{}", self.info_code)?;
+
+        if ! self.extra_text.is_empty() {
+            write!(f, " \"{}\"", self.extra_text)?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Edns {
+
+    pub fn new(udp_payload_size : u16) -> Edns {
+
+        Edns {
+            udp_payload_size,
+            extended_rcode : 0,
+            version : EDNS_VERSION,
+            dnssec_ok : false,
+            options : Vec::new(),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Convert into OPT pseudo-record to be put into the additional section
+    pub fn to_record(&self) -> ResourceRecord {
+
+        let mut writer = WireWriter::new();
+
+        for option in &self.options {
+            option.write(&mut writer);
+        }
+
+        let mut flags = 0;
+        if self.dnssec_ok {
+            flags |= DO_FLAG;
+        }
+
+        let ttl = ((self.extended_rcode as u32) << 24)
+            | ((self.version as u32) << 16)
+            | flags as u32;
+
+        ResourceRecord {
+            name : Name::root(),
+            class : self.udp_payload_size,
+            ttl,
+            record : Record::Unknown(QuestionType::Opt.code(), writer.into_bytes()),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Interpret OPT pseudo-record.
+    pub fn from_record(rr : &ResourceRecord) -> Result<Edns, &'static str> {
+
+        if QuestionType::Opt.code() != rr.type_code() {
+            return Err("Not an OPT record");
+        }
+
+        if ! rr.name.is_root() {
+            return Err("OPT record requires root name");
+        }
+
+        let data = match rr.record {
+            Record::Unknown(_, ref data) => data,
+            _ => return Err("Not an OPT record"),
+        };
+
+        let mut options = Vec::new();
+        let mut reader = WireReader::new(data);
+
+        while 0 < reader.remaining() {
+            options.push(EdnsOption::read(&mut reader)?);
+        }
+
+        Ok(Edns {
+            udp_payload_size : rr.class,
+            extended_rcode : (rr.ttl >> 24) as u8,
+            version : (rr.ttl >> 16) as u8,
+            dnssec_ok : 0 != (rr.ttl as u16) & DO_FLAG,
+            options,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Get first option with code `code`
+    pub fn option(&self, code : u16) -> Option<&EdnsOption> {
+
+        self.options.iter().find(|o| code == o.code())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn client_subnet(&self) -> Option<&ClientSubnet> {
+
+        match self.option(OPTION_CLIENT_SUBNET) {
+            Some(EdnsOption::ClientSubnet(ref subnet)) => Some(subnet),
+            _ => None,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn cookie(&self) -> Option<&Cookie> {
+
+        match self.option(OPTION_COOKIE) {
+            Some(EdnsOption::Cookie(ref cookie)) => Some(cookie),
+            _ => None,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn padding(&self) -> Option<u16> {
+
+        match self.option(OPTION_PADDING) {
+            Some(EdnsOption::Padding(len)) => Some(*len),
+            _ => None,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn extended_errors(&self) -> Vec<&ExtendedError> {
+
+        self.options.iter().filter_map(|o| match o {
+            EdnsOption::ExtendedError(ref e) => Some(e),
+            _ => None,
+        }).collect()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn remove_option(&mut self, code : u16) {
+
+        self.options.retain(|o| code != o.code());
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Replace any option with the same code by `option`
+    pub fn set_option(&mut self, option : EdnsOption) {
+
+        self.remove_option(option.code());
+        self.options.push(option);
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Edns {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "EDNS version {} udp {}", self.version, self.udp_payload_size)?;
+
+        if self.dnssec_ok {
+            write!(f, " DO")?;
+        }
+
+        for option in &self.options {
+            write!(f, "; {}", option)?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
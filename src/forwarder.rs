@@ -0,0 +1,490 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ::std::collections::{HashMap, HashSet};
+use ::std::net::SocketAddr;
+use ::std::sync::{mpsc, Arc, Mutex};
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+use authoritative::format_error;
+use cache::Cache;
+use client::Client;
+use dnsmessage::{Message as DnsMessage, Opcode, Rcode, FLAG_AD, FLAG_CD, FLAG_RA};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use edns::ExtendedErrorCode;
+use udp::{ContinueState, Handler, Message, Responder, Transport};
+use wire::WireReader;
+
+/*----------------------------------------------------------------------------*/
+
+/// Time to wait for an upstream to answer before asking the next one
+pub const DEFAULT_FORWARD_TIMEOUT_MS : u64 = 2000;
+
+/// Time to wait for upstream before answering with stale records
+/// (RFC 8767, 5)
+pub const DEFAULT_STALE_ANSWER_TIMEOUT_MS : u64 = 1800;
+
+/// Failures in a row after which an upstream is considered down
+const MAX_FAILURES : u32 = 3;
+
+/// Time an upstream considered down is asked only if all others fail
+const HOLD_DOWN_SECS : u64 = 30;
+
+/*----------------------------------------------------------------------------*/
+
+/// Forwards queries to upstream resolvers and relays their answers,
+/// caching them. Queries for names within particular domains might be
+/// sent to other upstreams than the rest.
+///
+/// Upstreams are asked in the order given. Those failing repeatedly are
+/// put last for a while, giving the others a chance first.
+///
+/// If the cache keeps expired records, they are served while upstream
+/// cannot be reached or takes too long to answer (RFC 8767). Records
+/// used often are refreshed in the background shortly before they
+/// expire.
+pub struct ForwardingHandler {
+
+    upstreams : Vec<SocketAddr>,
+    /// Domains forwarded to upstreams of their own
+    rules : Vec<(Name, Vec<SocketAddr>)>,
+    timeout : Duration,
+    stale_answer_timeout : Duration,
+    relay : Arc<Relay>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// State shared with the threads refreshing the cache
+struct Relay {
+
+    cache : Arc<Cache>,
+    health : Mutex<HashMap<SocketAddr, Health>>,
+    /// Questions currently fetched into the cache in the background
+    refreshing : Mutex<HashSet<(Name, QuestionType)>>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[derive(Clone, Copy, Default)]
+struct Health {
+
+    /// Failures in a row
+    failures : u32,
+    down_until : Option<Instant>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ForwardingHandler {
+
+    pub fn new(cache : Arc<Cache>) -> ForwardingHandler {
+
+        ForwardingHandler {
+            upstreams : Vec::new(),
+            rules : Vec::new(),
+            timeout : Duration::from_millis(DEFAULT_FORWARD_TIMEOUT_MS),
+            stale_answer_timeout : Duration::from_millis(DEFAULT_STALE_ANSWER_TIMEOUT_MS),
+            relay : Arc::new(Relay {
+                cache,
+                health : Mutex::new(HashMap::new()),
+                refreshing : Mutex::new(HashSet::new()),
+            }),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Forward queries not covered by any rule to `upstream`
+    pub fn add_upstream(&mut self, upstream : SocketAddr) {
+
+        self.upstreams.push(upstream);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Forward queries for names within `domain` to `upstreams`.
+    /// The rule of the most specific domain applies.
+    pub fn add_rule(&mut self, domain : Name, upstreams : Vec<SocketAddr>) {
+
+        self.rules.retain(|(d, _)| *d != domain);
+        self.rules.push((domain, upstreams));
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn set_timeout(&mut self, timeout : Duration) {
+
+        self.timeout = timeout;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Answer with stale records if upstream did not answer within
+    /// `timeout`. The answer still refreshes the cache once it arrives.
+    pub fn set_stale_answer_timeout(&mut self, timeout : Duration) {
+
+        self.stale_answer_timeout = timeout;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn cache(&self) -> &Arc<Cache> {
+        &self.relay.cache
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Upstreams to forward queries for `name` to
+    pub fn upstreams_for(&self, name : &Name) -> &[SocketAddr] {
+
+        self.rules.iter()
+            .filter(|(domain, _)| name.is_subdomain_of(domain))
+            .max_by_key(|(domain, _)| domain.label_count())
+            .map(|(_, upstreams)| upstreams.as_slice())
+            .unwrap_or(&self.upstreams)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether `upstream` is asked in the order given rather than last
+    pub fn is_healthy(&self, upstream : &SocketAddr) -> bool {
+
+        self.relay.is_healthy(upstream)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Create the response to `query`, from the cache or from upstream.
+    /// Returns None if the message must not be answered at all.
+    pub fn answer(&self, query : &DnsMessage) -> Option<DnsMessage> {
+
+        if query.is_response() {
+            return None;
+        }
+
+        let mut response = DnsMessage::response_to(query);
+        response.set_flag(FLAG_RA, true);
+
+        if let Some(ref edns) = query.edns {
+            if 0 != edns.version {
+                response.set_rcode(Rcode::BadVers);
+                return Some(response);
+            }
+        }
+
+        if Opcode::Query as u16 != query.opcode() {
+            response.set_rcode(Rcode::NotImp);
+            return Some(response);
+        }
+
+        if 1 != query.questions.len() {
+            response.set_rcode(Rcode::FormErr);
+            return Some(response);
+        }
+
+        let question = &query.questions[0];
+
+        match question.qtype {
+            QuestionType::Axfr | QuestionType::Ixfr | QuestionType::Opt => {
+                response.set_rcode(Rcode::NotImp);
+                return Some(response);
+            },
+            _ => {},
+        };
+
+        // Cached answers lack the signatures
+        let cached = match query.dnssec_ok() {
+            true => None,
+            false => self.relay.cache.find(&question.name, question.qtype, true),
+        };
+
+        let answer = match cached {
+            Some(found) if ! found.stale => {
+                if found.prefetch {
+                    self.refresh(query);
+                }
+                Some((found.response, false))
+            },
+            stale => self.forward(query, stale.map(|found| found.response)),
+        };
+
+        let answer = match answer {
+            Some((answer, stale)) => {
+                // EDNS only if the client supports it
+                if stale && response.edns.is_some() {
+                    response.add_extended_error(ExtendedErrorCode::StaleAnswer, "");
+                }
+                answer
+            },
+            None => {
+                response.set_rcode(Rcode::ServFail);
+                return Some(response);
+            },
+        };
+
+        if Rcode::NxDomain as u16 == answer.rcode() {
+            response.set_rcode(Rcode::NxDomain);
+        }
+
+        if query.dnssec_ok() || query.flag(FLAG_AD) {
+            response.set_flag(FLAG_AD, answer.flag(FLAG_AD));
+        }
+
+        response.answers = answer.answers;
+        response.authority = answer.authority;
+        response.additional = answer.additional;
+
+        Some(response)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Answer to `query` from upstream, and whether it is stale.
+    /// If `stale` records are at hand, they are returned instead if
+    /// upstream fails, takes too long or is known to be down.
+    fn forward(&self, query : &DnsMessage, stale : Option<DnsMessage>)
+        -> Option<(DnsMessage, bool)> {
+
+        let upstreams = self.upstreams_for(&query.questions[0].name);
+
+        let stale = match stale {
+            None => {
+                return self.relay.forward(query, upstreams, self.timeout)
+                    .map(|answer| (answer, false));
+            },
+            Some(stale) => stale,
+        };
+
+        // Do not wait for upstreams failing anyway (RFC 8767, 5)
+        if ! upstreams.iter().any(|u| self.relay.is_healthy(u)) {
+            return Some((stale, true));
+        }
+
+        // Some other query is waiting for upstream already
+        let receiver = match self.refresh(query) {
+            Some(receiver) => receiver,
+            None => return Some((stale, true)),
+        };
+
+        match receiver.recv_timeout(self.stale_answer_timeout) {
+            Ok(Some(answer)) => Some((answer, false)),
+            _ => Some((stale, true)),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Fetch the answer to `query` into the cache in the background.
+    /// Only one refresh per question is in flight at a time: Returns
+    /// None if there is one already, otherwise where the answer is sent
+    /// to once it arrives.
+    fn refresh(&self, query : &DnsMessage) -> Option<mpsc::Receiver<Option<DnsMessage>>> {
+
+        let question = &query.questions[0];
+        let key = (question.name.clone(), question.qtype);
+
+        if ! self.relay.refreshing.lock().unwrap().insert(key.clone()) {
+            return None;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+
+        let upstreams = self.upstreams_for(&question.name).to_vec();
+        let relay = self.relay.clone();
+        let query = query.clone();
+        let timeout = self.timeout;
+
+        thread::spawn(move || {
+            let answer = relay.forward(&query, &upstreams, timeout);
+            relay.refreshing.lock().unwrap().remove(&key);
+            // Nobody might be waiting anymore
+            let _ = sender.send(answer);
+        });
+
+        Some(receiver)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Relay {
+
+    /// Ask `upstreams` for `query` until one answers, and cache the answer
+    fn forward(&self, query : &DnsMessage, upstreams : &[SocketAddr], timeout : Duration)
+        -> Option<DnsMessage> {
+
+        let question = &query.questions[0];
+
+        let mut upstream_query = DnsMessage::query(
+            DnsMessage::random_id().ok()?, question.name.clone(), question.qtype);
+
+        upstream_query.questions[0].qclass = question.qclass;
+        upstream_query.set_flag(FLAG_CD, query.flag(FLAG_CD));
+        upstream_query.set_dnssec_ok(query.dnssec_ok());
+
+        for upstream in self.order(upstreams) {
+
+            let mut client = Client::new();
+            client.add_server(upstream);
+            client.set_retries(0, timeout);
+
+            match client.send(&upstream_query) {
+                Ok(answer) => {
+                    self.record(upstream, true);
+                    self.cache.insert(&answer);
+                    return Some(answer);
+                },
+                Err(msg) => {
+                    println!("Upstream {} failed: {}", upstream, msg);
+                    self.record(upstream, false);
+                },
+            };
+
+        }
+
+        None
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn is_healthy(&self, upstream : &SocketAddr) -> bool {
+
+        let health = self.health.lock().unwrap();
+
+        match health.get(upstream).and_then(|h| h.down_until) {
+            Some(until) => until <= Instant::now(),
+            None => true,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// `upstreams` in the order to ask them: Those considered down last,
+    /// the one to come back first ahead
+    fn order(&self, upstreams : &[SocketAddr]) -> Vec<SocketAddr> {
+
+        let health = self.health.lock().unwrap();
+        let now = Instant::now();
+
+        let mut ordered = upstreams.to_vec();
+
+        ordered.sort_by_key(|u| health.get(u)
+            .and_then(|h| h.down_until)
+            .filter(|until| *until > now));
+
+        ordered
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn record(&self, upstream : SocketAddr, success : bool) {
+
+        let mut health = self.health.lock().unwrap();
+        let entry = health.entry(upstream).or_default();
+
+        if success {
+            *entry = Health::default();
+            return;
+        }
+
+        entry.failures += 1;
+
+        if MAX_FAILURES <= entry.failures {
+            entry.down_until = Some(Instant::now() + Duration::from_secs(HOLD_DOWN_SECS));
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Handler for ForwardingHandler {
+
+    fn handle (&self, msg : Message, responder : Responder) -> ContinueState {
+
+        let bytes = &msg.buffer[.. msg.num_bytes];
+
+        let query = match DnsMessage::read(&mut WireReader::new(bytes)) {
+            Ok(q) => q,
+            Err(_) => {
+                if let Some(response) = format_error(bytes) {
+                    responder.respond(response);
+                }
+                return ContinueState::Continue;
+            }
+        };
+
+        if let Some(mut response) = self.answer(&query) {
+
+            if let Some(cookie) = msg.cookie {
+                response.set_cookie(cookie);
+            }
+
+            let max_len = match msg.transport {
+                Transport::Udp => msg.max_response_len
+                    .unwrap_or_else(|| query.max_udp_response_len()),
+                Transport::Tcp => usize::MAX,
+            };
+
+            responder.respond(response.to_udp_bytes(max_len));
+
+        }
+
+        ContinueState::Continue
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
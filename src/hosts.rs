@@ -0,0 +1,232 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ::std::fs;
+use ::std::io::ErrorKind;
+use ::std::net::IpAddr;
+use ::std::path::Path;
+use ::std::str::FromStr;
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{Record, ResourceRecord};
+
+/*----------------------------------------------------------------------------*/
+
+pub const HOSTS_PATH : &str = "/etc/hosts";
+
+/// Records made up from the hosts file are not to be cached
+const HOSTS_TTL : u32 = 0;
+
+/*----------------------------------------------------------------------------*/
+
+/// Static table of host names as read from `/etc/hosts`: An address per
+/// line, followed by the canonical name and aliases of the host.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hosts {
+
+    entries : Vec<HostEntry>,
+
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct HostEntry {
+
+    addr : IpAddr,
+    /// Canonical name first
+    names : Vec<Name>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Hosts {
+
+    pub fn new() -> Hosts {
+        Hosts { entries : Vec::new() }
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read the table from `path`. A missing file gives an empty table.
+    pub fn load<P : AsRef<Path>>(path : P) -> Result<Hosts, &'static str> {
+
+        match fs::read_to_string(path) {
+            Ok(text) => Hosts::from_str(&text),
+            Err(ref e) if ErrorKind::NotFound == e.kind() => Ok(Hosts::new()),
+            Err(_) => Err("Could not read hosts file"),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn add(&mut self, addr : IpAddr, names : Vec<Name>) {
+
+        if ! names.is_empty() {
+            self.entries.push(HostEntry { addr, names });
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Addresses of `name`, in the order of the file
+    pub fn addresses(&self, name : &Name) -> Vec<IpAddr> {
+
+        self.entries.iter()
+            .filter(|e| e.names.contains(name))
+            .map(|e| e.addr)
+            .collect()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// A, AAAA and PTR records for `name` according to the table.
+    /// Addresses are looked up in reverse via their `in-addr.arpa` or
+    /// `ip6.arpa` names. Any other type gives no records.
+    pub fn lookup(&self, name : &Name, qtype : QuestionType) -> Vec<ResourceRecord> {
+
+        let record = |r| ResourceRecord::new(name.clone(), HOSTS_TTL, r);
+
+        match qtype {
+
+            QuestionType::A => self.addresses(name).into_iter()
+                .filter_map(|a| match a {
+                    IpAddr::V4(a) => Some(record(Record::A(a))),
+                    _ => None,
+                })
+                .collect(),
+
+            QuestionType::Aaaa => self.addresses(name).into_iter()
+                .filter_map(|a| match a {
+                    IpAddr::V6(a) => Some(record(Record::AAAA(a))),
+                    _ => None,
+                })
+                .collect(),
+
+            QuestionType::Ptr => self.entries.iter()
+                .find(|e| reverse_name(&e.addr) == *name)
+                .map(|e| vec![record(Record::PTR(e.names[0].clone()))])
+                .unwrap_or_default(),
+
+            _ => Vec::new(),
+
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for Hosts {
+
+    fn default() -> Self {
+        Hosts::new()
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for Hosts {
+
+    type Err = &'static str;
+
+    /// Lines we cannot make sense of are skipped, as the C library does
+    fn from_str(s : &str) -> Result<Hosts, &'static str> {
+
+        let mut hosts = Hosts::new();
+
+        for line in s.lines() {
+
+            let line = line.split('#').next().unwrap_or("");
+            let mut fields = line.split_whitespace();
+
+            let addr = match fields.next().map(IpAddr::from_str) {
+                Some(Ok(a)) => a,
+                _ => continue,
+            };
+
+            let names = fields.filter_map(|n| Name::from_str(n).ok()).collect();
+            hosts.add(addr, names);
+
+        }
+
+        Ok(hosts)
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Name to look up `addr` in reverse, e.g. `1.2.0.192.in-addr.arpa`
+pub fn reverse_name(addr : &IpAddr) -> Name {
+
+    let labels : Vec<String> = match addr {
+
+        IpAddr::V4(a) => a.octets().iter().rev()
+            .map(|o| o.to_string())
+            .chain(vec!["in-addr".to_string(), "arpa".to_string()])
+            .collect(),
+
+        IpAddr::V6(a) => a.octets().iter().rev()
+            .flat_map(|o| vec![o & 0x0f, o >> 4])
+            .map(|n| format!("{:x}", n))
+            .chain(vec!["ip6".to_string(), "arpa".to_string()])
+            .collect(),
+
+    };
+
+    Name::from_str(&labels.join(".")).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,478 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::collections::HashSet;
+use ::std::fs::{self, OpenOptions};
+use ::std::io::{ErrorKind, Write};
+use ::std::path::{Path, PathBuf};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{Record, ResourceRecord, Soa};
+use zone::{Zone, DEFAULT_TTL};
+use zonefile::parse_entry;
+
+/*----------------------------------------------------------------------------*/
+
+/// Number of differences kept by default
+pub const DEFAULT_MAX_JOURNAL_LEN : usize = 100;
+
+/// Terminates a difference within a journal file
+const END_OF_DIFF : &str = ".";
+
+/*----------------------------------------------------------------------------*/
+
+/// Changes between two versions of a zone, identified by their SOA records
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diff {
+
+    /// SOA record of the version the changes apply to
+    pub from : ResourceRecord,
+    pub deleted : Vec<ResourceRecord>,
+    /// SOA record of the version the changes result in
+    pub to : ResourceRecord,
+    pub added : Vec<ResourceRecord>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Sequence of the differences between consecutive versions of a zone,
+/// oldest first.
+/// If a path is given, the journal is persisted to a file differences are
+/// appended to. Once it holds twice its max length, the oldest differences
+/// are dropped and the file is rewritten.
+#[derive(Clone, Debug)]
+pub struct Journal {
+
+    diffs : Vec<Diff>,
+    path : Option<PathBuf>,
+    max_len : usize,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Diff {
+
+    /// The changes turning `old` into `new`, both zones with apex `origin`
+    pub fn between(origin : &Name, old : &Zone, new : &Zone) -> Result<Diff, &'static str> {
+
+        let from = old.soa(origin).ok_or("Zone lacks SOA")?;
+        let to = new.soa(origin).ok_or("Zone lacks SOA")?;
+
+        let old_records = without_soa(old.records());
+        let new_records = without_soa(new.records());
+
+        let old_set : HashSet<&ResourceRecord> = old_records.iter().collect();
+        let new_set : HashSet<&ResourceRecord> = new_records.iter().collect();
+
+        let deleted = old_records.iter()
+            .filter(|rr| ! new_set.contains(rr))
+            .cloned()
+            .collect();
+
+        let added = new_records.iter()
+            .filter(|rr| ! old_set.contains(rr))
+            .cloned()
+            .collect();
+
+        Ok(Diff { from, deleted, to, added })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn from_serial(&self) -> u32 {
+        serial_of(&self.from)
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn to_serial(&self) -> u32 {
+        serial_of(&self.to)
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether the serial increases, as required for consecutive versions
+    pub fn increases_serial(&self) -> bool {
+
+        match (soa_of(&self.from), soa_of(&self.to)) {
+            (Some(from), Some(to)) => to.is_newer_than(from),
+            _ => false,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Apply the changes to the records of a zone
+    pub fn apply(&self, records : &mut Vec<ResourceRecord>) -> Result<(), &'static str> {
+
+        for rr in &self.deleted {
+            let index = records.iter()
+                .position(|r| r.name == rr.name && r.record == rr.record)
+                .ok_or("Deleted record not in zone")?;
+            records.remove(index);
+        }
+
+        records.retain(|r| QuestionType::Soa.code() != r.type_code());
+        records.push(self.to.clone());
+        records.extend(self.added.iter().cloned());
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Merge the following difference `next` into this one.
+    /// Records added and deleted again cancel out (RFC 1995, section 5).
+    fn condense(&mut self, next : &Diff) {
+
+        for rr in &next.deleted {
+            match self.added.iter().position(|r| r == rr) {
+                Some(i) => { self.added.remove(i); },
+                None => self.deleted.push(rr.clone()),
+            };
+        }
+
+        for rr in &next.added {
+            match self.deleted.iter().position(|r| r == rr) {
+                Some(i) => { self.deleted.remove(i); },
+                None => self.added.push(rr.clone()),
+            };
+        }
+
+        self.to = next.to.clone();
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn to_text(&self) -> String {
+
+        let mut text = String::new();
+
+        for rr in Some(&self.from).into_iter().chain(&self.deleted) {
+            text.push_str(&format!("- {}\n", entry(rr)));
+        }
+
+        for rr in Some(&self.to).into_iter().chain(&self.added) {
+            text.push_str(&format!("+ {}\n", entry(rr)));
+        }
+
+        text.push_str(END_OF_DIFF);
+        text.push('\n');
+
+        text
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Journal {
+
+    pub fn new() -> Journal {
+
+        Journal {
+            diffs : Vec::new(),
+            path : None,
+            max_len : DEFAULT_MAX_JOURNAL_LEN,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Journal persisted to the file at `path`, which is read if it exists.
+    /// A difference cut short by a crash is dropped.
+    pub fn open<P : AsRef<Path>>(path : P) -> Result<Journal, &'static str> {
+
+        let mut journal = Journal::new();
+        journal.path = Some(path.as_ref().to_path_buf());
+
+        let text = match fs::read_to_string(path.as_ref()) {
+            Ok(t) => t,
+            Err(ref e) if ErrorKind::NotFound == e.kind() => return Ok(journal),
+            Err(_) => return Err("Could not read journal"),
+        };
+
+        let (diffs, complete) = parse_journal(&text)?;
+        journal.diffs = diffs;
+
+        if ! complete {
+            journal.rewrite()?;
+        }
+
+        Ok(journal)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Keep the last `max_len` differences
+    pub fn set_max_len(&mut self, max_len : usize) {
+
+        self.max_len = max_len.max(1);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn len(&self) -> usize {
+        self.diffs.len()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn is_empty(&self) -> bool {
+        self.diffs.is_empty()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn diffs(&self) -> &[Diff] {
+        &self.diffs
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Serial of the newest version recorded
+    pub fn last_serial(&self) -> Option<u32> {
+        self.diffs.last().map(Diff::to_serial)
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Record `diff`. If it does not continue the last difference,
+    /// the journal starts anew.
+    pub fn append(&mut self, diff : Diff) -> Result<(), &'static str> {
+
+        if self.last_serial().is_some_and(|s| s != diff.from_serial()) {
+            self.clear()?;
+        }
+
+        if let Some(ref path) = self.path {
+
+            let mut file = OpenOptions::new().create(true).append(true).open(path)
+                .map_err(|_| "Could not open journal")?;
+
+            file.write_all(diff.to_text().as_bytes())
+                .and_then(|_| file.sync_data())
+                .map_err(|_| "Could not write journal")?;
+
+        }
+
+        self.diffs.push(diff);
+
+        if self.diffs.len() >= 2 * self.max_len {
+            self.compact()?;
+        }
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn clear(&mut self) -> Result<(), &'static str> {
+
+        self.diffs.clear();
+        self.rewrite()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Drop all but the last `max_len` differences
+    pub fn compact(&mut self) -> Result<(), &'static str> {
+
+        if self.diffs.len() <= self.max_len {
+            return Ok(());
+        }
+
+        let obsolete = self.diffs.len() - self.max_len;
+        self.diffs.drain(.. obsolete);
+
+        self.rewrite()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// All changes since version `serial` condensed into one difference.
+    /// Returns None if the journal does not reach back to `serial`.
+    pub fn changes_since(&self, serial : u32) -> Option<Diff> {
+
+        let start = self.diffs.iter().position(|d| serial == d.from_serial())?;
+
+        let mut condensed = self.diffs[start].clone();
+
+        for diff in &self.diffs[start + 1 ..] {
+            condensed.condense(diff);
+        }
+
+        Some(condensed)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Replace the file by the differences currently held
+    fn rewrite(&self) -> Result<(), &'static str> {
+
+        let path = match self.path {
+            None => return Ok(()),
+            Some(ref p) => p,
+        };
+
+        let text : String = self.diffs.iter().map(Diff::to_text).collect();
+
+        let mut temporary = path.clone().into_os_string();
+        temporary.push(".tmp");
+
+        fs::write(&temporary, text)
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|_| "Could not write journal")
+
+    }
+
+}
+
+impl Default for Journal {
+
+    fn default() -> Self {
+        Journal::new()
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+fn soa_of(rr : &ResourceRecord) -> Option<&Soa> {
+
+    match rr.record {
+        Record::SOA(ref soa) => Some(soa),
+        _ => None,
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn serial_of(rr : &ResourceRecord) -> u32 {
+
+    soa_of(rr).map(|soa| soa.serial).unwrap_or(0)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn without_soa(records : Vec<ResourceRecord>) -> Vec<ResourceRecord> {
+
+    records.into_iter()
+        .filter(|rr| QuestionType::Soa.code() != rr.type_code())
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// `rr` in the form accepted by `parse_entry`
+fn entry(rr : &ResourceRecord) -> String {
+
+    match rr.name.is_root() {
+        true => format!(". {} {}", rr.ttl, rr.record),
+        false => rr.to_string(),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Parse the content of a journal file.
+/// Returns the differences along with whether the last one was complete.
+fn parse_journal(text : &str) -> Result<(Vec<Diff>, bool), &'static str> {
+
+    let mut diffs = Vec::new();
+    let mut deleted : Vec<ResourceRecord> = Vec::new();
+    let mut added : Vec<ResourceRecord> = Vec::new();
+
+    for line in text.lines() {
+
+        if END_OF_DIFF == line {
+
+            let is_soa = |rr : Option<&ResourceRecord>|
+                rr.is_some_and(|rr| QuestionType::Soa.code() == rr.type_code());
+
+            if ! is_soa(deleted.first()) || ! is_soa(added.first()) {
+                return Err("Journal entry lacks SOA");
+            }
+
+            diffs.push(Diff {
+                from : deleted.remove(0),
+                deleted : deleted.split_off(0),
+                to : added.remove(0),
+                added : added.split_off(0),
+            });
+
+            continue;
+
+        }
+
+        let (target, entry) = match line.split_at(line.len().min(2)) {
+            ("- ", entry) => (&mut deleted, entry),
+            ("+ ", entry) => (&mut added, entry),
+            // Cut short while writing
+            _ => return Ok((diffs, false)),
+        };
+
+        match parse_entry(entry, &Name::root(), DEFAULT_TTL) {
+            Ok((name, ttl, record)) => target.push(ResourceRecord::new(name, ttl, record)),
+            Err(_) => return Ok((diffs, false)),
+        };
+
+    }
+
+    let complete = deleted.is_empty() && added.is_empty();
+
+    Ok((diffs, complete))
+
+}
+
+/*----------------------------------------------------------------------------*/
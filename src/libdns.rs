@@ -26,18 +26,91 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //
+extern crate getrandom;
+extern crate mio;
+extern crate ring;
+extern crate siphasher;
+extern crate signal_hook;
+
 pub use dnstraits::{AsBytes, DnsEntity};
 pub use dnsname::Name;
 pub use dnslabel::Label;
-pub use zone::Zone;
-pub use dnsquestion::QuestionType;
-pub use dnsrecord::Record;
-pub use udp::{Handler, Message};
+pub use zone::{Lookup, Zone, DEFAULT_TTL};
+pub use zonefile::{format_zone, load_zone, parse_entry, parse_zone};
+pub use dnsquestion::{Question, QuestionType, CLASS_IN, CLASS_CH, CLASS_NONE, CLASS_ANY};
+pub use dnsrecord::{Record, ResourceRecord, Soa};
+pub use dnssec::{Dnskey, Ds, Nsec, Nsec3, Nsec3Param, Rrsig};
+pub use dnssec::{DNSKEY_FLAG_SEP, DNSKEY_FLAG_ZONE, DNSKEY_PROTOCOL, NSEC3_FLAG_OPT_OUT};
+pub use dnssec::{ALGORITHM_RSASHA1, ALGORITHM_RSASHA1_NSEC3_SHA1, ALGORITHM_RSASHA256, ALGORITHM_RSASHA512};
+pub use dnssec::{ALGORITHM_ECDSAP256SHA256, ALGORITHM_ECDSAP384SHA384, ALGORITHM_ED25519};
+pub use dnssec::{DIGEST_SHA1, DIGEST_SHA256, DIGEST_SHA384};
+pub use dnssec::{nsec3_hash, NSEC3_HASH_SHA1};
+pub use signer::{SigningAlgorithm, SigningKey, Signer, DEFAULT_SIGNATURE_VALIDITY_SECS};
+pub use validator::{verify_rrsig, QuerySource, Security, Validation, Validator};
+pub use client::{Client, DEFAULT_CLIENT_RETRIES, DEFAULT_CLIENT_TIMEOUT_SECS};
+pub use resolvconf::{ResolvConf, RESOLV_CONF_PATH};
+pub use hosts::{reverse_name, Hosts, HOSTS_PATH};
+pub use resolver::{Resolver, DEFAULT_MAX_QUERIES, DEFAULT_MAX_REFERRALS};
+pub use resolver::DEFAULT_RESOLVER_TIMEOUT_MS;
+pub use cache::{Cache, CacheAnswer, CacheStats, DEFAULT_CACHE_SIZE, DEFAULT_MAX_NEGATIVE_TTL,
+                DEFAULT_MAX_TTL};
+pub use forwarder::{ForwardingHandler, DEFAULT_FORWARD_TIMEOUT_MS};
+pub use forwarder::DEFAULT_STALE_ANSWER_TIMEOUT_MS;
+pub use dnsmessage::{Message as DnsMessage, Opcode, Rcode};
+pub use dnsmessage::{QUERY_PADDING_BLOCK, RESPONSE_PADDING_BLOCK};
+pub use dnsmessage::{FLAG_QR, FLAG_AA, FLAG_TC, FLAG_RD, FLAG_RA, FLAG_AD, FLAG_CD};
+pub use edns::{ClientSubnet, Cookie, Edns, EdnsOption, EDNS_VERSION};
+pub use edns::{ExtendedError, ExtendedErrorCode};
+pub use cookie::{CookieConfig, CookieFilter, CookieStatus, ServerCookies};
+pub use network::Network;
+pub use udp::{ContinueState, Handler, Message, Responder, Transport, MAX_SAFE_UDP_PAYLOAD_LEN, MAX_UDP_PAYLOAD_LEN};
+pub use server::{Listener, Server};
+pub use udpserver::UdpServer;
+pub use tcpserver::{TcpConfig, TcpServer};
+pub use threadpool::Threadpool;
+pub use authoritative::AuthoritativeHandler;
+pub use catalog::{ZoneCatalog, ZoneObserver};
+pub use reload::ZoneReloader;
+pub use journal::{Diff, Journal, DEFAULT_MAX_JOURNAL_LEN};
+pub use secondary::{transfer_zone, RefreshTrigger, Secondary};
+pub use update::apply_update;
+pub use tsig::{Algorithm, KeyStore, Tsig, TsigError, TsigKey, TsigSession, DEFAULT_FUDGE};
+pub use notify::{send_notify, Notifier, DEFAULT_NOTIFY_INTERVAL_SECS, DEFAULT_NOTIFY_RETRIES};
+pub use transfer::{axfr_messages, ixfr_messages, MAX_TRANSFER_MESSAGE_LEN};
+pub use wire::{WireReader, WireWriter};
 mod dnstraits;
 mod dnslabel;
 mod dnsname;
 mod dnsrecord;
+mod dnssec;
 mod zone;
+mod zonefile;
 mod dnsquestion;
+mod dnsmessage;
+mod edns;
+mod cookie;
+mod network;
 mod udp;
+mod server;
+mod udpserver;
+mod tcpserver;
+mod threadpool;
+mod authoritative;
+mod catalog;
+mod reload;
+mod transfer;
+mod journal;
+mod secondary;
+mod notify;
+mod update;
+mod tsig;
+mod signer;
+mod validator;
+mod client;
+mod resolvconf;
+mod hosts;
+mod resolver;
+mod cache;
+mod forwarder;
+mod wire;
 /*----------------------------------------------------------------------------*/
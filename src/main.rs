@@ -31,97 +31,613 @@
  * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-extern crate mio;
+extern crate rustydns;
+
+use rustydns::{AuthoritativeHandler, Name, Server, TcpServer, UdpServer, Threadpool};
+use rustydns::{KeyStore, Network, Notifier, Secondary, ZoneCatalog, ZoneReloader};
+use rustydns::{format_zone, load_zone, Nsec3Param, Signer, SigningAlgorithm, SigningKey};
+use rustydns::{Cache, ForwardingHandler, QuestionType, Rcode, Resolver, DEFAULT_CACHE_SIZE};
+use rustydns::DEFAULT_STALE_ANSWER_TIMEOUT_MS;
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
-mod udp;
-mod threadpool;
-mod udpserver;
-use self::udp::{ContinueState, Message, Handler};
-use self::udpserver::UdpServer;
-use self::threadpool::Threadpool;
-use std::sync::{Arc, Mutex};
+/*----------------------------------------------------------------------------*/
+
+const DEFAULT_LISTEN_ADDR : &str = "127.0.0.1:53";
+const DEFAULT_NUM_THREADS : usize = 4;
+const DEFAULT_RELOAD_INTERVAL_SECS : u64 = 5;
+const SIGNATURE_REFRESH_INTERVAL_SECS : u64 = 3600;
+
+const USAGE : &str =
+"Usage: rustydns serve [--zone ORIGIN=FILE ...] [--secondary ORIGIN=PRIMARY ...]
+                      [--listen ADDRESS:PORT] [--threads NUM]
+                      [--reload-interval SECONDS]
+                      [--allow-transfer NETWORK ...] [--allow-update NETWORK ...]
+                      [--journal-dir DIRECTORY]
+                      [--notify ORIGIN=SECONDARY ...]
+                      [--keys FILE] [--sign-with KEY]
+                      [--allow-transfer-key KEY ...] [--allow-update-key KEY ...]
+                      [--dnssec-keys ORIGIN=FILE ...]
+                      [--nsec3 ITERATIONS:SALT[:opt-out]]
+       rustydns sign --zone ORIGIN=FILE --dnssec-keys FILE [--output FILE]
+                     [--nsec3 ITERATIONS:SALT[:opt-out]]
+       rustydns keygen [--algorithm ECDSAP256SHA256|ED25519] [--role KSK|ZSK]
+       rustydns resolve NAME [TYPE]
+       rustydns forward --upstream ADDRESS:PORT ... [--forward-zone DOMAIN=ADDRESS:PORT ...]
+                        [--listen ADDRESS:PORT] [--threads NUM] [--cache-size OCTETS]
+                        [--max-stale SECONDS] [--stale-answer-timeout MILLISECONDS]
+
+Serve the given zones authoritatively via UDP and TCP.
+Zone files are reloaded if changed, or on SIGHUP.
+Secondary zones are transferred from PRIMARY, given as ADDRESS:PORT,
+which might announce new versions via NOTIFY.
+SECONDARY, given as ADDRESS:PORT, is notified whenever zone ORIGIN changes.
+Clients within the allowed networks might transfer zones via AXFR or IXFR,
+or change them via dynamic updates.
+If a journal directory is given, changes of zones are recorded there
+to serve IXFR.
+TSIG keys are read from FILE, one per line as NAME ALGORITHM SECRET.
+Requests signed with an allowed KEY might transfer or update zones
+from anywhere. Zone transfers and NOTIFY we send are signed with the
+KEY given via --sign-with. NOTIFY received by secondaries must then be
+signed with that KEY as well.
+Zones with DNSSEC keys are signed whenever they change, and again before
+their signatures expire. DNSSEC keys are read from FILE, one per line as
+FLAGS ALGORITHM PRIVATE_KEY. Names are chained via NSEC, or via NSEC3 if
+given, where SALT is given in hex or as -.
+
+sign signs the zone file once, writing the result to FILE.signed unless
+another output file is given.
+
+keygen generates a DNSSEC key and prints it in the format of key files.
+
+resolve looks up records of TYPE, A by default, for NAME, starting
+from the root servers.
+
+forward relays queries to the upstream resolvers and caches the answers.
+Queries for names within DOMAIN are relayed to the servers given for it
+instead. Answers are kept for up to SECONDS after they expired, to be
+served if no upstream answers within the stale answer timeout.
+Answers used often are refreshed shortly before they expire.";
+
+/*----------------------------------------------------------------------------*/
+
+struct ServeConfig {
+
+    zones : Vec<(String, String)>,
+    secondaries : Vec<(Name, SocketAddr)>,
+    notify : Vec<(Name, SocketAddr)>,
+    listen_addr : String,
+    num_threads : usize,
+    reload_interval : Duration,
+    transfer_acl : Vec<Network>,
+    update_acl : Vec<Network>,
+    journal_dir : Option<PathBuf>,
+    key_file : Option<PathBuf>,
+    sign_key : Option<Name>,
+    transfer_keys : Vec<Name>,
+    update_keys : Vec<Name>,
+    dnssec_keys : Vec<(Name, PathBuf)>,
+    nsec3 : Option<Nsec3Param>,
 
-use std::collections::VecDeque;
+}
 
 /*----------------------------------------------------------------------------*/
 
-struct DummyHandler {
+struct SignConfig {
 
-    queue : Arc<Mutex<VecDeque<Message>>>,
+    zone : Option<(Name, String)>,
+    key_file : Option<PathBuf>,
+    output : Option<String>,
+    nsec3 : Option<Nsec3Param>,
+
+}
+
+struct ForwardConfig {
+
+    upstreams : Vec<SocketAddr>,
+    rules : Vec<(Name, SocketAddr)>,
+    listen_addr : String,
+    num_threads : usize,
+    cache_size : usize,
+    max_stale : Duration,
+    stale_answer_timeout : Duration,
 
 }
 
 /*----------------------------------------------------------------------------*/
 
-/**
- * Simple echoing handler
- */
-impl Handler for DummyHandler {
+fn parse_serve_args(args : &[String]) -> Result<ServeConfig, &'static str> {
+
+    let mut config = ServeConfig {
+        zones : Vec::new(),
+        secondaries : Vec::new(),
+        notify : Vec::new(),
+        listen_addr : DEFAULT_LISTEN_ADDR.to_string(),
+        num_threads : DEFAULT_NUM_THREADS,
+        reload_interval : Duration::from_secs(DEFAULT_RELOAD_INTERVAL_SECS),
+        transfer_acl : Vec::new(),
+        update_acl : Vec::new(),
+        journal_dir : None,
+        key_file : None,
+        sign_key : None,
+        transfer_keys : Vec::new(),
+        update_keys : Vec::new(),
+        dnssec_keys : Vec::new(),
+        nsec3 : None,
+    };
 
-    fn handle (&self, msg : Message) -> ContinueState {
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+
+        let value = args.next().ok_or("Option requires a value")?;
+
+        match arg.as_str() {
+            "--zone" => config.zones.push(parse_zone_file(value)?),
+            "--secondary" => config.secondaries.push(parse_zone_server(value)
+                .map_err(|_| "Secondary must be given as ORIGIN=ADDRESS:PORT")?),
+            "--notify" => config.notify.push(parse_zone_server(value)
+                .map_err(|_| "Notify must be given as ORIGIN=ADDRESS:PORT")?),
+            "--listen" => config.listen_addr = value.clone(),
+            "--threads" => config.num_threads = usize::from_str(value)
+                .map_err(|_| "Could not parse number of threads")?,
+            "--reload-interval" => config.reload_interval = u64::from_str(value)
+                .map(Duration::from_secs)
+                .map_err(|_| "Could not parse reload interval")?,
+            "--journal-dir" => config.journal_dir = Some(PathBuf::from(value)),
+            "--allow-transfer" => config.transfer_acl.push(Network::from_str(value)?),
+            "--allow-update" => config.update_acl.push(Network::from_str(value)?),
+            "--keys" => config.key_file = Some(PathBuf::from(value)),
+            "--sign-with" => config.sign_key = Some(Name::from_str(value)?),
+            "--allow-transfer-key" => config.transfer_keys.push(Name::from_str(value)?),
+            "--allow-update-key" => config.update_keys.push(Name::from_str(value)?),
+            "--dnssec-keys" => {
+                let (origin, path) = parse_zone_file(value)?;
+                config.dnssec_keys.push((Name::from_str(&origin)?, PathBuf::from(path)));
+            },
+            "--nsec3" => config.nsec3 = Some(parse_nsec3(value)?),
+            _ => return Err("Unknown option"),
+        };
 
-        {
-            let actually_used = &msg.buffer[.. msg.num_bytes];
-            let data_str = match std::str::from_utf8(actually_used) {
-                Err(_) => "Could not decode data",
-                Ok(s) => s,
-            };
-            println!("{}", data_str);
-        }
+    }
+
+    if config.zones.is_empty() && config.secondaries.is_empty() {
+        return Err("Require at least one zone");
+    }
+
+    Ok(config)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn parse_sign_args(args : &[String]) -> Result<SignConfig, &'static str> {
+
+    let mut config = SignConfig {
+        zone : None,
+        key_file : None,
+        output : None,
+        nsec3 : None,
+    };
 
-        self.queue.lock().unwrap().push_back(msg);
+    let mut args = args.iter();
 
-        ContinueState::Continue
+    while let Some(arg) = args.next() {
+
+        let value = args.next().ok_or("Option requires a value")?;
+
+        match arg.as_str() {
+            "--zone" => {
+                let (origin, path) = parse_zone_file(value)?;
+                config.zone = Some((Name::from_str(&origin)?, path));
+            },
+            "--dnssec-keys" => config.key_file = Some(PathBuf::from(value)),
+            "--output" => config.output = Some(value.clone()),
+            "--nsec3" => config.nsec3 = Some(parse_nsec3(value)?),
+            _ => return Err("Unknown option"),
+        };
 
     }
 
+    Ok(config)
+
 }
 
-fn create_worker() -> DummyHandler {
-    let queue = Arc::new(Mutex::new(VecDeque::with_capacity(100)));
-    DummyHandler{queue}
+/*----------------------------------------------------------------------------*/
+
+fn parse_forward_args(args : &[String]) -> Result<ForwardConfig, &'static str> {
+
+    let mut config = ForwardConfig {
+        upstreams : Vec::new(),
+        rules : Vec::new(),
+        listen_addr : DEFAULT_LISTEN_ADDR.to_string(),
+        num_threads : DEFAULT_NUM_THREADS,
+        cache_size : DEFAULT_CACHE_SIZE,
+        max_stale : Duration::from_secs(0),
+        stale_answer_timeout : Duration::from_millis(DEFAULT_STALE_ANSWER_TIMEOUT_MS),
+    };
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+
+        let value = args.next().ok_or("Option requires a value")?;
+
+        match arg.as_str() {
+            "--upstream" => config.upstreams.push(SocketAddr::from_str(value)
+                .map_err(|_| "Could not parse upstream address")?),
+            "--forward-zone" => config.rules.push(parse_zone_server(value)
+                .map_err(|_| "Forward zone must be given as DOMAIN=ADDRESS:PORT")?),
+            "--listen" => config.listen_addr = value.clone(),
+            "--threads" => config.num_threads = usize::from_str(value)
+                .map_err(|_| "Could not parse number of threads")?,
+            "--cache-size" => config.cache_size = usize::from_str(value)
+                .map_err(|_| "Could not parse cache size")?,
+            "--max-stale" => config.max_stale = u64::from_str(value)
+                .map(Duration::from_secs)
+                .map_err(|_| "Could not parse maximum stale age")?,
+            "--stale-answer-timeout" => config.stale_answer_timeout = u64::from_str(value)
+                .map(Duration::from_millis)
+                .map_err(|_| "Could not parse stale answer timeout")?,
+            _ => return Err("Unknown option"),
+        };
+
+    }
+
+    if config.upstreams.is_empty() {
+        return Err("Require at least one upstream");
+    }
+
+    Ok(config)
+
 }
 
 /*----------------------------------------------------------------------------*/
 
-fn main() {
+/// Parse `ORIGIN=FILE`
+fn parse_zone_file(value : &str) -> Result<(String, String), &'static str> {
 
-    let test = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    let mut parts = value.splitn(2, '=');
+    let origin = parts.next().unwrap_or("");
+    let path = parts.next().ok_or("Zone must be given as ORIGIN=FILE")?;
+
+    Ok((origin.to_string(), path.to_string()))
+
+}
 
-    println!("0..0 {:?}    0..1 {:?}    0..2 {:?}    ..0 {:?}    ..1 {:?}  0.. {:?}   1.. {:?}",
-             &test[0..0], &test[0..1], &test[0..2],
-             &test[..0], &test[..1], &test[0..],
-             &test[1..]);
+/*----------------------------------------------------------------------------*/
 
-    let listen_addr_str = "127.0.0.1:1104";
+/// Parse `ITERATIONS:SALT[:opt-out]`
+fn parse_nsec3(value : &str) -> Result<Nsec3Param, &'static str> {
 
-    let worker = Arc::new(create_worker());
+    let parts : Vec<&str> = value.split(':').collect();
 
-    let threadpool = Threadpool::new(worker.clone(), 100);
+    let flags = match parts.get(2) {
+        None => "0",
+        Some(&"opt-out") if 3 == parts.len() => "1",
+        _ => return Err("NSEC3 parameters must be given as ITERATIONS:SALT[:opt-out]"),
+    };
 
-    threadpool.run(4);
+    let salt = parts.get(1).ok_or("NSEC3 parameters must be given as ITERATIONS:SALT")?;
+    let fields = ["1", flags, parts[0], salt];
 
-    println!("Start listening on {}", listen_addr_str);
+    Nsec3Param::from_fields(&fields.iter().map(|f| f.to_string()).collect::<Vec<String>>())
 
-    let udp_server = UdpServer::bind_to(
-        listen_addr_str,
-        &threadpool,
-        Some(worker.queue.clone()));
+}
 
-    let udp_server = match udp_server {
+/*----------------------------------------------------------------------------*/
 
-        Ok(p) => p,
+fn load_signer(path : &PathBuf, nsec3 : &Option<Nsec3Param>) -> Result<Signer, &'static str> {
 
-        Err(msg) => {
-            println!("{}", msg);
-            return;
-        }
+    let mut signer = Signer::load(path)?;
+
+    if let Some(ref param) = nsec3 {
+        signer.set_nsec3(param.iterations, param.salt.clone(), 0 != param.flags);
+    }
+
+    Ok(signer)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Parse `ORIGIN=ADDRESS:PORT`
+fn parse_zone_server(value : &str) -> Result<(Name, SocketAddr), &'static str> {
+
+    let mut parts = value.splitn(2, '=');
+    let origin = Name::from_str(parts.next().unwrap_or(""))?;
+
+    let server = parts.next()
+        .and_then(|p| SocketAddr::from_str(p).ok())
+        .ok_or("Could not parse address")?;
+
+    Ok((origin, server))
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn journal_path(config : &ServeConfig, origin : &Name) -> Option<String> {
+
+    config.journal_dir.as_ref()
+        .map(|dir| dir.join(format!("{}.jnl", origin)).to_string_lossy().into_owned())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn serve(config : ServeConfig) -> Result<(), &'static str> {
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    let mut reloader = ZoneReloader::new(catalog.clone());
+
+    for (origin, path) in &config.dnssec_keys {
+        catalog.set_signer(origin.clone(), load_signer(path, &config.nsec3)?);
+    }
+
+    if ! config.dnssec_keys.is_empty() {
+        ZoneCatalog::spawn_refresh(catalog.clone(),
+                                   Duration::from_secs(SIGNATURE_REFRESH_INTERVAL_SECS));
+    }
+
+    for (origin, path) in &config.zones {
+
+        let origin = Name::from_str(origin)?;
+
+        match journal_path(&config, &origin) {
+            None => reloader.add(origin.clone(), path)?,
+            Some(journal) => reloader.add_with_journal(origin.clone(), path, &journal)?,
+        };
+
+        println!("Loaded zone {} from {}", origin, path);
+
+    }
+
+    reloader.spawn(config.reload_interval)?;
+
+    let keys = match config.key_file {
+        Some(ref path) => KeyStore::load(path)?,
+        None => KeyStore::new(),
+    };
+
+    let sign_key = match config.sign_key {
+        Some(ref name) => Some(keys.get(name).ok_or("Unknown key to sign with")?.clone()),
+        None => None,
     };
 
-    udp_server.run();
+    let mut notifier = Notifier::new();
+
+    for (origin, secondary) in &config.notify {
+        notifier.add_target(origin.clone(), *secondary);
+    }
+
+    if let Some(ref key) = sign_key {
+        notifier.set_key(key.clone());
+    }
+
+    catalog.add_observer(Arc::new(notifier));
+
+    let mut handler = AuthoritativeHandler::with_catalog(catalog.clone());
+
+    for network in &config.transfer_acl {
+        handler.allow_transfer(*network);
+    }
+
+    for network in &config.update_acl {
+        handler.allow_update(*network);
+    }
+
+    for key in &config.transfer_keys {
+        handler.allow_transfer_key(key.clone());
+    }
+
+    for key in &config.update_keys {
+        handler.allow_update_key(key.clone());
+    }
+
+    handler.set_keys(keys);
+
+    for (origin, primary) in &config.secondaries {
+
+        let mut secondary = Secondary::new(catalog.clone(), origin.clone(), *primary);
+
+        if let Some(journal) = journal_path(&config, origin) {
+            secondary.set_journal_path(journal);
+        }
+
+        if let Some(ref key) = sign_key {
+            secondary.set_key(key.clone());
+        }
+
+        // The primary signs NOTIFY with the key transfers are signed with
+        handler.accept_notify(origin.clone(), primary.ip(), config.sign_key.clone(),
+                              secondary.trigger());
+        secondary.spawn();
+
+    }
+
+    let threadpool = Threadpool::new(Arc::new(handler), 100);
+    threadpool.run(config.num_threads);
+
+    let udp_server = UdpServer::bind_to(&config.listen_addr, &threadpool)?;
+    let tcp_server = TcpServer::bind_to(&config.listen_addr, &threadpool)?;
+
+    let mut server = Server::new()?;
+    server.add(udp_server)?;
+    server.add(tcp_server)?;
+
+    println!("Start listening on {}", config.listen_addr);
+
+    server.run();
+    threadpool.join();
+
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
 
+fn forward(config : ForwardConfig) -> Result<(), &'static str> {
+
+    let mut cache = Cache::new(config.cache_size);
+    cache.set_max_stale(config.max_stale);
+
+    let mut handler = ForwardingHandler::new(Arc::new(cache));
+    handler.set_stale_answer_timeout(config.stale_answer_timeout);
+
+    for upstream in &config.upstreams {
+        handler.add_upstream(*upstream);
+    }
+
+    let mut domains : Vec<&Name> = Vec::new();
+
+    for (domain, _) in &config.rules {
+        if ! domains.contains(&domain) {
+            domains.push(domain);
+        }
+    }
+
+    for domain in domains {
+
+        let upstreams = config.rules.iter()
+            .filter(|(d, _)| d == domain)
+            .map(|(_, upstream)| *upstream)
+            .collect();
+
+        handler.add_rule(domain.clone(), upstreams);
+
+    }
+
+    let threadpool = Threadpool::new(Arc::new(handler), 100);
+    threadpool.run(config.num_threads);
+
+    let udp_server = UdpServer::bind_to(&config.listen_addr, &threadpool)?;
+    let tcp_server = TcpServer::bind_to(&config.listen_addr, &threadpool)?;
+
+    let mut server = Server::new()?;
+    server.add(udp_server)?;
+    server.add(tcp_server)?;
+
+    println!("Start forwarding on {}", config.listen_addr);
+
+    server.run();
     threadpool.join();
 
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn sign(config : SignConfig) -> Result<(), &'static str> {
+
+    let (origin, path) = config.zone.ok_or("Require zone to sign")?;
+    let key_file = config.key_file.ok_or("Require DNSSEC keys to sign with")?;
+
+    let signer = load_signer(&key_file, &config.nsec3)?;
+    let zone = load_zone(&origin, &path)?;
+    let signed = signer.sign(&origin, &zone, Some(&zone))?;
+
+    let output = config.output.unwrap_or(format!("{}.signed", path));
+
+    fs::write(&output, format_zone(&signed)).map_err(|_| "Could not write signed zone")?;
+
+    println!("Signed zone {} written to {}", origin, output);
+
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn keygen(args : &[String]) -> Result<(), &'static str> {
+
+    let mut algorithm = SigningAlgorithm::EcdsaP256Sha256;
+    let mut sep = false;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+
+        let value = args.next().ok_or("Option requires a value")?;
+
+        match arg.as_str() {
+            "--algorithm" => algorithm = SigningAlgorithm::from_str(value)?,
+            "--role" => sep = match value.to_uppercase().as_str() {
+                "KSK" => true,
+                "ZSK" => false,
+                _ => return Err("Role must be KSK or ZSK"),
+            },
+            _ => return Err("Unknown option"),
+        };
+
+    }
+
+    let key = SigningKey::generate(algorithm, sep)?;
+
+    println!("; {} key, tag {}", if sep { "KSK" } else { "ZSK" }, key.key_tag());
+    println!("{}", key);
+
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn resolve(args : &[String]) -> Result<(), &'static str> {
+
+    let name = Name::from_str(args.first().ok_or("Require name to resolve")?)?;
+
+    let qtype = match args.get(1) {
+        None => QuestionType::A,
+        Some(t) => QuestionType::from_str(&t.to_uppercase())?,
+    };
+
+    let response = Resolver::new().resolve(&name, qtype)?;
+
+    if Rcode::NxDomain as u16 == response.rcode() {
+        println!("{} does not exist", name);
+    }
+
+    for rr in &response.answers {
+        println!("{}", rr);
+    }
+
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn main() {
+
+    let args : Vec<String> = env::args().collect();
+
+    let command = args.get(1).map(|c| c.as_str()).unwrap_or("");
+
+    let result = match command {
+        "serve" => parse_serve_args(&args[2 ..]).and_then(serve),
+        "sign" => parse_sign_args(&args[2 ..]).and_then(sign),
+        "keygen" => keygen(&args[2 ..]),
+        "resolve" => resolve(&args[2 ..]),
+        "forward" => parse_forward_args(&args[2 ..]).and_then(forward),
+        _ => {
+            println!("{}", USAGE);
+            return;
+        },
+    };
+
+    if let Err(msg) = result {
+        println!("{}", msg);
+        println!("{}", USAGE);
+    }
+
 }
 
 /*----------------------------------------------------------------------------*/
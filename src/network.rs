@@ -0,0 +1,200 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::fmt;
+use ::std::str::FromStr;
+use ::std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/*----------------------------------------------------------------------------*/
+
+/// An IP network given as address and prefix length, e.g. 10.0.0.0/8
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Network {
+
+    address : IpAddr,
+    prefix_len : u8,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Network {
+
+    /// Host bits of `address` beyond `prefix_len` are cleared
+    pub fn new(address : IpAddr, prefix_len : u8) -> Result<Network, &'static str> {
+
+        if prefix_len > max_prefix_len(&address) {
+            return Err("Prefix length exceeds address length");
+        }
+
+        Ok(Network {
+            address : mask_address(&address, prefix_len),
+            prefix_len,
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn contains(&self, address : &IpAddr) -> bool {
+
+        if self.address.is_ipv4() != address.is_ipv4() {
+            return false;
+        }
+
+        self.address == mask_address(address, self.prefix_len)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// True if `other` lies completely within this network
+    pub fn covers(&self, other : &Network) -> bool {
+
+        self.prefix_len <= other.prefix_len && self.contains(&other.address)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for Network {
+
+    type Err = &'static str;
+
+    /// Parse network in CIDR notation. Plain addresses are
+    /// treated as host networks.
+    fn from_str(s : &str) -> Result<Network, &'static str> {
+
+        let mut parts = s.trim().splitn(2, '/');
+
+        let address = IpAddr::from_str(parts.next().unwrap_or(""))
+            .map_err(|_| "Could not parse IP address")?;
+
+        let prefix_len = match parts.next() {
+            None => max_prefix_len(&address),
+            Some(p) => u8::from_str(p).map_err(|_| "Could not parse prefix length")?,
+        };
+
+        Network::new(address, prefix_len)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Network {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{}/{}", self.address, self.prefix_len)
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+pub fn max_prefix_len(address : &IpAddr) -> u8 {
+
+    match address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Clear all bits beyond `prefix_len`
+pub fn mask_address(address : &IpAddr, prefix_len : u8) -> IpAddr {
+
+    match address {
+
+        IpAddr::V4(a) => {
+            let mut octets = a.octets();
+            mask_octets(&mut octets, prefix_len);
+            IpAddr::V4(Ipv4Addr::from(octets))
+        },
+
+        IpAddr::V6(a) => {
+            let mut octets = a.octets();
+            mask_octets(&mut octets, prefix_len);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        },
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn mask_octets(octets : &mut [u8], prefix_len : u8) {
+
+    let prefix_len = prefix_len as usize;
+
+    for (i, octet) in octets.iter_mut().enumerate() {
+
+        let bit_offset = i * 8;
+
+        if bit_offset + 8 <= prefix_len {
+            continue;
+        }
+
+        if bit_offset >= prefix_len {
+            *octet = 0;
+        } else {
+            *octet &= 0xFFu8 << (8 - (prefix_len - bit_offset));
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,231 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::net::{SocketAddr, UdpSocket};
+use ::std::thread::spawn;
+use ::std::time::Duration;
+use catalog::ZoneObserver;
+use dnsmessage::{Message as DnsMessage, Opcode, Rcode, FLAG_AA, FLAG_RD};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::ResourceRecord;
+use tsig::{TsigKey, TsigSession};
+use udp::MAX_SAFE_UDP_PAYLOAD_LEN;
+use wire::WireReader;
+use zone::Zone;
+
+/*----------------------------------------------------------------------------*/
+
+/// Number of times a NOTIFY is sent again if not answered (RFC 1996, 3.6)
+pub const DEFAULT_NOTIFY_RETRIES : usize = 5;
+
+/// Time to wait for the answer to a NOTIFY before sending it again
+pub const DEFAULT_NOTIFY_INTERVAL_SECS : u64 = 10;
+
+/*----------------------------------------------------------------------------*/
+
+/// Tells the secondaries of zones about new versions (RFC 1996).
+/// As observer of a catalog, every zone replaced there is announced.
+pub struct Notifier {
+
+    targets : Vec<(Name, SocketAddr)>,
+    retries : usize,
+    interval : Duration,
+    key : Option<TsigKey>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Notifier {
+
+    pub fn new() -> Notifier {
+
+        Notifier {
+            targets : Vec::new(),
+            retries : DEFAULT_NOTIFY_RETRIES,
+            interval : Duration::from_secs(DEFAULT_NOTIFY_INTERVAL_SECS),
+            key : None,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Notify the secondary at `target` about changes of zone `origin`
+    pub fn add_target(&mut self, origin : Name, target : SocketAddr) {
+
+        self.targets.push((origin, target));
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Send NOTIFY up to `retries` times more, waiting `interval` for
+    /// the answer each time
+    pub fn set_retries(&mut self, retries : usize, interval : Duration) {
+
+        self.retries = retries;
+        self.interval = interval;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Sign NOTIFY messages with `key` (RFC 8945)
+    pub fn set_key(&mut self, key : TsigKey) {
+
+        self.key = Some(key);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Notify all secondaries of zone `origin` about the version with
+    /// SOA record `soa` in background threads
+    pub fn notify(&self, origin : &Name, soa : &ResourceRecord) {
+
+        let targets = self.targets.iter().filter(|(o, _)| o == origin);
+
+        for (_, target) in targets {
+
+            let (origin, soa, target) = (origin.clone(), soa.clone(), *target);
+            let (retries, interval, key) = (self.retries, self.interval, self.key.clone());
+
+            spawn(move || {
+                let result = send_notify(&target, &origin, &soa, retries, interval, key.as_ref());
+                if let Err(msg) = result {
+                    println!("Could not notify {} about zone {}: {}", target, origin, msg);
+                }
+            });
+
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for Notifier {
+
+    fn default() -> Self {
+        Notifier::new()
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ZoneObserver for Notifier {
+
+    fn zone_changed(&self, origin : &Name, zone : &Zone) {
+
+        if let Some(soa) = zone.soa(origin) {
+            self.notify(origin, &soa);
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Send NOTIFY for zone `origin` with SOA record `soa` to `target` via UDP
+/// and wait for the answer, sending again up to `retries` times.
+/// If `key` is given, NOTIFY is signed and the answer must be signed as well.
+pub fn send_notify(target : &SocketAddr, origin : &Name, soa : &ResourceRecord,
+                   retries : usize, interval : Duration, key : Option<&TsigKey>)
+    -> Result<(), &'static str> {
+
+    let mut notify = DnsMessage::query(DnsMessage::random_id()?, origin.clone(), QuestionType::Soa);
+    notify.set_flag(FLAG_RD, false);
+    notify.set_flag(FLAG_AA, true);
+    notify.set_opcode(Opcode::Notify);
+    notify.answers.push(soa.clone());
+
+    let mut session = key.map(|k| TsigSession::new(k.clone()));
+
+    if let Some(ref mut session) = session {
+        session.sign(&mut notify);
+    }
+
+    let local = match target {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+
+    let socket = UdpSocket::bind(local).map_err(|_| "Could not bind socket")?;
+    socket.set_read_timeout(Some(interval)).map_err(|_| "Could not set timeout")?;
+
+    let bytes = notify.to_vec();
+    let mut buffer = [0u8; MAX_SAFE_UDP_PAYLOAD_LEN];
+
+    for _ in 0 ..= retries {
+
+        socket.send_to(&bytes, target).map_err(|_| "Could not send NOTIFY")?;
+
+        // Skip everything but the answer until timed out
+        while let Ok((len, from)) = socket.recv_from(&mut buffer) {
+
+            let response = match DnsMessage::read(&mut WireReader::new(&buffer[.. len])) {
+                Ok(r) => r,
+                Err(_) => continue,
+            };
+
+            let answers = from == *target && response.is_response() &&
+                notify.id == response.id &&
+                Opcode::Notify as u16 == response.opcode();
+
+            if ! answers {
+                continue;
+            }
+
+            if let Some(ref session) = session {
+                session.clone().verify(&buffer[.. len]).map_err(|e| e.to_str())?;
+            }
+
+            return match Rcode::NoError as u16 == response.rcode() {
+                true => Ok(()),
+                false => Err("NOTIFY refused"),
+            };
+
+        }
+
+    }
+
+    Err("No answer to NOTIFY")
+
+}
+
+/*----------------------------------------------------------------------------*/
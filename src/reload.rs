@@ -0,0 +1,277 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::fs;
+use ::std::sync::Arc;
+use ::std::sync::atomic::{AtomicBool, Ordering};
+use ::std::thread::{sleep, spawn, JoinHandle};
+use ::std::time::{Duration, Instant, SystemTime};
+use catalog::ZoneCatalog;
+use dnsname::Name;
+use dnsrecord::Record;
+use journal::Journal;
+use signal_hook::consts::SIGHUP;
+use signal_hook::flag;
+use zone::Zone;
+use zonefile::load_zone;
+
+/*----------------------------------------------------------------------------*/
+
+/// How often the background thread checks for SIGHUP
+const SIGNAL_CHECK_INTERVAL_MS : u64 = 100;
+
+/*----------------------------------------------------------------------------*/
+
+struct ZoneSource {
+
+    origin : Name,
+    path : String,
+    /// Modification time of the file when it was read last
+    modified : Option<SystemTime>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Keeps the zones of a catalog in sync with their zone files.
+/// A changed file is only swapped into the catalog if it parses and
+/// the serial of its SOA increased, otherwise the old zone keeps
+/// being served.
+pub struct ZoneReloader {
+
+    catalog : Arc<ZoneCatalog>,
+    sources : Vec<ZoneSource>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ZoneReloader {
+
+    pub fn new(catalog : Arc<ZoneCatalog>) -> ZoneReloader {
+
+        ZoneReloader { catalog, sources : Vec::new() }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Load zone with apex `origin` from `path` into the catalog and
+    /// watch the file from now on
+    pub fn add(&mut self, origin : Name, path : &str) -> Result<(), &'static str> {
+
+        self.add_zone(origin, path, None)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// As `add`, additionally recording the changes of the zone in the
+    /// journal at `journal_path`
+    pub fn add_with_journal(&mut self, origin : Name, path : &str, journal_path : &str)
+        -> Result<(), &'static str> {
+
+        self.add_zone(origin, path, Some(journal_path))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn add_zone(&mut self, origin : Name, path : &str, journal_path : Option<&str>)
+        -> Result<(), &'static str> {
+
+        let modified = modification_time(path);
+        let mut zone = load_zone(&origin, path)?;
+
+        if let Some(journal_path) = journal_path {
+            zone.set_journal(Journal::open(journal_path)?);
+        }
+
+        self.catalog.add(origin.clone(), zone)?;
+
+        self.sources.push(ZoneSource {
+            origin,
+            path : path.to_string(),
+            modified,
+        });
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Reload all zones whose files were modified since they were read.
+    /// Returns the number of zones replaced.
+    pub fn reload_changed(&mut self) -> usize {
+
+        self.reload(false)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Reload all zones, regardless of their files being modified.
+    /// Returns the number of zones replaced.
+    pub fn reload_all(&mut self) -> usize {
+
+        self.reload(true)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Reload zones in a background thread: Changed files are picked up
+    /// every `interval`, all zones are reloaded on SIGHUP.
+    pub fn spawn(mut self, interval : Duration) -> Result<JoinHandle<()>, &'static str> {
+
+        let hangup = Arc::new(AtomicBool::new(false));
+
+        if flag::register(SIGHUP, hangup.clone()).is_err() {
+            return Err("Could not register SIGHUP handler");
+        }
+
+        let thread = spawn(move || {
+
+            let mut last_check = Instant::now();
+
+            loop {
+
+                sleep(Duration::from_millis(SIGNAL_CHECK_INTERVAL_MS));
+
+                if hangup.swap(false, Ordering::Relaxed) {
+                    println!("SIGHUP received, reloading all zones");
+                    self.reload_all();
+                } else if last_check.elapsed() >= interval {
+                    self.reload_changed();
+                } else {
+                    continue;
+                }
+
+                last_check = Instant::now();
+
+            }
+
+        });
+
+        Ok(thread)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn reload(&mut self, force : bool) -> usize {
+
+        let mut replaced = 0;
+
+        for source in self.sources.iter_mut() {
+
+            let modified = modification_time(&source.path);
+
+            if ! force && modified == source.modified {
+                continue;
+            }
+
+            // Do not retry a broken file before it is changed again
+            source.modified = modified;
+
+            let zone = match load_zone(&source.origin, &source.path) {
+                Ok(z) => z,
+                Err(msg) => {
+                    println!("Could not reload zone {}: {} - keeping old version",
+                             source.origin, msg);
+                    continue;
+                }
+            };
+
+            let current = self.catalog.get(&source.origin);
+
+            if let Err(msg) = check_serial(current.as_deref(), &source.origin, &zone) {
+                println!("Not reloading zone {}: {} - keeping old version",
+                         source.origin, msg);
+                continue;
+            }
+
+            match self.catalog.replace_if(source.origin.clone(), zone, current.as_ref()) {
+                Ok(_) => {
+                    println!("Reloaded zone {} from {}", source.origin, source.path);
+                    replaced += 1;
+                },
+                Err(msg) => println!("Could not reload zone {}: {} - keeping old version",
+                                     source.origin, msg),
+            };
+
+        }
+
+        replaced
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+fn modification_time(path : &str) -> Option<SystemTime> {
+
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// The serial of `zone` must be greater than the one of `current`
+fn check_serial(current : Option<&Zone>, origin : &Name, zone : &Zone)
+    -> Result<(), &'static str> {
+
+    let soa = |z : &Zone| match z.soa(origin).map(|rr| rr.record) {
+        Some(Record::SOA(soa)) => Some(soa),
+        _ => None,
+    };
+
+    let new = soa(zone).ok_or("Zone requires SOA record at its apex")?;
+
+    let current = match current.and_then(soa) {
+        None => return Ok(()),
+        Some(s) => s,
+    };
+
+    match new.is_newer_than(&current) {
+        true => Ok(()),
+        false => Err("Serial not increased"),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,220 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ::std::fs;
+use ::std::io::ErrorKind;
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use ::std::path::Path;
+use ::std::str::FromStr;
+use ::std::time::Duration;
+use dnsname::Name;
+
+/*----------------------------------------------------------------------------*/
+
+pub const RESOLV_CONF_PATH : &str = "/etc/resolv.conf";
+
+/// Name servers beyond this number are ignored (MAXNS in resolv.h)
+const MAX_NAMESERVERS : usize = 3;
+
+const DEFAULT_NDOTS : usize = 1;
+const MAX_NDOTS : usize = 15;
+
+const DEFAULT_TIMEOUT_SECS : u64 = 5;
+const MAX_TIMEOUT_SECS : u64 = 30;
+
+const DEFAULT_ATTEMPTS : usize = 2;
+const MAX_ATTEMPTS : usize = 5;
+
+const DNS_PORT : u16 = 53;
+
+/*----------------------------------------------------------------------------*/
+
+/// Resolver configuration as read from `/etc/resolv.conf`, interpreted
+/// the way glibc does (see resolv.conf(5)).
+/// Values out of range are clamped, unknown keywords and options ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvConf {
+
+    /// Servers to ask, the loopback address if none is configured
+    pub nameservers : Vec<SocketAddr>,
+    /// Domains to append to names with less than `ndots` dots
+    pub search : Vec<Name>,
+    pub ndots : usize,
+    /// Time to wait for an answer from a server
+    pub timeout : Duration,
+    /// Number of times each server is asked
+    pub attempts : usize,
+    /// Spread queries over the servers rather than asking the first one
+    pub rotate : bool,
+    /// Announce EDNS support in queries
+    pub edns0 : bool,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl ResolvConf {
+
+    pub fn new() -> ResolvConf {
+
+        ResolvConf {
+            nameservers : vec![
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), DNS_PORT)],
+            search : Vec::new(),
+            ndots : DEFAULT_NDOTS,
+            timeout : Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            attempts : DEFAULT_ATTEMPTS,
+            rotate : false,
+            edns0 : false,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read the configuration from `path`.
+    /// A missing file gives the default configuration.
+    pub fn load<P : AsRef<Path>>(path : P) -> Result<ResolvConf, &'static str> {
+
+        match fs::read_to_string(path) {
+            Ok(text) => ResolvConf::from_str(&text),
+            Err(ref e) if ErrorKind::NotFound == e.kind() => Ok(ResolvConf::new()),
+            Err(_) => Err("Could not read resolver configuration"),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn set_option(&mut self, option : &str) {
+
+        let (key, value) = match option.split_once(':') {
+            Some((k, v)) => (k, v.parse::<u64>().ok()),
+            None => (option, None),
+        };
+
+        match (key, value) {
+            ("ndots", Some(n)) => self.ndots = (n as usize).min(MAX_NDOTS),
+            ("timeout", Some(n)) => self.timeout =
+                Duration::from_secs(n.clamp(1, MAX_TIMEOUT_SECS)),
+            ("attempts", Some(n)) => self.attempts = (n as usize).clamp(1, MAX_ATTEMPTS),
+            ("rotate", None) => self.rotate = true,
+            ("edns0", None) => self.edns0 = true,
+            _ => (),
+        };
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for ResolvConf {
+
+    fn default() -> Self {
+        ResolvConf::new()
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for ResolvConf {
+
+    type Err = &'static str;
+
+    fn from_str(s : &str) -> Result<ResolvConf, &'static str> {
+
+        let mut conf = ResolvConf::new();
+        let mut nameservers = Vec::new();
+
+        for line in s.lines() {
+
+            let line = line.split(['#', ';']).next().unwrap_or("");
+
+            let mut fields = line.split_whitespace();
+
+            match fields.next() {
+
+                Some("nameserver") => {
+
+                    // Addresses we cannot parse, like those with a zone
+                    // index, are skipped as glibc does
+                    let addr = fields.next().and_then(|a| IpAddr::from_str(a).ok());
+
+                    if let Some(addr) = addr {
+                        if nameservers.len() < MAX_NAMESERVERS {
+                            nameservers.push(SocketAddr::new(addr, DNS_PORT));
+                        }
+                    }
+
+                },
+
+                // `domain` and `search` replace each other, the last one wins
+                Some("domain") => conf.search = parse_domains(fields.take(1)),
+                Some("search") => conf.search = parse_domains(fields),
+
+                Some("options") => fields.for_each(|o| conf.set_option(o)),
+
+                _ => (),
+
+            };
+
+        }
+
+        if ! nameservers.is_empty() {
+            conf.nameservers = nameservers;
+        }
+
+        Ok(conf)
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Search domains, skipping the root and anything not a name
+fn parse_domains<'a, I : Iterator<Item = &'a str>>(domains : I) -> Vec<Name> {
+
+    domains
+        .filter_map(|d| Name::from_str(d).ok())
+        .filter(|d| ! d.is_root())
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,599 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ::std::collections::HashMap;
+use ::std::net::{IpAddr, SocketAddr};
+use ::std::str::FromStr;
+use ::std::sync::{Arc, Mutex};
+use ::std::time::{Duration, Instant};
+use cache::Cache;
+use client::Client;
+use dnsmessage::{Message as DnsMessage, Rcode, FLAG_AA};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{Record, ResourceRecord};
+
+/*----------------------------------------------------------------------------*/
+
+/// Referrals followed at most to get from the root to the zone of a name
+pub const DEFAULT_MAX_REFERRALS : usize = 16;
+
+/// Queries sent at most to resolve a name, including those to resolve
+/// the addresses of name servers
+pub const DEFAULT_MAX_QUERIES : usize = 64;
+
+/// Time to wait for an answer before asking another server
+pub const DEFAULT_RESOLVER_TIMEOUT_MS : u64 = 1500;
+
+/// Root servers (IANA root hints, named.root)
+const ROOT_HINTS : &[(&str, &str)] = &[
+    ("a.root-servers.net", "198.41.0.4"),
+    ("b.root-servers.net", "170.247.170.2"),
+    ("c.root-servers.net", "192.33.4.12"),
+    ("d.root-servers.net", "199.7.91.13"),
+    ("e.root-servers.net", "192.203.230.10"),
+    ("f.root-servers.net", "192.5.5.241"),
+    ("g.root-servers.net", "192.112.36.4"),
+    ("h.root-servers.net", "198.97.190.53"),
+    ("i.root-servers.net", "192.36.148.17"),
+    ("j.root-servers.net", "192.58.128.30"),
+    ("k.root-servers.net", "193.0.14.129"),
+    ("l.root-servers.net", "199.7.83.42"),
+    ("m.root-servers.net", "202.12.27.33"),
+];
+
+const DNS_PORT : u16 = 53;
+
+const MAX_CNAME_CHAIN_LEN : usize = 8;
+
+/// Resolutions of name server addresses nested at most, to break
+/// delegations depending on each other
+const MAX_GLUELESS_DEPTH : usize = 4;
+
+/// RTT assumed for servers not asked yet
+const UNKNOWN_RTT_MS : u64 = 376;
+
+/// Servers whose RTTs lie within this band are chosen among randomly
+const RTT_BAND_MS : u64 = 400;
+
+const MAX_RTT_MS : u64 = 120_000;
+
+/*----------------------------------------------------------------------------*/
+
+/// Iterative resolver: Resolves names itself, starting from the root
+/// servers and following referrals down to the servers authoritative
+/// for a name.
+///
+/// Among the servers of a zone, those answering fast are preferred,
+/// with some randomness to spread the load and to try others now and
+/// then. Round trip times are tracked across resolutions.
+pub struct Resolver {
+
+    hints : Vec<IpAddr>,
+    port : u16,
+    timeout : Duration,
+    max_referrals : usize,
+    max_queries : usize,
+    /// Smoothed round trip time per server
+    rtt : Mutex<HashMap<IpAddr, Duration>>,
+    cache : Option<Arc<Cache>>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Queries left and nesting of a single resolution
+struct Budget {
+
+    queries : usize,
+    depth : usize,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Resolver {
+
+    pub fn new() -> Resolver {
+
+        Resolver {
+            hints : ROOT_HINTS.iter()
+                .map(|(_, addr)| IpAddr::from_str(addr).unwrap())
+                .collect(),
+            port : DNS_PORT,
+            timeout : Duration::from_millis(DEFAULT_RESOLVER_TIMEOUT_MS),
+            max_referrals : DEFAULT_MAX_REFERRALS,
+            max_queries : DEFAULT_MAX_QUERIES,
+            rtt : Mutex::new(HashMap::new()),
+            cache : None,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Start from the root servers given by `hints` in the format of
+    /// named.root: NS records of the root, and the addresses of the servers
+    pub fn set_root_hints(&mut self, hints : &[ResourceRecord]) -> Result<(), &'static str> {
+
+        let servers : Vec<&Name> = hints.iter()
+            .filter(|rr| rr.name.is_root())
+            .filter_map(|rr| match rr.record {
+                Record::NS(ref server) => Some(server),
+                _ => None,
+            })
+            .collect();
+
+        let addresses : Vec<IpAddr> = hints.iter()
+            .filter(|rr| servers.contains(&&rr.name))
+            .filter_map(|rr| address_of(&rr.record))
+            .collect();
+
+        if addresses.is_empty() {
+            return Err("Root hints lack addresses of root servers");
+        }
+
+        self.hints = addresses;
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn root_hints(&self) -> &[IpAddr] {
+        &self.hints
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Port to send queries to, 53 unless testing
+    pub fn set_port(&mut self, port : u16) {
+
+        self.port = port;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Time to wait for an answer before asking another server
+    pub fn set_timeout(&mut self, timeout : Duration) {
+
+        self.timeout = timeout;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Stop resolving a name after `max_referrals` referrals in a row,
+    /// or after sending `max_queries` queries overall
+    pub fn set_limits(&mut self, max_referrals : usize, max_queries : usize) {
+
+        self.max_referrals = max_referrals;
+        self.max_queries = max_queries;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Answer from `cache` what it holds, and keep the results there
+    pub fn set_cache(&mut self, cache : Arc<Cache>) {
+
+        self.cache = Some(cache);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Smoothed round trip time of `server`, if asked before
+    pub fn rtt(&self, server : &IpAddr) -> Option<Duration> {
+
+        self.rtt.lock().unwrap().get(server).cloned()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Response to a query for `name` and `qtype`, as a recursive server
+    /// would give: The answer carries the CNAME records leading to the
+    /// records asked for. NXDOMAIN and NODATA come with the SOA record
+    /// of the zone, if given.
+    /// Fails if no server could tell, or if running out of limits.
+    pub fn resolve(&self, name : &Name, qtype : QuestionType)
+        -> Result<DnsMessage, &'static str> {
+
+        if let Some(response) = self.cache.as_ref().and_then(|c| c.lookup(name, qtype)) {
+            return Ok(response);
+        }
+
+        let mut budget = Budget {
+            queries : self.max_queries,
+            depth : 0,
+        };
+
+        let response = match self.resolve_with(name, qtype, &mut budget) {
+            Ok(response) => response,
+            // Rather stale records than none at all (RFC 8767)
+            Err(msg) => return self.cache.as_ref()
+                .and_then(|c| c.find(name, qtype, true))
+                .map(|found| found.response)
+                .ok_or(msg),
+        };
+
+        if let Some(ref cache) = self.cache {
+            cache.insert(&response);
+        }
+
+        Ok(response)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn resolve_with(&self, name : &Name, qtype : QuestionType, budget : &mut Budget)
+        -> Result<DnsMessage, &'static str> {
+
+        let mut result = DnsMessage::response_to(&DnsMessage::query(0, name.clone(), qtype));
+        let mut target = name.clone();
+
+        // Chase CNAMEs pointing to other zones
+        for _ in 0 .. MAX_CNAME_CHAIN_LEN {
+
+            let response = self.iterate(&target, qtype, budget)?;
+            let (last, records, complete) = follow_chain(&response, &target, qtype);
+
+            result.answers.extend(records);
+
+            if complete {
+                return Ok(result);
+            }
+
+            let nxdomain = Rcode::NxDomain as u16 == response.rcode();
+
+            if nxdomain || last == target || response.authority.iter().any(is_soa) {
+                if nxdomain {
+                    result.set_rcode(Rcode::NxDomain);
+                }
+                result.authority = response.authority.into_iter().filter(is_soa).collect();
+                return Ok(result);
+            }
+
+            target = last;
+
+        }
+
+        Err("CNAME chain too long")
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Follow referrals from the root down to the servers answering
+    /// for `name`, and return their response
+    fn iterate(&self, name : &Name, qtype : QuestionType, budget : &mut Budget)
+        -> Result<DnsMessage, &'static str> {
+
+        let mut zone = Name::root();
+        let mut servers = self.hints.clone();
+
+        for _ in 0 ..= self.max_referrals {
+
+            let mut response = self.ask(&servers, name, qtype, budget)?;
+
+            // Servers of a zone are not to be trusted for anything outside
+            response.answers.retain(|rr| rr.name.is_subdomain_of(&zone));
+            response.additional.retain(|rr| rr.name.is_subdomain_of(&zone));
+
+            let final_answer = ! response.answers.is_empty() ||
+                Rcode::NxDomain as u16 == response.rcode() ||
+                response.flag(FLAG_AA) || response.authority.iter().any(is_soa);
+
+            if final_answer {
+                return Ok(response);
+            }
+
+            let (cut, nameservers) = delegation(&response, &zone, name)
+                .ok_or("Lame delegation")?;
+
+            servers = self.nameserver_addresses(&response, &nameservers, budget)?;
+            zone = cut;
+
+        }
+
+        Err("Too many referrals")
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Addresses of `nameservers`, taken from the glue of `referral`.
+    /// Without glue, the addresses are resolved.
+    fn nameserver_addresses(&self, referral : &DnsMessage, nameservers : &[Name],
+                            budget : &mut Budget) -> Result<Vec<IpAddr>, &'static str> {
+
+        let glue : Vec<IpAddr> = referral.additional.iter()
+            .filter(|rr| nameservers.contains(&rr.name))
+            .filter_map(|rr| address_of(&rr.record))
+            .collect();
+
+        if ! glue.is_empty() {
+            return Ok(glue);
+        }
+
+        if MAX_GLUELESS_DEPTH <= budget.depth {
+            return Err("Too many glueless delegations");
+        }
+
+        let mut error = "No address for any name server";
+
+        budget.depth += 1;
+
+        for nameserver in nameservers {
+
+            let addresses = self.resolve_with(nameserver, QuestionType::A, budget)
+                .map(|r| r.answers.iter().filter_map(|rr| address_of(&rr.record)).collect());
+
+            match addresses {
+                Ok(ref a) if Vec::<IpAddr>::is_empty(a) => (),
+                Ok(a) => {
+                    budget.depth -= 1;
+                    return Ok(a);
+                },
+                Err(msg) => error = msg,
+            };
+
+        }
+
+        budget.depth -= 1;
+
+        Err(error)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Ask `servers` one by one until one answers, preferring fast ones
+    fn ask(&self, servers : &[IpAddr], name : &Name, qtype : QuestionType,
+           budget : &mut Budget) -> Result<DnsMessage, &'static str> {
+
+        let mut error = "No server to ask";
+
+        for server in self.order(servers)? {
+
+            if 0 == budget.queries {
+                return Err("Too many queries");
+            }
+
+            budget.queries -= 1;
+
+            let mut client = Client::new();
+            client.add_server(SocketAddr::new(server, self.port));
+            client.set_retries(0, self.timeout);
+            client.set_recursion_desired(false);
+
+            let start = Instant::now();
+
+            match client.query(name, qtype) {
+                Ok(response) => {
+                    self.update_rtt(server, start.elapsed());
+                    return Ok(response);
+                },
+                Err(msg) => {
+                    self.penalize(server);
+                    error = msg;
+                },
+            };
+
+        }
+
+        Err(error)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// `servers` in the order to ask them: By RTT, with a random share
+    /// of up to `RTT_BAND_MS` added to each
+    fn order(&self, servers : &[IpAddr]) -> Result<Vec<IpAddr>, &'static str> {
+
+        let rtt = self.rtt.lock().unwrap();
+        let unknown = Duration::from_millis(UNKNOWN_RTT_MS);
+
+        let mut ranked = Vec::new();
+
+        for server in servers {
+
+            if ranked.iter().any(|(_, s)| s == server) {
+                continue;
+            }
+
+            let jitter = Duration::from_millis(random_below(RTT_BAND_MS)?);
+            ranked.push((*rtt.get(server).unwrap_or(&unknown) + jitter, *server));
+
+        }
+
+        ranked.sort();
+
+        Ok(ranked.into_iter().map(|(_, server)| server).collect())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn update_rtt(&self, server : IpAddr, sample : Duration) {
+
+        let mut rtt = self.rtt.lock().unwrap();
+
+        let smoothed = match rtt.get(&server) {
+            None => sample,
+            Some(previous) => *previous * 7 / 10 + sample * 3 / 10,
+        };
+
+        rtt.insert(server, smoothed);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Make `server`, which failed to answer, be asked last for a while
+    fn penalize(&self, server : IpAddr) {
+
+        let mut rtt = self.rtt.lock().unwrap();
+
+        let previous = rtt.get(&server).cloned()
+            .unwrap_or(Duration::from_millis(UNKNOWN_RTT_MS));
+
+        let penalized = (previous * 2).max(self.timeout)
+            .min(Duration::from_millis(MAX_RTT_MS));
+
+        rtt.insert(server, penalized);
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for Resolver {
+
+    fn default() -> Self {
+        Resolver::new()
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Follow the CNAMEs in the answer of `response` from `name`.
+/// Returns the last name reached, the records on the way, and whether
+/// records of type `qtype` were found.
+fn follow_chain(response : &DnsMessage, name : &Name, qtype : QuestionType)
+    -> (Name, Vec<ResourceRecord>, bool) {
+
+    let mut current = name.clone();
+    let mut records = Vec::new();
+
+    for _ in 0 .. MAX_CNAME_CHAIN_LEN {
+
+        let rrset : Vec<ResourceRecord> = response.answers.iter()
+            .filter(|rr| rr.name == current && qtype.code() == rr.type_code())
+            .cloned()
+            .collect();
+
+        if ! rrset.is_empty() {
+            records.extend(rrset);
+            return (current, records, true);
+        }
+
+        let cname = response.answers.iter()
+            .find(|rr| rr.name == current && QuestionType::Cname.code() == rr.type_code());
+
+        match cname {
+            Some(rr) => match rr.record {
+                Record::CNAME(ref target) => {
+                    records.push(rr.clone());
+                    current = target.clone();
+                },
+                _ => break,
+            },
+            None => break,
+        };
+
+    }
+
+    (current, records, false)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Zone cut and name servers `referral` delegates `name` to.
+/// Only delegations below `zone`, the zone of the servers referring,
+/// are followed.
+fn delegation(referral : &DnsMessage, zone : &Name, name : &Name)
+    -> Option<(Name, Vec<Name>)> {
+
+    let ns : Vec<&ResourceRecord> = referral.authority.iter()
+        .filter(|rr| QuestionType::Ns.code() == rr.type_code())
+        .filter(|rr| rr.name != *zone && rr.name.is_subdomain_of(zone))
+        .filter(|rr| name.is_subdomain_of(&rr.name))
+        .collect();
+
+    let cut = ns.first()?.name.clone();
+
+    let nameservers = ns.iter()
+        .filter(|rr| rr.name == cut)
+        .filter_map(|rr| match rr.record {
+            Record::NS(ref server) => Some(server.clone()),
+            _ => None,
+        })
+        .collect();
+
+    Some((cut, nameservers))
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn is_soa(rr : &ResourceRecord) -> bool {
+
+    QuestionType::Soa.code() == rr.type_code()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn address_of(record : &Record) -> Option<IpAddr> {
+
+    match record {
+        Record::A(addr) => Some(IpAddr::V4(*addr)),
+        Record::AAAA(addr) => Some(IpAddr::V6(*addr)),
+        _ => None,
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn random_below(limit : u64) -> Result<u64, &'static str> {
+
+    let mut bytes = [0u8; 8];
+    ::getrandom::getrandom(&mut bytes).map_err(|_| "Could not gather random data")?;
+
+    Ok(u64::from_be_bytes(bytes) % limit)
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,596 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::io::{Read, Write};
+use ::std::net::{SocketAddr, TcpStream};
+use ::std::path::{Path, PathBuf};
+use ::std::sync::{Arc, Condvar, Mutex};
+use ::std::thread::{spawn, JoinHandle};
+use ::std::time::{Duration, Instant};
+use catalog::ZoneCatalog;
+use dnsmessage::{Message as DnsMessage, Rcode, FLAG_RD};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{Record, ResourceRecord, Soa};
+use journal::{Diff, Journal};
+use tsig::{TsigKey, TsigSession};
+use wire::WireReader;
+use zone::Zone;
+
+/*----------------------------------------------------------------------------*/
+
+/// Timeout for connecting to and receiving from the primary
+const TRANSFER_TIMEOUT_SECS : u64 = 10;
+
+/// Delay before retrying a zone that could not be loaded at all
+const INITIAL_RETRY_SECS : u64 = 10;
+
+/*----------------------------------------------------------------------------*/
+
+/// Makes a secondary check its zone right away, e.g. when NOTIFY was
+/// received
+#[derive(Clone, Default)]
+pub struct RefreshTrigger {
+
+    triggered : Arc<(Mutex<bool>, Condvar)>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Keeps a zone of a catalog in sync with the zone on a primary server.
+/// The zone is checked according to the timers of its SOA record:
+/// Every `refresh` seconds, every `retry` seconds if the primary could not
+/// be reached. If the primary cannot be reached for `expire` seconds, the
+/// zone is removed from the catalog.
+pub struct Secondary {
+
+    catalog : Arc<ZoneCatalog>,
+    origin : Name,
+    primary : SocketAddr,
+    journal_path : Option<PathBuf>,
+    key : Option<TsigKey>,
+    trigger : RefreshTrigger,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Secondary {
+
+    pub fn new(catalog : Arc<ZoneCatalog>, origin : Name, primary : SocketAddr) -> Secondary {
+
+        Secondary {
+            catalog,
+            origin,
+            primary,
+            journal_path : None,
+            key : None,
+            trigger : RefreshTrigger::default(),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Record the changes of the zone in the journal at `path`,
+    /// allowing secondaries of our own to transfer them via IXFR
+    pub fn set_journal_path<P : AsRef<Path>>(&mut self, path : P) {
+
+        self.journal_path = Some(path.as_ref().to_path_buf());
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Sign all requests to the primary with `key` (RFC 8945) and
+    /// require its responses to be signed as well
+    pub fn set_key(&mut self, key : TsigKey) {
+
+        self.key = Some(key);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn origin(&self) -> &Name {
+        &self.origin
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn primary(&self) -> &SocketAddr {
+        &self.primary
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Trigger to make the background thread check the zone right away
+    pub fn trigger(&self) -> RefreshTrigger {
+        self.trigger.clone()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Compare the SOA serial of the primary to ours and transfer the
+    /// zone if the primary has got a newer version.
+    /// Returns whether the zone was updated.
+    pub fn refresh(&self) -> Result<bool, &'static str> {
+
+        let current = self.catalog.get(&self.origin);
+
+        if let Some(soa) = current.as_ref().and_then(|z| zone_soa(z, &self.origin)) {
+
+            let primary_soa = query_soa(&self.primary, &self.origin, self.key.as_ref())?;
+
+            if ! primary_soa.is_newer_than(&soa) {
+                return Ok(false);
+            }
+
+        }
+
+        let key = self.key.as_ref();
+
+        match transfer_zone(&self.primary, &self.origin, current.as_deref(), key)? {
+            None => Ok(false),
+            Some(mut zone) => {
+
+                if let (None, Some(path)) = (&current, &self.journal_path) {
+                    zone.set_journal(Journal::open(path)?);
+                }
+
+                self.catalog.replace_if(self.origin.clone(), zone, current.as_ref())?;
+                Ok(true)
+
+            },
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Keep the zone in sync in a background thread
+    pub fn spawn(self) -> JoinHandle<()> {
+
+        spawn(move || {
+
+            let mut last_refresh = None;
+
+            loop {
+
+                let refreshed = match self.refresh() {
+                    Ok(updated) => {
+                        if updated {
+                            println!("Transferred zone {} from {}", self.origin, self.primary);
+                        }
+                        true
+                    },
+                    Err(msg) => {
+                        println!("Could not refresh zone {} from {}: {}",
+                                 self.origin, self.primary, msg);
+                        false
+                    },
+                };
+
+                if refreshed {
+                    last_refresh = Some(Instant::now());
+                }
+
+                let soa = self.catalog.get(&self.origin)
+                    .and_then(|z| zone_soa(&z, &self.origin));
+
+                if let (false, Some(t), Some(soa)) = (refreshed, last_refresh, &soa) {
+                    if t.elapsed() >= Duration::from_secs(soa.expire as u64) {
+                        println!("Zone {} expired", self.origin);
+                        self.catalog.remove(&self.origin);
+                        last_refresh = None;
+                    }
+                }
+
+                self.trigger.wait(next_check(soa.as_ref(), refreshed));
+
+            }
+
+        })
+
+    }
+
+}
+
+impl RefreshTrigger {
+
+    pub fn new() -> RefreshTrigger {
+        RefreshTrigger::default()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn trigger(&self) {
+
+        let (ref triggered, ref condition) = *self.triggered;
+
+        *triggered.lock().unwrap() = true;
+        condition.notify_all();
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Wait until triggered, at most for `timeout`.
+    /// Returns whether triggered.
+    pub fn wait(&self, timeout : Duration) -> bool {
+
+        let (ref triggered, ref condition) = *self.triggered;
+
+        let guard = triggered.lock().unwrap();
+        let (mut guard, _) = condition
+            .wait_timeout_while(guard, timeout, |t| ! *t)
+            .unwrap();
+
+        let was_triggered = *guard;
+        *guard = false;
+
+        was_triggered
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Transfer the zone with apex `origin` from `primary`.
+/// If `current` is given, only the changes since its serial are
+/// requested via IXFR (RFC 1995), falling back to AXFR if the primary
+/// does not support IXFR.
+/// If `key` is given, the transfer is authenticated via TSIG.
+/// Returns None if `current` is up to date.
+pub fn transfer_zone(primary : &SocketAddr, origin : &Name, current : Option<&Zone>,
+                     key : Option<&TsigKey>) -> Result<Option<Zone>, &'static str> {
+
+    if let Some(zone) = current {
+
+        let soa = zone.soa(origin).ok_or("Zone lacks SOA")?;
+        let mut query = transfer_query(origin, QuestionType::Ixfr)?;
+        query.authority.push(soa);
+
+        match request(primary, &query, Some(zone), key) {
+            Ok(records) => return apply_transfer(origin, Some(zone), records),
+            Err(msg) => println!("IXFR of {} failed: {} - trying AXFR", origin, msg),
+        };
+
+    }
+
+    let query = transfer_query(origin, QuestionType::Axfr)?;
+    let records = request(primary, &query, None, key)?;
+
+    apply_transfer(origin, None, records)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// The current SOA of zone `origin` on `primary`
+fn query_soa(primary : &SocketAddr, origin : &Name, key : Option<&TsigKey>)
+    -> Result<Soa, &'static str> {
+
+    let query = transfer_query(origin, QuestionType::Soa)?;
+    let mut stream = connect(primary)?;
+    let mut session = key.map(|k| TsigSession::new(k.clone()));
+
+    let response = exchange(&mut stream, &query, &mut session)?;
+
+    response.answers.iter()
+        .find_map(soa_of)
+        .ok_or("Primary did not return SOA")
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn transfer_query(origin : &Name, qtype : QuestionType) -> Result<DnsMessage, &'static str> {
+
+    let mut query = DnsMessage::query(DnsMessage::random_id()?, origin.clone(), qtype);
+    query.set_flag(FLAG_RD, false);
+
+    Ok(query)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn connect(primary : &SocketAddr) -> Result<TcpStream, &'static str> {
+
+    let timeout = Duration::from_secs(TRANSFER_TIMEOUT_SECS);
+
+    let stream = TcpStream::connect_timeout(primary, timeout)
+        .map_err(|_| "Could not connect to primary")?;
+
+    stream.set_read_timeout(Some(timeout)).map_err(|_| "Could not set timeout")?;
+
+    Ok(stream)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Send `query`, signed by `session` if given, and receive the first
+/// message answering it
+fn exchange(stream : &mut TcpStream, query : &DnsMessage, session : &mut Option<TsigSession>)
+    -> Result<DnsMessage, &'static str> {
+
+    let mut signed = query.clone();
+
+    if let Some(ref mut session) = session {
+        session.sign(&mut signed);
+    }
+
+    let bytes = signed.to_vec();
+
+    stream.write_all(&(bytes.len() as u16).to_be_bytes())
+        .and_then(|_| stream.write_all(&bytes))
+        .map_err(|_| "Could not send query")?;
+
+    receive(stream, query, session)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Receive the next message answering `query`, verified by `session`
+/// if given
+fn receive(stream : &mut TcpStream, query : &DnsMessage, session : &mut Option<TsigSession>)
+    -> Result<DnsMessage, &'static str> {
+
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len).map_err(|_| "Could not receive response")?;
+
+    let mut frame = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut frame).map_err(|_| "Could not receive response")?;
+
+    let response = DnsMessage::read(&mut WireReader::new(&frame))?;
+
+    if ! response.is_response() || query.id != response.id {
+        return Err("Response does not match query");
+    }
+
+    if let Some(ref mut session) = session {
+        session.verify(&frame).map_err(|e| e.to_str())?;
+    }
+
+    if Rcode::NoError as u16 != response.rcode() {
+        return Err("Primary returned error");
+    }
+
+    Ok(response)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Send the transfer `query` and collect the records of all messages
+/// of the response
+fn request(primary : &SocketAddr, query : &DnsMessage, current : Option<&Zone>,
+           key : Option<&TsigKey>) -> Result<Vec<ResourceRecord>, &'static str> {
+
+    let current_soa = current.and_then(|z| zone_soa(z, &query.questions[0].name));
+
+    let mut stream = connect(primary)?;
+    let mut session = key.map(|k| TsigSession::new(k.clone()));
+    let mut records = exchange(&mut stream, query, &mut session)?.answers;
+
+    while ! is_complete(&records, current_soa.as_ref())? {
+        records.extend(receive(&mut stream, query, &mut session)?.answers);
+    }
+
+    Ok(records)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Whether `records` form a complete AXFR or IXFR response
+fn is_complete(records : &[ResourceRecord], current : Option<&Soa>)
+    -> Result<bool, &'static str> {
+
+    let first = match records.first() {
+        None => return Ok(false),
+        Some(rr) => soa_of(rr).ok_or("Transfer does not start with SOA")?,
+    };
+
+    // Only the SOA if we are up to date already
+    if 1 == records.len() {
+        return Ok(current.map(|c| ! first.is_newer_than(c)).unwrap_or(false));
+    }
+
+    let last = soa_of(&records[records.len() - 1]);
+
+    if last.map(|s| s.serial) != Some(first.serial) {
+        return Ok(false);
+    }
+
+    if 2 == records.len() || current.is_none() || soa_of(&records[1]).is_none() {
+        return Ok(true);
+    }
+
+    Ok(parse_diffs(&records[1 ..], first.serial)?.is_some())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Split the records of an IXFR response following the first SOA into
+/// the sequence of differences.
+/// Returns None if the sequence is not terminated by the SOA with `serial`.
+fn parse_diffs(records : &[ResourceRecord], serial : u32)
+    -> Result<Option<Vec<Diff>>, &'static str> {
+
+    let mut diffs = Vec::new();
+    let mut rest = records;
+
+    loop {
+
+        let from = match rest.first() {
+            None => return Ok(None),
+            Some(rr) => rr.clone(),
+        };
+
+        let from_serial = soa_of(&from).ok_or("Malformed IXFR response")?.serial;
+
+        if 1 == rest.len() && serial == from_serial {
+            return Ok(Some(diffs));
+        }
+
+        let deleted_len = match rest[1 ..].iter().position(|rr| soa_of(rr).is_some()) {
+            None => return Ok(None),
+            Some(n) => n,
+        };
+
+        let deleted = rest[1 .. 1 + deleted_len].to_vec();
+        let to = rest[1 + deleted_len].clone();
+        rest = &rest[2 + deleted_len ..];
+
+        let added_len = match rest.iter().position(|rr| soa_of(rr).is_some()) {
+            None => return Ok(None),
+            Some(n) => n,
+        };
+
+        diffs.push(Diff {
+            from,
+            deleted,
+            to,
+            added : rest[.. added_len].to_vec(),
+        });
+
+        rest = &rest[added_len ..];
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Create the zone resulting from the complete transfer response `records`
+fn apply_transfer(origin : &Name, current : Option<&Zone>, records : Vec<ResourceRecord>)
+    -> Result<Option<Zone>, &'static str> {
+
+    let first = soa_of(&records[0]).ok_or("Transfer does not start with SOA")?;
+
+    if 1 == records.len() {
+        return Ok(None);
+    }
+
+    let incremental = 2 < records.len() && soa_of(&records[1]).is_some();
+
+    let zone = match current {
+        Some(zone) if incremental => zone,
+        _ => return build_zone(origin, &records[.. records.len() - 1]).map(Some),
+    };
+
+    let diffs = parse_diffs(&records[1 ..], first.serial)?
+        .ok_or("Incomplete IXFR response")?;
+
+    let mut serial = zone_soa(zone, origin).ok_or("Zone lacks SOA")?.serial;
+    let mut records = zone.records();
+
+    for diff in diffs {
+
+        if serial != diff.from_serial() {
+            return Err("IXFR response does not match our serial");
+        }
+
+        diff.apply(&mut records)?;
+        serial = diff.to_serial();
+
+    }
+
+    build_zone(origin, &records).map(Some)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn build_zone(origin : &Name, records : &[ResourceRecord]) -> Result<Zone, &'static str> {
+
+    let mut zone = Zone::new();
+
+    for rr in records {
+
+        if ! rr.name.is_subdomain_of(origin) {
+            return Err("Record outside of zone");
+        }
+
+        zone.add_with_ttl(rr.name.clone(), rr.ttl, rr.record.clone())?;
+
+    }
+
+    Ok(zone)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn soa_of(rr : &ResourceRecord) -> Option<Soa> {
+
+    match rr.record {
+        Record::SOA(ref soa) => Some(soa.clone()),
+        _ => None,
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn zone_soa(zone : &Zone, origin : &Name) -> Option<Soa> {
+
+    zone.soa(origin).as_ref().and_then(soa_of)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Delay until the next refresh according to the SOA timers
+fn next_check(soa : Option<&Soa>, refreshed : bool) -> Duration {
+
+    let secs = match (soa, refreshed) {
+        (None, _) => INITIAL_RETRY_SECS,
+        (Some(soa), true) => soa.refresh as u64,
+        (Some(soa), false) => soa.retry as u64,
+    };
+
+    Duration::from_secs(secs)
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,136 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use mio::{Poll, Events, Token};
+use mio::event::Event;
+use std::time::Duration;
+
+/*----------------------------------------------------------------------------*/
+
+/// Number of mio tokens reserved for each listener
+pub const TOKEN_SPACE : usize = 1 << 20;
+
+const POLL_TIMEOUT_MS : u64 = 100;
+
+/*----------------------------------------------------------------------------*/
+
+/// Something that waits for events on a `Poll`, e.g. a UDP or TCP server.
+/// Each listener is given a range of TOKEN_SPACE tokens starting at
+/// `first_token` it might use to register its sockets.
+pub trait Listener {
+
+    fn register(&mut self, poll : &Poll, first_token : usize) -> Result<(), &'static str>;
+
+    /// Handle an event for one of the tokens of this listener
+    fn handle_event(&mut self, poll : &Poll, event : &Event);
+
+    /// Called after each poll cycle, e.g. to send queued data or
+    /// enforce timeouts
+    fn handle_tick(&mut self, poll : &Poll);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Event loop driving several listeners on one mio `Poll`
+pub struct Server<'a> {
+
+    poll : Poll,
+    listeners : Vec<Box<dyn Listener + 'a>>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl<'a> Server<'a> {
+
+    pub fn new() -> Result<Server<'a>, &'static str> {
+
+        let poll = match Poll::new() {
+            Ok(p) => p,
+            Err(_) => return Err("could not create mio:Poll")
+        };
+
+        Ok(Server { poll, listeners : Vec::new() })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn add<L : Listener + 'a>(&mut self, mut listener : L) -> Result<(), &'static str> {
+
+        let first_token = self.listeners.len() * TOKEN_SPACE;
+        listener.register(&self.poll, first_token)?;
+        self.listeners.push(Box::new(listener));
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn run(&mut self) {
+
+        let mut events = Events::with_capacity(1024);
+        let timeout = Duration::from_millis(POLL_TIMEOUT_MS);
+
+        loop {
+
+            let result = self.poll.poll(&mut events, Some(timeout));
+
+            if result.is_err() {
+                println!("Exception occured during polling");
+                continue;
+            };
+
+            for event in &events {
+
+                let Token(token) = event.token();
+
+                match self.listeners.get_mut(token / TOKEN_SPACE) {
+                    Some(listener) => listener.handle_event(&self.poll, &event),
+                    None => println!("Event for unknown token {}", token),
+                };
+
+            }
+
+            for listener in self.listeners.iter_mut() {
+                listener.handle_tick(&self.poll);
+            }
+
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,840 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ::std::collections::HashSet;
+use ::std::fmt;
+use ::std::fs;
+use ::std::path::Path;
+use ::std::str::FromStr;
+use ::std::time::{Duration, SystemTime, UNIX_EPOCH};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{base64_to_bytes, bytes_to_base64, tokenize, Record, ResourceRecord};
+use dnssec::{bytes_to_base32hex, nsec3_hash, Dnskey, Ds, Nsec, Nsec3, Nsec3Param, Rrsig};
+use dnssec::{ALGORITHM_ECDSAP256SHA256, ALGORITHM_ED25519, DIGEST_SHA256};
+use dnssec::{DNSKEY_FLAG_SEP, DNSKEY_FLAG_ZONE, DNSKEY_PROTOCOL, NSEC3_FLAG_OPT_OUT};
+use dnssec::NSEC3_HASH_SHA1;
+use ring::rand::SystemRandom;
+use ring::signature::{self, EcdsaKeyPair, Ed25519KeyPair, KeyPair as RingKeyPair};
+use zone::Zone;
+
+/*----------------------------------------------------------------------------*/
+
+/// How long signatures we create are valid
+pub const DEFAULT_SIGNATURE_VALIDITY_SECS : u32 = 14 * 86400;
+
+/// Signatures are valid from a bit in the past to cope with clock skew
+const INCEPTION_OFFSET_SECS : u32 = 3600;
+
+/*----------------------------------------------------------------------------*/
+
+/// Algorithms we are able to sign with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SigningAlgorithm {
+
+    EcdsaP256Sha256,
+    Ed25519,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+enum KeyPair {
+
+    Ecdsa(EcdsaKeyPair),
+    Ed25519(Ed25519KeyPair),
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Private key to sign zones with, along with its public DNSKEY
+pub struct SigningKey {
+
+    algorithm : SigningAlgorithm,
+    /// Private key in PKCS#8 format
+    pkcs8 : Vec<u8>,
+    key_pair : KeyPair,
+    dnskey : Dnskey,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Signs zones (RFC 4035, 2): Every authoritative RRset gets signed, the
+/// DNSKEY, CDS and CDNSKEY RRsets are published at the apex and names
+/// are chained by NSEC or NSEC3 records.
+/// Keys with the SEP flag set sign the DNSKEY, CDS and CDNSKEY RRsets,
+/// the others everything else. If there is no key for either role,
+/// all keys sign.
+pub struct Signer {
+
+    keys : Vec<SigningKey>,
+    /// Chain names via NSEC3 with these parameters rather than via NSEC.
+    /// The flags carry the opt-out flag of the NSEC3 records.
+    nsec3 : Option<Nsec3Param>,
+    validity : u32,
+    /// Signatures expiring within this time are renewed
+    refresh : u32,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl SigningAlgorithm {
+
+    /// Algorithm number as used in DNSKEY and RRSIG records
+    pub fn number(self) -> u8 {
+
+        match self {
+            SigningAlgorithm::EcdsaP256Sha256 => ALGORITHM_ECDSAP256SHA256,
+            SigningAlgorithm::Ed25519 => ALGORITHM_ED25519,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn from_number(number : u8) -> Option<SigningAlgorithm> {
+
+        match number {
+            ALGORITHM_ECDSAP256SHA256 => Some(SigningAlgorithm::EcdsaP256Sha256),
+            ALGORITHM_ED25519 => Some(SigningAlgorithm::Ed25519),
+            _ => None,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn name(self) -> &'static str {
+
+        match self {
+            SigningAlgorithm::EcdsaP256Sha256 => "ECDSAP256SHA256",
+            SigningAlgorithm::Ed25519 => "ED25519",
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for SigningAlgorithm {
+
+    type Err = &'static str;
+
+    /// Parse mnemonic or number of the algorithm
+    fn from_str(s : &str) -> Result<SigningAlgorithm, &'static str> {
+
+        match s.to_uppercase().as_str() {
+            "ECDSAP256SHA256" => Ok(SigningAlgorithm::EcdsaP256Sha256),
+            "ED25519" => Ok(SigningAlgorithm::Ed25519),
+            n => u8::from_str(n).ok()
+                .and_then(SigningAlgorithm::from_number)
+                .ok_or("Unsupported signing algorithm"),
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl SigningKey {
+
+    /// Generate a new key. `sep` sets the SEP flag, marking the key as
+    /// key signing key.
+    pub fn generate(algorithm : SigningAlgorithm, sep : bool)
+        -> Result<SigningKey, &'static str> {
+
+        let rng = SystemRandom::new();
+
+        let pkcs8 = match algorithm {
+            SigningAlgorithm::EcdsaP256Sha256 => EcdsaKeyPair::generate_pkcs8(
+                &signature::ECDSA_P256_SHA256_FIXED_SIGNING, &rng),
+            SigningAlgorithm::Ed25519 => Ed25519KeyPair::generate_pkcs8(&rng),
+        }.map_err(|_| "Could not generate key")?;
+
+        let flags = DNSKEY_FLAG_ZONE | if sep { DNSKEY_FLAG_SEP } else { 0 };
+
+        SigningKey::from_pkcs8(algorithm, flags, pkcs8.as_ref())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Key from its private key in PKCS#8 format. `flags` go into the DNSKEY.
+    pub fn from_pkcs8(algorithm : SigningAlgorithm, flags : u16, pkcs8 : &[u8])
+        -> Result<SigningKey, &'static str> {
+
+        let key_pair = match algorithm {
+            SigningAlgorithm::EcdsaP256Sha256 => EcdsaKeyPair::from_pkcs8(
+                &signature::ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8, &SystemRandom::new())
+                .map(KeyPair::Ecdsa),
+            SigningAlgorithm::Ed25519 => Ed25519KeyPair::from_pkcs8(pkcs8)
+                .map(KeyPair::Ed25519),
+        }.map_err(|_| "Could not parse private key")?;
+
+        // ECDSA public keys go without the leading 0x04 (RFC 6605, 4)
+        let public_key = match key_pair {
+            KeyPair::Ecdsa(ref k) => k.public_key().as_ref()[1 ..].to_vec(),
+            KeyPair::Ed25519(ref k) => k.public_key().as_ref().to_vec(),
+        };
+
+        let dnskey = Dnskey {
+            flags,
+            protocol : DNSKEY_PROTOCOL,
+            algorithm : algorithm.number(),
+            public_key,
+        };
+
+        Ok(SigningKey { algorithm, pkcs8 : pkcs8.to_vec(), key_pair, dnskey })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn algorithm(&self) -> SigningAlgorithm {
+        self.algorithm
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn dnskey(&self) -> &Dnskey {
+        &self.dnskey
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn key_tag(&self) -> u16 {
+        self.dnskey.key_tag()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn is_sep(&self) -> bool {
+        self.dnskey.is_sep()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Signature over `data` in the format of RRSIG records
+    pub fn sign(&self, data : &[u8]) -> Result<Vec<u8>, &'static str> {
+
+        match self.key_pair {
+            KeyPair::Ecdsa(ref k) => k.sign(&SystemRandom::new(), data)
+                .map(|s| s.as_ref().to_vec())
+                .map_err(|_| "Could not sign"),
+            KeyPair::Ed25519(ref k) => Ok(k.sign(data).as_ref().to_vec()),
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for SigningKey {
+
+    type Err = &'static str;
+
+    /// Parse `FLAGS ALGORITHM PRIVATE_KEY`, the private key as base64
+    /// encoded PKCS#8
+    fn from_str(s : &str) -> Result<SigningKey, &'static str> {
+
+        let fields = tokenize(s)?;
+
+        if 3 != fields.len() {
+            return Err("Malformed key: Expected flags, algorithm and private key");
+        }
+
+        let flags = u16::from_str(&fields[0]).map_err(|_| "Could not parse key flags")?;
+        let algorithm = SigningAlgorithm::from_str(&fields[1])?;
+
+        SigningKey::from_pkcs8(algorithm, flags, &base64_to_bytes(&fields[2])?)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for SigningKey {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {} {}", self.dnskey.flags, self.algorithm.name(),
+               bytes_to_base64(&self.pkcs8))
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Debug for SigningKey {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "SigningKey {{ {}, tag {} }}", self.algorithm.name(), self.key_tag())
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Signer {
+
+    pub fn new() -> Signer {
+
+        Signer {
+            keys : Vec::new(),
+            nsec3 : None,
+            validity : DEFAULT_SIGNATURE_VALIDITY_SECS,
+            refresh : DEFAULT_SIGNATURE_VALIDITY_SECS / 4,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Signer using the keys of file `path`, one per line as
+    /// `FLAGS ALGORITHM PRIVATE_KEY`
+    pub fn load<P : AsRef<Path>>(path : P) -> Result<Signer, &'static str> {
+
+        let text = fs::read_to_string(path).map_err(|_| "Could not read key file")?;
+        Signer::from_str(&text)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn add_key(&mut self, key : SigningKey) {
+        self.keys.push(key);
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn keys(&self) -> &[SigningKey] {
+        &self.keys
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Chain names via NSEC3 (RFC 5155) rather than NSEC. With `opt_out`,
+    /// delegations without DS are left out of the chain.
+    pub fn set_nsec3(&mut self, iterations : u16, salt : Vec<u8>, opt_out : bool) {
+
+        self.nsec3 = Some(Nsec3Param {
+            hash_algorithm : NSEC3_HASH_SHA1,
+            flags : if opt_out { NSEC3_FLAG_OPT_OUT } else { 0 },
+            iterations,
+            salt,
+        });
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Create signatures valid for `validity`. Signatures are renewed
+    /// once only a quarter of it is left.
+    pub fn set_validity(&mut self, validity : Duration) {
+
+        self.validity = validity.as_secs().min(u32::MAX as u64) as u32;
+        self.refresh = self.validity / 4;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Sign `zone` with apex `origin`, see `sign_at`
+    pub fn sign(&self, origin : &Name, zone : &Zone, previous : Option<&Zone>)
+        -> Result<Zone, &'static str> {
+
+        self.sign_at(origin, zone, previous, now())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Create a signed copy of `zone` with apex `origin` at time `now`,
+    /// given in seconds since the epoch.
+    /// DNSSEC records the zone already contains are replaced, except DS.
+    /// Signatures of `previous`, an earlier signed version of the zone,
+    /// are taken over for RRsets that did not change, as long as they
+    /// are not about to expire.
+    /// Subnet variants are not signed.
+    pub fn sign_at(&self, origin : &Name, zone : &Zone, previous : Option<&Zone>, now : u32)
+        -> Result<Zone, &'static str> {
+
+        if self.keys.is_empty() {
+            return Err("No keys to sign with");
+        }
+
+        let soa = zone.soa(origin).ok_or("Zone requires SOA record at its apex")?;
+
+        let negative_ttl = match soa.record {
+            Record::SOA(ref data) => soa.ttl.min(data.minimum),
+            _ => soa.ttl,
+        };
+
+        let mut signed = strip_dnssec(origin, zone);
+
+        for key in &self.keys {
+
+            let dnskey = key.dnskey();
+            signed.add_with_ttl(origin.clone(), soa.ttl, Record::DNSKEY(dnskey.clone()))?;
+
+            // Tell the parent about key signing keys (RFC 7344)
+            if key.is_sep() {
+                let ds = Ds::from_dnskey(origin, dnskey, DIGEST_SHA256)?;
+                signed.add_with_ttl(origin.clone(), soa.ttl, Record::CDS(ds))?;
+                signed.add_with_ttl(origin.clone(), soa.ttl, Record::CDNSKEY(dnskey.clone()))?;
+            }
+
+        }
+
+        match self.nsec3 {
+            None => add_nsec_chain(&mut signed, origin, negative_ttl)?,
+            Some(ref param) => add_nsec3_chain(&mut signed, origin, param, negative_ttl)?,
+        };
+
+        for (name, records) in authoritative_names(origin, &signed) {
+
+            let delegation = is_delegation(origin, &name, &records);
+
+            for rrset in rrsets(records) {
+
+                let type_code = rrset[0].type_code();
+
+                // Delegations are authoritative for DS and NSEC only
+                let signed_at_delegation = [QuestionType::Ds, QuestionType::Nsec]
+                    .iter().any(|t| t.code() == type_code);
+
+                if delegation && ! signed_at_delegation {
+                    continue;
+                }
+
+                for key in self.keys_for(type_code) {
+
+                    let rrsig = match previous.and_then(|p| self.reusable(p, origin, &rrset, key, now)) {
+                        Some(rrsig) => rrsig,
+                        None => self.create(origin, &rrset, key, now)?,
+                    };
+
+                    signed.add_with_ttl(name.clone(), rrset[0].ttl, Record::RRSIG(rrsig))?;
+
+                }
+
+            }
+
+        }
+
+        Ok(signed)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether signatures of `zone` expire soon or are missing entirely,
+    /// thus the zone should be signed again
+    pub fn needs_refresh(&self, zone : &Zone, now : u32) -> bool {
+
+        let mut signed = false;
+
+        for rr in zone.records() {
+
+            if let Record::RRSIG(ref rrsig) = rr.record {
+
+                if ! self.is_fresh(rrsig, now) {
+                    return true;
+                }
+
+                signed = true;
+
+            }
+
+        }
+
+        ! signed
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Keys to sign RRsets of type `type_code` with
+    fn keys_for(&self, type_code : u16) -> Vec<&SigningKey> {
+
+        let key_set = [QuestionType::Dnskey, QuestionType::Cds, QuestionType::Cdnskey]
+            .iter().any(|t| t.code() == type_code);
+
+        let keys : Vec<&SigningKey> = self.keys.iter()
+            .filter(|k| key_set == k.is_sep())
+            .collect();
+
+        match keys.is_empty() {
+            true => self.keys.iter().collect(),
+            false => keys,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn create(&self, origin : &Name, rrset : &[ResourceRecord], key : &SigningKey, now : u32)
+        -> Result<Rrsig, &'static str> {
+
+        let owner = &rrset[0].name;
+        let wildcard = if owner.is_wildcard() { 1 } else { 0 };
+
+        let mut rrsig = Rrsig {
+            type_covered : rrset[0].type_code(),
+            algorithm : key.dnskey().algorithm,
+            labels : (owner.label_count() - wildcard) as u8,
+            original_ttl : rrset[0].ttl,
+            expiration : now.wrapping_add(self.validity),
+            inception : now.wrapping_sub(INCEPTION_OFFSET_SECS),
+            key_tag : key.key_tag(),
+            signer : origin.clone(),
+            signature : Vec::new(),
+        };
+
+        rrsig.signature = key.sign(&rrsig.signed_data(rrset))?;
+
+        Ok(rrsig)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Signature by `key` over `rrset` found in `previous`, provided the
+    /// RRset is the same there and the signature is not about to expire
+    fn reusable(&self, previous : &Zone, origin : &Name, rrset : &[ResourceRecord],
+                key : &SigningKey, now : u32) -> Option<Rrsig> {
+
+        let name = &rrset[0].name;
+        let type_code = rrset[0].type_code();
+        let old = previous.rrset(name, type_code);
+
+        let unchanged = old.len() == rrset.len() && rrset.iter()
+            .all(|rr| old.iter().any(|o| o.ttl == rr.ttl && o.record == rr.record));
+
+        if ! unchanged {
+            return None;
+        }
+
+        previous.rrset(name, QuestionType::Rrsig.code()).into_iter()
+            .filter_map(|rr| match rr.record {
+                Record::RRSIG(rrsig) => Some(rrsig),
+                _ => None,
+            })
+            .find(|rrsig| type_code == rrsig.type_covered &&
+                  key.key_tag() == rrsig.key_tag &&
+                  key.dnskey().algorithm == rrsig.algorithm &&
+                  rrset[0].ttl == rrsig.original_ttl &&
+                  *origin == rrsig.signer &&
+                  self.is_fresh(rrsig, now))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether `rrsig` is valid at `now` and will be for longer than
+    /// the refresh period
+    fn is_fresh(&self, rrsig : &Rrsig, now : u32) -> bool {
+
+        0 <= now.wrapping_sub(rrsig.inception) as i32 &&
+            (self.refresh as i32) < rrsig.expiration.wrapping_sub(now) as i32
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for Signer {
+
+    type Err = &'static str;
+
+    /// Parse keys, one per line as `FLAGS ALGORITHM PRIVATE_KEY`
+    fn from_str(s : &str) -> Result<Signer, &'static str> {
+
+        let mut signer = Signer::new();
+
+        for line in s.lines() {
+
+            if tokenize(line)?.is_empty() {
+                continue;
+            }
+
+            signer.add_key(SigningKey::from_str(line)?);
+
+        }
+
+        Ok(signer)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for Signer {
+
+    fn default() -> Self {
+        Signer::new()
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Seconds since the epoch, in serial number arithmetic
+pub fn now() -> u32 {
+
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or(0)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Copy of `zone` without the records created when signing
+fn strip_dnssec(origin : &Name, zone : &Zone) -> Zone {
+
+    let mut stripped = zone.clone();
+
+    let generated = [QuestionType::Rrsig, QuestionType::Nsec, QuestionType::Nsec3,
+                     QuestionType::Nsec3param];
+
+    let apex = [QuestionType::Dnskey, QuestionType::Cds, QuestionType::Cdnskey];
+
+    for rr in zone.records() {
+
+        let type_code = rr.type_code();
+
+        if generated.iter().any(|t| t.code() == type_code) ||
+            (rr.name == *origin && apex.iter().any(|t| t.code() == type_code)) {
+            stripped.remove(&rr.name, &rr.record);
+        }
+
+    }
+
+    stripped
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Names the zone is authoritative for along with their records, in
+/// canonical order. Delegations are included, names below them - glue -
+/// are not.
+fn authoritative_names(origin : &Name, zone : &Zone) -> Vec<(Name, Vec<ResourceRecord>)> {
+
+    let mut names : Vec<(Name, Vec<ResourceRecord>)> = Vec::new();
+
+    // Names above are ordered before names below
+    for rr in zone.records() {
+
+        match names.last_mut() {
+            Some((ref name, ref mut records)) if *name == rr.name => records.push(rr),
+            _ => names.push((rr.name.clone(), vec![rr])),
+        };
+
+    }
+
+    let delegations : Vec<Name> = names.iter()
+        .filter(|(name, records)| is_delegation(origin, name, records))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    names.retain(|(name, _)| name.is_subdomain_of(origin) &&
+                 ! delegations.iter().any(|d| name != d && name.is_subdomain_of(d)));
+
+    names.sort_by(|a, b| a.0.canonical_cmp(&b.0));
+
+    names
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn is_delegation(origin : &Name, name : &Name, records : &[ResourceRecord]) -> bool {
+
+    name != origin && records.iter().any(|rr| QuestionType::Ns.code() == rr.type_code())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Split `records` of one name into RRsets
+fn rrsets(records : Vec<ResourceRecord>) -> Vec<Vec<ResourceRecord>> {
+
+    let mut rrsets : Vec<Vec<ResourceRecord>> = Vec::new();
+
+    for rr in records {
+
+        match rrsets.iter_mut().find(|s| s[0].type_code() == rr.type_code()) {
+            Some(rrset) => rrset.push(rr),
+            None => rrsets.push(vec![rr]),
+        };
+
+    }
+
+    rrsets
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn types_of(records : &[ResourceRecord]) -> Vec<u16> {
+
+    let mut types : Vec<u16> = records.iter().map(|rr| rr.type_code()).collect();
+
+    types.sort_unstable();
+    types.dedup();
+
+    types
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Link all authoritative names of `zone` by NSEC records (RFC 4034, 4)
+fn add_nsec_chain(zone : &mut Zone, origin : &Name, ttl : u32) -> Result<(), &'static str> {
+
+    let names = authoritative_names(origin, zone);
+
+    for (i, (name, records)) in names.iter().enumerate() {
+
+        let mut types = types_of(records);
+        types.push(QuestionType::Rrsig.code());
+        types.push(QuestionType::Nsec.code());
+        types.sort_unstable();
+
+        let next = names[(i + 1) % names.len()].0.clone();
+
+        zone.add_with_ttl(name.clone(), ttl, Record::NSEC(Nsec { next, types }))?;
+
+    }
+
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Link the hashes of all authoritative names of `zone` and the empty
+/// non-terminals between them by NSEC3 records (RFC 5155, 7.1)
+fn add_nsec3_chain(zone : &mut Zone, origin : &Name, param : &Nsec3Param, ttl : u32)
+    -> Result<(), &'static str> {
+
+    let opt_out = 0 != param.flags & NSEC3_FLAG_OPT_OUT;
+
+    zone.add_with_ttl(origin.clone(), ttl, Record::NSEC3PARAM(Nsec3Param {
+        flags : 0,
+        ..param.clone()
+    }))?;
+
+    let mut hashed : Vec<(Vec<u8>, Vec<u16>)> = Vec::new();
+    let mut names : HashSet<Name> = HashSet::new();
+
+    for (name, records) in authoritative_names(origin, zone) {
+
+        let mut types = types_of(&records);
+
+        let insecure = is_delegation(origin, &name, &records) &&
+            ! types.contains(&(QuestionType::Ds.code()));
+
+        if insecure && opt_out {
+            continue;
+        }
+
+        if ! insecure {
+            types.push(QuestionType::Rrsig.code());
+            types.sort_unstable();
+        }
+
+        // Empty non-terminals up to the apex
+        let mut parent = name.parent();
+
+        while let Some(p) = parent {
+
+            if ! p.is_subdomain_of(origin) || names.contains(&p) ||
+                ! zone.records_at(&p).is_empty() {
+                break;
+            }
+
+            hashed.push((nsec3_hash(&p, &param.salt, param.iterations), Vec::new()));
+            names.insert(p.clone());
+            parent = p.parent();
+
+        }
+
+        hashed.push((nsec3_hash(&name, &param.salt, param.iterations), types));
+        names.insert(name);
+
+    }
+
+    hashed.sort();
+
+    if hashed.windows(2).any(|w| w[0].0 == w[1].0) {
+        return Err("NSEC3 hash collision");
+    }
+
+    for (i, (hash, types)) in hashed.iter().enumerate() {
+
+        let owner = Name::from_str(&bytes_to_base32hex(hash))?.join(origin);
+
+        zone.add_with_ttl(owner, ttl, Record::NSEC3(Nsec3 {
+            hash_algorithm : param.hash_algorithm,
+            flags : param.flags,
+            iterations : param.iterations,
+            salt : param.salt.clone(),
+            next_hashed : hashed[(i + 1) % hashed.len()].0.clone(),
+            types : types.clone(),
+        }))?;
+
+    }
+
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,566 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use mio::{Poll, Ready, Registration, SetReadiness, Token, PollOpt};
+use mio::event::Event;
+use mio::net::{TcpListener, TcpStream};
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use server::{Listener, Server, TOKEN_SPACE};
+use udp::{Handler, Message, Responder, Transport};
+
+/*----------------------------------------------------------------------------*/
+
+const LENGTH_PREFIX_LEN : usize = 2;
+const READ_CHUNK_LEN : usize = 4096;
+/// Length prefix plus the largest message it can announce
+const MAX_FRAME_LEN : usize = LENGTH_PREFIX_LEN + u16::MAX as usize;
+
+/// Local token of the listening socket
+const LISTENER_TOKEN : usize = 0;
+/// Local token of the registration responders signal
+const WAKEUP_TOKEN : usize = 1;
+/// Local tokens of connections start here
+const FIRST_CONNECTION_TOKEN : usize = 2;
+
+/*----------------------------------------------------------------------------*/
+
+/// Limits for DNS over TCP (RFC 7766)
+#[derive(Clone, Debug)]
+pub struct TcpConfig {
+
+    /// Close connections without any traffic for this long
+    pub idle_timeout : Duration,
+    /// Max number of connections open at the same time
+    pub max_connections : usize,
+    /// Max number of connections from a single client address
+    pub max_connections_per_client : usize,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+struct Connection {
+
+    stream : TcpStream,
+    peer : SocketAddr,
+    read_buffer : Vec<u8>,
+    write_buffer : Vec<u8>,
+    last_activity : Instant,
+    /// Number of queries not answered yet
+    outstanding : usize,
+    /// Client shut down its sending side
+    peer_closed : bool,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Serves DNS over TCP, with every message prefixed by its length
+/// as two octets. Clients might send several queries without waiting
+/// for the responses, which are sent in the order they become available.
+pub struct TcpServer<'a> {
+
+    listener : TcpListener,
+    out_queue : Arc<Mutex<VecDeque<Message>>>,
+    handler : &'a dyn Handler,
+    config : TcpConfig,
+    wakeup : Registration,
+    wakeup_trigger : SetReadiness,
+    first_token : usize,
+    next_token : usize,
+    connections : HashMap<usize, Connection>,
+    peers : HashMap<SocketAddr, usize>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for TcpConfig {
+
+    fn default() -> Self {
+
+        TcpConfig {
+            idle_timeout : Duration::from_secs(10),
+            max_connections : 256,
+            max_connections_per_client : 8,
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl<'a> TcpServer<'a> {
+
+    pub fn bind_to<TH : Handler> (
+        listen_addr_str : &str,
+        handler : &'a TH)
+        -> Result<TcpServer<'a>, &'static str> {
+
+        let max_queue_len = 200;
+
+        let listen_addr = match listen_addr_str.parse() {
+            Ok(addr) => addr,
+            Err(_) => return Err("Could not parse address string")
+        };
+
+        let listener = match TcpListener::bind(&listen_addr) {
+            Ok(l) => l,
+            Err(_) => return Err("Could not bind to socket")
+        };
+
+        let out_queue = Arc::new(Mutex::new(VecDeque::with_capacity(max_queue_len)));
+        let (wakeup, wakeup_trigger) = Registration::new2();
+
+        Ok(TcpServer {
+            listener,
+            out_queue,
+            handler,
+            config : TcpConfig::default(),
+            wakeup,
+            wakeup_trigger,
+            first_token : 0,
+            next_token : FIRST_CONNECTION_TOKEN,
+            connections : HashMap::new(),
+            peers : HashMap::new(),
+        })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn set_config(&mut self, config : TcpConfig) {
+        self.config = config;
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn local_addr(&self) -> Result<SocketAddr, &'static str> {
+
+        self.listener.local_addr().map_err(|_| "Could not get local address")
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Serve on a `Server` of its own
+    pub fn run(self) {
+
+        let mut server = match Server::new() {
+            Ok(s) => s,
+            Err(msg) => {
+                println!("{}", msg);
+                return;
+            }
+        };
+
+        if let Err(msg) = server.add(self) {
+            println!("{}", msg);
+            return;
+        }
+
+        server.run();
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn accept(&mut self, poll : &Poll) {
+
+        loop {
+
+            let (stream, peer) = match self.listener.accept() {
+                Ok(s) => s,
+                Err(ref e) if ErrorKind::WouldBlock == e.kind() => return,
+                Err(_) => {
+                    println!("Could not accept connection");
+                    return;
+                }
+            };
+
+            let from_peer = self.connections.values()
+                .filter(|c| c.peer.ip() == peer.ip())
+                .count();
+
+            if self.connections.len() >= self.config.max_connections ||
+                from_peer >= self.config.max_connections_per_client {
+                println!("Refusing connection from {}", peer);
+                stream.shutdown(Shutdown::Both).ok();
+                continue;
+            }
+
+            let local_token = match self.allocate_token() {
+                Some(t) => t,
+                None => {
+                    stream.shutdown(Shutdown::Both).ok();
+                    continue;
+                }
+            };
+
+            if poll.register(
+                &stream,
+                Token(self.first_token + local_token),
+                Ready::readable() | Ready::writable(),
+                PollOpt::edge()).is_err() {
+
+                println!("Could not register connection from {}", peer);
+                continue;
+
+            }
+
+            self.peers.insert(peer, local_token);
+            self.connections.insert(local_token, Connection {
+                stream,
+                peer,
+                read_buffer : Vec::new(),
+                write_buffer : Vec::new(),
+                last_activity : Instant::now(),
+                outstanding : 0,
+                peer_closed : false,
+            });
+
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn allocate_token(&mut self) -> Option<usize> {
+
+        for _ in FIRST_CONNECTION_TOKEN .. TOKEN_SPACE {
+
+            let token = self.next_token;
+
+            self.next_token += 1;
+            if self.next_token >= TOKEN_SPACE {
+                self.next_token = FIRST_CONNECTION_TOKEN;
+            }
+
+            if ! self.connections.contains_key(&token) {
+                return Some(token);
+            }
+
+        }
+
+        None
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn read(&mut self, local_token : usize) -> Result<(), &'static str> {
+
+        let connection = match self.connections.get_mut(&local_token) {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+
+        let mut chunk = [0u8; READ_CHUNK_LEN];
+
+        loop {
+
+            match connection.stream.read(&mut chunk) {
+                Ok(0) => {
+                    connection.peer_closed = true;
+                    break;
+                },
+                Ok(n) => connection.read_buffer.extend_from_slice(&chunk[.. n]),
+                Err(ref e) if ErrorKind::WouldBlock == e.kind() => break,
+                Err(ref e) if ErrorKind::Interrupted == e.kind() => continue,
+                Err(_) => return Err("Could not read from connection"),
+            };
+
+            connection.last_activity = Instant::now();
+
+            // Hand over complete messages right away to keep the buffer small
+            while let Some(buffer) = next_frame(&mut connection.read_buffer) {
+
+                connection.outstanding += 1;
+
+                let responder = Responder::new(
+                    connection.peer,
+                    Transport::Tcp,
+                    self.out_queue.clone(),
+                    self.wakeup_trigger.clone());
+
+                self.handler.handle(Message {
+                    addr : connection.peer,
+                    transport : Transport::Tcp,
+                    num_bytes : buffer.len(),
+                    buffer,
+                    cookie : None,
+                    max_response_len : None,
+                }, responder);
+
+            }
+
+            if connection.read_buffer.len() > MAX_FRAME_LEN {
+                return Err("Message exceeds max TCP message length");
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn queue_responses(&mut self) {
+
+        loop {
+
+            let next = self.out_queue.lock().unwrap().pop_back();
+
+            let msg = match next {
+                None => return,
+                Some(m) => m,
+            };
+
+            let connections = &mut self.connections;
+            let connection = self.peers.get(&msg.addr)
+                .and_then(|t| connections.get_mut(t));
+
+            let connection = match connection {
+                Some(c) => c,
+                None => {
+                    println!("Dropping response to {}: Not connected", msg.addr);
+                    continue;
+                }
+            };
+
+            connection.outstanding = connection.outstanding.saturating_sub(1);
+
+            if msg.num_bytes > u16::MAX as usize {
+                println!("Dropping response to {}: Too large", msg.addr);
+                continue;
+            }
+
+            connection.write_buffer.extend_from_slice(&(msg.num_bytes as u16).to_be_bytes());
+            connection.write_buffer.extend_from_slice(&msg.buffer[.. msg.num_bytes]);
+
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn write(&mut self, local_token : usize) -> Result<(), &'static str> {
+
+        let connection = match self.connections.get_mut(&local_token) {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+
+        while ! connection.write_buffer.is_empty() {
+
+            match connection.stream.write(&connection.write_buffer) {
+                Ok(0) => return Err("Could not write to connection"),
+                Ok(n) => {
+                    connection.write_buffer.drain(.. n);
+                    connection.last_activity = Instant::now();
+                },
+                Err(ref e) if ErrorKind::WouldBlock == e.kind() => break,
+                Err(ref e) if ErrorKind::Interrupted == e.kind() => continue,
+                Err(_) => return Err("Could not write to connection"),
+            };
+
+        }
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn flush_responses(&mut self, poll : &Poll) {
+
+        self.queue_responses();
+
+        let tokens : Vec<usize> = self.connections.iter()
+            .filter(|(_, c)| ! c.write_buffer.is_empty())
+            .map(|(t, _)| *t)
+            .collect();
+
+        for local_token in tokens {
+            if self.write(local_token).is_err() {
+                self.close(poll, local_token);
+            }
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn close(&mut self, poll : &Poll, local_token : usize) {
+
+        if let Some(connection) = self.connections.remove(&local_token) {
+            poll.deregister(&connection.stream).ok();
+            connection.stream.shutdown(Shutdown::Both).ok();
+            self.peers.remove(&connection.peer);
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl<'a> Listener for TcpServer<'a> {
+
+    fn register(&mut self, poll : &Poll, first_token : usize) -> Result<(), &'static str> {
+
+        self.first_token = first_token;
+
+        if poll.register(
+            &self.listener,
+            Token(first_token),
+            Ready::readable(),
+            PollOpt::edge()).is_err() {
+
+            return Err("could not register listening socket");
+
+        };
+
+        if poll.register(
+            &self.wakeup,
+            Token(first_token + WAKEUP_TOKEN),
+            Ready::readable(),
+            PollOpt::edge()).is_err() {
+
+            return Err("could not register wakeup");
+
+        };
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn handle_event(&mut self, poll : &Poll, event : &Event) {
+
+        let Token(token) = event.token();
+        let local_token = token - self.first_token;
+
+        if LISTENER_TOKEN == local_token {
+            self.accept(poll);
+            return;
+        }
+
+        if WAKEUP_TOKEN == local_token {
+            // Reset before draining, replies queued meanwhile wake us again
+            self.wakeup_trigger.set_readiness(Ready::empty()).ok();
+            self.flush_responses(poll);
+            return;
+        }
+
+        let readiness = event.readiness();
+        let mut result = Ok(());
+
+        if readiness.is_readable() {
+            result = self.read(local_token);
+        }
+
+        if result.is_ok() && readiness.is_writable() {
+            result = self.write(local_token);
+        }
+
+        if let Err(msg) = result {
+            println!("{}", msg);
+            self.close(poll, local_token);
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn handle_tick(&mut self, poll : &Poll) {
+
+        let tokens : Vec<usize> = self.connections.keys().cloned().collect();
+        let idle_timeout = self.config.idle_timeout;
+
+        for local_token in tokens {
+
+            let done = match self.connections.get(&local_token) {
+                None => continue,
+                Some(c) => {
+                    // Queries still being answered keep the connection open
+                    let idle = 0 == c.outstanding &&
+                        c.last_activity.elapsed() >= idle_timeout;
+                    let finished = c.peer_closed && 0 == c.outstanding;
+                    c.write_buffer.is_empty() && (idle || finished)
+                }
+            };
+
+            if done {
+                self.close(poll, local_token);
+            }
+
+        }
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Remove the next complete length-prefixed message from `buffer`
+fn next_frame(buffer : &mut Vec<u8>) -> Option<Vec<u8>> {
+
+    if buffer.len() < LENGTH_PREFIX_LEN {
+        return None;
+    }
+
+    let len = u16::from_be_bytes([buffer[0], buffer[1]]) as usize;
+
+    if buffer.len() < LENGTH_PREFIX_LEN + len {
+        return None;
+    }
+
+    let frame = buffer[LENGTH_PREFIX_LEN .. LENGTH_PREFIX_LEN + len].to_vec();
+    buffer.drain(.. LENGTH_PREFIX_LEN + len);
+
+    Some(frame)
+
+}
+
+/*----------------------------------------------------------------------------*/
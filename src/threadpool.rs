@@ -37,7 +37,7 @@ use std::sync::{Arc,Mutex,Condvar};
 use std::sync::atomic::{Ordering, AtomicBool};
 use std::collections::VecDeque;
 
-use udp::{ContinueState, Handler, Message};
+use udp::{ContinueState, Handler, Message, Responder};
 
 
 /*----------------------------------------------------------------------------*/
@@ -66,7 +66,6 @@ impl<T> SyncQueue<T> {
     pub fn deque(&self) -> T {
         let mut guard = self.queue.lock().unwrap();
         loop {
-            println!("Poll...");
             match guard.pop_back() {
                 Some(msg) => return msg,
                 None => guard = self.cond_var.wait(guard).unwrap(),
@@ -81,7 +80,7 @@ impl<T> SyncQueue<T> {
 pub struct Threadpool<H: Handler + Send + Sync> {
     stop : Arc<AtomicBool>,
     handler : Arc<H>,
-    in_queue : Arc<SyncQueue<Message>>,
+    in_queue : Arc<SyncQueue<(Message, Responder)>>,
     threads : RefCell<Vec<JoinHandle<()>>>,
 }
 
@@ -95,7 +94,7 @@ where H: 'static + Handler + Send + Sync {
 
             Threadpool {
                 stop : Arc::new(AtomicBool::new(false)),
-                handler : handler,
+                handler,
                 in_queue : Arc::new(SyncQueue::new(max_queue_len)),
                 threads : RefCell::new(Vec::new()),
 
@@ -105,7 +104,7 @@ where H: 'static + Handler + Send + Sync {
 
     pub fn run(&self, num_threads : usize) {
 
-        for i in 1 .. num_threads {
+        for _ in 1 .. num_threads {
 
             let in_queue = self.in_queue.clone();
             let handler = self.handler.clone();
@@ -113,13 +112,10 @@ where H: 'static + Handler + Send + Sync {
 
             let thread = spawn(move || {
                 loop {
-                    let msg = in_queue.deque();
+                    let (msg, responder) = in_queue.deque();
 
-                    println!("thread {}", i);
-                    match handler.handle(msg) {
-                        ContinueState::Stop => 
-                            stop.store(true, Ordering::Relaxed),
-                        _ => {},
+                    if let ContinueState::Stop = handler.handle(msg, responder) {
+                        stop.store(true, Ordering::Relaxed);
                     }
 
                     if stop.load(Ordering::Relaxed) {
@@ -150,9 +146,9 @@ where H: 'static + Handler + Send + Sync {
 impl <H> Handler for Threadpool<H>
 where H: 'static + Handler + Send + Sync {
 
-    fn handle (&self, msg : Message) -> ContinueState {
+    fn handle (&self, msg : Message, responder : Responder) -> ContinueState {
 
-        self.in_queue.enqueue(msg);
+        self.in_queue.enqueue((msg, responder));
         ContinueState::Continue
 
     }
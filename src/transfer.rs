@@ -0,0 +1,148 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use dnsmessage::{Message as DnsMessage, FLAG_AA};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{Record, ResourceRecord, Soa};
+use wire::WireWriter;
+use zone::Zone;
+
+/*----------------------------------------------------------------------------*/
+
+/// Max size of a single message of a zone transfer.
+/// Stays below the 64 KiB TCP frame limit to leave room for a signature.
+pub const MAX_TRANSFER_MESSAGE_LEN : usize = 64000;
+
+/*----------------------------------------------------------------------------*/
+
+/// The messages answering the AXFR `query` for the zone with apex `origin`
+/// (RFC 5936): The SOA record first and last, all other records in between.
+/// Returns None if the zone has no SOA record.
+pub fn axfr_messages(query : &DnsMessage, origin : &Name, zone : &Zone)
+    -> Option<Vec<DnsMessage>> {
+
+    let soa = zone.soa(origin)?;
+
+    let records = zone.records().into_iter()
+        .filter(|rr| QuestionType::Soa.code() != rr.type_code());
+
+    let mut records : Vec<ResourceRecord> = records.collect();
+    records.insert(0, soa.clone());
+    records.push(soa);
+
+    Some(split_into_messages(query, records))
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// The messages answering the IXFR `query` (RFC 1995) for the zone with apex
+/// `origin` from a client having version `client`: The changes since that
+/// version condensed, if the journal of the zone reaches back that far,
+/// the whole zone as for AXFR otherwise.
+/// Returns None if the zone has no SOA record.
+pub fn ixfr_messages(query : &DnsMessage, origin : &Name, zone : &Zone, client : &Soa)
+    -> Option<Vec<DnsMessage>> {
+
+    let soa = zone.soa(origin)?;
+
+    let current = match soa.record {
+        Record::SOA(ref data) => data.clone(),
+        _ => return None,
+    };
+
+    // Client is up to date
+    if ! current.is_newer_than(client) {
+        return Some(split_into_messages(query, vec![soa]));
+    }
+
+    let diff = zone.journal()
+        .filter(|j| Some(current.serial) == j.last_serial())
+        .and_then(|j| j.changes_since(client.serial));
+
+    let diff = match diff {
+        None => return axfr_messages(query, origin, zone),
+        Some(d) => d,
+    };
+
+    let mut records = vec![soa.clone(), diff.from];
+    records.extend(diff.deleted);
+    records.push(diff.to);
+    records.extend(diff.added);
+    records.push(soa);
+
+    Some(split_into_messages(query, records))
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Distribute `records` across as few responses to `query` as possible
+/// without exceeding MAX_TRANSFER_MESSAGE_LEN
+pub(crate) fn split_into_messages(query : &DnsMessage, records : Vec<ResourceRecord>)
+    -> Vec<DnsMessage> {
+
+    let mut empty = DnsMessage::response_to(query);
+    empty.set_flag(FLAG_AA, true);
+
+    let empty_len = empty.to_vec().len();
+
+    let mut messages = Vec::new();
+    let mut current = empty.clone();
+    let mut current_len = empty_len;
+
+    for rr in records {
+
+        // Compression within the message only shrinks the record
+        let mut writer = WireWriter::new();
+        rr.write(&mut writer);
+        let len = writer.as_slice().len();
+
+        if ! current.answers.is_empty() && current_len + len > MAX_TRANSFER_MESSAGE_LEN {
+            messages.push(current);
+            current = empty.clone();
+            current_len = empty_len;
+        }
+
+        current.answers.push(rr);
+        current_len += len;
+
+    }
+
+    messages.push(current);
+    messages
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,735 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::fmt;
+use ::std::fs;
+use ::std::path::Path;
+use ::std::str::FromStr;
+use ::std::time::{SystemTime, UNIX_EPOCH};
+use ring::hmac;
+use dnsmessage::{Message as DnsMessage, Rcode};
+use dnsname::Name;
+use dnsquestion::{QuestionType, CLASS_ANY};
+use dnsrecord::{Record, ResourceRecord, base64_to_bytes, bytes_to_base64, tokenize};
+use wire::{WireReader, WireWriter};
+
+/*----------------------------------------------------------------------------*/
+
+/// Seconds the clocks of signer and verifier might differ (RFC 8945, 10)
+pub const DEFAULT_FUDGE : u16 = 300;
+
+/// Number of messages of a stream that might go without TSIG (RFC 8945, 5.3.1)
+const MAX_UNSIGNED_MESSAGES : usize = 99;
+
+const HEADER_LEN : usize = 12;
+const ARCOUNT_OFFSET : usize = 10;
+
+/*----------------------------------------------------------------------------*/
+
+/// MAC algorithms supported for TSIG
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Shared secret to sign messages with
+#[derive(Clone)]
+pub struct TsigKey {
+
+    pub name : Name,
+    pub algorithm : Algorithm,
+    key : hmac::Key,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// All TSIG keys known, looked up by the key name of received messages
+#[derive(Clone, Default)]
+pub struct KeyStore {
+
+    keys : Vec<TsigKey>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// RDATA of a TSIG record (RFC 8945, 4.2), together with its owner name
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tsig {
+
+    pub key_name : Name,
+    pub algorithm : Name,
+    /// Seconds since the epoch, 48 bits
+    pub time_signed : u64,
+    pub fudge : u16,
+    pub mac : Vec<u8>,
+    pub original_id : u16,
+    pub error : u16,
+    pub other : Vec<u8>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Errors reported in the error field of TSIG records
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TsigError {
+
+    BadSig = 16,
+    BadKey = 17,
+    BadTime = 18,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Signs and verifies the messages of one exchange: A request and
+/// its response, which might consist of several messages via TCP.
+/// The MAC of each message covers the MAC of the one before.
+#[derive(Clone)]
+pub struct TsigSession {
+
+    key : TsigKey,
+    fudge : u16,
+    prior_mac : Option<Vec<u8>>,
+    /// Number of signed messages so far
+    signed : usize,
+    /// Messages received since the last signed one
+    unsigned : Vec<u8>,
+    unsigned_count : usize,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Algorithm {
+
+    pub fn name(self) -> Name {
+
+        let name = match self {
+            Algorithm::HmacSha256 => "hmac-sha256.",
+            Algorithm::HmacSha384 => "hmac-sha384.",
+            Algorithm::HmacSha512 => "hmac-sha512.",
+        };
+
+        Name::from_str(name).unwrap()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn from_name(name : &Name) -> Option<Algorithm> {
+
+        [Algorithm::HmacSha256, Algorithm::HmacSha384, Algorithm::HmacSha512]
+            .iter()
+            .find(|a| a.name() == *name)
+            .cloned()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn hmac_algorithm(self) -> hmac::Algorithm {
+
+        match self {
+            Algorithm::HmacSha256 => hmac::HMAC_SHA256,
+            Algorithm::HmacSha384 => hmac::HMAC_SHA384,
+            Algorithm::HmacSha512 => hmac::HMAC_SHA512,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn mac_len(self) -> usize {
+        self.hmac_algorithm().digest_algorithm().output_len()
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for Algorithm {
+
+    type Err = &'static str;
+
+    fn from_str(s : &str) -> Result<Algorithm, &'static str> {
+
+        let name = Name::from_str(s).map_err(|_| "Could not parse algorithm")?;
+        Algorithm::from_name(&name).ok_or("Unsupported TSIG algorithm")
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl TsigKey {
+
+    pub fn new(name : Name, algorithm : Algorithm, secret : &[u8]) -> TsigKey {
+
+        let key = hmac::Key::new(algorithm.hmac_algorithm(), secret);
+        TsigKey { name, algorithm, key }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Debug for TsigKey {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "TsigKey {} {}", self.name, self.algorithm.name())
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl KeyStore {
+
+    pub fn new() -> KeyStore {
+        KeyStore { keys : Vec::new() }
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Load keys from file `path`, one per line: Name, algorithm, and the
+    /// base64 encoded secret, e.g.
+    /// `transfer.ubeer.org hmac-sha256 c2VjcmV0`
+    pub fn load<P : AsRef<Path>>(path : P) -> Result<KeyStore, &'static str> {
+
+        let text = fs::read_to_string(path).map_err(|_| "Could not read key file")?;
+        KeyStore::from_str(&text)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Add `key`, replacing any key of the same name
+    pub fn add(&mut self, key : TsigKey) {
+
+        self.keys.retain(|k| k.name != key.name);
+        self.keys.push(key);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn get(&self, name : &Name) -> Option<&TsigKey> {
+
+        self.keys.iter().find(|k| k.name == *name)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Start a session to verify the request carrying `tsig` and to sign
+    /// the responses, with the key the request names
+    pub fn session_for(&self, tsig : &Tsig) -> Result<TsigSession, TsigError> {
+
+        self.get(&tsig.key_name)
+            .filter(|k| k.algorithm.name() == tsig.algorithm)
+            .map(|k| TsigSession::new(k.clone()))
+            .ok_or(TsigError::BadKey)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for KeyStore {
+
+    type Err = &'static str;
+
+    fn from_str(s : &str) -> Result<KeyStore, &'static str> {
+
+        let mut keys = KeyStore::new();
+
+        for line in s.lines() {
+
+            let fields = tokenize(line)?;
+
+            if fields.is_empty() {
+                continue;
+            }
+
+            if 3 != fields.len() {
+                return Err("Malformed key: Expected name, algorithm and secret");
+            }
+
+            let name = Name::from_str(&fields[0]).map_err(|_| "Could not parse key name")?;
+            let algorithm = Algorithm::from_str(&fields[1])?;
+            let secret = base64_to_bytes(&fields[2])?;
+
+            keys.add(TsigKey::new(name, algorithm, &secret));
+
+        }
+
+        Ok(keys)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Tsig {
+
+    /// Convert into TSIG pseudo-record to be put into the additional section
+    pub fn to_record(&self) -> ResourceRecord {
+
+        let mut writer = WireWriter::new();
+
+        writer.write_name_uncompressed(&self.algorithm);
+        write_time(&mut writer, self.time_signed);
+        writer.write_u16(self.fudge);
+        writer.write_u16(self.mac.len() as u16);
+        writer.write_bytes(&self.mac);
+        writer.write_u16(self.original_id);
+        writer.write_u16(self.error);
+        writer.write_u16(self.other.len() as u16);
+        writer.write_bytes(&self.other);
+
+        ResourceRecord {
+            name : self.key_name.clone(),
+            class : CLASS_ANY,
+            ttl : 0,
+            record : Record::Unknown(QuestionType::Tsig.code(), writer.into_bytes()),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Interpret TSIG pseudo-record
+    pub fn from_record(rr : &ResourceRecord) -> Result<Tsig, &'static str> {
+
+        let data = match rr.record {
+            Record::Unknown(code, ref data) if QuestionType::Tsig.code() == code => data,
+            _ => return Err("Not a TSIG record"),
+        };
+
+        if CLASS_ANY != rr.class || 0 != rr.ttl {
+            return Err("Malformed TSIG record");
+        }
+
+        let mut reader = WireReader::new(data);
+
+        let algorithm = reader.read_name()?;
+        let time_signed = (reader.read_u16()? as u64) << 32 | reader.read_u32()? as u64;
+        let fudge = reader.read_u16()?;
+        let mac_len = reader.read_u16()? as usize;
+        let mac = reader.read_bytes(mac_len)?.to_vec();
+        let original_id = reader.read_u16()?;
+        let error = reader.read_u16()?;
+        let other_len = reader.read_u16()? as usize;
+        let other = reader.read_bytes(other_len)?.to_vec();
+
+        if 0 != reader.remaining() {
+            return Err("Malformed TSIG record");
+        }
+
+        Ok(Tsig {
+            key_name : rr.name.clone(),
+            algorithm,
+            time_signed,
+            fudge,
+            mac,
+            original_id,
+            error,
+            other,
+        })
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Tsig {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "{} {} {} {} {} {} {} {}",
+               self.key_name, self.algorithm, self.time_signed, self.fudge,
+               bytes_to_base64(&self.mac), self.original_id, self.error,
+               bytes_to_base64(&self.other))
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl TsigError {
+
+    pub fn from_u16(value : u16) -> Option<TsigError> {
+
+        match value {
+            16 => Some(TsigError::BadSig),
+            17 => Some(TsigError::BadKey),
+            18 => Some(TsigError::BadTime),
+            _ => None,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn to_str(self) -> &'static str {
+
+        match self {
+            TsigError::BadSig => "TSIG verification failed",
+            TsigError::BadKey => "TSIG key not recognized",
+            TsigError::BadTime => "TSIG signed at wrong time",
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Create the error response to `query`, signed by `session` if given.
+    /// Responses without session carry an empty MAC (RFC 8945, 5.3.2).
+    pub fn response_to(self, query : &DnsMessage, session : Option<&mut TsigSession>)
+        -> DnsMessage {
+
+        let mut response = DnsMessage::response_to(query);
+        response.set_rcode(Rcode::NotAuth);
+
+        let request = match query.tsig {
+            Some(ref t) => t,
+            None => return response,
+        };
+
+        match session {
+
+            Some(session) => {
+                let now = (current_time() & 0xffff_ffff_ffff).to_be_bytes();
+                session.sign_with(&mut response, request.time_signed, self as u16,
+                                  now[2 ..].to_vec());
+            },
+
+            None => response.tsig = Some(Tsig {
+                mac : Vec::new(),
+                original_id : response.id,
+                error : self as u16,
+                other : Vec::new(),
+                .. request.clone()
+            }),
+
+        };
+
+        response
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl TsigSession {
+
+    pub fn new(key : TsigKey) -> TsigSession {
+
+        TsigSession {
+            key,
+            fudge : DEFAULT_FUDGE,
+            prior_mac : None,
+            signed : 0,
+            unsigned : Vec::new(),
+            unsigned_count : 0,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn key(&self) -> &TsigKey {
+        &self.key
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Sign `msg`, which is to be sent next
+    pub fn sign(&mut self, msg : &mut DnsMessage) {
+
+        self.sign_at(msg, current_time());
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Sign `msg` as if it was `now` seconds since the epoch
+    pub fn sign_at(&mut self, msg : &mut DnsMessage, now : u64) {
+
+        self.sign_with(msg, now, 0, Vec::new());
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Send `msg` without TSIG, which is allowed for all but the first
+    /// message of a response. It is covered by the next signed message.
+    pub fn skip(&mut self, msg : &DnsMessage) {
+
+        self.unsigned.extend_from_slice(&msg.to_vec());
+        self.unsigned_count += 1;
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Verify the message `bytes` received next.
+    /// Within a stream of responses, messages might lack a TSIG, which
+    /// are then covered by the next signed one.
+    pub fn verify(&mut self, bytes : &[u8]) -> Result<(), TsigError> {
+
+        self.verify_at(bytes, current_time())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Verify `bytes` as if it was `now` seconds since the epoch
+    pub fn verify_at(&mut self, bytes : &[u8], now : u64) -> Result<(), TsigError> {
+
+        let (offset, tsig) = split_tsig(bytes).map_err(|_| TsigError::BadSig)?;
+
+        let tsig = match tsig {
+
+            Some(tsig) => tsig,
+
+            None if 2 <= self.signed && self.unsigned_count < MAX_UNSIGNED_MESSAGES => {
+                self.unsigned.extend_from_slice(bytes);
+                self.unsigned_count += 1;
+                return Ok(());
+            },
+
+            None => return Err(TsigError::BadSig),
+
+        };
+
+        if let Some(error) = TsigError::from_u16(tsig.error).filter(|_| tsig.mac.is_empty()) {
+            return Err(error);
+        }
+
+        if tsig.key_name != self.key.name || tsig.algorithm != self.key.algorithm.name() {
+            return Err(TsigError::BadKey);
+        }
+
+        if tsig.mac.len() != self.key.algorithm.mac_len() {
+            return Err(TsigError::BadSig);
+        }
+
+        let mut message = bytes[.. offset].to_vec();
+        let arcount = u16::from_be_bytes([message[ARCOUNT_OFFSET], message[ARCOUNT_OFFSET + 1]]);
+        message[.. 2].copy_from_slice(&tsig.original_id.to_be_bytes());
+        let arcount = arcount.checked_sub(1).ok_or(TsigError::BadSig)?;
+        message[ARCOUNT_OFFSET .. ARCOUNT_OFFSET + 2].copy_from_slice(&arcount.to_be_bytes());
+
+        let data = self.digest_data(&message, &tsig);
+
+        hmac::verify(&self.key.key, &data, &tsig.mac).map_err(|_| TsigError::BadSig)?;
+
+        self.prior_mac = Some(tsig.mac.clone());
+        self.signed += 1;
+        self.unsigned.clear();
+        self.unsigned_count = 0;
+
+        if tsig.fudge as u64 + now < tsig.time_signed || tsig.time_signed + (tsig.fudge as u64) < now {
+            return Err(TsigError::BadTime);
+        }
+
+        match TsigError::from_u16(tsig.error) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn sign_with(&mut self, msg : &mut DnsMessage, time_signed : u64, error : u16,
+                 other : Vec<u8>) {
+
+        msg.tsig = None;
+
+        let mut tsig = Tsig {
+            key_name : self.key.name.clone(),
+            algorithm : self.key.algorithm.name(),
+            time_signed,
+            fudge : self.fudge,
+            mac : Vec::new(),
+            original_id : msg.id,
+            error,
+            other,
+        };
+
+        let data = self.digest_data(&msg.to_vec(), &tsig);
+        tsig.mac = hmac::sign(&self.key.key, &data).as_ref().to_vec();
+
+        self.prior_mac = Some(tsig.mac.clone());
+        self.signed += 1;
+        self.unsigned.clear();
+        self.unsigned_count = 0;
+
+        msg.tsig = Some(tsig);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Data the MAC of `message`, without TSIG, is calculated of
+    /// (RFC 8945, 4.3).
+    /// Subsequent messages of a response only cover the timers of the TSIG.
+    fn digest_data(&self, message : &[u8], tsig : &Tsig) -> Vec<u8> {
+
+        let mut writer = WireWriter::new();
+
+        if let Some(ref mac) = self.prior_mac {
+            writer.write_u16(mac.len() as u16);
+            writer.write_bytes(mac);
+        }
+
+        writer.write_bytes(&self.unsigned);
+        writer.write_bytes(message);
+
+        let subsequent = 2 <= self.signed;
+
+        if ! subsequent {
+            writer.write_name_uncompressed(&tsig.key_name.to_lowercase());
+            writer.write_u16(CLASS_ANY);
+            writer.write_u32(0);
+            writer.write_name_uncompressed(&tsig.algorithm.to_lowercase());
+        }
+
+        write_time(&mut writer, tsig.time_signed);
+        writer.write_u16(tsig.fudge);
+
+        if ! subsequent {
+            writer.write_u16(tsig.error);
+            writer.write_u16(tsig.other.len() as u16);
+            writer.write_bytes(&tsig.other);
+        }
+
+        writer.into_bytes()
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+fn current_time() -> u64 {
+
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn write_time(writer : &mut WireWriter, time : u64) {
+
+    writer.write_u16((time >> 32) as u16);
+    writer.write_u32(time as u32);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Find the TSIG of the message `bytes`.
+/// Returns the offset the TSIG record starts at, i.e. the length of the
+/// message without it, and the TSIG if there is one.
+fn split_tsig(bytes : &[u8]) -> Result<(usize, Option<Tsig>), &'static str> {
+
+    if bytes.len() < HEADER_LEN {
+        return Err("Message too short");
+    }
+
+    let mut reader = WireReader::new(bytes);
+    reader.read_bytes(4)?;
+
+    let qdcount = reader.read_u16()?;
+    let records = reader.read_u16()? as usize + reader.read_u16()? as usize
+        + reader.read_u16()? as usize;
+
+    for _ in 0 .. qdcount {
+        reader.read_name()?;
+        reader.read_u32()?;
+    }
+
+    let mut offset = reader.offset();
+    let mut last = None;
+
+    for _ in 0 .. records {
+        offset = reader.offset();
+        last = Some(ResourceRecord::read(&mut reader)?);
+    }
+
+    match last {
+        Some(ref rr) if QuestionType::Tsig.code() == rr.type_code() =>
+            Ok((offset, Some(Tsig::from_record(rr)?))),
+        _ => Ok((bytes.len(), None)),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
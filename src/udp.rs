@@ -31,12 +31,19 @@
  * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
+use edns::Cookie;
+use mio::{Ready, SetReadiness};
 use std::net::SocketAddr;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
 
 /*----------------------------------------------------------------------------*/
 
 pub const MAX_SAFE_UDP_PAYLOAD_LEN : usize = 512;
 
+/// Max UDP payload we are willing to receive / send if EDNS is used
+pub const MAX_UDP_PAYLOAD_LEN : usize = 4096;
+
 /*----------------------------------------------------------------------------*/
 
 pub enum ContinueState {
@@ -46,16 +53,113 @@ pub enum ContinueState {
 
 pub trait Handler {
 
-    fn handle (&self, msg : Message) -> ContinueState;
+    /// Handle a received message. Replies are sent via `responder`,
+    /// which might be moved to another thread to reply later on.
+    fn handle (&self, msg : Message, responder : Responder) -> ContinueState;
+
+}
+
+/*----------------------------------------------------------------------------*/
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    Udp,
+    Tcp
 }
 
 /*----------------------------------------------------------------------------*/
 
 pub struct Message {
     pub addr : SocketAddr,
+    /// Transport the message was received by / is to be sent with
+    pub transport : Transport,
     pub num_bytes : usize,
-    pub buffer : [u8; MAX_SAFE_UDP_PAYLOAD_LEN],
+    pub buffer : Vec<u8>,
+    /// DNS Cookie (RFC 7873) the response is to carry, if the listener
+    /// checks cookies and the query came with a client cookie
+    pub cookie : Option<Cookie>,
+    /// Max size of the response to a UDP query, if the listener
+    /// restricts it beyond the size the client announced
+    pub max_response_len : Option<usize>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Sends replies back to the client a message was received from, via the
+/// listener that received it
+#[derive(Clone)]
+pub struct Responder {
+
+    addr : SocketAddr,
+    transport : Transport,
+    queue : Arc<Mutex<VecDeque<Message>>>,
+    wakeup : SetReadiness,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Responder {
+
+    pub(crate) fn new(
+        addr : SocketAddr,
+        transport : Transport,
+        queue : Arc<Mutex<VecDeque<Message>>>,
+        wakeup : SetReadiness) -> Responder {
+
+        Responder {addr, transport, queue, wakeup}
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Queue `buffer` to be sent to the client and wake up the listener
+    pub fn respond(&self, buffer : Vec<u8>) {
+
+        self.respond_all(vec![buffer]);
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Queue several replies at once, to be sent in order without other
+    /// replies in between
+    pub fn respond_all(&self, buffers : Vec<Vec<u8>>) {
+
+        {
+            let mut queue = self.queue.lock().unwrap();
+
+            for buffer in buffers {
+                queue.push_front(Message {
+                    addr : self.addr,
+                    transport : self.transport,
+                    num_bytes : buffer.len(),
+                    buffer,
+                    cookie : None,
+                    max_response_len : None,
+                });
+            }
+        }
+
+        if self.wakeup.set_readiness(Ready::readable()).is_err() {
+            println!("Could not wake up listener");
+        }
+
+    }
 
 }
 
+/*----------------------------------------------------------------------------*/
@@ -31,39 +31,47 @@
  * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
  * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
-extern crate mio;
-
-use mio::{Poll, Ready, Token, PollOpt, Events};
+use mio::{Poll, Ready, Registration, SetReadiness, Token, PollOpt};
+use mio::event::Event;
 use mio::net::UdpSocket;
-use std::time::Duration;
+use std::io::ErrorKind;
 use std::net::SocketAddr;
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 
-use udp::{MAX_SAFE_UDP_PAYLOAD_LEN, Handler, Message};
+use cookie::{CookieConfig, CookieFilter};
+use server::{Listener, Server};
+use udp::{MAX_UDP_PAYLOAD_LEN, Handler, Message, Responder, Transport};
+
+/*----------------------------------------------------------------------------*/
+
+/// Local token of the registration responders signal
+const WAKEUP_TOKEN : usize = 1;
 
 /*----------------------------------------------------------------------------*/
 
 pub struct UdpServer<'a> {
 
-    poll : Poll,
     listen_socket : UdpSocket,
     out_queue : Arc<Mutex<VecDeque<Message>>>,
     handler : &'a dyn Handler,
+    cookies : Option<CookieFilter>,
+    token : Token,
+    /// Signalled by responders whenever a reply is queued
+    wakeup : Registration,
+    wakeup_trigger : SetReadiness,
+    /// Writable interest is only registered while responses are pending
+    awaiting_writable : bool,
 
 }
 
 /*----------------------------------------------------------------------------*/
 
-/**
- * TODO: due to edge trigger mode, data is currently not read reliably
- */
 impl<'a> UdpServer<'a> {
 
     pub fn bind_to<UH : Handler> (
         listen_addr_str : &str,
-        handler : &'a UH,
-        queue : Option<Arc<Mutex<VecDeque<Message>>>>)
+        handler : &'a UH)
         -> Result<UdpServer<'a>, &'static str> {
 
             let max_queue_len = 200;
@@ -80,115 +88,252 @@ impl<'a> UdpServer<'a> {
                 Err(_) => return Err("Could not bind to socket")
             };
 
-            let poll = match Poll::new() {
-                Ok(p) => p,
-                Err(_) => return Err("could not create mio:Poll")
-            };
+            let out_queue = Arc::new(Mutex::new(VecDeque::with_capacity(max_queue_len)));
+            let (wakeup, wakeup_trigger) = Registration::new2();
+
+            Ok(UdpServer {
+                listen_socket,
+                out_queue,
+                handler,
+                cookies : None,
+                token : Token(0),
+                wakeup,
+                wakeup_trigger,
+                awaiting_writable : false,
+            })
 
-            if poll.register(
-                &listen_socket,
-                Token(0),
-                Ready::readable() | Ready::writable(),
-                PollOpt::edge()).is_err() {
+        }
 
-                return Err("could not register listening socket");
+    /// Enable DNS Cookies (RFC 7873) for this listener, or disable them
+    /// by passing `None`.
+    pub fn set_cookie_config(&mut self, config : Option<CookieConfig>)
+        -> Result<(), &'static str> {
 
-            };
+        self.cookies = match config {
+            None => None,
+            Some(c) => Some(CookieFilter::new(c)?),
+        };
 
-            let out_queue = match queue {
-                None => Arc::new(Mutex::new(VecDeque::with_capacity(max_queue_len))),
-                Some(q) => q.clone(),
-            };
+        Ok(())
+
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, &'static str> {
+
+        self.listen_socket.local_addr().map_err(|_| "Could not get local address")
 
-            Ok(UdpServer {poll, listen_socket, out_queue, handler})
+    }
+
+    /// Serve on a `Server` of its own
+    pub fn run(self) {
+
+        let mut server = match Server::new() {
+            Ok(s) => s,
+            Err(msg) => {
+                println!("{}", msg);
+                return;
+            }
+        };
 
+        if let Err(msg) = server.add(self) {
+            println!("{}", msg);
+            return;
         }
 
-    pub fn run(&self) {
+        server.run();
 
-        let mut events = Events::with_capacity(1024);
-        let timeout = Duration::from_millis(500);
+    }
 
-        loop {
+    /// Queue `buffer` to be sent to `dest`
+    pub fn send(&self, dest : SocketAddr, buffer : Vec<u8>) {
 
-            let result = self.poll.poll(&mut events, Some(timeout));
+        Responder::new(
+            dest,
+            Transport::Udp,
+            self.out_queue.clone(),
+            self.wakeup_trigger.clone()).respond(buffer);
 
-            if result.is_err() {
-                println!("Exception occured during polling");
-                continue;
-            };
+    }
 
+}
 
-            for event in &events {
+/*----------------------------------------------------------------------------*/
 
-                let readiness = event.readiness();
+impl<'a> Listener for UdpServer<'a> {
 
-                if readiness.is_readable() {
-                    int_handle_read(self);
-                }
+    fn register(&mut self, poll : &Poll, first_token : usize) -> Result<(), &'static str> {
 
-                if readiness.is_writable() {
+        self.token = Token(first_token);
 
-                    if int_handle_write(self).is_err() {
-                        println!("Sending of data failed");
-                    }
+        if poll.register(
+            &self.listen_socket,
+            self.token,
+            Ready::readable(),
+            PollOpt::edge()).is_err() {
 
-                }
+            return Err("could not register listening socket");
 
-            }
+        };
 
-        }
+        if poll.register(
+            &self.wakeup,
+            Token(first_token + WAKEUP_TOKEN),
+            Ready::readable(),
+            PollOpt::edge()).is_err() {
+
+            return Err("could not register wakeup");
+
+        };
+
+        Ok(())
 
     }
 
-    pub fn send(&self, dest : SocketAddr, num_bytes : usize, buffer : [u8; 512]) {
+    fn handle_event(&mut self, poll : &Poll, event : &Event) {
 
-        self.out_queue.lock().unwrap().push_front(Message{
-            addr : dest,
-            num_bytes : num_bytes,
-            buffer : buffer,
-        });
+        let Token(token) = event.token();
+
+        if token == self.token.0 + WAKEUP_TOKEN {
+            // Reset before draining, replies queued meanwhile wake us again
+            self.wakeup_trigger.set_readiness(Ready::empty()).ok();
+        } else if event.readiness().is_readable() {
+            int_handle_read(self);
+        }
+
+        // While waiting for writability, the next writable event
+        // drains the queue
+        if ! self.awaiting_writable || event.readiness().is_writable() {
+            int_handle_write(self, poll);
+        }
 
     }
 
+    fn handle_tick(&mut self, _poll : &Poll) {
+    }
+
 }
 
 /*----------------------------------------------------------------------------*/
 
-fn int_handle_read(udp_server : &UdpServer) {
+/// With edge triggered events, readiness is only reported once,
+/// thus read until the socket would block.
+fn int_handle_read(udp_server : &mut UdpServer) {
 
-    println!("Incoming data:");
+    loop {
 
-    let mut buffer = [0; MAX_SAFE_UDP_PAYLOAD_LEN];
+        // Clients might use EDNS, thus we must accept more than 512 bytes
+        let mut buffer = vec![0; MAX_UDP_PAYLOAD_LEN];
 
-    match udp_server.listen_socket.recv_from(&mut buffer) {
-        Ok((num_bytes, addr)) => {
-            udp_server.handler.handle(Message{addr, buffer, num_bytes});
-        }
-        //    udp_server.handler.handle(udp_server, soa, num_bytes, buffer),
-        Err(_) => println!("Did not receive data"),
+        let (num_bytes, addr) = match udp_server.listen_socket.recv_from(&mut buffer) {
+            Ok(r) => r,
+            Err(ref e) if ErrorKind::WouldBlock == e.kind() => return,
+            Err(ref e) if ErrorKind::Interrupted == e.kind() => continue,
+            Err(_) => {
+                println!("Did not receive data");
+                return;
+            }
+        };
+
+        buffer.truncate(num_bytes);
+
+        let (cookie, max_response_len) = match udp_server.cookies {
+            None => (None, None),
+            Some(ref mut cookies) => match cookies.check_query(&addr, &buffer) {
+                Ok((cookie, max_len)) => (cookie, Some(max_len)),
+                Err(response) => {
+                    udp_server.send(addr, response);
+                    continue;
+                },
+            },
+        };
+
+        let responder = Responder::new(
+            addr,
+            Transport::Udp,
+            udp_server.out_queue.clone(),
+            udp_server.wakeup_trigger.clone());
+
+        udp_server.handler.handle(Message{
+            addr,
+            transport : Transport::Udp,
+            buffer,
+            num_bytes,
+            cookie,
+            max_response_len,
+        }, responder);
+
+    }
 
-    };
 }
 
 /*----------------------------------------------------------------------------*/
 
-fn int_handle_write(udp_server : &UdpServer) -> ::std::io::Result<usize> {
+/// Send queued messages until the queue is empty or the socket would block.
+/// In the latter case, writable interest is registered to resume once the
+/// socket accepts data again.
+fn int_handle_write(udp_server : &mut UdpServer, poll : &Poll) {
+
+    let mut would_block = false;
+
+    loop {
+
+        let next = udp_server.out_queue.lock().unwrap().pop_back();
 
-    println!("Ready to write");
+        let msg = match next {
+            None => break,
+            Some(m) => m,
+        };
 
-    let mut out_queue = udp_server.out_queue.lock().unwrap();
+        let fits = match udp_server.cookies {
+            None => true,
+            Some(ref mut cookies) => cookies.check_response(
+                &msg.addr, &msg.buffer[.. msg.num_bytes]),
+        };
 
-    let bytes_sent = match out_queue.pop_back() {
-        None => 0,
-        Some(msg) => {
-            let slice_to_send = &msg.buffer[0 .. msg.num_bytes];
-            udp_server.listen_socket.send_to(slice_to_send, &msg.addr)?
+        if ! fits {
+            println!("Dropping response to {}: Too large", msg.addr);
+            continue;
         }
+
+        let result = udp_server.listen_socket.send_to(
+            &msg.buffer[0 .. msg.num_bytes], &msg.addr);
+
+        match result {
+            Ok(_) => (),
+            Err(ref e) if ErrorKind::WouldBlock == e.kind() => {
+                // Oldest messages are taken from the back
+                udp_server.out_queue.lock().unwrap().push_back(msg);
+                would_block = true;
+                break;
+            },
+            Err(_) => println!("Sending of data to {} failed", msg.addr),
+        };
+
+    }
+
+    if would_block == udp_server.awaiting_writable {
+        return;
+    }
+
+    let interest = if would_block {
+        Ready::readable() | Ready::writable()
+    } else {
+        Ready::readable()
     };
 
-    Ok(bytes_sent)
+    if poll.reregister(
+        &udp_server.listen_socket,
+        udp_server.token,
+        interest,
+        PollOpt::edge()).is_err() {
+
+        println!("Could not reregister socket");
+        return;
+
+    }
+
+    udp_server.awaiting_writable = would_block;
+
 }
 
 /*----------------------------------------------------------------------------*/
-
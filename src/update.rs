@@ -0,0 +1,308 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use dnsmessage::{Message as DnsMessage, Rcode};
+use dnsname::Name;
+use dnsquestion::{QuestionType, CLASS_ANY, CLASS_IN, CLASS_NONE};
+use dnsrecord::{Record, ResourceRecord};
+use zone::Zone;
+
+/*----------------------------------------------------------------------------*/
+
+/// Apply the dynamic update `update` (RFC 2136) to `zone` with apex
+/// `origin`: Once all prerequisites are met, the changes are applied to a
+/// copy of the zone and its SOA serial is increased.
+/// Returns the new version of the zone, None if nothing changed.
+pub fn apply_update(update : &DnsMessage, origin : &Name, zone : &Zone)
+    -> Result<Option<Zone>, Rcode> {
+
+    check_prerequisites(update, origin, zone)?;
+    prescan(update, origin)?;
+
+    let mut updated = zone.clone();
+    let mut changed = false;
+
+    for rr in &update.authority {
+        changed |= apply_change(&mut updated, origin, rr)?;
+    }
+
+    if ! changed {
+        return Ok(None);
+    }
+
+    increase_serial(&mut updated, origin, zone)?;
+
+    Ok(Some(updated))
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Check the prerequisite section (RFC 2136, 3.2)
+fn check_prerequisites(update : &DnsMessage, origin : &Name, zone : &Zone)
+    -> Result<(), Rcode> {
+
+    let mut rrsets : Vec<&ResourceRecord> = Vec::new();
+
+    for rr in &update.answers {
+
+        if 0 != rr.ttl {
+            return Err(Rcode::FormErr);
+        }
+
+        if ! rr.name.is_subdomain_of(origin) {
+            return Err(Rcode::NotZone);
+        }
+
+        let any_type = QuestionType::Any.code() == rr.type_code();
+
+        let in_use = match any_type {
+            true => ! zone.records_at(&rr.name).is_empty(),
+            false => ! zone.rrset(&rr.name, rr.type_code()).is_empty(),
+        };
+
+        match rr.class {
+
+            CLASS_ANY if ! has_rdata(rr) => if ! in_use {
+                return Err(if any_type { Rcode::NxDomain } else { Rcode::NxRrset });
+            },
+
+            CLASS_NONE if ! has_rdata(rr) => if in_use {
+                return Err(if any_type { Rcode::YxDomain } else { Rcode::YxRrset });
+            },
+
+            CLASS_IN if ! any_type => rrsets.push(rr),
+
+            _ => return Err(Rcode::FormErr),
+
+        };
+
+    }
+
+    // RRsets must match exactly, regardless of order
+    for rr in &rrsets {
+
+        let expected : Vec<&Record> = rrsets.iter()
+            .filter(|r| r.name == rr.name && r.type_code() == rr.type_code())
+            .map(|r| &r.record)
+            .collect();
+
+        let actual = zone.rrset(&rr.name, rr.type_code());
+
+        let matches = actual.iter().all(|r| expected.contains(&&r.record)) &&
+            expected.iter().all(|e| actual.iter().any(|r| r.record == **e));
+
+        if ! matches {
+            return Err(Rcode::NxRrset);
+        }
+
+    }
+
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Check the update section before changing anything (RFC 2136, 3.4.1)
+fn prescan(update : &DnsMessage, origin : &Name) -> Result<(), Rcode> {
+
+    for rr in &update.authority {
+
+        if ! rr.name.is_subdomain_of(origin) {
+            return Err(Rcode::NotZone);
+        }
+
+        let type_code = rr.type_code();
+        let meta = is_meta_type(type_code);
+
+        let valid = match rr.class {
+            CLASS_IN => ! meta,
+            CLASS_ANY => 0 == rr.ttl && ! has_rdata(rr) &&
+                (! meta || QuestionType::Any.code() == type_code),
+            CLASS_NONE => 0 == rr.ttl && ! meta,
+            _ => false,
+        };
+
+        if ! valid {
+            return Err(Rcode::FormErr);
+        }
+
+    }
+
+    Ok(())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Apply one RR of the update section (RFC 2136, 3.4.2).
+/// Returns whether the zone changed.
+fn apply_change(zone : &mut Zone, origin : &Name, rr : &ResourceRecord)
+    -> Result<bool, Rcode> {
+
+    let type_code = rr.type_code();
+    let at_apex = rr.name == *origin;
+    let soa = QuestionType::Soa.code();
+    let ns = QuestionType::Ns.code();
+    let cname = QuestionType::Cname.code();
+
+    match rr.class {
+
+        CLASS_IN => {
+
+            let existing = zone.records_at(&rr.name);
+
+            // CNAME must not coexist with other data
+            if existing.iter().any(|e| (cname == type_code) != (cname == e.type_code())) {
+                return Ok(false);
+            }
+
+            if soa == type_code {
+
+                let newer = match (&rr.record, zone.soa(origin).map(|s| s.record)) {
+                    (Record::SOA(ref new), Some(Record::SOA(ref old))) => new.is_newer_than(old),
+                    _ => false,
+                };
+
+                if ! at_apex || ! newer {
+                    return Ok(false);
+                }
+
+                zone.remove_rrset(&rr.name, soa);
+
+            } else if cname == type_code {
+                zone.remove_rrset(&rr.name, cname);
+            } else if existing.iter().any(|e| e.record == rr.record) {
+                if existing.iter().any(|e| e.record == rr.record && e.ttl == rr.ttl) {
+                    return Ok(false);
+                }
+                zone.remove(&rr.name, &rr.record);
+            }
+
+            zone.add_with_ttl(rr.name.clone(), rr.ttl, rr.record.clone())
+                .map_err(|_| Rcode::ServFail)?;
+
+            Ok(true)
+
+        },
+
+        CLASS_ANY => {
+
+            let mut types : Vec<u16> = match QuestionType::Any.code() == type_code {
+                true => zone.records_at(&rr.name).iter().map(|r| r.type_code()).collect(),
+                false => vec![type_code],
+            };
+
+            // The apex keeps its SOA and NS records
+            if at_apex {
+                types.retain(|t| soa != *t && ns != *t);
+            }
+
+            let removed : usize = types.iter()
+                .map(|t| zone.remove_rrset(&rr.name, *t))
+                .sum();
+
+            Ok(0 < removed)
+
+        },
+
+        CLASS_NONE => {
+
+            let last_ns = at_apex && ns == type_code && 1 >= zone.rrset(&rr.name, ns).len();
+
+            if soa == type_code || last_ns {
+                return Ok(false);
+            }
+
+            Ok(zone.remove(&rr.name, &rr.record))
+
+        },
+
+        _ => Err(Rcode::FormErr),
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Increase the SOA serial of `updated` unless the update set a newer one
+/// itself
+fn increase_serial(updated : &mut Zone, origin : &Name, previous : &Zone)
+    -> Result<(), Rcode> {
+
+    let (new, old) = match (updated.soa(origin), previous.soa(origin)) {
+        (Some(new), Some(old)) => (new, old),
+        _ => return Err(Rcode::ServFail),
+    };
+
+    let mut soa = match (new.record, old.record) {
+        (Record::SOA(ref new), Record::SOA(ref old)) if new.is_newer_than(old) => return Ok(()),
+        (_, Record::SOA(old)) => old,
+        _ => return Err(Rcode::ServFail),
+    };
+
+    soa.serial = soa.serial.wrapping_add(1);
+
+    updated.remove_rrset(origin, QuestionType::Soa.code());
+    updated.add_with_ttl(origin.clone(), new.ttl, Record::SOA(soa))
+        .map_err(|_| Rcode::ServFail)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Whether `rr` carries RDATA - records of class ANY or NONE
+/// referring to whole RRsets do not
+fn has_rdata(rr : &ResourceRecord) -> bool {
+
+    match rr.record {
+        Record::Unknown(_, ref data) => ! data.is_empty(),
+        _ => true,
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// OPT and the meta types (RFC 6895, 3.1) cannot be stored in a zone
+fn is_meta_type(type_code : u16) -> bool {
+
+    QuestionType::Opt.code() == type_code || (128 ..= 255).contains(&type_code)
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,1190 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ::std::collections::HashMap;
+use ::std::fmt;
+use ::std::fs;
+use ::std::path::Path;
+use ::std::str::FromStr;
+use dnsmessage::{Message, Rcode};
+use dnsname::Name;
+use dnsquestion::QuestionType;
+use dnsrecord::{tokenize, Record, ResourceRecord};
+use dnssec::{base32hex_to_bytes, Dnskey, Nsec, Nsec3, Rrsig};
+use dnssec::{ALGORITHM_RSASHA1, ALGORITHM_RSASHA1_NSEC3_SHA1, ALGORITHM_RSASHA256};
+use dnssec::{ALGORITHM_RSASHA512, ALGORITHM_ECDSAP256SHA256, ALGORITHM_ECDSAP384SHA384};
+use dnssec::{ALGORITHM_ED25519, DIGEST_SHA1, DIGEST_SHA256, DIGEST_SHA384};
+use dnssec::{DNSKEY_FLAG_ZONE, DNSKEY_PROTOCOL, NSEC3_HASH_SHA1};
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use signer::now;
+use zone::DEFAULT_TTL;
+use zonefile::parse_entry;
+
+/*----------------------------------------------------------------------------*/
+
+/// Trust anchors of the root zone as published by IANA: KSK-2017 and
+/// KSK-2024
+const ROOT_ANCHORS : &[&str] = &[
+    ". IN DS 20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D",
+    ". IN DS 38696 8 2 683D2D0ACB8C9B712A1948B27F741219298D0A450D612C483AF444A4C0FB2B16",
+];
+
+const MAX_CNAME_CHAIN_LEN : usize = 8;
+
+/// NSEC3 iterations beyond which proofs are not checked, but the response
+/// considered insecure, to bound the hashing effort (RFC 9276, 3.2)
+const MAX_NSEC3_ITERATIONS : u16 = 150;
+
+/*----------------------------------------------------------------------------*/
+
+/// Security status of a response (RFC 4033, 5)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Security {
+
+    /// Every RRset is covered by a chain of trust from a trust anchor
+    Secure,
+    /// Provably lies below a delegation without DS
+    Insecure,
+    /// Should be secure, but signatures or proofs do not check out
+    Bogus,
+    /// No trust anchor covers the response, or the chain of trust could
+    /// not be fetched
+    Indeterminate,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Outcome of validating a response
+#[derive(Clone, Debug, PartialEq)]
+pub struct Validation {
+
+    pub security : Security,
+    /// Why the response is not secure, empty if it is
+    pub reason : &'static str,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Where the validator gets the DS and DNSKEY RRsets of the chain of trust
+/// from, e.g. a resolver or recorded responses
+pub trait QuerySource {
+
+    /// Response to a query for `name` and `qtype` with the DO flag set
+    fn query(&self, name : &Name, qtype : QuestionType) -> Result<Message, &'static str>;
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Validates responses (RFC 4035, 5): Builds the chain of trust from a
+/// trust anchor down to the zones that signed the response, checks the
+/// signatures of every RRset and the proofs of non-existence and of
+/// wildcard expansions.
+/// Supported are the RSA, ECDSA and Ed25519 algorithms. Zones only
+/// referred to by DS records with unsupported algorithms are insecure.
+pub struct Validator {
+
+    /// DS or DNSKEY records
+    anchors : Vec<ResourceRecord>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Zone along with its keys, authenticated by the chain of trust
+#[derive(Clone)]
+struct SecureZone {
+
+    apex : Name,
+    keys : Vec<Dnskey>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// State of one validation: Zones established along the chain of trust,
+/// keyed by the names they were looked up for
+struct Chain<'a> {
+
+    anchors : &'a [ResourceRecord],
+    source : &'a dyn QuerySource,
+    now : u32,
+    zones : HashMap<Name, Result<SecureZone, Validation>>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Authenticated NSEC and NSEC3 records of a response
+struct Denial {
+
+    nsec : Vec<(Name, Nsec)>,
+    /// NSEC3 records along with the hash their owner stands for
+    nsec3 : Vec<(Vec<u8>, Nsec3)>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+enum Proof {
+
+    Secure,
+    /// Proof relies on an opt-out span, thus there might be an insecure
+    /// delegation
+    OptOut,
+    Missing,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Validation {
+
+    pub fn is_secure(&self) -> bool {
+        Security::Secure == self.security
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn secure() -> Validation {
+        Validation { security : Security::Secure, reason : "" }
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn insecure(reason : &'static str) -> Validation {
+        Validation { security : Security::Insecure, reason }
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn bogus(reason : &'static str) -> Validation {
+        Validation { security : Security::Bogus, reason }
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn indeterminate(reason : &'static str) -> Validation {
+        Validation { security : Security::Indeterminate, reason }
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Bogus outweighs indeterminate, which outweighs insecure
+    fn severity(&self) -> u8 {
+
+        match self.security {
+            Security::Secure => 0,
+            Security::Insecure => 1,
+            Security::Indeterminate => 2,
+            Security::Bogus => 3,
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl fmt::Display for Validation {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        match self.security {
+            Security::Secure => write!(f, "{:?}", self.security),
+            _ => write!(f, "{:?}: {}", self.security, self.reason),
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Validator {
+
+    /// Validator without any trust anchor
+    pub fn new() -> Validator {
+
+        Validator {
+            anchors : Vec::new(),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Validator trusting the keys of the root zone
+    pub fn with_root_anchors() -> Validator {
+
+        let mut validator = Validator::new();
+
+        for anchor in ROOT_ANCHORS {
+            let (name, ttl, record) = parse_entry(anchor, &Name::root(), DEFAULT_TTL).unwrap();
+            validator.anchors.push(ResourceRecord::new(name, ttl, record));
+        }
+
+        validator
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read trust anchors from a file, one DS or DNSKEY record per line
+    pub fn load<P : AsRef<Path>>(path : P) -> Result<Validator, &'static str> {
+
+        let text = fs::read_to_string(path).map_err(|_| "Could not read trust anchors")?;
+        Validator::from_str(&text)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn add_anchor(&mut self, anchor : ResourceRecord) -> Result<(), &'static str> {
+
+        match anchor.record {
+            Record::DS(_) | Record::DNSKEY(_) => self.anchors.push(anchor),
+            _ => return Err("Trust anchor must be DS or DNSKEY"),
+        };
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn anchors(&self) -> &[ResourceRecord] {
+        &self.anchors
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Validate `response`, fetching the chain of trust from `source`
+    pub fn validate(&self, response : &Message, source : &dyn QuerySource) -> Validation {
+
+        self.validate_at(response, source, now())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Validate `response` at time `now`, given in seconds since the epoch.
+    /// `response` is expected to be a final answer rather than a referral.
+    pub fn validate_at(&self, response : &Message, source : &dyn QuerySource, now : u32)
+        -> Validation {
+
+        let question = match response.questions.first() {
+            Some(q) => q,
+            None => return Validation::indeterminate("Response without question"),
+        };
+
+        let rcode = response.rcode();
+
+        if Rcode::NoError as u16 != rcode && Rcode::NxDomain as u16 != rcode {
+            return Validation::indeterminate("Response indicates an error");
+        }
+
+        let mut chain = Chain {
+            anchors : &self.anchors,
+            source,
+            now,
+            zones : HashMap::new(),
+        };
+
+        let mut results = Vec::new();
+
+        for rrset in rrsets(&response.answers) {
+            results.push(chain.check_answer(response, &rrset));
+        }
+
+        let qtype = question.qtype.code();
+        let target = cname_target(&response.answers, &question.name);
+
+        let answered = response.answers.iter()
+            .any(|rr| rr.name == target &&
+                 (qtype == rr.type_code() || QuestionType::Any == question.qtype));
+
+        if ! answered {
+            let nxdomain = Rcode::NxDomain as u16 == rcode;
+            results.push(chain.check_denial(response, &target, qtype, nxdomain));
+        }
+
+        results.into_iter()
+            .filter_map(|r| r.err())
+            .max_by_key(|v| v.severity())
+            .unwrap_or_else(Validation::secure)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl FromStr for Validator {
+
+    type Err = &'static str;
+
+    /// Parse trust anchors, one DS or DNSKEY record per line with
+    /// absolute owner names
+    fn from_str(s : &str) -> Result<Validator, &'static str> {
+
+        let mut validator = Validator::new();
+
+        for line in s.lines() {
+
+            if tokenize(line)?.is_empty() {
+                continue;
+            }
+
+            let (name, ttl, record) = parse_entry(line, &Name::root(), DEFAULT_TTL)?;
+            validator.add_anchor(ResourceRecord::new(name, ttl, record))?;
+
+        }
+
+        Ok(validator)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for Validator {
+
+    fn default() -> Self {
+        Validator::new()
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl<'a> Chain<'a> {
+
+    /// Check an RRset of the answer section, synthesized from a wildcard
+    /// or not
+    fn check_answer(&mut self, response : &Message, rrset : &[ResourceRecord])
+        -> Result<(), Validation> {
+
+        let owner = &rrset[0].name;
+
+        let signer = signatures(&response.answers, owner, rrset[0].type_code())
+            .first()
+            .map(|rrsig| rrsig.signer.clone());
+
+        let zone = self.zone_of(signer.as_ref().unwrap_or(owner))?;
+        let labels = self.verify(&response.answers, rrset, &zone)? as usize;
+
+        if labels < owner.label_count() {
+
+            let denial = self.denial(response, &zone)?;
+
+            if ! denial.proves_expansion(owner, labels) {
+                return Err(Validation::bogus("Missing proof for wildcard expansion"));
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Check the proof that `name` does not exist, or does not own
+    /// records of type `qtype`
+    fn check_denial(&mut self, response : &Message, name : &Name, qtype : u16,
+                    nxdomain : bool) -> Result<(), Validation> {
+
+        let soa = rrsets(&response.authority).into_iter()
+            .find(|rrset| QuestionType::Soa.code() == rrset[0].type_code());
+
+        let hint = signers(&response.authority).into_iter().next()
+            .or_else(|| soa.as_ref().map(|s| s[0].name.clone()))
+            .unwrap_or_else(|| name.clone());
+
+        let zone = self.zone_of(&hint)?;
+
+        if ! name.is_subdomain_of(&zone.apex) {
+            return Err(Validation::bogus("Denial by a zone not enclosing the name"));
+        }
+
+        if let Some(ref soa) = soa {
+            self.verify(&response.authority, soa, &zone)?;
+        }
+
+        let denial = self.denial(response, &zone)?;
+
+        let proof = match nxdomain {
+            true => denial.proves_nxdomain(name, &zone.apex),
+            false => denial.proves_nodata(name, qtype, &zone.apex),
+        };
+
+        match proof {
+            Proof::Secure => Ok(()),
+            Proof::OptOut => Err(Validation::insecure("Name lies within an opt-out span")),
+            Proof::Missing if nxdomain =>
+                Err(Validation::bogus("Missing proof of non-existence")),
+            Proof::Missing => Err(Validation::bogus("Missing proof of no data")),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// The secure zone `name` belongs to. Fails with insecure if `name`
+    /// lies below a delegation without DS.
+    fn zone_of(&mut self, name : &Name) -> Result<SecureZone, Validation> {
+
+        if let Some(zone) = self.zones.get(name) {
+            return zone.clone();
+        }
+
+        let zone = self.find_zone(name);
+        self.zones.insert(name.clone(), zone.clone());
+
+        zone
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn find_zone(&mut self, name : &Name) -> Result<SecureZone, Validation> {
+
+        let anchors : Vec<Record> = self.anchors.iter()
+            .filter(|a| a.name == *name)
+            .map(|a| a.record.clone())
+            .collect();
+
+        if ! anchors.is_empty() {
+            return self.trusted_zone(name, &anchors);
+        }
+
+        let parent = match name.parent() {
+            Some(ref p) if self.anchors.iter().any(|a| p.is_subdomain_of(&a.name)) => p.clone(),
+            _ => return Err(Validation::indeterminate("No trust anchor")),
+        };
+
+        // The DS RRset is served by the parent zone, which the signer
+        // tells about. If `name` is no zone apex, the zone containing
+        // it answers.
+        let response = self.query(name, QuestionType::Ds)?;
+
+        let hint = zone_hint(&response)
+            .filter(|apex| apex != name && name.is_subdomain_of(apex))
+            .unwrap_or(parent);
+
+        let parent = self.zone_of(&hint)?;
+        let ds = rrset(&response.answers, name, QuestionType::Ds.code());
+
+        if ! ds.is_empty() {
+
+            self.verify(&response.answers, &ds, &parent)?;
+
+            let supported : Vec<Record> = ds.into_iter()
+                .map(|rr| rr.record)
+                .filter(is_supported_anchor)
+                .collect();
+
+            if supported.is_empty() {
+                return Err(Validation::insecure("No DS with supported algorithm"));
+            }
+
+            return self.trusted_zone(name, &supported);
+
+        }
+
+        let denial = self.denial(&response, &parent)?;
+
+        match denial.proves_insecure_delegation(name, &parent.apex) {
+            Ok(true) => Err(Validation::insecure("Delegation without DS")),
+            // Not a zone cut, thus `name` belongs to the parent zone
+            Ok(false) => Ok(parent),
+            Err(reason) => Err(Validation::bogus(reason)),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Fetch the keys of the zone with apex `apex` and authenticate them
+    /// by `anchors`, which are DS or DNSKEY records
+    fn trusted_zone(&mut self, apex : &Name, anchors : &[Record])
+        -> Result<SecureZone, Validation> {
+
+        let anchors : Vec<&Record> = anchors.iter()
+            .filter(|a| is_supported_anchor(a))
+            .collect();
+
+        if anchors.is_empty() {
+            return Err(Validation::insecure("No trust anchor with supported algorithm"));
+        }
+
+        let response = self.query(apex, QuestionType::Dnskey)?;
+        let rrset = rrset(&response.answers, apex, QuestionType::Dnskey.code());
+
+        let keys : Vec<Dnskey> = rrset.iter()
+            .filter_map(|rr| match rr.record {
+                Record::DNSKEY(ref key) => Some(key.clone()),
+                _ => None,
+            })
+            .filter(|key| 0 != key.flags & DNSKEY_FLAG_ZONE && DNSKEY_PROTOCOL == key.protocol)
+            .collect();
+
+        let trusted : Vec<Dnskey> = keys.iter()
+            .filter(|key| anchors.iter().any(|anchor| match **anchor {
+                Record::DS(ref ds) => ds.matches(apex, key),
+                Record::DNSKEY(ref anchor) => anchor.algorithm == key.algorithm &&
+                    anchor.public_key == key.public_key,
+                _ => false,
+            }))
+            .cloned()
+            .collect();
+
+        if trusted.is_empty() {
+            return Err(Validation::bogus("No DNSKEY matches the trust anchor"));
+        }
+
+        self.verify(&response.answers, &rrset, &SecureZone {
+            apex : apex.clone(),
+            keys : trusted,
+        })?;
+
+        Ok(SecureZone { apex : apex.clone(), keys })
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn query(&self, name : &Name, qtype : QuestionType) -> Result<Message, Validation> {
+
+        let reason = match qtype {
+            QuestionType::Ds => "Could not fetch DS",
+            _ => "Could not fetch DNSKEY",
+        };
+
+        let response = self.source.query(name, qtype)
+            .map_err(|_| Validation::indeterminate(reason))?;
+
+        match response.rcode() {
+            r if Rcode::NoError as u16 == r || Rcode::NxDomain as u16 == r => Ok(response),
+            _ => Err(Validation::indeterminate(reason)),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Check the signatures of `rrset` found in `section` against the keys
+    /// of `zone`. Returns the label count of the first valid signature.
+    fn verify(&self, section : &[ResourceRecord], rrset : &[ResourceRecord], zone : &SecureZone)
+        -> Result<u8, Validation> {
+
+        let rrsigs = signatures(section, &rrset[0].name, rrset[0].type_code());
+        let mut reason = "Missing signature";
+
+        for rrsig in rrsigs {
+
+            match self.check_rrsig(rrsig, rrset, zone) {
+                Ok(()) => return Ok(rrsig.labels),
+                Err(r) => reason = r,
+            };
+
+        }
+
+        Err(Validation::bogus(reason))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn check_rrsig(&self, rrsig : &Rrsig, rrset : &[ResourceRecord], zone : &SecureZone)
+        -> Result<(), &'static str> {
+
+        let owner = &rrset[0].name;
+
+        if rrsig.signer != zone.apex {
+            return Err("Signer is not the apex of the zone");
+        }
+
+        if ! owner.is_subdomain_of(&rrsig.signer) {
+            return Err("Signer does not enclose the owner");
+        }
+
+        if rrsig.labels as usize > owner.label_count() {
+            return Err("Signature with too many labels");
+        }
+
+        if serial_less(self.now, rrsig.inception) {
+            return Err("Signature not yet valid");
+        }
+
+        if serial_less(rrsig.expiration, self.now) {
+            return Err("Signature expired");
+        }
+
+        let mut keys = zone.keys.iter()
+            .filter(|k| k.algorithm == rrsig.algorithm && k.key_tag() == rrsig.key_tag)
+            .peekable();
+
+        if keys.peek().is_none() {
+            return Err("No key for signature");
+        }
+
+        match keys.any(|key| verify_rrsig(rrsig, rrset, key).is_ok()) {
+            true => Ok(()),
+            false => Err("Invalid signature"),
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Authenticate the NSEC and NSEC3 records in the authority section
+    /// of `response` against the keys of `zone`
+    fn denial(&self, response : &Message, zone : &SecureZone) -> Result<Denial, Validation> {
+
+        let mut denial = Denial {
+            nsec : Vec::new(),
+            nsec3 : Vec::new(),
+        };
+
+        for rrset in rrsets(&response.authority) {
+
+            match rrset[0].record {
+                Record::NSEC(_) | Record::NSEC3(_) => (),
+                _ => continue,
+            };
+
+            self.verify(&response.authority, &rrset, zone)?;
+
+            for rr in rrset {
+
+                match rr.record {
+                    Record::NSEC(nsec) => denial.nsec.push((rr.name, nsec)),
+                    Record::NSEC3(nsec3) => {
+                        // Signature checked already, thus the count is genuine
+                        if MAX_NSEC3_ITERATIONS < nsec3.iterations {
+                            return Err(Validation::insecure("Too many NSEC3 iterations"));
+                        }
+                        if let Some(hash) = nsec3_owner_hash(&rr.name, &zone.apex) {
+                            if NSEC3_HASH_SHA1 == nsec3.hash_algorithm {
+                                denial.nsec3.push((hash, nsec3));
+                            }
+                        }
+                    },
+                    _ => (),
+                };
+
+            }
+
+        }
+
+        Ok(denial)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Denial {
+
+    /// NSEC record owned by `name`
+    fn nsec_at(&self, name : &Name) -> Option<&Nsec> {
+
+        self.nsec.iter().find(|(owner, _)| owner == name).map(|(_, nsec)| nsec)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// NSEC record proving that `name` does not exist
+    fn nsec_covering(&self, name : &Name) -> Option<(&Name, &Nsec)> {
+
+        self.nsec.iter()
+            .find(|(owner, nsec)| nsec.covers(owner, name))
+            .map(|(owner, nsec)| (owner, nsec))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// NSEC3 record whose owner is the hash of `name`
+    fn nsec3_at(&self, name : &Name) -> Option<&Nsec3> {
+
+        self.nsec3.iter()
+            .find(|(hash, nsec3)| *hash == nsec3.hash(name))
+            .map(|(_, nsec3)| nsec3)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// NSEC3 record proving that `name` does not exist
+    fn nsec3_covering(&self, name : &Name) -> Option<&Nsec3> {
+
+        self.nsec3.iter()
+            .find(|(hash, nsec3)| nsec3.covers(hash, &nsec3.hash(name)))
+            .map(|(_, nsec3)| nsec3)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Closest provable encloser of `name` within the zone with apex `apex`
+    /// (RFC 5155, 8.3), along with the NSEC3 record covering the next
+    /// closer name
+    fn closest_encloser(&self, name : &Name, apex : &Name) -> Option<(Name, &Nsec3)> {
+
+        let mut next_closer = name.clone();
+
+        loop {
+
+            let encloser = next_closer.parent()?;
+
+            if ! encloser.is_subdomain_of(apex) {
+                return None;
+            }
+
+            if let Some(nsec3) = self.nsec3_at(&encloser) {
+
+                // Names at or below a delegation are not authoritative
+                if is_delegation(&nsec3.types) {
+                    return None;
+                }
+
+                return self.nsec3_covering(&next_closer).map(|cover| (encloser, cover));
+
+            }
+
+            next_closer = encloser;
+
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether `name` does not exist, nor a wildcard that could have been
+    /// expanded (RFC 4035, 5.4; RFC 5155, 8.4)
+    fn proves_nxdomain(&self, name : &Name, apex : &Name) -> Proof {
+
+        if let Some((owner, nsec)) = self.nsec_covering(name) {
+
+            let wildcard = wildcard_at(&nsec_closest_encloser(name, owner, &nsec.next));
+
+            return match self.nsec_covering(&wildcard) {
+                Some(_) => Proof::Secure,
+                None => Proof::Missing,
+            };
+
+        }
+
+        match self.closest_encloser(name, apex) {
+            Some((encloser, cover)) => match self.nsec3_covering(&wildcard_at(&encloser)) {
+                None => Proof::Missing,
+                Some(_) if cover.opt_out() => Proof::OptOut,
+                Some(_) => Proof::Secure,
+            },
+            None => Proof::Missing,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether `name` exists without records of type `qtype`, possibly
+    /// via a wildcard (RFC 4035, 3.1.3; RFC 5155, 8.5 - 8.7)
+    fn proves_nodata(&self, name : &Name, qtype : u16, apex : &Name) -> Proof {
+
+        let lacks = |types : &[u16]| match types.contains(&qtype) ||
+            types.contains(&(QuestionType::Cname.code())) {
+            true => Proof::Missing,
+            false => Proof::Secure,
+        };
+
+        if let Some(nsec) = self.nsec_at(name) {
+            return lacks(&nsec.types);
+        }
+
+        if let Some(nsec3) = self.nsec3_at(name) {
+            return lacks(&nsec3.types);
+        }
+
+        if let Some((owner, nsec)) = self.nsec_covering(name) {
+
+            // Empty non-terminal
+            if nsec.next.is_subdomain_of(name) {
+                return Proof::Secure;
+            }
+
+            let wildcard = wildcard_at(&nsec_closest_encloser(name, owner, &nsec.next));
+
+            return match self.nsec_at(&wildcard) {
+                Some(nsec) => lacks(&nsec.types),
+                None => Proof::Missing,
+            };
+
+        }
+
+        match self.closest_encloser(name, apex) {
+            Some((_, cover)) if QuestionType::Ds.code() == qtype && cover.opt_out() =>
+                Proof::OptOut,
+            Some((encloser, _)) => match self.nsec3_at(&wildcard_at(&encloser)) {
+                Some(nsec3) => lacks(&nsec3.types),
+                None => Proof::Missing,
+            },
+            None => Proof::Missing,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether `name`, an expansion of a wildcard with `labels` labels,
+    /// does not exist itself (RFC 4035, 5.3.4; RFC 5155, 8.8)
+    fn proves_expansion(&self, name : &Name, labels : usize) -> bool {
+
+        let next_closer = Name::from_labels(name.non_root_labels()[.. labels + 1].to_vec());
+
+        self.nsec_covering(name).is_some() || self.nsec3_covering(&next_closer).is_some()
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Whether `name` is a delegation without DS from the zone with apex
+    /// `apex`. If `name` is no delegation at all, false is returned.
+    fn proves_insecure_delegation(&self, name : &Name, apex : &Name)
+        -> Result<bool, &'static str> {
+
+        let types = self.nsec_at(name).map(|nsec| &nsec.types)
+            .or_else(|| self.nsec3_at(name).map(|nsec3| &nsec3.types));
+
+        if let Some(types) = types {
+
+            if types.contains(&(QuestionType::Ds.code())) {
+                return Err("DS denied although listed in the type bitmap");
+            }
+
+            return Ok(is_delegation(types));
+
+        }
+
+        if self.nsec_covering(name).is_some() {
+            return Ok(false);
+        }
+
+        // Delegations without DS might be left out of opt-out spans
+        // (RFC 5155, 8.6)
+        match self.closest_encloser(name, apex) {
+            Some((_, cover)) => Ok(cover.opt_out()),
+            None => Err("Missing proof of no DS"),
+        }
+
+    }
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Check the signature of `rrsig` over `rrset`, made with `key`.
+/// Neither validity period nor signer are checked.
+pub fn verify_rrsig(rrsig : &Rrsig, rrset : &[ResourceRecord], key : &Dnskey)
+    -> Result<(), &'static str> {
+
+    if rrsig.algorithm != key.algorithm {
+        return Err("Algorithm of key and signature differ");
+    }
+
+    let data = rrsig.signed_data(rrset);
+    let sig = &rrsig.signature[..];
+
+    let valid = match key.algorithm {
+        ALGORITHM_RSASHA1 | ALGORITHM_RSASHA1_NSEC3_SHA1 => verify_rsa(
+            &signature::RSA_PKCS1_1024_8192_SHA1_FOR_LEGACY_USE_ONLY, key, &data, sig),
+        ALGORITHM_RSASHA256 => verify_rsa(
+            &signature::RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY, key, &data, sig),
+        ALGORITHM_RSASHA512 => verify_rsa(
+            &signature::RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY, key, &data, sig),
+        ALGORITHM_ECDSAP256SHA256 => verify_ecdsa(
+            &signature::ECDSA_P256_SHA256_FIXED, key, &data, sig),
+        ALGORITHM_ECDSAP384SHA384 => verify_ecdsa(
+            &signature::ECDSA_P384_SHA384_FIXED, key, &data, sig),
+        ALGORITHM_ED25519 => UnparsedPublicKey::new(&signature::ED25519, &key.public_key)
+            .verify(&data, sig)
+            .is_ok(),
+        _ => return Err("Unsupported algorithm"),
+    };
+
+    match valid {
+        true => Ok(()),
+        false => Err("Invalid signature"),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// RSA public keys consist of exponent length, exponent and modulus
+/// (RFC 3110, 2)
+fn verify_rsa(params : &'static signature::RsaParameters, key : &Dnskey, data : &[u8],
+              sig : &[u8]) -> bool {
+
+    let (len, rest) = match key.public_key.split_first() {
+        Some((0, rest)) if 2 < rest.len() =>
+            (((rest[0] as usize) << 8) | rest[1] as usize, &rest[2 ..]),
+        Some((len, rest)) => (*len as usize, rest),
+        None => return false,
+    };
+
+    if 0 == len || rest.len() <= len {
+        return false;
+    }
+
+    RsaPublicKeyComponents { n : &rest[len ..], e : &rest[.. len] }
+        .verify(params, data, sig)
+        .is_ok()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// ECDSA public keys go without the leading 0x04 (RFC 6605, 4)
+fn verify_ecdsa(algorithm : &'static signature::EcdsaVerificationAlgorithm, key : &Dnskey,
+                data : &[u8], sig : &[u8]) -> bool {
+
+    let mut public_key = vec![4u8];
+    public_key.extend_from_slice(&key.public_key);
+
+    UnparsedPublicKey::new(algorithm, &public_key).verify(data, sig).is_ok()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn is_supported_algorithm(algorithm : u8) -> bool {
+
+    matches!(algorithm,
+             ALGORITHM_RSASHA1 | ALGORITHM_RSASHA1_NSEC3_SHA1 | ALGORITHM_RSASHA256 |
+             ALGORITHM_RSASHA512 | ALGORITHM_ECDSAP256SHA256 | ALGORITHM_ECDSAP384SHA384 |
+             ALGORITHM_ED25519)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn is_supported_anchor(anchor : &Record) -> bool {
+
+    match *anchor {
+        Record::DS(ref ds) => is_supported_algorithm(ds.algorithm) &&
+            matches!(ds.digest_type, DIGEST_SHA1 | DIGEST_SHA256 | DIGEST_SHA384),
+        Record::DNSKEY(ref key) => is_supported_algorithm(key.algorithm),
+        _ => false,
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// `a` lies before `b` in serial number arithmetic (RFC 1982)
+fn serial_less(a : u32, b : u32) -> bool {
+
+    a != b && 0 < b.wrapping_sub(a) as i32
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Type bitmap of a delegation, i.e. NS without SOA
+fn is_delegation(types : &[u16]) -> bool {
+
+    types.contains(&(QuestionType::Ns.code())) && ! types.contains(&(QuestionType::Soa.code()))
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Records of `section` grouped into RRsets, signatures left out
+fn rrsets(section : &[ResourceRecord]) -> Vec<Vec<ResourceRecord>> {
+
+    let mut rrsets : Vec<Vec<ResourceRecord>> = Vec::new();
+
+    for rr in section {
+
+        if let Record::RRSIG(_) = rr.record {
+            continue;
+        }
+
+        let position = rrsets.iter()
+            .position(|s| s[0].name == rr.name && s[0].type_code() == rr.type_code());
+
+        match position {
+            Some(i) => rrsets[i].push(rr.clone()),
+            None => rrsets.push(vec![rr.clone()]),
+        };
+
+    }
+
+    rrsets
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn rrset(section : &[ResourceRecord], owner : &Name, type_code : u16) -> Vec<ResourceRecord> {
+
+    section.iter()
+        .filter(|rr| rr.name == *owner && type_code == rr.type_code())
+        .cloned()
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn signatures<'a>(section : &'a [ResourceRecord], owner : &Name, type_code : u16)
+    -> Vec<&'a Rrsig> {
+
+    section.iter()
+        .filter(|rr| rr.name == *owner)
+        .filter_map(|rr| match rr.record {
+            Record::RRSIG(ref rrsig) if type_code == rrsig.type_covered => Some(rrsig),
+            _ => None,
+        })
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn signers(section : &[ResourceRecord]) -> Vec<Name> {
+
+    section.iter()
+        .filter_map(|rr| match rr.record {
+            Record::RRSIG(ref rrsig) => Some(rrsig.signer.clone()),
+            _ => None,
+        })
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Apex of the zone that answered `response`, as told by the signatures
+/// or the SOA record
+fn zone_hint(response : &Message) -> Option<Name> {
+
+    signers(&response.answers).into_iter()
+        .chain(signers(&response.authority))
+        .next()
+        .or_else(|| response.authority.iter()
+                 .find(|rr| QuestionType::Soa.code() == rr.type_code())
+                 .map(|rr| rr.name.clone()))
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Name the CNAME chain starting at `name` ends with
+fn cname_target(answers : &[ResourceRecord], name : &Name) -> Name {
+
+    let mut target = name.clone();
+
+    for _ in 0 .. MAX_CNAME_CHAIN_LEN {
+
+        let next = answers.iter()
+            .filter(|rr| rr.name == target)
+            .filter_map(|rr| match rr.record {
+                Record::CNAME(ref cname) => Some(cname.clone()),
+                _ => None,
+            })
+            .next();
+
+        match next {
+            Some(cname) => target = cname,
+            None => break,
+        };
+
+    }
+
+    target
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Hash an NSEC3 owner within the zone with apex `apex` stands for
+fn nsec3_owner_hash(owner : &Name, apex : &Name) -> Option<Vec<u8>> {
+
+    if owner.parent().as_ref() != Some(apex) {
+        return None;
+    }
+
+    owner.non_root_labels().last()
+        .and_then(|label| base32hex_to_bytes(&label.to_string()).ok())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Closest encloser of `name` given the NSEC record from `owner` to `next`
+/// covering it: The longest ancestor shared with either of them
+fn nsec_closest_encloser(name : &Name, owner : &Name, next : &Name) -> Name {
+
+    let shared = |other : &Name| name.non_root_labels().iter()
+        .zip(other.non_root_labels().iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let len = shared(owner).max(shared(next));
+
+    Name::from_labels(name.non_root_labels()[.. len].to_vec())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn wildcard_at(name : &Name) -> Name {
+
+    Name::from_str("*").unwrap().join(name)
+
+}
+
+/*----------------------------------------------------------------------------*/
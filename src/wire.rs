@@ -0,0 +1,361 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::collections::HashMap;
+use dnslabel::Label;
+use dnsname::Name;
+use dnstraits::AsBytes;
+
+/*----------------------------------------------------------------------------*/
+
+const MAX_NAME_LEN : usize = 255;
+const MAX_POINTER_HOPS : usize = 64;
+const POINTER_MASK : u8 = 0xC0;
+const MAX_POINTER_OFFSET : usize = 0x3FFF;
+
+/******************************************************************************
+ *                                          WRITER
+ ******************************************************************************/
+
+/// Serializes DNS data in wire format (RFC 1035, section 4).
+/// Keeps track of names already written to allow for name compression.
+pub struct WireWriter {
+
+    buffer : Vec<u8>,
+    names : HashMap<Vec<Label>, u16>,
+    /// Whether names might be compressed at all
+    compress : bool,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl WireWriter {
+
+    pub fn new() -> WireWriter {
+
+        WireWriter {
+            buffer : Vec::with_capacity(512),
+            names : HashMap::new(),
+            compress : true,
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Writer never compressing names, as required for the canonical
+    /// form of records (RFC 4034, section 6.2)
+    pub fn uncompressed() -> WireWriter {
+
+        WireWriter {
+            compress : false,
+            ..WireWriter::new()
+        }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write_u8(&mut self, value : u8) {
+        self.buffer.push(value);
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write_u16(&mut self, value : u16) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write_u32(&mut self, value : u32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn write_bytes(&mut self, bytes : &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Overwrite a 16 bit value that has already been written,
+    /// e.g. a length field that was not known in advance.
+    pub fn patch_u16(&mut self, offset : usize, value : u16) {
+        self.buffer[offset .. offset + 2].copy_from_slice(&value.to_be_bytes());
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Write name, using compression pointers to names written before.
+    pub fn write_name(&mut self, name : &Name) {
+        let compress = self.compress;
+        self.int_write_name(name, compress);
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Write name without compression, as required for RDATA of
+    /// record types not known in RFC 1035 (see RFC 3597, section 4).
+    pub fn write_name_uncompressed(&mut self, name : &Name) {
+        self.int_write_name(name, false);
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn int_write_name(&mut self, name : &Name, compress : bool) {
+
+        // Labels are stored root-first, but the wire format starts with
+        // the leftmost label. The remaining suffix of the name to write
+        // thus always is labels[.. i]
+        let labels = name.to_slice();
+        let mut i = labels.len();
+
+        while 0 < i && labels[i - 1].is_empty() {
+            i -= 1;
+        }
+
+        while 0 < i {
+
+            let suffix = &labels[.. i];
+
+            if compress {
+                if let Some(offset) = self.names.get(suffix) {
+                    self.write_u16(0xC000 | *offset);
+                    return;
+                }
+            }
+
+            let offset = self.buffer.len();
+            if offset <= MAX_POINTER_OFFSET {
+                self.names.insert(suffix.to_vec(), offset as u16);
+            }
+
+            let mut label_bytes = [0u8; 64];
+            let len = labels[i - 1].to_bytes(&mut label_bytes).unwrap_or(0);
+            self.buffer.extend_from_slice(&label_bytes[.. len]);
+
+            i -= 1;
+
+        }
+
+        self.write_u8(0);
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Default for WireWriter {
+
+    fn default() -> Self {
+        WireWriter::new()
+    }
+
+}
+
+/******************************************************************************
+ *                                          READER
+ ******************************************************************************/
+
+/// Reads DNS data in wire format.
+/// Since compression pointers refer to absolute positions, a reader always
+/// covers the entire message.
+pub struct WireReader<'a> {
+
+    data : &'a [u8],
+    offset : usize,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl<'a> WireReader<'a> {
+
+    pub fn new(data : &'a [u8]) -> WireReader<'a> {
+
+        WireReader { data, offset : 0 }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read_u8(&mut self) -> Result<u8, &'static str> {
+
+        let bytes = self.read_bytes(1)?;
+        Ok(bytes[0])
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read_u16(&mut self) -> Result<u16, &'static str> {
+
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read_u32(&mut self) -> Result<u32, &'static str> {
+
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    pub fn read_bytes(&mut self, len : usize) -> Result<&'a [u8], &'static str> {
+
+        if self.remaining() < len {
+            return Err("Message too short");
+        }
+
+        let bytes = &self.data[self.offset .. self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    /// Read a (possibly compressed) name.
+    pub fn read_name(&mut self) -> Result<Name, &'static str> {
+
+        let mut labels = Vec::<Label>::new();
+        let mut position = self.offset;
+        let mut end_of_name = None;
+        let mut hops = 0;
+        let mut name_len = 1;
+
+        loop {
+
+            if position >= self.data.len() {
+                return Err("Name exceeds message");
+            }
+
+            let len_octet = self.data[position];
+
+            if POINTER_MASK == len_octet & POINTER_MASK {
+
+                if position + 1 >= self.data.len() {
+                    return Err("Name exceeds message");
+                }
+
+                if end_of_name.is_none() {
+                    end_of_name = Some(position + 2);
+                }
+
+                hops += 1;
+                if hops > MAX_POINTER_HOPS {
+                    return Err("Too many compression pointers");
+                }
+
+                position = (((len_octet & !POINTER_MASK) as usize) << 8)
+                    | self.data[position + 1] as usize;
+                continue;
+
+            }
+
+            if 0 != len_octet & POINTER_MASK {
+                return Err("Unsupported label type");
+            }
+
+            if 0 == len_octet {
+                position += 1;
+                break;
+            }
+
+            let label = Label::from_bytes(&self.data[position ..])?;
+            name_len += label.len() + 1;
+
+            if name_len > MAX_NAME_LEN {
+                return Err("Name too long");
+            }
+
+            position += label.len() + 1;
+            labels.push(label);
+
+        }
+
+        self.offset = end_of_name.unwrap_or(position);
+
+        labels.reverse();
+        Ok(Name::from_labels(labels))
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -27,27 +27,102 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //
 use ::std::collections::HashMap;
+use ::std::cmp::Ordering;
 use ::std::fmt;
+use ::std::str::FromStr;
+use ::std::sync::OnceLock;
 use dnslabel::Label;
 use dnsname::Name;
-use dnsrecord::Record;
-use std::str::FromStr;
+use dnsquestion::QuestionType;
+use dnsrecord::{Record, ResourceRecord};
+use dnssec::{base32hex_to_bytes, nsec3_hash, Nsec3Param};
+use edns::ClientSubnet;
+use journal::{Diff, Journal};
+use network::Network;
+use zonefile::parse_entry;
+
+/*----------------------------------------------------------------------------*/
+
+/// TTL of records added without explicit TTL
+pub const DEFAULT_TTL : u32 = 3600;
 
 /******************************************************************************
  *                                             TYPE
  ******************************************************************************/
 
-enum ZoneEntry {
+/// Outcome of looking up a name within a zone
+#[derive(Debug, PartialEq)]
+pub enum Lookup {
+
+    /// Records of the requested type, along with the scope prefix length
+    /// to put into the ECS option of the response
+    Answer(Vec<ResourceRecord>, u8),
+    /// Name is an alias - the CNAME record
+    Alias(ResourceRecord),
+    /// Name lies within a delegated zone - NS records of the delegation
+    Referral(Vec<ResourceRecord>),
+    /// Name exists, but has no records of the requested type
+    NoData,
+    NxDomain,
+    /// Name does not exist, the outcome was synthesized from the
+    /// wildcard given instead (RFC 4592)
+    Wildcard(Name, Box<Lookup>),
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Everything found at one name
+#[derive(Clone)]
+struct ZoneEntry {
+
+    /// Records along with their TTL
+    records : Vec<(u32, Record)>,
+    /// Records served instead of those of the same type to particular
+    /// client subnets
+    variants : Vec<SubnetVariant>,
+    /// Names below this one
+    zone : Zone,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[derive(Clone)]
+struct SubnetVariant {
+
+    network : Network,
+    record : Record,
 
-    Record(Record),
-    Zone(Zone),
 }
 
 /*----------------------------------------------------------------------------*/
 
+#[derive(Clone)]
 pub struct Zone {
 
     entries : HashMap<Label, ZoneEntry>,
+    /// Records owned by the root name, thus only found in the root zone
+    root : Option<Box<ZoneEntry>>,
+    /// Changes of the zone. Only set for the zone as a whole,
+    /// not for the zones below its names.
+    journal : Option<Box<Journal>>,
+    /// NSEC or NSEC3 records of the zone as a whole, built on first use
+    chain : OnceLock<DenialChain>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// NSEC and NSEC3 records of a zone, sorted to find the one covering a
+/// name by binary search
+#[derive(Clone, Default)]
+struct DenialChain {
+
+    /// NSEC records in canonical order of their owners
+    nsecs : Vec<ResourceRecord>,
+    /// NSEC3 records along with their owner hash, ordered by the hash
+    nsec3s : Vec<(Vec<u8>, ResourceRecord)>,
 
 }
 
@@ -58,16 +133,261 @@ impl<'a> Zone {
 
     pub fn new() -> Zone {
 
-        Zone { entries : HashMap::new(),}
+        Zone {
+            entries : HashMap::new(),
+            root : None,
+            journal : None,
+            chain : OnceLock::new(),
+        }
 
     }
 
     /*-----------------------------------------------------------------------*/
 
     /// Tries to find a record for name within the zone.
-    pub fn lookup(&'a self, name : &Name) -> Option<&Record> {
+    /// If there are several, the first one added is returned.
+    pub fn lookup(&'a self, name : &Name) -> Option<&'a Record> {
+
+        self.internal_lookup(name.non_root_labels())
+            .and_then(|e| e.records.first())
+            .map(|(_, record)| record)
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Tries to find the record for name that is most specific for
+    /// the client subnet.
+    /// Returns the record along with the scope prefix length to
+    /// put into the ECS option of the response.
+    pub fn lookup_for_subnet(&'a self, name : &Name, subnet : Option<&ClientSubnet>)
+        -> Option<(&'a Record, u8)> {
+
+        let entry = self.internal_lookup(name.non_root_labels())?;
+        let type_code = entry.records.first()?.1.type_code();
+
+        let (records, scope) = entry.records_for_subnet(type_code, subnet);
+
+        records.first().map(|(_, record)| (*record, scope))
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// All records of type `type_code` at `name`
+    pub fn rrset(&self, name : &Name, type_code : u16) -> Vec<ResourceRecord> {
+
+        match self.internal_lookup(name.non_root_labels()) {
+            None => Vec::new(),
+            Some(entry) => entry.rrset(name, type_code),
+        }
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// All records at `name`
+    pub fn records_at(&self, name : &Name) -> Vec<ResourceRecord> {
+
+        match self.internal_lookup(name.non_root_labels()) {
+            None => Vec::new(),
+            Some(entry) => entry.records.iter()
+                .map(|(ttl, r)| ResourceRecord::new(name.clone(), *ttl, r.clone()))
+                .collect(),
+        }
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// The SOA record of the zone with apex `origin`
+    pub fn soa(&self, origin : &Name) -> Option<ResourceRecord> {
+
+        self.rrset(origin, QuestionType::Soa.code()).into_iter().next()
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// All records of the zone, names above ordered before names below.
+    /// Subnet variants are not included.
+    pub fn records(&self) -> Vec<ResourceRecord> {
+
+        let mut records = Vec::new();
+        self.internal_records(&[], &mut records);
+        records
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Look up `name` and type `type_code` the way an authoritative server
+    /// for the zone with apex `origin` would: Names below a delegation
+    /// result in a referral, aliases are reported instead of NODATA,
+    /// wildcards are expanded for names that do not exist.
+    /// Subnet variants are applied for `subnet`. Since they are not signed,
+    /// `subnet` must be None if signatures are to accompany the answer.
+    pub fn find(&self, origin : &Name, name : &Name, type_code : u16,
+                subnet : Option<&ClientSubnet>) -> Lookup {
+
+        let labels = name.non_root_labels();
+        let apex_depth = origin.label_count();
+
+        let mut zone = self;
+        let mut entry = self.root.as_deref();
+
+        for (depth, label) in labels.iter().enumerate() {
+
+            let e = match zone.entries.get(label) {
+                Some(e) => e,
+                None if depth < apex_depth => return Lookup::NxDomain,
+                None => {
+                    // The name found last is the closest encloser
+                    let wildcard = wildcard_at(&Name::from_labels(labels[.. depth].to_vec()));
+                    return match zone.entries.get(&Label::from_str("*").unwrap()) {
+                        None => Lookup::NxDomain,
+                        Some(w) => Lookup::Wildcard(
+                            wildcard, Box::new(w.find(name, type_code, subnet))),
+                    };
+                },
+            };
+
+            // DS records belong to the parent side of a delegation
+            let ds_at_cut = depth + 1 == labels.len() && QuestionType::Ds.code() == type_code;
+
+            if depth >= apex_depth && ! ds_at_cut {
+
+                let owner = Name::from_labels(labels[.. depth + 1].to_vec());
+                let ns = e.rrset(&owner, QuestionType::Ns.code());
+
+                if ! ns.is_empty() {
+                    return Lookup::Referral(ns);
+                }
+
+            }
+
+            zone = &e.zone;
+            entry = Some(e);
+
+        }
+
+        match entry {
+            None => Lookup::NxDomain,
+            Some(e) => e.find(name, type_code, subnet),
+        }
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// RRSIG records at `name` covering type `type_code`
+    pub fn signatures(&self, name : &Name, type_code : u16) -> Vec<ResourceRecord> {
+
+        self.rrset(name, QuestionType::Rrsig.code()).into_iter()
+            .filter(|rr| match rr.record {
+                Record::RRSIG(ref rrsig) => type_code == rrsig.type_covered,
+                _ => false,
+            })
+            .collect()
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// NSEC or NSEC3 records along with their signatures proving that
+    /// `name` has no records of the type asked for or, if `nxdomain`,
+    /// does not exist at all (RFC 4035, 3.1.3, RFC 5155, 7.2).
+    /// Empty if the zone with apex `origin` is not signed.
+    pub fn denial(&self, origin : &Name, name : &Name, nxdomain : bool)
+        -> Vec<ResourceRecord> {
+
+        let param = self.rrset(origin, QuestionType::Nsec3param.code()).into_iter()
+            .find_map(|rr| match rr.record {
+                Record::NSEC3PARAM(param) => Some(param),
+                _ => None,
+            });
+
+        let proof = match param {
+            None => self.nsec_denial(origin, name, nxdomain),
+            Some(ref param) => self.nsec3_denial(origin, name, nxdomain, param),
+        };
+
+        self.signed(proof)
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// NSEC or NSEC3 records along with their signatures proving that
+    /// `name`, answered from `wildcard`, does not exist itself. For
+    /// `nodata`, the proof that `wildcard` lacks the type asked for is
+    /// added (RFC 4035, 3.1.3.3 and 3.1.3.4, RFC 5155, 7.2.5 and 7.2.6).
+    /// Empty if the zone with apex `origin` is not signed.
+    pub fn wildcard_denial(&self, origin : &Name, name : &Name, wildcard : &Name,
+                           nodata : bool) -> Vec<ResourceRecord> {
+
+        let param = self.rrset(origin, QuestionType::Nsec3param.code()).into_iter()
+            .find_map(|rr| match rr.record {
+                Record::NSEC3PARAM(param) => Some(param),
+                _ => None,
+            });
+
+        let chain = self.denial_chain();
+        let mut proof = Vec::new();
+
+        match param {
+            None => {
+                proof.extend(chain.nsec_covering(name));
+                if nodata {
+                    proof.extend(self.rrset(wildcard, QuestionType::Nsec.code()));
+                }
+            },
+            Some(ref param) => {
+                let hash = |n : &Name| nsec3_hash(n, &param.salt, param.iterations);
+                let encloser = wildcard.parent().unwrap_or_else(Name::root);
+                let next_closer = Name::from_labels(
+                    name.non_root_labels()[.. encloser.label_count() + 1].to_vec());
+                proof.extend(chain.nsec3_covering(&hash(&next_closer)));
+                if nodata {
+                    proof.extend(chain.nsec3_matching(&hash(&encloser)));
+                    proof.extend(chain.nsec3_matching(&hash(wildcard)));
+                }
+            },
+        };
+
+        self.signed(proof)
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// `proof` without duplicates, along with the signatures of its records
+    fn signed(&self, proof : Vec<ResourceRecord>) -> Vec<ResourceRecord> {
 
-        self.internal_lookup(name.to_slice())
+        let mut records : Vec<ResourceRecord> = Vec::new();
+
+        for rr in proof {
+            if ! records.contains(&rr) {
+                records.push(rr);
+            }
+        }
+
+        let signatures : Vec<ResourceRecord> = records.iter()
+            .flat_map(|rr| self.signatures(&rr.name, rr.type_code()))
+            .collect();
+
+        records.extend(signatures);
+        records
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Sort the NSEC or NSEC3 records for `denial` ahead of time, instead
+    /// of on the first negative answer
+    pub fn build_denial_chain(&self) {
+
+        self.denial_chain();
 
     }
 
@@ -75,14 +395,132 @@ impl<'a> Zone {
 
     pub fn add(&mut self, name : Name, record : Record) -> Result<(), &'static str> {
 
-        // Get rid of terminal empty label
-        let labels = name.to_slice();
-        let len = labels.len();
-        if 1 > len {
-            return Err("Require NAME");
+        self.add_with_ttl(name, DEFAULT_TTL, record)
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    pub fn add_with_ttl(&mut self, name : Name, ttl : u32, record : Record)
+        -> Result<(), &'static str> {
+
+        self.chain.take();
+
+        let labels = name.non_root_labels();
+
+        if labels.is_empty() {
+            return self.root.get_or_insert_with(|| Box::new(ZoneEntry::new()))
+                .add(ttl, record);
+        }
+
+        self.internal_entry_mut(labels).add(ttl, record)
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Remove `record` from `name`.
+    /// Returns whether the record was found.
+    pub fn remove(&mut self, name : &Name, record : &Record) -> bool {
+
+        self.chain.take();
+        0 < self.internal_remove(name.non_root_labels(), &|r| r == record)
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Remove all records of type `type_code` from `name` along with
+    /// their subnet variants.
+    /// Returns the number of records removed.
+    pub fn remove_rrset(&mut self, name : &Name, type_code : u16) -> usize {
+
+        self.chain.take();
+        self.internal_remove(name.non_root_labels(), &|r| type_code == r.type_code())
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Add record to serve instead of the records of the same type at
+    /// `name` to clients within `network`.
+    pub fn add_for_subnet(&mut self, name : &Name, network : Network, record : Record)
+        -> Result<(), &'static str> {
+
+        let type_code = record.type_code();
+
+        let entry = self.internal_lookup_mut(name.non_root_labels())
+            .ok_or("Require default record for subnet variant")?;
+
+        if ! entry.records.iter().any(|(_, r)| type_code == r.type_code()) {
+            return Err("Require default record for subnet variant");
+        }
+
+        let exists = entry.variants.iter()
+            .any(|v| v.network == network && type_code == v.record.type_code());
+
+        if exists {
+            return Err("Variant for subnet exists already");
+        }
+
+        entry.variants.push(SubnetVariant { network, record });
+        Ok(())
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Record the changes of the zone in `journal` from now on
+    pub fn set_journal(&mut self, journal : Journal) {
+
+        self.journal = Some(Box::new(journal));
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_deref()
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Changes turning `previous`, the version of the zone with apex
+    /// `origin` this one replaces, into this one. None if `previous` keeps
+    /// no journal to record them in.
+    pub fn changes_since(&self, origin : &Name, previous : &Zone)
+        -> Result<Option<Diff>, &'static str> {
+
+        match previous.journal {
+            None => Ok(None),
+            Some(_) => Diff::between(origin, previous, self).map(Some),
         }
 
-        self.internal_add(&labels[0 .. len - 1], record)
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Take over the journal of `previous`, the version of the zone this
+    /// one replaces, and record `changes` in between, as determined by
+    /// `changes_since`.
+    /// Without journal, nothing is recorded.
+    pub fn succeed(&mut self, previous : &Zone, changes : Option<Diff>)
+        -> Result<(), &'static str> {
+
+        let mut journal = match previous.journal {
+            None => return Ok(()),
+            Some(ref j) => j.clone(),
+        };
+
+        // Serial not increased - versions cannot be told apart
+        match changes {
+            Some(diff) if diff.increases_serial() => journal.append(diff)?,
+            _ => journal.clear()?,
+        };
+
+        self.journal = Some(journal);
+
+        Ok(())
 
     }
 
@@ -94,65 +532,245 @@ impl<'a> Zone {
 
     /*------------------------------------------------------------------------*/
 
+    /// Add record given as `NAME [TTL] [CLASS] TYPE RDATA`
     pub fn add_from_str(&mut self, s: &str) -> Result<(), &'static str> {
 
-        // First version: use iterators:
-        //  Guess its rather imperformant, but the simplest solution iI could come
-        //  up with right now ...
-        let mut parts = s.split_whitespace();
-        let name_str = parts.next().ok_or("DNS name missing")?;
-        let record_string = parts.collect::<Vec<_>>().join(" ");
-        let name = Name::from_str(&name_str)?;
-        let record = Record::from_str(&record_string)?;
-
-        self.add(name, record)
+        let (name, ttl, record) = parse_entry(s, &Name::root(), DEFAULT_TTL)?;
+        self.add_with_ttl(name, ttl, record)
 
     }
 
     /*-----------------------------------------------------------------------*/
 
-    fn internal_lookup(&self, labels : &[Label]) -> Option<&Record> {
+    fn nsec_denial(&self, origin : &Name, name : &Name, nxdomain : bool)
+        -> Vec<ResourceRecord> {
 
-        match self.entries.get(&labels[0]){
+        let matching = self.rrset(name, QuestionType::Nsec.code());
 
-            Some(ZoneEntry::Zone(zone)) => {
-                zone.internal_lookup(&labels[1..])
-            },
-            Some(ZoneEntry::Record(record)) => Some(record),
-            None => None
+        if ! nxdomain && ! matching.is_empty() {
+            return matching;
+        }
 
+        let chain = self.denial_chain();
+
+        // Empty non-terminal - its successor lies below
+        if ! nxdomain {
+            return chain.nsec_covering(name).into_iter().collect();
         }
 
+        let wildcard = wildcard_at(&self.closest_encloser(origin, name));
+
+        chain.nsec_covering(name).into_iter()
+            .chain(chain.nsec_covering(&wildcard))
+            .collect()
+
     }
 
     /*-----------------------------------------------------------------------*/
 
-    fn internal_add(&mut self, labels: &[Label], record : Record) -> Result<(), &'static str> {
+    fn nsec3_denial(&self, origin : &Name, name : &Name, nxdomain : bool, param : &Nsec3Param)
+        -> Vec<ResourceRecord> {
 
-        match labels.len() {
+        let chain = self.denial_chain();
+
+        let hash = |n : &Name| nsec3_hash(n, &param.salt, param.iterations);
+        let matching = |n : &Name| chain.nsec3_matching(&hash(n));
+        let covering = |n : &Name| chain.nsec3_covering(&hash(n));
+
+        if ! nxdomain {
+            if let Some(rr) = matching(name) {
+                return vec![rr];
+            }
+        }
+
+        // Closest provable encloser, and the name one label below
+        // towards `name`
+        let mut next_closer = name.clone();
+        let mut encloser = name.parent();
+
+        while let Some(candidate) = encloser {
 
-            0 => Err("Require name"),
-            1 => {
-                if self.entries.contains_key(&labels[0]) {
-                    return Err("Entry already there")
+            if let Some(rr) = matching(&candidate) {
+
+                let mut proof = vec![rr];
+                proof.extend(covering(&next_closer));
+
+                if nxdomain {
+                    proof.extend(covering(&wildcard_at(&candidate)));
                 }
-                self.entries.insert(labels[0].clone(), ZoneEntry::Record(record));
-                Ok(())
-            },
-            _ => {
-
-                match self.entries.get_mut(&labels[0]) {
-
-                    Some(ZoneEntry::Zone(ref mut zone)) => return zone.internal_add(&labels[1..], record),
-                    Some(ZoneEntry::Record(_)) => return Err("Record exists already"),
-                    None => {},
-                };
-                let mut zone = Zone::new();
-                zone.internal_add(&labels[1..], record)?;
-                self.entries.insert(labels[0].clone(), ZoneEntry::Zone(zone));
-                Ok(())
+
+                return proof;
+
+            }
+
+            if candidate == *origin {
+                break;
             }
 
+            next_closer = candidate.clone();
+            encloser = candidate.parent();
+
+        }
+
+        Vec::new()
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    fn denial_chain(&self) -> &DenialChain {
+
+        self.chain.get_or_init(|| DenialChain::new(self.records()))
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Longest ancestor of `name` that exists within the zone with
+    /// apex `origin`
+    fn closest_encloser(&self, origin : &Name, name : &Name) -> Name {
+
+        let mut candidate = name.parent();
+
+        while let Some(c) = candidate {
+
+            if c == *origin || ! c.is_subdomain_of(origin) ||
+                self.internal_lookup(c.non_root_labels()).is_some() {
+                return c;
+            }
+
+            candidate = c.parent();
+
+        }
+
+        origin.clone()
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    fn internal_lookup(&self, labels : &[Label]) -> Option<&ZoneEntry> {
+
+        let label = match labels.first() {
+            None => return self.root.as_deref(),
+            Some(l) => l,
+        };
+
+        let entry = self.entries.get(label)?;
+
+        match labels.len() {
+            1 => Some(entry),
+            _ => entry.zone.internal_lookup(&labels[1..]),
+        }
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    fn internal_lookup_mut(&mut self, labels : &[Label]) -> Option<&mut ZoneEntry> {
+
+        let label = match labels.first() {
+            None => return self.root.as_deref_mut(),
+            Some(l) => l,
+        };
+
+        let entry = self.entries.get_mut(label)?;
+
+        match labels.len() {
+            1 => Some(entry),
+            _ => entry.zone.internal_lookup_mut(&labels[1..]),
+        }
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Get entry for `labels`, creating it and all entries above if required
+    fn internal_entry_mut(&mut self, labels : &[Label]) -> &mut ZoneEntry {
+
+        let entry = self.entries.entry(labels[0]).or_insert_with(ZoneEntry::new);
+
+        match labels.len() {
+            1 => entry,
+            _ => entry.zone.internal_entry_mut(&labels[1..]),
+        }
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Remove the records at `labels` matching `filter`, and names left
+    /// empty. Variants are kept as long as records of their type are.
+    fn internal_remove(&mut self, labels : &[Label], filter : &dyn Fn(&Record) -> bool)
+        -> usize {
+
+        let label = match labels.first() {
+            None => return self.internal_remove_root(filter),
+            Some(l) => *l,
+        };
+
+        let entry = match self.entries.get_mut(&label) {
+            None => return 0,
+            Some(e) => e,
+        };
+
+        let removed = match labels.len() {
+            1 => entry.remove(filter),
+            _ => entry.zone.internal_remove(&labels[1..], filter),
+        };
+
+        if entry.records.is_empty() && entry.zone.entries.is_empty() {
+            self.entries.remove(&label);
+        }
+
+        removed
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    fn internal_remove_root(&mut self, filter : &dyn Fn(&Record) -> bool) -> usize {
+
+        let entry = match self.root.as_mut() {
+            None => return 0,
+            Some(e) => e,
+        };
+
+        let removed = entry.remove(filter);
+
+        if entry.records.is_empty() {
+            self.root = None;
+        }
+
+        removed
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    fn internal_records(&self, labels : &[Label], records : &mut Vec<ResourceRecord>) {
+
+        if let Some(ref root) = self.root {
+            records.extend(root.records.iter()
+                .map(|(ttl, r)| ResourceRecord::new(Name::root(), *ttl, r.clone())));
+        }
+
+        let mut children : Vec<&Label> = self.entries.keys().collect();
+        children.sort_by_key(|l| l.to_string().to_lowercase());
+
+        for label in children {
+
+            let mut labels = labels.to_vec();
+            labels.push(*label);
+
+            let entry = &self.entries[label];
+            let name = Name::from_labels(labels.clone());
+
+            records.extend(entry.records.iter()
+                .map(|(ttl, r)| ResourceRecord::new(name.clone(), *ttl, r.clone())));
+
+            entry.zone.internal_records(&labels, records);
+
         }
 
     }
@@ -161,19 +779,29 @@ impl<'a> Zone {
 
     fn internal_fmt(&self, f: &mut dyn fmt::Write, labels: &[Label]) -> fmt::Result {
 
+        if let Some(ref root) = self.root {
+            for (ttl, record) in &root.records {
+                writeln!(f, ". {} {}", ttl, record)?;
+            }
+        }
+
         for (t, e) in &self.entries {
 
-            match e  {
+            let mut labels = labels.to_vec();
+            labels.push(*t);
 
-                ZoneEntry::Zone(zone) => {
-                    let mut labels = labels.to_vec();
-                    labels.push(*t);
-                    zone.internal_fmt(f, &labels)?;
-                },
-                ZoneEntry::Record(record) => write!(f, "{} {}\n", Name::labels_to_string(labels), record)?
+            let name = Name::labels_to_string(&labels);
 
+            for (ttl, record) in &e.records {
+                writeln!(f, "{} {} {}", name, ttl, record)?;
             }
 
+            for v in &e.variants {
+                writeln!(f, "{} {} ; subnet {}", name, v.record, v.network)?;
+            }
+
+            e.zone.internal_fmt(f, &labels)?;
+
         }
 
         Ok(())
@@ -184,15 +812,274 @@ impl<'a> Zone {
 
 /*-----------------------------------------------------------------------*/
 
+impl DenialChain {
+
+    fn new(records : Vec<ResourceRecord>) -> DenialChain {
+
+        let mut chain = DenialChain::default();
+
+        for rr in records {
+
+            match rr.record {
+                Record::NSEC(_) => chain.nsecs.push(rr),
+                Record::NSEC3(_) => {
+                    let hash = rr.name.non_root_labels().last()
+                        .and_then(|l| base32hex_to_bytes(&l.to_string()).ok());
+                    if let Some(hash) = hash {
+                        chain.nsec3s.push((hash, rr));
+                    }
+                },
+                _ => {},
+            };
+
+        }
+
+        chain.nsecs.sort_by(|a, b| a.name.canonical_cmp(&b.name));
+        chain.nsec3s.sort_by(|a, b| a.0.cmp(&b.0));
+
+        chain
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// NSEC record proving that `name` does not exist
+    fn nsec_covering(&self, name : &Name) -> Option<ResourceRecord> {
+
+        // Last owner before `name`, or the last one of the chain
+        let index = self.nsecs
+            .partition_point(|rr| Ordering::Less == rr.name.canonical_cmp(name));
+
+        let rr = match index {
+            0 => self.nsecs.last()?,
+            i => &self.nsecs[i - 1],
+        };
+
+        match rr.record {
+            Record::NSEC(ref nsec) if nsec.covers(&rr.name, name) => Some(rr.clone()),
+            _ => None,
+        }
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// NSEC3 record owned by `hash`
+    fn nsec3_matching(&self, hash : &[u8]) -> Option<ResourceRecord> {
+
+        self.nsec3s.binary_search_by(|(owner, _)| owner[..].cmp(hash)).ok()
+            .map(|i| self.nsec3s[i].1.clone())
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// NSEC3 record covering `hash`
+    fn nsec3_covering(&self, hash : &[u8]) -> Option<ResourceRecord> {
+
+        let index = self.nsec3s.partition_point(|(owner, _)| owner[..] < *hash);
+
+        let (owner, rr) = match index {
+            0 => self.nsec3s.last()?,
+            i => &self.nsec3s[i - 1],
+        };
+
+        match rr.record {
+            Record::NSEC3(ref nsec3) if nsec3.covers(owner, hash) => Some(rr.clone()),
+            _ => None,
+        }
+
+    }
+
+}
+
+/*-----------------------------------------------------------------------*/
+
+impl ZoneEntry {
+
+    fn new() -> ZoneEntry {
+
+        ZoneEntry {
+            records : Vec::new(),
+            variants : Vec::new(),
+            zone : Zone::new(),
+        }
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    fn add(&mut self, ttl : u32, record : Record) -> Result<(), &'static str> {
+
+        if self.records.iter().any(|(_, r)| *r == record) {
+            return Err("Entry already there");
+        }
+
+        let is_cname = |r : &Record| QuestionType::Cname.code() == r.type_code();
+
+        // RRSIG and NSEC accompany the CNAME (RFC 4035, 2.5)
+        let is_data = |r : &Record| ! [QuestionType::Rrsig, QuestionType::Nsec]
+            .iter().any(|t| t.code() == r.type_code());
+
+        let conflicts = |r : &Record| is_data(r) && (is_cname(&record) || is_cname(r));
+
+        if is_data(&record) && self.records.iter().any(|(_, r)| conflicts(r)) {
+            return Err("CNAME must not coexist with other records");
+        }
+
+        let is_soa = QuestionType::Soa.code() == record.type_code();
+
+        if is_soa && self.records.iter().any(|(_, r)| r.type_code() == record.type_code()) {
+            return Err("SOA exists already");
+        }
+
+        self.records.push((ttl, record));
+        Ok(())
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Remove the records matching `filter`, and variants left without
+    /// records of their type. Returns the number of records removed.
+    fn remove(&mut self, filter : &dyn Fn(&Record) -> bool) -> usize {
+
+        let before = self.records.len();
+        self.records.retain(|(_, r)| ! filter(r));
+
+        let records = &self.records;
+        self.variants.retain(|v| {
+            let type_code = v.record.type_code();
+            records.iter().any(|(_, r)| type_code == r.type_code())
+        });
+
+        before - self.records.len()
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    fn rrset(&self, name : &Name, type_code : u16) -> Vec<ResourceRecord> {
+
+        self.records.iter()
+            .filter(|(_, r)| type_code == r.type_code())
+            .map(|(ttl, r)| ResourceRecord::new(name.clone(), *ttl, r.clone()))
+            .collect()
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Look up type `type_code` at this entry, the records owned by `name`
+    fn find(&self, name : &Name, type_code : u16, subnet : Option<&ClientSubnet>) -> Lookup {
+
+        if QuestionType::Any.code() == type_code {
+
+            let records : Vec<ResourceRecord> = self.records.iter()
+                .map(|(ttl, r)| ResourceRecord::new(name.clone(), *ttl, r.clone()))
+                .collect();
+
+            return match records.is_empty() {
+                true => Lookup::NoData,
+                false => Lookup::Answer(records, 0),
+            };
+
+        }
+
+        if QuestionType::Cname.code() != type_code {
+            let cname = self.rrset(name, QuestionType::Cname.code());
+            if let Some(rr) = cname.into_iter().next() {
+                return Lookup::Alias(rr);
+            }
+        }
+
+        let (records, scope) = self.records_for_subnet(type_code, subnet);
+
+        if records.is_empty() {
+            return Lookup::NoData;
+        }
+
+        Lookup::Answer(records.into_iter()
+            .map(|(ttl, r)| ResourceRecord::new(name.clone(), ttl, r.clone()))
+            .collect(), scope)
+
+    }
+
+    /*-----------------------------------------------------------------------*/
+
+    /// Records of `type_code` most specific for the client subnet along
+    /// with the scope prefix length
+    fn records_for_subnet(&self, type_code : u16, subnet : Option<&ClientSubnet>)
+        -> (Vec<(u32, &Record)>, u8) {
+
+        let records : Vec<(u32, &Record)> = self.records.iter()
+            .filter(|(_, r)| type_code == r.type_code())
+            .map(|(ttl, r)| (*ttl, r))
+            .collect();
+
+        let subnet = match subnet {
+            None => return (records, 0),
+            Some(s) => s,
+        };
+
+        let variants : Vec<&SubnetVariant> = self.variants.iter()
+            .filter(|v| type_code == v.record.type_code())
+            .collect();
+
+        if variants.is_empty() {
+            return (records, 0);
+        }
+
+        let client_network = subnet.network();
+
+        let best = variants.iter()
+            .filter(|v| v.network.covers(&client_network))
+            .max_by_key(|v| v.network.prefix_len());
+
+        // Variants within the client network apply to parts of it only,
+        // the answer thus depends on their longer prefix (RFC 7871, 7.2.1)
+        let narrower = variants.iter()
+            .filter(|v| client_network.covers(&v.network))
+            .map(|v| v.network.prefix_len())
+            .max();
+
+        // Without a matching variant, the default records are only valid for
+        // the client network - others might match a variant
+        match best {
+            Some(v) => {
+                let ttl = records.first().map(|(ttl, _)| *ttl).unwrap_or(DEFAULT_TTL);
+                let scope = v.network.prefix_len().max(narrower.unwrap_or(0));
+                (vec![(ttl, &v.record)], scope)
+            },
+            None => (records, narrower.unwrap_or(subnet.source_prefix_len)),
+        }
+
+    }
+
+}
+
+/*-----------------------------------------------------------------------*/
+
+impl Default for Zone {
+
+    fn default() -> Self {
+        Zone::new()
+    }
+
+}
+
+/*-----------------------------------------------------------------------*/
+
 impl fmt::Display for ZoneEntry {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 
-        match self {
-            ZoneEntry::Record(record) => write!(f, "{}", record),
-            ZoneEntry::Zone(zone) => write!(f, "{}", zone)
+        for (_, record) in &self.records {
+            write!(f, "{} ", record)?;
         }
 
+        write!(f, "{}", self.zone)
+
     }
 
 }
@@ -205,9 +1092,9 @@ impl fmt::Display for Zone {
 
         write!(f, "[").ok();
 
-        for (name, record) in &self.entries {
+        for (name, entry) in &self.entries {
 
-            write!(f, "{} {};", name, record).ok();
+            write!(f, "{} {};", name, entry).ok();
 
         }
 
@@ -219,4 +1106,15 @@ impl fmt::Display for Zone {
 
 }
 
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+/// Wildcard directly below `name`
+fn wildcard_at(name : &Name) -> Name {
+
+    Name::from_str("*").unwrap().join(name)
+
+}
+
 /*----------------------------------------------------------------------------*/
@@ -0,0 +1,262 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+use ::std::fs;
+use ::std::str::FromStr;
+use dnsname::Name;
+use dnsrecord::{Record, tokenize};
+use zone::Zone;
+
+/******************************************************************************
+ *                                        PUBLIC FUNCTIONS
+ ******************************************************************************/
+
+/// Load zone with apex `origin` from a master file (RFC 1035, section 5)
+pub fn load_zone(origin : &Name, path : &str) -> Result<Zone, &'static str> {
+
+    let text = match fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(_) => return Err("Could not read zone file"),
+    };
+
+    parse_zone(origin, &text).map_err(|(line, msg)| {
+        println!("{}:{}: {}", path, line, msg);
+        msg
+    })
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Parse a zone with apex `origin` given in master file format.
+/// Supported are `$ORIGIN` and `$TTL`, relative names and `@`, omitted
+/// owner names, TTL and class fields and entries spanning several lines
+/// by means of parentheses.
+/// On error, the number of the offending line is returned.
+pub fn parse_zone(origin : &Name, text : &str) -> Result<Zone, (usize, &'static str)> {
+
+    let mut zone = Zone::new();
+    let mut parser = Parser {
+        origin : origin.clone(),
+        default_ttl : ::zone::DEFAULT_TTL,
+        last_owner : None,
+    };
+
+    let mut fields = Vec::new();
+    let mut owner_omitted = false;
+    let mut depth = 0;
+    let mut entry_line = 0;
+
+    for (index, line) in text.lines().enumerate() {
+
+        let line_fields = tokenize(line).map_err(|e| (index + 1, e))?;
+
+        if 0 == depth {
+
+            if line_fields.is_empty() {
+                continue;
+            }
+
+            owner_omitted = line.starts_with(char::is_whitespace);
+            entry_line = index + 1;
+
+        }
+
+        for field in line_fields {
+            match field.as_str() {
+                "(" => depth += 1,
+                ")" if 0 == depth => return Err((index + 1, "Unbalanced parentheses")),
+                ")" => depth -= 1,
+                _ => fields.push(field),
+            };
+        }
+
+        if 0 < depth {
+            continue;
+        }
+
+        if let Some((name, ttl, record)) = parser.entry(&fields, owner_omitted)
+            .map_err(|e| (entry_line, e))? {
+
+            if ! name.is_subdomain_of(origin) {
+                return Err((entry_line, "Record outside of zone"));
+            }
+
+            zone.add_with_ttl(name, ttl, record).map_err(|e| (entry_line, e))?;
+
+        }
+
+        fields.clear();
+
+    }
+
+    if 0 < depth {
+        return Err((entry_line, "Unbalanced parentheses"));
+    }
+
+    Ok(zone)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Format `zone` in master file format as understood by `parse_zone`.
+/// Subnet variants are not included.
+pub fn format_zone(zone : &Zone) -> String {
+
+    // Names are written without trailing dot
+    let mut text = "$ORIGIN .\n".to_string();
+
+    for rr in zone.records() {
+        text.push_str(&format!("{}\n", rr));
+    }
+
+    text
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Parse a single entry `NAME [TTL] [CLASS] TYPE RDATA`
+pub fn parse_entry(s : &str, origin : &Name, default_ttl : u32)
+    -> Result<(Name, u32, Record), &'static str> {
+
+    let fields = tokenize(s)?;
+
+    if fields.is_empty() {
+        return Err("DNS name missing");
+    }
+
+    let name = Name::from_str_relative(&fields[0], origin)?;
+    let (ttl, record) = parse_record(&fields[1 ..], origin, default_ttl)?;
+
+    Ok((name, ttl, record))
+
+}
+
+/******************************************************************************
+ *                                        HELPER METHODS
+ ******************************************************************************/
+
+struct Parser {
+
+    origin : Name,
+    default_ttl : u32,
+    last_owner : Option<Name>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Parser {
+
+    /// Process the fields of one entry - either a directive or a record
+    fn entry(&mut self, fields : &[String], owner_omitted : bool)
+        -> Result<Option<(Name, u32, Record)>, &'static str> {
+
+        match fields[0].as_str() {
+
+            "$ORIGIN" => {
+                if 2 != fields.len() {
+                    return Err("$ORIGIN requires exactly one name");
+                }
+                self.origin = Name::from_str_relative(&fields[1], &self.origin)?;
+                return Ok(None);
+            },
+
+            "$TTL" => {
+                if 2 != fields.len() {
+                    return Err("$TTL requires exactly one value");
+                }
+                self.default_ttl = u32::from_str(&fields[1])
+                    .map_err(|_| "Could not parse TTL")?;
+                return Ok(None);
+            },
+
+            d if d.starts_with('$') => return Err("Unsupported directive"),
+            _ => {},
+
+        };
+
+        let (owner, fields) = match owner_omitted {
+            true => (self.last_owner.clone().ok_or("DNS name missing")?, fields),
+            false => (Name::from_str_relative(&fields[0], &self.origin)?, &fields[1 ..]),
+        };
+
+        let (ttl, record) = parse_record(fields, &self.origin, self.default_ttl)?;
+
+        self.last_owner = Some(owner.clone());
+
+        Ok(Some((owner, ttl, record)))
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Parse `[TTL] [CLASS] TYPE RDATA`, TTL and CLASS might appear in any order
+fn parse_record(fields : &[String], origin : &Name, default_ttl : u32)
+    -> Result<(u32, Record), &'static str> {
+
+    let mut ttl = None;
+    let mut class_seen = false;
+    let mut fields = fields;
+
+    while let Some(field) = fields.first() {
+
+        if ttl.is_none() && field.chars().all(|c| c.is_ascii_digit()) {
+            ttl = Some(u32::from_str(field).map_err(|_| "Could not parse TTL")?);
+        } else if ! class_seen && "IN" == field.to_uppercase() {
+            class_seen = true;
+        } else if ! class_seen && ["CH", "HS", "CS"].contains(&field.to_uppercase().as_str()) {
+            return Err("Unsupported class");
+        } else {
+            break;
+        }
+
+        fields = &fields[1 ..];
+
+    }
+
+    if fields.len() < 2 {
+        return Err("Malformed record: Missing whitespace?");
+    }
+
+    let record = Record::from_fields(&fields[0].to_uppercase(), &fields[1 ..], origin)?;
+
+    Ok((ttl.unwrap_or(default_ttl), record))
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,283 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::IpAddr;
+use ::std::str::FromStr;
+use rustydns::{parse_zone, AuthoritativeHandler, DnsMessage, QuestionType, Rcode, Record};
+use rustydns::{Edns, ExtendedErrorCode, Opcode, RefreshTrigger, FLAG_AA, FLAG_QR};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+const ZONE : &str = "
+$TTL 3600
+@        SOA   ns1 hostmaster 1 7200 3600 1209600 300
+         NS    ns1
+ns1      A     192.0.2.1
+www      A     192.0.2.10
+         A     192.0.2.11
+alias    CNAME www
+outside  CNAME www.example.net.
+sub      NS    ns.sub
+ns.sub   A     192.0.2.53
+a.b.c    A     192.0.2.12
+*.wild   A     192.0.2.20
+*.alias  CNAME www
+";
+
+/*----------------------------------------------------------------------------*/
+
+fn handler() -> AuthoritativeHandler {
+
+    let origin = name("ubeer.org");
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(origin.clone(), parse_zone(&origin, ZONE).unwrap()).unwrap();
+    handler
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn ask(handler : &AuthoritativeHandler, qname : &str, qtype : QuestionType) -> DnsMessage {
+
+    let query = DnsMessage::query(42, name(qname), qtype);
+    let response = handler.answer(&query).unwrap();
+
+    assert_eq!(42, response.id);
+    assert!(response.flag(FLAG_QR));
+    assert_eq!(query.questions, response.questions);
+
+    response
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_authoritative_answer() {
+
+    let handler = handler();
+    let response = ask(&handler, "WWW.ubeer.org", QuestionType::A);
+
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(response.flag(FLAG_AA));
+    assert_eq!(2, response.answers.len());
+    assert_eq!(name("www.ubeer.org"), response.answers[0].name);
+    assert_eq!(Record::from_str("A 192.0.2.11").unwrap(), response.answers[1].record);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_authoritative_negative() {
+
+    let handler = handler();
+    let soa = Record::from_str("SOA ns1.ubeer.org hostmaster.ubeer.org 1 7200 3600 1209600 300").unwrap();
+
+    // NODATA
+    let response = ask(&handler, "www.ubeer.org", QuestionType::Mx);
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(response.flag(FLAG_AA));
+    assert!(response.answers.is_empty());
+    assert_eq!(1, response.authority.len());
+    assert_eq!(soa, response.authority[0].record);
+    assert_eq!(300, response.authority[0].ttl);
+
+    // Empty non-terminal
+    let response = ask(&handler, "b.c.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(response.answers.is_empty());
+
+    // NXDOMAIN
+    let response = ask(&handler, "nothere.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+    assert!(response.flag(FLAG_AA));
+    assert_eq!(soa, response.authority[0].record);
+
+    // Not our zone
+    let response = ask(&handler, "www.example.net", QuestionType::A);
+    assert_eq!(Rcode::Refused as u16, response.rcode());
+    assert!(! response.flag(FLAG_AA));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_authoritative_cname() {
+
+    let handler = handler();
+
+    let response = ask(&handler, "alias.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert_eq!(3, response.answers.len());
+    assert_eq!(Record::from_str("CNAME www.ubeer.org").unwrap(), response.answers[0].record);
+
+    let response = ask(&handler, "alias.ubeer.org", QuestionType::Cname);
+    assert_eq!(1, response.answers.len());
+
+    // Target outside of our zones is left to the client
+    let response = ask(&handler, "outside.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert_eq!(1, response.answers.len());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_authoritative_referral() {
+
+    let handler = handler();
+
+    let response = ask(&handler, "www.sub.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(! response.flag(FLAG_AA));
+    assert!(response.answers.is_empty());
+    assert_eq!(Record::from_str("NS ns.sub.ubeer.org").unwrap(), response.authority[0].record);
+    assert_eq!(Record::from_str("A 192.0.2.53").unwrap(), response.additional[0].record);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_authoritative_wildcard() {
+
+    let handler = handler();
+
+    let response = ask(&handler, "host.wild.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(response.flag(FLAG_AA));
+    assert_eq!(1, response.answers.len());
+    assert_eq!(name("host.wild.ubeer.org"), response.answers[0].name);
+    assert_eq!(Record::from_str("A 192.0.2.20").unwrap(), response.answers[0].record);
+
+    // Several labels below the closest encloser
+    let response = ask(&handler, "a.b.wild.ubeer.org", QuestionType::A);
+    assert_eq!(name("a.b.wild.ubeer.org"), response.answers[0].name);
+
+    // NODATA from the wildcard
+    let response = ask(&handler, "host.wild.ubeer.org", QuestionType::Mx);
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(response.answers.is_empty());
+    assert_eq!(1, response.authority.len());
+
+    // Alias from the wildcard, followed
+    let response = ask(&handler, "host.alias.ubeer.org", QuestionType::A);
+    assert_eq!(3, response.answers.len());
+    assert_eq!(name("host.alias.ubeer.org"), response.answers[0].name);
+    assert_eq!(name("www.ubeer.org"), response.answers[1].name);
+
+    // Names that exist are not expanded
+    let response = ask(&handler, "wild.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(response.answers.is_empty());
+
+    let response = ask(&handler, "host.www.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_authoritative_zone_requires_soa() {
+
+    let origin = name("ubeer.org");
+    let mut handler = AuthoritativeHandler::new();
+
+    let zone = parse_zone(&origin, "www A 192.0.2.10").unwrap();
+    assert!(handler.add_zone(origin.clone(), zone).is_err());
+
+    let zone = parse_zone(&origin, ZONE).unwrap();
+    assert!(handler.add_zone(origin.clone(), zone).is_ok());
+
+    let zone = parse_zone(&origin, ZONE).unwrap();
+    assert!(handler.add_zone(origin, zone).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_authoritative_extended_errors() {
+
+    let handler = handler();
+    let client = IpAddr::from_str("198.51.100.1").unwrap();
+
+    let query = |qname : &str, qtype : QuestionType, opcode : Opcode| {
+        let mut query = DnsMessage::query(42, name(qname), qtype);
+        query.set_opcode(opcode);
+        query.edns = Some(Edns::new(1232));
+        query
+    };
+
+    let check = |response : &DnsMessage, rcode : Rcode, code : ExtendedErrorCode| {
+        assert_eq!(rcode as u16, response.rcode());
+        let errors = response.edns.as_ref().unwrap().extended_errors();
+        assert_eq!(1, errors.len());
+        assert_eq!(Some(code), errors[0].code());
+    };
+
+    let response = handler.answer(&query("www.example.net", QuestionType::A, Opcode::Query));
+    check(&response.unwrap(), Rcode::Refused, ExtendedErrorCode::NotAuthoritative);
+
+    let response = handler.transfer(&query("ubeer.org", QuestionType::Axfr, Opcode::Query),
+                                    &client, None);
+    check(&response.unwrap()[0], Rcode::Refused, ExtendedErrorCode::Prohibited);
+
+    let response = handler.update(&query("ubeer.org", QuestionType::Soa, Opcode::Update),
+                                  &client, None);
+    check(&response.unwrap(), Rcode::Refused, ExtendedErrorCode::Prohibited);
+
+    let mut handler = handler;
+    handler.accept_notify(name("ubeer.org"), IpAddr::from_str("192.0.2.1").unwrap(),
+                          None, RefreshTrigger::new());
+    let response = handler.notified(&query("ubeer.org", QuestionType::Soa, Opcode::Notify),
+                                    &client, None);
+    check(&response.unwrap(), Rcode::Refused, ExtendedErrorCode::Prohibited);
+
+    // No EDNS in the query, hence no EDE in the response
+    let response = ask(&handler, "www.example.net", QuestionType::A);
+    assert_eq!(Rcode::Refused as u16, response.rcode());
+    assert!(response.edns.is_none());
+
+}
+
+/*----------------------------------------------------------------------------*/
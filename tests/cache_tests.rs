@@ -0,0 +1,412 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+use rustydns::{parse_entry, Cache, DnsMessage, Name, QuestionType, Rcode, ResourceRecord};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+/// Record given as `NAME TTL TYPE RDATA`
+fn rr(s : &str) -> ResourceRecord {
+
+    let (name, ttl, record) = parse_entry(s, &Name::root(), 3600).unwrap();
+    ResourceRecord::new(name, ttl, record)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn response(qname : &str, qtype : QuestionType, answers : &[&str], authority : &[&str])
+    -> DnsMessage {
+
+    let mut response = DnsMessage::response_to(&DnsMessage::query(1, name(qname), qtype));
+    response.answers = answers.iter().map(|s| rr(s)).collect();
+    response.authority = authority.iter().map(|s| rr(s)).collect();
+    response
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+const SOA : &str = "ubeer.org. 3600 SOA ns1.ubeer.org. hostmaster.ubeer.org. 1 3600 900 604800 300";
+
+/*----------------------------------------------------------------------------*/
+
+fn ttls(response : &DnsMessage) -> Vec<u32> {
+
+    response.answers.iter().chain(&response.authority).map(|rr| rr.ttl).collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn secs(s : u64) -> Duration {
+    Duration::from_secs(s)
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cache_decay() {
+
+    let cache = Cache::default();
+    let now = Instant::now();
+
+    cache.insert_at(&response("www.ubeer.org", QuestionType::A,
+        &["www.ubeer.org. 300 A 192.0.2.1", "www.ubeer.org. 200 A 192.0.2.2"], &[]), now);
+
+    assert_eq!(1, cache.len());
+
+    // The RRset lives as long as its shortest TTL
+    let cached = cache.lookup_at(&name("WWW.Ubeer.org"), QuestionType::A, now).unwrap();
+    assert_eq!(Rcode::NoError as u16, cached.rcode());
+    assert_eq!(vec![200, 200], ttls(&cached));
+
+    let cached = cache.lookup_at(&name("www.ubeer.org"), QuestionType::A, now + secs(150))
+        .unwrap();
+    assert_eq!(vec![50, 50], ttls(&cached));
+
+    assert!(cache.lookup_at(&name("www.ubeer.org"), QuestionType::Aaaa, now).is_none());
+    assert!(cache.lookup_at(&name("www.ubeer.org"), QuestionType::A, now + secs(200)).is_none());
+    assert!(cache.is_empty());
+
+    let stats = cache.stats();
+    assert_eq!(2, stats.hits);
+    assert_eq!(2, stats.misses);
+    assert_eq!(0, stats.entries);
+    assert_eq!(0, stats.size);
+
+    // Failures are not kept
+    let mut failure = response("www.ubeer.org", QuestionType::A,
+        &["www.ubeer.org. 300 A 192.0.2.1"], &[]);
+    failure.set_rcode(Rcode::ServFail);
+    cache.insert_at(&failure, now);
+    assert!(cache.is_empty());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cache_cname() {
+
+    let cache = Cache::default();
+    let now = Instant::now();
+
+    cache.insert_at(&response("ftp.ubeer.org", QuestionType::A,
+        &["ftp.ubeer.org. 600 CNAME www.ubeer.org.", "www.ubeer.org. 300 A 192.0.2.1"], &[]),
+        now);
+
+    assert_eq!(2, cache.len());
+
+    let cached = cache.lookup_at(&name("ftp.ubeer.org"), QuestionType::A, now).unwrap();
+    assert_eq!(name("ftp.ubeer.org"), cached.questions[0].name);
+    assert_eq!(2, cached.answers.len());
+
+    let cached = cache.lookup_at(&name("www.ubeer.org"), QuestionType::A, now).unwrap();
+    assert_eq!(1, cached.answers.len());
+
+    let cached = cache.lookup_at(&name("ftp.ubeer.org"), QuestionType::Cname, now).unwrap();
+    assert_eq!(vec![600], ttls(&cached));
+
+    // Target expired
+    assert!(cache.lookup_at(&name("ftp.ubeer.org"), QuestionType::A, now + secs(300))
+        .is_none());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cache_negative() {
+
+    let cache = Cache::default();
+    let now = Instant::now();
+
+    // NXDOMAIN holds for any type, as long as the SOA minimum says
+    let mut nxdomain = response("nope.ubeer.org", QuestionType::A, &[], &[SOA]);
+    nxdomain.set_rcode(Rcode::NxDomain);
+    cache.insert_at(&nxdomain, now);
+
+    for qtype in &[QuestionType::A, QuestionType::Mx] {
+        let cached = cache.lookup_at(&name("nope.ubeer.org"), *qtype, now + secs(100)).unwrap();
+        assert_eq!(Rcode::NxDomain as u16, cached.rcode());
+        assert!(cached.answers.is_empty());
+        assert_eq!(vec![200], ttls(&cached));
+    }
+
+    assert!(cache.lookup_at(&name("nope.ubeer.org"), QuestionType::A, now + secs(300))
+        .is_none());
+
+    // NODATA only for the type
+    cache.insert_at(&response("www.ubeer.org", QuestionType::Mx, &[], &[SOA]), now);
+
+    let cached = cache.lookup_at(&name("www.ubeer.org"), QuestionType::Mx, now).unwrap();
+    assert_eq!(Rcode::NoError as u16, cached.rcode());
+    assert!(cached.answers.is_empty());
+    assert_eq!(name("ubeer.org"), cached.authority[0].name);
+
+    assert!(cache.lookup_at(&name("www.ubeer.org"), QuestionType::A, now).is_none());
+
+    // NXDOMAIN at the end of a CNAME chain
+    let mut dangling = response("alias.ubeer.org", QuestionType::A,
+        &["alias.ubeer.org. 600 CNAME gone.ubeer.org."], &[SOA]);
+    dangling.set_rcode(Rcode::NxDomain);
+    cache.insert_at(&dangling, now);
+
+    let cached = cache.lookup_at(&name("alias.ubeer.org"), QuestionType::A, now).unwrap();
+    assert_eq!(Rcode::NxDomain as u16, cached.rcode());
+    assert_eq!(1, cached.answers.len());
+
+    // Without SOA, nothing is known for how long
+    let cache = Cache::default();
+    cache.insert_at(&response("www.ubeer.org", QuestionType::Mx, &[], &[]), now);
+    assert!(cache.is_empty());
+
+    // Limited by the maximum
+    let mut cache = Cache::default();
+    cache.set_max_negative_ttl(60);
+    cache.insert_at(&nxdomain, now);
+    assert!(cache.lookup_at(&name("nope.ubeer.org"), QuestionType::A, now + secs(60))
+        .is_none());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cache_ttl_limits() {
+
+    let mut cache = Cache::default();
+    cache.set_ttl_limits(60, 120);
+
+    let now = Instant::now();
+
+    cache.insert_at(&response("a.ubeer.org", QuestionType::A,
+        &["a.ubeer.org. 0 A 192.0.2.1"], &[]), now);
+    cache.insert_at(&response("b.ubeer.org", QuestionType::A,
+        &["b.ubeer.org. 86400 A 192.0.2.2"], &[]), now);
+
+    let a = cache.lookup_at(&name("a.ubeer.org"), QuestionType::A, now + secs(30)).unwrap();
+    assert_eq!(vec![30], ttls(&a));
+
+    let b = cache.lookup_at(&name("b.ubeer.org"), QuestionType::A, now).unwrap();
+    assert_eq!(vec![120], ttls(&b));
+
+    // Without a minimum, TTL 0 is not cached at all
+    let cache = Cache::default();
+    cache.insert_at(&response("a.ubeer.org", QuestionType::A,
+        &["a.ubeer.org. 0 A 192.0.2.1"], &[]), now);
+    assert!(cache.is_empty());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cache_eviction() {
+
+    let now = Instant::now();
+
+    let insert = |cache : &Cache, i : usize| cache.insert_at(&response(
+        &format!("host{}.ubeer.org", i), QuestionType::A,
+        &[&format!("host{}.ubeer.org. 300 A 192.0.2.{}", i, i)], &[]), now);
+
+    // Find out the size of an entry
+    let cache = Cache::default();
+    insert(&cache, 1);
+    let size = cache.stats().size;
+
+    let cache = Cache::new(3 * size);
+
+    for i in 1 ..= 3 {
+        insert(&cache, i);
+    }
+
+    assert_eq!(3, cache.len());
+
+    // host1 used recently, host2 is the one used least recently
+    assert!(cache.lookup_at(&name("host1.ubeer.org"), QuestionType::A, now).is_some());
+    insert(&cache, 4);
+
+    assert_eq!(3, cache.len());
+    assert!(cache.lookup_at(&name("host2.ubeer.org"), QuestionType::A, now).is_none());
+
+    for i in &[1, 3, 4] {
+        let host = name(&format!("host{}.ubeer.org", i));
+        assert!(cache.lookup_at(&host, QuestionType::A, now).is_some());
+    }
+
+    let stats = cache.stats();
+    assert_eq!(1, stats.evictions);
+    assert_eq!(3 * size, stats.size);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(0, cache.stats().size);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cache_threads() {
+
+    let cache = Arc::new(Cache::default());
+
+    let threads : Vec<_> = (0 .. 8).map(|t| {
+
+        let cache = cache.clone();
+
+        thread::spawn(move || for i in 0 .. 50 {
+
+            let host = format!("host{}-{}.ubeer.org", t, i);
+
+            cache.insert(&response(&host, QuestionType::A,
+                &[&format!("{}. 300 A 192.0.2.{}", host, i)], &[]));
+
+            assert!(cache.lookup(&name(&host), QuestionType::A).is_some());
+
+        })
+
+    }).collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(400, cache.len());
+    assert_eq!(400, cache.stats().hits);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cache_stale() {
+
+    let mut cache = Cache::default();
+    cache.set_max_stale(secs(600));
+
+    let now = Instant::now();
+
+    cache.insert_at(&response("www.ubeer.org", QuestionType::A,
+        &["www.ubeer.org. 300 A 192.0.2.1"], &[]), now);
+
+    let mut nxdomain = response("nope.ubeer.org", QuestionType::A, &[], &[SOA]);
+    nxdomain.set_rcode(Rcode::NxDomain);
+    cache.insert_at(&nxdomain, now);
+
+    let found = cache.find_at(&name("www.ubeer.org"), QuestionType::A, true, now).unwrap();
+    assert!(! found.stale);
+    assert_eq!(vec![300], ttls(&found.response));
+
+    // Expired records are kept, but only served if asked for
+    let later = now + secs(400);
+
+    assert!(cache.lookup_at(&name("www.ubeer.org"), QuestionType::A, later).is_none());
+    assert_eq!(2, cache.len());
+
+    let found = cache.find_at(&name("www.ubeer.org"), QuestionType::A, true, later).unwrap();
+    assert!(found.stale);
+    assert_eq!(vec![30], ttls(&found.response));
+
+    let found = cache.find_at(&name("nope.ubeer.org"), QuestionType::A, true, later).unwrap();
+    assert!(found.stale);
+    assert_eq!(Rcode::NxDomain as u16, found.response.rcode());
+
+    // Until the maximum stale age passed
+    assert!(cache.find_at(&name("www.ubeer.org"), QuestionType::A, true, now + secs(900))
+        .is_none());
+    assert_eq!(1, cache.len());
+
+    let stats = cache.stats();
+    assert_eq!(3, stats.hits);
+    assert_eq!(2, stats.stale_hits);
+
+    // Stale records are not kept unless enabled
+    let cache = Cache::default();
+
+    cache.insert_at(&response("www.ubeer.org", QuestionType::A,
+        &["www.ubeer.org. 300 A 192.0.2.1"], &[]), now);
+
+    assert!(cache.find_at(&name("www.ubeer.org"), QuestionType::A, true, later).is_none());
+    assert!(cache.is_empty());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cache_prefetch() {
+
+    let cache = Cache::default();
+    let now = Instant::now();
+
+    cache.insert_at(&response("www.ubeer.org", QuestionType::A,
+        &["www.ubeer.org. 300 A 192.0.2.1"], &[]), now);
+    cache.insert_at(&response("ftp.ubeer.org", QuestionType::A,
+        &["ftp.ubeer.org. 300 A 192.0.2.2"], &[]), now);
+
+    let prefetch = |host : &str, at : u64|
+        cache.find_at(&name(host), QuestionType::A, false, now + secs(at)).unwrap().prefetch;
+
+    // Used often, but far from expiry
+    assert!(! prefetch("www.ubeer.org", 0));
+    assert!(! prefetch("www.ubeer.org", 100));
+
+    // Close to expiry, but used only once
+    assert!(! prefetch("ftp.ubeer.org", 280));
+
+    // Reported once only
+    assert!(prefetch("www.ubeer.org", 280));
+    assert!(! prefetch("www.ubeer.org", 290));
+    assert_eq!(1, cache.stats().prefetches);
+
+    // Until refreshed
+    cache.insert_at(&response("www.ubeer.org", QuestionType::A,
+        &["www.ubeer.org. 300 A 192.0.2.1"], &[]), now + secs(290));
+
+    assert!(! prefetch("www.ubeer.org", 300));
+    assert!(prefetch("www.ubeer.org", 570));
+
+}
+
+/*----------------------------------------------------------------------------*/
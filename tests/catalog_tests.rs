@@ -0,0 +1,182 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::str::FromStr;
+use ::std::sync::Arc;
+use ::std::thread;
+use rustydns::{parse_zone, AuthoritativeHandler, DnsMessage, QuestionType, Rcode, Record};
+use rustydns::{Zone, ZoneCatalog, FLAG_AA};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+fn zone(origin : &str, address : &str) -> Zone {
+
+    let text = format!(
+        "@ SOA ns1 hostmaster 1 7200 3600 1209600 300\n@ NS ns1\nwww A {}\n",
+        address);
+
+    parse_zone(&name(origin), &text).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_catalog_longest_match() {
+
+    let catalog = ZoneCatalog::new();
+
+    assert!(catalog.add(name("ubeer.org"), zone("ubeer.org", "192.0.2.1")).is_ok());
+    assert!(catalog.add(name("sub.ubeer.org"), zone("sub.ubeer.org", "192.0.2.2")).is_ok());
+    assert!(catalog.add(name("ubeer.org"), zone("ubeer.org", "192.0.2.3")).is_err());
+
+    // Zone without SOA at the apex
+    assert!(catalog.add(name("example.net"), zone("ubeer.org", "192.0.2.4")).is_err());
+
+    assert_eq!(2, catalog.len());
+
+    let origin = |n : &str| catalog.find(&name(n)).map(|(origin, _)| origin);
+
+    assert_eq!(Some(name("ubeer.org")), origin("www.ubeer.org"));
+    assert_eq!(Some(name("ubeer.org")), origin("ubeer.org"));
+    assert_eq!(Some(name("sub.ubeer.org")), origin("www.sub.ubeer.org"));
+    assert_eq!(Some(name("sub.ubeer.org")), origin("SUB.ubeer.org"));
+    assert_eq!(None, origin("org"));
+    assert_eq!(None, origin("www.example.net"));
+
+    assert!(catalog.remove(&name("sub.ubeer.org")).is_some());
+    assert_eq!(Some(name("ubeer.org")), origin("www.sub.ubeer.org"));
+
+    assert!(catalog.remove(&name("ubeer.org")).is_some());
+    assert!(catalog.is_empty());
+    assert_eq!(None, origin("www.ubeer.org"));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn ask(handler : &AuthoritativeHandler, qname : &str) -> DnsMessage {
+
+    let query = DnsMessage::query(1, name(qname), QuestionType::A);
+    handler.answer(&query).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_catalog_changes_while_serving() {
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    let handler = Arc::new(AuthoritativeHandler::with_catalog(catalog.clone()));
+
+    catalog.add(name("ubeer.org"), zone("ubeer.org", "192.0.2.1")).unwrap();
+
+    let response = ask(&handler, "www.sub.ubeer.org");
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+
+    let readers : Vec<_> = (0 .. 4).map(|_| {
+
+        let handler = handler.clone();
+
+        thread::spawn(move || {
+            for _ in 0 .. 200 {
+                let response = ask(&handler, "www.ubeer.org");
+                assert_eq!(Rcode::NoError as u16, response.rcode());
+                assert!(response.flag(FLAG_AA));
+                assert_eq!(1, response.answers.len());
+            }
+        })
+
+    }).collect();
+
+    for i in 0 .. 50 {
+        let address = format!("192.0.2.{}", i);
+        catalog.replace(name("ubeer.org"), zone("ubeer.org", &address)).unwrap();
+    }
+
+    catalog.add(name("sub.ubeer.org"), zone("sub.ubeer.org", "192.0.2.100")).unwrap();
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    let response = ask(&handler, "www.ubeer.org");
+    assert_eq!(Record::from_str("A 192.0.2.49").unwrap(), response.answers[0].record);
+
+    let response = ask(&handler, "www.sub.ubeer.org");
+    assert_eq!(Record::from_str("A 192.0.2.100").unwrap(), response.answers[0].record);
+
+    catalog.remove(&name("ubeer.org"));
+    catalog.remove(&name("sub.ubeer.org"));
+
+    let response = ask(&handler, "www.ubeer.org");
+    assert_eq!(Rcode::Refused as u16, response.rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_catalog_replace_if() {
+
+    let catalog = ZoneCatalog::new();
+    catalog.add(name("ubeer.org"), zone("ubeer.org", "192.0.2.1")).unwrap();
+    let base = catalog.get(&name("ubeer.org")).unwrap();
+
+    // Someone else replaced the zone meanwhile
+    catalog.replace(name("ubeer.org"), zone("ubeer.org", "192.0.2.2")).unwrap();
+
+    let stale = catalog.replace_if(name("ubeer.org"), zone("ubeer.org", "192.0.2.3"),
+                                   Some(&base));
+    assert_eq!(Err("Zone changed meanwhile"), stale.map(|_| ()));
+
+    let current = catalog.get(&name("ubeer.org")).unwrap();
+    assert_eq!(Record::from_str("A 192.0.2.2").unwrap(),
+               current.rrset(&name("www.ubeer.org"), QuestionType::A.code())[0].record);
+
+    // Expecting no zone where there is one
+    assert!(catalog.replace_if(name("ubeer.org"), zone("ubeer.org", "192.0.2.3"), None)
+        .is_err());
+
+    assert!(catalog.replace_if(name("ubeer.org"), zone("ubeer.org", "192.0.2.3"),
+                               Some(&current)).is_ok());
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,345 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::{SocketAddr, UdpSocket};
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+use rustydns::{parse_zone, AuthoritativeHandler, Client, DnsMessage, Hosts};
+use rustydns::{QuestionType, Rcode, Record, ResolvConf, WireReader};
+use testhelpers::{name, start_server};
+
+/*----------------------------------------------------------------------------*/
+
+const FIXTURES : &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/client");
+
+/*----------------------------------------------------------------------------*/
+
+/// Upstream serving ubeer.org, with an RRset too large for UDP at `big`
+fn start_upstream() -> SocketAddr {
+
+    let mut text = "
+@     SOA   ns1 hostmaster 1 3600 3600 1209600 300
+      NS    ns1
+ns1   A     192.0.2.1
+www   A     192.0.2.2
+ftp   CNAME www
+".to_string();
+
+    for i in 0 .. 30 {
+        text.push_str(&format!("big TXT \"{:02}{}\"\n", i, "x".repeat(80)));
+    }
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(name("ubeer.org"), parse_zone(&name("ubeer.org"), &text).unwrap())
+        .unwrap();
+
+    start_server(handler)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Server answering the first query with the `bogus` responses first,
+/// then with the proper one
+fn start_fake_server(bogus : fn(&DnsMessage) -> DnsMessage) -> SocketAddr {
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    thread::spawn(move || {
+
+        let mut buffer = [0u8; 4096];
+        let (len, from) = socket.recv_from(&mut buffer).unwrap();
+        let query = DnsMessage::read(&mut WireReader::new(&buffer[.. len])).unwrap();
+
+        socket.send_to(&bogus(&query).to_vec(), from).unwrap();
+
+        let mut response = DnsMessage::response_to(&query);
+        response.answers.push(rustydns::ResourceRecord::new(
+            name("www.ubeer.org"), 60, Record::A("192.0.2.99".parse().unwrap())));
+        socket.send_to(&response.to_vec(), from).unwrap();
+
+    });
+
+    addr
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Address nobody answers on
+fn silent_server() -> (UdpSocket, SocketAddr) {
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    (socket, addr)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn new_client(servers : &[SocketAddr]) -> Client {
+
+    let mut client = Client::new();
+    client.set_retries(1, Duration::from_millis(300));
+
+    for server in servers {
+        client.add_server(*server);
+    }
+
+    client
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_client_lookup() {
+
+    let client = new_client(&[start_upstream()]);
+
+    let records = client.lookup(&name("www.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(1, records.len());
+    assert_eq!(name("www.ubeer.org"), records[0].name);
+    assert_eq!(Record::A("192.0.2.2".parse().unwrap()), records[0].record);
+
+    let records = client.lookup(&name("ftp.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(2, records.len());
+    assert_eq!(Record::CNAME(name("www.ubeer.org")), records[0].record);
+
+    let records = client.lookup(&name("www.ubeer.org"), QuestionType::Mx).unwrap();
+    assert!(records.is_empty());
+
+    assert_eq!(Err("Name does not exist"),
+        client.lookup(&name("nope.ubeer.org"), QuestionType::A));
+
+    let response = client.query(&name("nope.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+    assert_eq!(1, response.authority.len());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_client_tcp_fallback() {
+
+    let client = new_client(&[start_upstream()]);
+
+    let records = client.lookup(&name("big.ubeer.org"), QuestionType::Txt).unwrap();
+    assert_eq!(30, records.len());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_client_timeout() {
+
+    let (_socket, silent) = silent_server();
+    let client = new_client(&[silent]);
+
+    let start = Instant::now();
+    assert_eq!(Err("No answer"), client.lookup(&name("www.ubeer.org"), QuestionType::A));
+
+    // Two rounds of 300ms each
+    assert!(start.elapsed() >= Duration::from_millis(600));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    assert_eq!(Err("No server to ask"),
+        Client::new().lookup(&name("www.ubeer.org"), QuestionType::A));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_client_failover() {
+
+    let (_socket, silent) = silent_server();
+    let upstream = start_upstream();
+
+    let client = new_client(&[silent, upstream]);
+    let records = client.lookup(&name("www.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(1, records.len());
+
+    // Upstream refuses zones it does not serve
+    let client = new_client(&[upstream]);
+    assert_eq!(Err("Server failed to answer"),
+        client.lookup(&name("www.example.com"), QuestionType::A));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_client_mismatch() {
+
+    fn wrong_id(query : &DnsMessage) -> DnsMessage {
+
+        let mut response = DnsMessage::response_to(query);
+        response.id = query.id.wrapping_add(1);
+        response.set_rcode(Rcode::NxDomain);
+        response
+
+    }
+
+    fn wrong_question(query : &DnsMessage) -> DnsMessage {
+
+        let mut response = DnsMessage::response_to(query);
+        response.questions[0].name = name("evil.ubeer.org");
+        response.set_rcode(Rcode::NxDomain);
+        response
+
+    }
+
+    fn not_a_response(query : &DnsMessage) -> DnsMessage {
+        query.clone()
+    }
+
+    for bogus in &[wrong_id as fn(&DnsMessage) -> DnsMessage, wrong_question, not_a_response] {
+
+        let client = new_client(&[start_fake_server(*bogus)]);
+        let records = client.lookup(&name("www.ubeer.org"), QuestionType::A).unwrap();
+
+        assert_eq!(1, records.len());
+        assert_eq!(Record::A("192.0.2.99".parse().unwrap()), records[0].record);
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_client_flags() {
+
+    let mut client = new_client(&[start_upstream()]);
+    client.set_dnssec_ok(true);
+    client.set_recursion_desired(false);
+
+    let response = client.query(&name("www.ubeer.org"), QuestionType::A).unwrap();
+    assert!(response.dnssec_ok());
+    assert!(!response.flag(rustydns::FLAG_RD));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_client_search() {
+
+    let mut conf = ResolvConf::load(format!("{}/resolv.conf", FIXTURES)).unwrap();
+    conf.nameservers = vec![start_upstream()];
+
+    let mut client = Client::from_resolv_conf(&conf);
+
+    // Fewer dots than ndots: search list first
+    assert_eq!(vec![name("ns1.ubeer.org"), name("ns1.example.org"), name("ns1")],
+        client.search_names("ns1").unwrap());
+    assert_eq!(vec![name("www.ubeer.org"), name("www.ubeer.org.ubeer.org"),
+            name("www.ubeer.org.example.org")],
+        client.search_names("www.ubeer.org").unwrap());
+    assert_eq!(vec![name("ns1.ubeer.org")], client.search_names("ns1.ubeer.org.").unwrap());
+
+    let records = client.resolve("ns1", QuestionType::A).unwrap();
+    assert_eq!(name("ns1.ubeer.org"), records[0].name);
+
+    let records = client.resolve("www.ubeer.org", QuestionType::A).unwrap();
+    assert_eq!(Record::A("192.0.2.2".parse().unwrap()), records[0].record);
+
+    // www.ubeer.org exists, its siblings in the search do not
+    assert!(client.resolve("www.ubeer.org", QuestionType::Mx).unwrap().is_empty());
+
+    // example.org is refused upstream
+    assert_eq!(Err("Server failed to answer"), client.resolve("nope", QuestionType::A));
+    assert_eq!(Err("Name does not exist"), client.resolve("nope.ubeer.org.", QuestionType::A));
+
+    // Names from the hosts table are not looked up
+    client.set_hosts(Hosts::load(format!("{}/hosts", FIXTURES)).unwrap());
+
+    let records = client.resolve("www", QuestionType::A).unwrap();
+    assert_eq!(name("www"), records[0].name);
+    assert_eq!(Record::A("192.0.2.77".parse().unwrap()), records[0].record);
+
+    let records = client.resolve("localhost", QuestionType::A).unwrap();
+    assert_eq!(Record::A("127.0.0.1".parse().unwrap()), records[0].record);
+
+    // Not for the host, though
+    let records = client.resolve("ns1", QuestionType::A).unwrap();
+    assert_eq!(Record::A("192.0.2.1".parse().unwrap()), records[0].record);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_client_rotate() {
+
+    let (_socket, silent) = silent_server();
+    let upstream = start_upstream();
+
+    let mut client = new_client(&[silent, upstream]);
+    client.set_retries(0, Duration::from_millis(300));
+    client.set_rotate(true);
+
+    // Every other query starts with the silent server, and fails over
+    let mut slow = 0;
+
+    for _ in 0 .. 4 {
+
+        let start = Instant::now();
+        assert!(client.lookup(&name("www.ubeer.org"), QuestionType::A).is_ok());
+
+        if start.elapsed() >= Duration::from_millis(300) {
+            slow += 1;
+        }
+
+    }
+
+    assert_eq!(2, slow);
+
+    // Without EDNS, the answer is truncated at 512 octets
+    client.set_edns(false);
+    let response = client.query(&name("big.ubeer.org"), QuestionType::Txt).unwrap();
+    assert!(response.edns.is_none());
+    assert_eq!(30, response.answers.len());
+
+}
+
+/*----------------------------------------------------------------------------*/
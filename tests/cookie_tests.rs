@@ -0,0 +1,238 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+
+use ::std::net::{IpAddr, SocketAddr};
+use ::std::str::FromStr;
+use ::std::time::Duration;
+use rustydns::{Cookie, CookieConfig, CookieFilter, CookieStatus, DnsMessage, Edns, EdnsOption};
+use rustydns::{Name, QuestionType, Record, ResourceRecord, ServerCookies};
+
+/*----------------------------------------------------------------------------*/
+
+// Test vectors from RFC 9018, appendix A
+const SECRET : [u8; 16] = [
+    0xe5, 0xe9, 0x73, 0xe5, 0xa6, 0xb2, 0xa4, 0x3f,
+    0x48, 0xe7, 0xdc, 0x84, 0x9e, 0x37, 0xbf, 0xcf];
+
+const CLIENT_COOKIE : [u8; 8] = [0x24, 0x64, 0xc4, 0xab, 0xcf, 0x10, 0xc9, 0x57];
+
+const CLIENT_IP : &str = "198.51.100.100";
+
+const TIMESTAMP : u32 = 1559731985;
+
+const SERVER_COOKIE : [u8; 16] = [
+    0x01, 0x00, 0x00, 0x00, 0x5c, 0xf7, 0x9f, 0x11,
+    0x1f, 0x81, 0x30, 0xc3, 0xee, 0xe2, 0x94, 0x80];
+
+const RENEWED_TIMESTAMP : u32 = 1559734385;
+
+const RENEWED_SERVER_COOKIE : [u8; 16] = [
+    0x01, 0x00, 0x00, 0x00, 0x5c, 0xf7, 0xa8, 0x71,
+    0xd4, 0xa5, 0x64, 0xa1, 0x44, 0x2a, 0xca, 0x77];
+
+/*----------------------------------------------------------------------------*/
+
+fn server_cookies() -> ServerCookies {
+    ServerCookies::with_secret(SECRET, Duration::from_secs(3600))
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn client_ip() -> IpAddr {
+    IpAddr::from_str(CLIENT_IP).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn cookie(server : &[u8]) -> Cookie {
+    Cookie { client : CLIENT_COOKIE, server : server.to_vec() }
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cookie_generate() {
+
+    let cookies = server_cookies();
+
+    assert_eq!(SERVER_COOKIE.to_vec(),
+               cookies.generate(&CLIENT_COOKIE, &client_ip(), TIMESTAMP));
+    assert_eq!(RENEWED_SERVER_COOKIE.to_vec(),
+               cookies.generate(&CLIENT_COOKIE, &client_ip(), RENEWED_TIMESTAMP));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cookie_check() {
+
+    let cookies = server_cookies();
+    let ip = client_ip();
+    let now = RENEWED_TIMESTAMP;
+
+    assert_eq!(CookieStatus::Missing, cookies.check(None, &ip, now));
+    assert_eq!(CookieStatus::ClientOnly, cookies.check(Some(&cookie(&[])), &ip, now));
+    assert_eq!(CookieStatus::Valid, cookies.check(Some(&cookie(&SERVER_COOKIE)), &ip, now));
+
+    // Cookie bound to other client address
+    let other_ip = IpAddr::from_str("198.51.100.101").unwrap();
+    assert_eq!(CookieStatus::Invalid,
+               cookies.check(Some(&cookie(&SERVER_COOKIE)), &other_ip, now));
+
+    let mut tampered = SERVER_COOKIE;
+    tampered[15] ^= 1;
+    assert_eq!(CookieStatus::Invalid, cookies.check(Some(&cookie(&tampered)), &ip, now));
+
+    let mut wrong_version = SERVER_COOKIE;
+    wrong_version[0] = 2;
+    assert_eq!(CookieStatus::Invalid,
+               cookies.check(Some(&cookie(&wrong_version)), &ip, now));
+
+    assert_eq!(CookieStatus::Invalid,
+               cookies.check(Some(&cookie(&SERVER_COOKIE[.. 8])), &ip, now));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cookie_check_timestamp() {
+
+    let cookies = server_cookies();
+    let ip = client_ip();
+    let valid = Some(cookie(&SERVER_COOKIE));
+
+    assert_eq!(CookieStatus::Valid, cookies.check(valid.as_ref(), &ip, TIMESTAMP + 3600));
+    assert_eq!(CookieStatus::Invalid, cookies.check(valid.as_ref(), &ip, TIMESTAMP + 3601));
+
+    // Some clock skew is tolerated
+    assert_eq!(CookieStatus::Valid, cookies.check(valid.as_ref(), &ip, TIMESTAMP - 300));
+    assert_eq!(CookieStatus::Invalid, cookies.check(valid.as_ref(), &ip, TIMESTAMP - 301));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cookie_rotate() {
+
+    let mut cookies = server_cookies();
+    let ip = client_ip();
+    let valid = Some(cookie(&SERVER_COOKIE));
+
+    cookies.rotate([1u8; 16]);
+    assert_eq!(CookieStatus::Valid, cookies.check(valid.as_ref(), &ip, TIMESTAMP));
+    assert!(SERVER_COOKIE.to_vec() != cookies.generate(&CLIENT_COOKIE, &ip, TIMESTAMP));
+
+    cookies.rotate([2u8; 16]);
+    assert_eq!(CookieStatus::Invalid, cookies.check(valid.as_ref(), &ip, TIMESTAMP));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Query from a client accepting responses of `max_len` octets
+fn query(id : u16, max_len : u16, cookie : Option<Cookie>) -> DnsMessage {
+
+    let mut query = DnsMessage::query(id, Name::from_str("ubeer.org").unwrap(), QuestionType::Txt);
+    let mut edns = Edns::new(max_len);
+
+    if let Some(cookie) = cookie {
+        edns.set_option(EdnsOption::Cookie(cookie));
+    }
+
+    query.edns = Some(edns);
+    query
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Serialized response to `query` of at least `len` octets
+fn response(query : &DnsMessage, len : usize) -> Vec<u8> {
+
+    let mut response = DnsMessage::response_to(query);
+
+    while response.to_vec().len() < len {
+        response.answers.push(ResourceRecord::new(query.questions[0].name.clone(), 60,
+            Record::TXT(vec!["x".repeat(100).into_bytes()])));
+    }
+
+    response.to_vec()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_cookie_filter() {
+
+    let mut filter = CookieFilter::new(CookieConfig::default()).unwrap();
+    let addr = SocketAddr::from_str("198.51.100.100:53000").unwrap();
+    let client_only = cookie(&[]);
+
+    // No cookie to add for clients not sending one
+    let bytes = query(1, 1232, None).to_vec();
+    assert_eq!(Ok((None, 512)), filter.check_query(&addr, &bytes));
+
+    // Client cookie only, thus the address is not verified
+    let first = query(2, 1232, Some(client_only.clone()));
+    let (server_cookie, max_len) = filter.check_query(&addr, &first.to_vec()).unwrap();
+    let server_cookie = server_cookie.unwrap();
+    assert_eq!(CLIENT_COOKIE, server_cookie.client);
+    assert_eq!(16, server_cookie.server.len());
+    assert_eq!(512, max_len);
+
+    assert!(! filter.check_response(&addr, &response(&first, 600)));
+
+    // Verified clients get responses of the size they accept
+    let second = query(3, 1232, Some(server_cookie.clone()));
+    let (cookie, max_len) = filter.check_query(&addr, &second.to_vec()).unwrap();
+    assert!(cookie.is_some());
+    assert_eq!(1232, max_len);
+
+    assert!(filter.check_response(&addr, &response(&second, 1000)));
+
+    assert!(filter.check_query(&addr, &second.to_vec()).is_ok());
+    assert!(! filter.check_response(&addr, &response(&second, 1300)));
+
+    // Responses to unknown queries are not checked
+    assert!(filter.check_response(&addr, &response(&second, 1300)));
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,186 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+
+use ::std::str::FromStr;
+use rustydns::{AsBytes, DnsMessage, Name, QuestionType, Rcode, Record, ResourceRecord};
+use rustydns::{FLAG_CD, FLAG_QR, FLAG_RD, FLAG_TC, CLASS_IN, MAX_SAFE_UDP_PAYLOAD_LEN};
+
+/*----------------------------------------------------------------------------*/
+
+const QUERY_WWW_EXAMPLE_COM : [u8; 33] = [
+    0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0,
+    3, b'w', b'w', b'w', 7, b'e', b'x', b'a', b'm', b'p', b'l', b'e',
+    3, b'c', b'o', b'm', 0,
+    0, 1, 0, 1];
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_message_from_bytes() {
+
+    let msg = DnsMessage::from_bytes(&QUERY_WWW_EXAMPLE_COM).unwrap();
+
+    assert_eq!(0x1234, msg.id);
+    assert!(msg.flag(FLAG_RD));
+    assert!(! msg.is_response());
+    assert_eq!(1, msg.questions.len());
+    assert_eq!(Name::from_str("www.example.com").unwrap(), msg.questions[0].name);
+    assert_eq!(QuestionType::A, msg.questions[0].qtype);
+    assert_eq!(CLASS_IN, msg.questions[0].qclass);
+    assert!(msg.edns.is_none());
+
+    assert!(DnsMessage::from_bytes(&QUERY_WWW_EXAMPLE_COM[.. 20]).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_message_to_from_bytes() {
+
+    let query = DnsMessage::from_bytes(&QUERY_WWW_EXAMPLE_COM).unwrap();
+    assert_eq!(QUERY_WWW_EXAMPLE_COM.to_vec(), query.to_vec());
+
+    let mut response = DnsMessage::response_to(&query);
+    let name = Name::from_str("www.example.com").unwrap();
+    response.answers.push(ResourceRecord::new(
+            name.clone(), 300, Record::from_str("A 1.2.3.4").unwrap()));
+    response.answers.push(ResourceRecord::new(
+            name, 300, Record::from_str("PTR mail.example.com").unwrap()));
+
+    let bytes = response.to_vec();
+
+    // Owner names are compressed to a pointer to the question
+    assert_eq!(&[0xc0, 12], &bytes[33 .. 35]);
+
+    let parsed = DnsMessage::from_bytes(&bytes).unwrap();
+    assert!(parsed.is_response());
+    assert_eq!(response, parsed);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_message_compression_loop() {
+
+    // Name pointing to itself
+    let mut bytes = QUERY_WWW_EXAMPLE_COM[.. 12].to_vec();
+    bytes.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1]);
+
+    assert!(DnsMessage::from_bytes(&bytes).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_message_truncation() {
+
+    let query = DnsMessage::from_bytes(&QUERY_WWW_EXAMPLE_COM).unwrap();
+    let mut response = DnsMessage::response_to(&query);
+
+    for i in 0 .. 100 {
+        response.answers.push(ResourceRecord::new(
+                Name::from_str(&format!("host{}.example.com", i)).unwrap(),
+                300,
+                Record::from_str("A 1.2.3.4").unwrap()));
+    }
+
+    let max_len = query.max_udp_response_len();
+    assert_eq!(MAX_SAFE_UDP_PAYLOAD_LEN, max_len);
+
+    let bytes = response.to_udp_bytes(max_len);
+    assert!(bytes.len() <= max_len);
+
+    let truncated = DnsMessage::from_bytes(&bytes).unwrap();
+    assert!(truncated.flag(FLAG_TC));
+    assert!(truncated.flag(FLAG_QR));
+    assert!(truncated.answers.is_empty());
+    assert_eq!(query.questions, truncated.questions);
+
+    response.set_rcode(Rcode::NxDomain);
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_message_dnssec_flags() {
+
+    let mut query = DnsMessage::query(7, Name::from_str("www.example.com").unwrap(), QuestionType::A);
+    assert!(! query.dnssec_ok());
+
+    query.set_dnssec_ok(false);
+    assert!(query.edns.is_none());
+
+    query.set_dnssec_ok(true);
+    query.set_flag(FLAG_CD, true);
+
+    let query = DnsMessage::from_bytes(&query.to_vec()).unwrap();
+    assert!(query.dnssec_ok());
+
+    let mut response = DnsMessage::response_to(&query);
+    assert!(response.flag(FLAG_CD));
+    assert!(response.dnssec_ok());
+
+    let rr = |name : &str, record : &str| ResourceRecord::new(
+        Name::from_str(name).unwrap(), 300, Record::from_str(record).unwrap());
+
+    let rrsig = "RRSIG A 13 3 300 20261119000000 20261019000000 2371 example.com AAAA";
+
+    response.answers.push(rr("www.example.com", "A 1.2.3.4"));
+    response.answers.push(rr("www.example.com", rrsig));
+    response.authority.push(rr("example.com", "NSEC www.example.com A RRSIG NSEC"));
+    response.additional.push(rr("ns.example.com", "A 1.2.3.5"));
+    response.additional.push(rr("ns.example.com", rrsig));
+
+    response.remove_dnssec_records();
+    assert_eq!(1, response.answers.len());
+    assert!(response.authority.is_empty());
+    assert_eq!(1, response.additional.len());
+
+    // Records of the type asked for explicitly are kept
+    let mut response = DnsMessage::response_to(&DnsMessage::query(
+            8, Name::from_str("www.example.com").unwrap(), QuestionType::Rrsig));
+    response.answers.push(rr("www.example.com", rrsig));
+    response.remove_dnssec_records();
+    assert_eq!(1, response.answers.len());
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -50,6 +50,8 @@ fn test_question_type_to_bytes() {
     assert!(check_to_bytes::<QuestionType>("PTR", vec![0u8, 12u8]));
     assert!(check_to_bytes::<QuestionType>("HINFO", vec![0u8, 13u8]));
     assert!(check_to_bytes::<QuestionType>("MX", vec![0u8, 15u8]));
+    assert!(check_to_bytes::<QuestionType>("AAAA", vec![0u8, 28u8]));
+    assert!(check_to_bytes::<QuestionType>("OPT", vec![0u8, 41u8]));
     assert!(check_to_bytes::<QuestionType>("AXFR", vec![0u8, 252u8]));
     assert!(check_to_bytes::<QuestionType>("ANY", vec![0u8, 255u8]));
 
@@ -66,7 +68,11 @@ fn test_question_type_from_bytes() {
     assert!(check_from_bytes::<QuestionType>( &[0u8, 12u8], Ok("PTR")));
     assert!(check_from_bytes::<QuestionType>( &[0u8, 13u8], Ok("HINFO")));
     assert!(check_from_bytes::<QuestionType>( &[0u8, 15u8], Ok("MX")));
+    assert!(check_from_bytes::<QuestionType>( &[0u8, 6u8], Ok("SOA")));
+    assert!(check_from_bytes::<QuestionType>( &[0u8, 251u8], Ok("IXFR")));
     assert!(check_from_bytes::<QuestionType>( &[0u8, 252u8], Ok("AXFR")));
+    assert!(check_from_bytes::<QuestionType>( &[1u8, 0u8], Ok("TYPE256")));
+    assert!(check_from_bytes::<QuestionType>( &[0u8, 65u8], Ok("TYPE65")));
     assert!(check_from_bytes::<QuestionType>( &[0u8, 255u8], Ok("ANY")));
 
 }
@@ -83,6 +89,7 @@ fn test_question_type_to_from_string() {
     assert!(check_to_from_string::<QuestionType>("MX", Ok("MX")));
     assert!(check_to_from_string::<QuestionType>("AXFR", Ok("AXFR")));
     assert!(check_to_from_string::<QuestionType>("ANY", Ok("ANY")));
+    assert!(check_to_from_string::<QuestionType>("TYPE65", Ok("TYPE65")));
     assert!(check_to_from_string::<QuestionType>("InvaLid", Err("")));
 
 }
@@ -34,7 +34,8 @@
 extern crate rustydns;
 mod testhelpers;
 
-use rustydns::Record;
+use ::std::str::FromStr;
+use rustydns::{DnsMessage, Name, QuestionType, Record, ResourceRecord, WireReader, WireWriter};
 use ::testhelpers::check_to_from_string;
 
 /*----------------------------------------------------------------------------*/
@@ -45,6 +46,153 @@ fn check_record_to_from_string() {
     assert!(check_to_from_string::<Record>("A 1.2.3.4", Ok("OK")));
     assert!(check_to_from_string::<Record>("PTR org.ubeer", Ok("OK")));
     assert!(check_to_from_string::<Record>("PTR", Err("Missing Name")));
+    assert!(check_to_from_string::<Record>("NS ns1.ubeer.org", Ok("OK")));
+    assert!(check_to_from_string::<Record>("CNAME www.ubeer.org", Ok("OK")));
+    assert!(check_to_from_string::<Record>("MX 10 mail.ubeer.org", Ok("OK")));
+    assert!(check_to_from_string::<Record>("MX mail.ubeer.org", Err("Missing preference")));
+    assert!(check_to_from_string::<Record>("AAAA 2001:db8::1", Ok("OK")));
+    assert!(check_to_from_string::<Record>("AAAA 1.2.3.4", Err("No IPv6 address")));
+    assert!(check_to_from_string::<Record>(
+            "SOA ns1.ubeer.org hostmaster.ubeer.org 2026101901 7200 3600 1209600 300",
+            Ok("OK")));
+    assert!(check_to_from_string::<Record>(
+            "SOA ns1.ubeer.org hostmaster.ubeer.org 1 2 3 4", Err("Missing fields")));
+    assert!(check_to_from_string::<Record>(r#"TXT "v=spf1 -all" "a \"quoted\" word""#, Ok("OK")));
+    assert!(check_to_from_string::<Record>(r#"TXT "unterminated"#, Err("Unterminated")));
+    assert!(check_to_from_string::<Record>(r"TYPE65280 \# 3 0a0b0c", Ok("OK")));
+    assert!(check_to_from_string::<Record>(r"TYPE65280 \# 4 0a0b0c", Err("Length")));
+    assert!(check_to_from_string::<Record>(
+            "DNSKEY 257 3 13 mdsswUyr3DPW132mOi8V9xESWE8jTo0dxCjjnopKl+GqJxpVXckHAeF+KkxLbxILfDLUT0rAK9iUzy1L53eKGQ==",
+            Ok("OK")));
+    assert!(check_to_from_string::<Record>("DNSKEY 257 3 13 m!==", Err("base64")));
+    assert!(check_to_from_string::<Record>(
+            "RRSIG A 13 2 3600 20261119000000 20261019000000 2371 ubeer.org c2lnbmF0dXJl",
+            Ok("OK")));
+    assert!(check_to_from_string::<Record>(
+            "RRSIG A 13 2 3600 20261319000000 20261019000000 2371 ubeer.org c2lnbmF0dXJl",
+            Err("Month")));
+    assert!(check_to_from_string::<Record>(
+            "DS 60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118", Ok("OK")));
+    assert!(check_to_from_string::<Record>("DS 60485 5 1", Err("Missing digest")));
+    assert!(check_to_from_string::<Record>(
+            "NSEC host.example.com A MX RRSIG NSEC TYPE1234", Ok("OK")));
+    assert!(check_to_from_string::<Record>("NSEC host.example.com BOGUS", Err("Type")));
+    assert!(check_to_from_string::<Record>(
+            "NSEC3 1 1 12 AABBCCDD 2t7b4g4vsa5smi47k61mv5bv1a22bojr NS SOA MX RRSIG DNSKEY NSEC3PARAM",
+            Ok("OK")));
+    assert!(check_to_from_string::<Record>("NSEC3 1 0 0 - 2t7b4g4vsa5smi47k61mv5bv1a22bojr", Ok("OK")));
+    assert!(check_to_from_string::<Record>("NSEC3PARAM 1 0 12 AABBCCDD", Ok("OK")));
+    assert!(check_to_from_string::<Record>("NSEC3PARAM 1 0 12", Err("Missing salt")));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_record_txt_escapes() {
+
+    let record = Record::from_str(r#"TXT "a\"b" "\065\010""#).unwrap();
+
+    assert_eq!(Record::TXT(vec![b"a\"b".to_vec(), b"A\n".to_vec()]), record);
+    assert_eq!(r#"TXT "a\"b" "A\010""#, record.to_string());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_record_to_from_wire() {
+
+    let records = [
+        "A 1.2.3.4",
+        "NS ns1.ubeer.org",
+        "CNAME www.ubeer.org",
+        "SOA ns1.ubeer.org hostmaster.ubeer.org 2026101901 7200 3600 1209600 300",
+        "PTR ubeer.org",
+        "MX 10 mail.ubeer.org",
+        r#"TXT "v=spf1 -all" """#,
+        "AAAA 2001:db8::1",
+        r"TYPE65280 \# 3 0a0b0c",
+        "DNSKEY 256 3 8 AwEAAcE=",
+        "RRSIG NS 8 2 3600 20261119000000 20261019000000 2371 ubeer.org c2lnbmF0dXJl",
+        "DS 2371 13 2 0A0B0C0D",
+        "NSEC ubeer.org A NS SOA RRSIG NSEC DNSKEY TYPE65280",
+        "NSEC3 1 1 12 AABBCCDD 2t7b4g4vsa5smi47k61mv5bv1a22bojr MX RRSIG",
+        "NSEC3PARAM 1 0 0 -",
+    ];
+
+    let mut response = DnsMessage::new(1);
+    let name = Name::from_str("ubeer.org").unwrap();
+
+    for r in records.iter() {
+        response.answers.push(ResourceRecord::new(
+                name.clone(), 300, Record::from_str(r).unwrap()));
+    }
+
+    let mut writer = WireWriter::new();
+    response.write(&mut writer);
+
+    let parsed = DnsMessage::read(&mut WireReader::new(writer.as_slice())).unwrap();
+    assert_eq!(response.answers, parsed.answers);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_record_dnssec() {
+
+    // RFC 4034, 3.3
+    let rrsig = match Record::from_str(
+        concat!("RRSIG A 5 3 86400 20030322173103 20030220173103 2642 example.com. ",
+                "oJB1W6WNGv+ldvQ3WDG0MQkg5IEhjRip8WTrPYGv07h108dUKGMeDPKijVCHX3DDKdfb+v6o",
+                "B9wfuh3DTJXUAfI/M0zmO/zz8bW0Rznl8O3tGNazPwQKkRN20XPXV6nwwfoXmJQbsLNrLfkG",
+                "J5D6fwFm8nN+6pBzeDQfsS3Ap3o=")).unwrap() {
+        Record::RRSIG(rrsig) => rrsig,
+        _ => panic!("Not an RRSIG"),
+    };
+
+    assert_eq!(QuestionType::A.code(), rrsig.type_covered);
+    assert_eq!(1048354263, rrsig.expiration);
+    assert_eq!(1045762263, rrsig.inception);
+    assert_eq!(2642, rrsig.key_tag);
+    assert_eq!(Name::from_str("example.com").unwrap(), rrsig.signer);
+    assert_eq!(128, rrsig.signature.len());
+
+    // Times might be given as seconds since the epoch as well
+    let rrsig = Record::from_str(
+        "RRSIG A 5 3 86400 1048354263 1045762263 2642 example.com AAAA").unwrap();
+    assert!(rrsig.to_string().contains(" 20030322173103 20030220173103 "));
+
+    // Type bitmap of RFC 4034, 4.3
+    let nsec = Record::from_str("NSEC host.example.com A MX RRSIG NSEC TYPE1234").unwrap();
+
+    let mut writer = WireWriter::new();
+    nsec.write_rdata(&mut writer);
+
+    let bitmap = &writer.as_slice()[2 + 18 ..];
+    assert_eq!(&[0x00, 0x06, 0x40, 0x01, 0x00, 0x00, 0x00, 0x03, 0x04, 0x1b], &bitmap[.. 10]);
+    assert_eq!(2 + 6 + 2 + 27, bitmap.len());
+    assert_eq!(0x20, bitmap[bitmap.len() - 1]);
+
+    let mut reader = WireReader::new(writer.as_slice());
+    let rdlength = reader.read_u16().unwrap() as usize;
+    let read = Record::read_rdata(QuestionType::Nsec.code(), rdlength, &mut reader).unwrap();
+    assert_eq!(nsec, read);
+
+    // Mnemonics are case insensitive, types are sorted
+    assert_eq!(nsec, Record::from_str("NSEC host.example.com type1234 nsec rrsig mx a").unwrap());
+
+    let nsec3 = match Record::from_str(
+        "NSEC3 1 1 12 aabbccdd 2T7B4G4VSA5SMI47K61MV5BV1A22BOJR MX").unwrap() {
+        Record::NSEC3(nsec3) => nsec3,
+        _ => panic!("Not an NSEC3"),
+    };
+
+    assert!(nsec3.opt_out());
+    assert_eq!(vec![0xaa, 0xbb, 0xcc, 0xdd], nsec3.salt);
+    assert_eq!(20, nsec3.next_hashed.len());
+    assert_eq!(0x17, nsec3.next_hashed[0]);
 
 }
 
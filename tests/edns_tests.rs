@@ -0,0 +1,306 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+
+use ::std::net::IpAddr;
+use ::std::str::FromStr;
+use rustydns::{AsBytes, ClientSubnet, DnsMessage, Edns, EdnsOption, Rcode};
+use rustydns::{ExtendedError, ExtendedErrorCode, QUERY_PADDING_BLOCK, RESPONSE_PADDING_BLOCK};
+use rustydns::{MAX_SAFE_UDP_PAYLOAD_LEN, MAX_UDP_PAYLOAD_LEN};
+
+/*----------------------------------------------------------------------------*/
+
+/// Query for example.com A with OPT: UDP size 1232, DO bit and
+/// an option with experimental code 65001
+const QUERY_WITH_OPT : [u8; 47] = [
+    0xab, 0xcd, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 1,
+    7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0,
+    0, 1, 0, 1,
+    0, 0, 41, 0x04, 0xd0, 0, 0, 0x80, 0, 0, 7,
+    0xfd, 0xe9, 0, 3, 1, 2, 3];
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_from_bytes() {
+
+    let msg = DnsMessage::from_bytes(&QUERY_WITH_OPT).unwrap();
+
+    // OPT is not kept as ordinary record
+    assert!(msg.additional.is_empty());
+
+    let edns = msg.edns.as_ref().unwrap();
+    assert_eq!(1232, edns.udp_payload_size);
+    assert_eq!(0, edns.version);
+    assert_eq!(0, edns.extended_rcode);
+    assert!(edns.dnssec_ok);
+    assert_eq!(vec![EdnsOption::Unknown(65001, vec![1, 2, 3])], edns.options);
+
+    // Unknown options are passed through unaltered
+    assert_eq!(QUERY_WITH_OPT.to_vec(), msg.to_vec());
+
+    assert_eq!(1232, msg.max_udp_response_len());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_invalid() {
+
+    // OPT RR must be owned by root
+    let mut bytes = QUERY_WITH_OPT[.. 29].to_vec();
+    bytes.extend_from_slice(&[1, b'a', 0, 0, 41, 0x04, 0xd0, 0, 0, 0, 0, 0, 0]);
+    assert!(DnsMessage::from_bytes(&bytes).is_err());
+
+    // Option exceeding RDATA
+    let mut bytes = QUERY_WITH_OPT.to_vec();
+    bytes[39] = 5;
+    bytes.truncate(45);
+    assert!(DnsMessage::from_bytes(&bytes).is_err());
+
+    // Two OPT records
+    let mut bytes = QUERY_WITH_OPT.to_vec();
+    bytes[11] = 2;
+    bytes.extend_from_slice(&[0, 0, 41, 0x04, 0xd0, 0, 0, 0, 0, 0, 0]);
+    assert!(DnsMessage::from_bytes(&bytes).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_payload_size() {
+
+    let mut msg = DnsMessage::from_bytes(&QUERY_WITH_OPT).unwrap();
+
+    msg.edns = Some(Edns::new(100));
+    assert_eq!(MAX_SAFE_UDP_PAYLOAD_LEN, msg.max_udp_response_len());
+
+    msg.edns = Some(Edns::new(65535));
+    assert_eq!(MAX_UDP_PAYLOAD_LEN, msg.max_udp_response_len());
+
+    let response = DnsMessage::response_to(&msg);
+    assert_eq!(MAX_UDP_PAYLOAD_LEN as u16,
+               response.edns.as_ref().unwrap().udp_payload_size);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_extended_rcode() {
+
+    let mut msg = DnsMessage::new(1);
+
+    msg.set_rcode(Rcode::BadVers);
+    assert_eq!(Rcode::BadVers as u16, msg.rcode());
+    assert_eq!(1, msg.edns.as_ref().unwrap().extended_rcode);
+
+    let parsed = DnsMessage::from_bytes(&msg.to_vec()).unwrap();
+    assert_eq!(Rcode::BadVers as u16, parsed.rcode());
+
+    msg.set_rcode(Rcode::Refused);
+    assert_eq!(Rcode::Refused as u16, msg.rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn query_with_option(option : &[u8]) -> Vec<u8> {
+
+    let mut bytes = QUERY_WITH_OPT[.. 38].to_vec();
+    bytes.extend_from_slice(&(option.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(option);
+    bytes
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_client_subnet() {
+
+    // 192.0.2.0/24, address truncated to 3 octets
+    let bytes = query_with_option(&[0, 8, 0, 7, 0, 1, 24, 0, 192, 0, 2]);
+    let msg = DnsMessage::from_bytes(&bytes).unwrap();
+
+    let subnet = msg.edns.as_ref().unwrap().client_subnet().unwrap();
+    assert_eq!(24, subnet.source_prefix_len);
+    assert_eq!(0, subnet.scope_prefix_len);
+    assert_eq!(IpAddr::from_str("192.0.2.0").unwrap(), subnet.address);
+    assert_eq!(bytes, msg.to_vec());
+
+    // 2001:db8::/33
+    let bytes = query_with_option(
+        &[0, 8, 0, 9, 0, 2, 33, 0, 0x20, 0x01, 0x0d, 0xb8, 0x80]);
+    let msg = DnsMessage::from_bytes(&bytes).unwrap();
+    let subnet = msg.edns.as_ref().unwrap().client_subnet().unwrap();
+    assert_eq!(IpAddr::from_str("2001:db8:8000::").unwrap(), subnet.address);
+    assert_eq!(bytes, msg.to_vec());
+
+    let response = subnet.response(48);
+    assert_eq!(48, response.scope_prefix_len);
+    assert_eq!(subnet.address, response.address);
+
+    let subnet = ClientSubnet::new(IpAddr::from_str("10.1.2.3").unwrap(), 16).unwrap();
+    assert_eq!(IpAddr::from_str("10.1.0.0").unwrap(), subnet.address);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_client_subnet_invalid() {
+
+    // Address bits set beyond source prefix
+    let bytes = query_with_option(&[0, 8, 0, 7, 0, 1, 23, 0, 192, 0, 3]);
+    assert!(DnsMessage::from_bytes(&bytes).is_err());
+
+    // Address not truncated
+    let bytes = query_with_option(&[0, 8, 0, 8, 0, 1, 24, 0, 192, 0, 2, 0]);
+    assert!(DnsMessage::from_bytes(&bytes).is_err());
+
+    // Prefix too long
+    let bytes = query_with_option(&[0, 8, 0, 9, 0, 1, 33, 0, 192, 0, 2, 0, 0]);
+    assert!(DnsMessage::from_bytes(&bytes).is_err());
+
+    // Unknown family
+    let bytes = query_with_option(&[0, 8, 0, 4, 0, 3, 0, 0]);
+    assert!(DnsMessage::from_bytes(&bytes).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_cookie() {
+
+    let client = [1u8, 2, 3, 4, 5, 6, 7, 8];
+
+    let mut option = vec![0, 10, 0, 8];
+    option.extend_from_slice(&client);
+    let bytes = query_with_option(&option);
+
+    let msg = DnsMessage::from_bytes(&bytes).unwrap();
+    let cookie = msg.edns.as_ref().unwrap().cookie().unwrap();
+    assert_eq!(client, cookie.client);
+    assert!(cookie.server.is_empty());
+    assert_eq!(bytes, msg.to_vec());
+
+    let mut option = vec![0, 10, 0, 24];
+    option.extend_from_slice(&client);
+    option.extend_from_slice(&[9u8; 16]);
+    let bytes = query_with_option(&option);
+
+    let msg = DnsMessage::from_bytes(&bytes).unwrap();
+    assert_eq!(vec![9u8; 16], msg.edns.as_ref().unwrap().cookie().unwrap().server);
+
+    // Client cookie too short, server cookie too short or too long
+    for len in &[7usize, 9, 15, 41] {
+        let mut option = vec![0, 10, 0, *len as u8];
+        option.extend_from_slice(&vec![1u8; *len]);
+        assert!(DnsMessage::from_bytes(&query_with_option(&option)).is_err());
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_padding() {
+
+    let mut query = DnsMessage::from_bytes(&QUERY_WITH_OPT).unwrap();
+    let mut response = DnsMessage::response_to(&query);
+
+    // Query not padded - response must not be either
+    response.pad_response(&query);
+    assert!(response.edns.as_ref().unwrap().padding().is_none());
+
+    query.pad_to_block(QUERY_PADDING_BLOCK);
+    let bytes = query.to_vec();
+    assert_eq!(QUERY_PADDING_BLOCK, bytes.len());
+
+    // Padding is replaced, not added
+    query.pad_to_block(QUERY_PADDING_BLOCK);
+    assert_eq!(QUERY_PADDING_BLOCK, query.to_vec().len());
+
+    let parsed = DnsMessage::from_bytes(&bytes).unwrap();
+    assert_eq!(query, parsed);
+
+    response.pad_response(&parsed);
+    assert_eq!(RESPONSE_PADDING_BLOCK, response.to_vec().len());
+
+    // Padding is added to a message without EDNS as well
+    let mut msg = DnsMessage::new(1);
+    msg.pad_to_block(64);
+    assert_eq!(64, msg.to_vec().len());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_edns_extended_error() {
+
+    let query = DnsMessage::from_bytes(&QUERY_WITH_OPT).unwrap();
+    let mut response = DnsMessage::response_to(&query);
+
+    response.set_rcode(Rcode::Refused);
+    response.add_extended_error(ExtendedErrorCode::NotAuthoritative, "not our zone");
+    response.add_extended_error(ExtendedErrorCode::Prohibited, "");
+
+    let parsed = DnsMessage::from_bytes(&response.to_vec()).unwrap();
+    assert_eq!(response, parsed);
+
+    let errors = parsed.edns.as_ref().unwrap().extended_errors();
+    assert_eq!(2, errors.len());
+    assert_eq!(Some(ExtendedErrorCode::NotAuthoritative), errors[0].code());
+    assert_eq!("not our zone", errors[0].extra_text);
+    assert_eq!(18, errors[1].info_code);
+    assert_eq!("", errors[1].extra_text);
+
+    // Unknown INFO-CODEs are kept
+    let bytes = query_with_option(&[0, 15, 0, 4, 0x01, 0x00, b'h', b'i']);
+    let msg = DnsMessage::from_bytes(&bytes).unwrap();
+    let errors = msg.edns.as_ref().unwrap().extended_errors();
+    assert_eq!(&ExtendedError { info_code : 256, extra_text : "hi".to_string() }, errors[0]);
+    assert!(errors[0].code().is_none());
+
+    assert!(DnsMessage::from_bytes(&query_with_option(&[0, 15, 0, 1, 0])).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
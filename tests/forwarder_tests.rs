@@ -0,0 +1,427 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::{SocketAddr, UdpSocket};
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+use rustydns::{parse_zone, AuthoritativeHandler, Cache, Client, DnsMessage, ForwardingHandler};
+use rustydns::{Edns, ExtendedErrorCode, Opcode, QuestionType, Rcode, Record};
+use rustydns::{ResourceRecord, WireReader, FLAG_RA};
+use testhelpers::{name, start_server};
+
+/*----------------------------------------------------------------------------*/
+
+fn start_upstream(origin : &str, text : &str) -> SocketAddr {
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(name(origin), parse_zone(&name(origin), text).unwrap()).unwrap();
+
+    start_server(handler)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Resolver for the world, and one for the internal domain
+fn start_upstreams() -> (SocketAddr, SocketAddr) {
+
+    let public = start_upstream("ubeer.org", "
+@     SOA   ns1 hostmaster 1 3600 3600 1209600 300
+      NS    ns1
+ns1   A     192.0.2.1
+www   A     192.0.2.2
+");
+
+    let corp = start_upstream("corp.example", "
+@     SOA   ns1 hostmaster 1 3600 3600 1209600 300
+      NS    ns1
+ns1   A     10.0.0.1
+intra A     10.0.0.2
+");
+
+    (public, corp)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn silent_server() -> (UdpSocket, SocketAddr) {
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    (socket, addr)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn forwarder(upstreams : &[SocketAddr]) -> ForwardingHandler {
+
+    let mut forwarder = ForwardingHandler::new(Arc::new(Cache::default()));
+    forwarder.set_timeout(Duration::from_millis(200));
+
+    for upstream in upstreams {
+        forwarder.add_upstream(*upstream);
+    }
+
+    forwarder
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn client(server : SocketAddr) -> Client {
+
+    let mut client = Client::new();
+    client.add_server(server);
+    client.set_retries(0, Duration::from_secs(2));
+    client
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_forwarder_relay() {
+
+    let (public, corp) = start_upstreams();
+
+    let mut handler = forwarder(&[public]);
+    handler.add_rule(name("corp.example"), vec![corp]);
+
+    assert_eq!(&[corp], handler.upstreams_for(&name("intra.corp.example")));
+    assert_eq!(&[public], handler.upstreams_for(&name("www.corp.example.org")));
+
+    let cache = handler.cache().clone();
+    let client = client(start_server(handler));
+
+    let records = client.lookup(&name("www.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(Record::A("192.0.2.2".parse().unwrap()), records[0].record);
+
+    let records = client.lookup(&name("intra.corp.example"), QuestionType::A).unwrap();
+    assert_eq!(Record::A("10.0.0.2".parse().unwrap()), records[0].record);
+
+    assert_eq!(Err("Name does not exist"),
+        client.lookup(&name("nope.corp.example"), QuestionType::A));
+
+    // Answered from the cache
+    assert_eq!(0, cache.stats().hits);
+
+    let records = client.lookup(&name("www.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(Record::A("192.0.2.2".parse().unwrap()), records[0].record);
+
+    assert_eq!(Err("Name does not exist"),
+        client.lookup(&name("nope.corp.example"), QuestionType::A));
+
+    assert_eq!(2, cache.stats().hits);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_forwarder_id() {
+
+    let (public, _) = start_upstreams();
+    let addr = start_server(forwarder(&[public]));
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+
+    for id in &[0xbeef, 0x1234] {
+
+        let query = DnsMessage::query(*id, name("www.ubeer.org"), QuestionType::A);
+        socket.send_to(&query.to_vec(), addr).unwrap();
+
+        let mut buffer = [0u8; 512];
+        let (len, _) = socket.recv_from(&mut buffer).unwrap();
+        let response = DnsMessage::read(&mut WireReader::new(&buffer[.. len])).unwrap();
+
+        assert_eq!(*id, response.id);
+        assert!(response.is_response());
+        assert!(response.flag(FLAG_RA));
+        assert_eq!(query.questions, response.questions);
+        assert_eq!(1, response.answers.len());
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_forwarder_failover() {
+
+    let (public, _) = start_upstreams();
+    let (_socket, silent) = silent_server();
+
+    let handler = Arc::new(forwarder(&[silent, public]));
+
+    let query = |host : &str| DnsMessage::query(1, name(host), QuestionType::A);
+
+    // Until given up on, the silent upstream is asked first
+    for host in &["a.ubeer.org", "b.ubeer.org", "c.ubeer.org"] {
+
+        let start = Instant::now();
+        let response = handler.answer(&query(host)).unwrap();
+
+        assert_eq!(Rcode::NxDomain as u16, response.rcode());
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+    }
+
+    assert!(! handler.is_healthy(&silent));
+    assert!(handler.is_healthy(&public));
+
+    let start = Instant::now();
+    let response = handler.answer(&query("www.ubeer.org")).unwrap();
+    assert_eq!(1, response.answers.len());
+    assert!(start.elapsed() < Duration::from_millis(200));
+
+    // No upstream left
+    let handler = forwarder(&[silent]);
+    let response = handler.answer(&query("www.ubeer.org")).unwrap();
+    assert_eq!(Rcode::ServFail as u16, response.rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_forwarder_unknown_type() {
+
+    let upstream = start_upstream("ubeer.org", "
+@     SOA   ns1 hostmaster 1 3600 3600 1209600 300
+      NS    ns1
+ns1   A     192.0.2.1
+svc   TYPE65  \\# 3 000100
+");
+
+    let client = client(start_server(forwarder(&[upstream])));
+    let https = QuestionType::from_u16(65);
+
+    // Types we know nothing about are relayed all the same
+    let response = client.query(&name("svc.ubeer.org"), https).unwrap();
+    assert_eq!(https, response.questions[0].qtype);
+    assert_eq!(Record::Unknown(65, vec![0, 1, 0]), response.answers[0].record);
+
+    // and answered with NODATA if there are none
+    let response = client.query(&name("ns1.ubeer.org"), https).unwrap();
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(response.answers.is_empty());
+    assert_eq!(QuestionType::Soa.code(), response.authority[0].type_code());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_forwarder_malformed() {
+
+    let handler = forwarder(&[]);
+
+    let mut query = DnsMessage::query(1, name("www.ubeer.org"), QuestionType::A);
+    query.set_opcode(Opcode::Notify);
+    assert_eq!(Rcode::NotImp as u16, handler.answer(&query).unwrap().rcode());
+
+    let mut query = DnsMessage::query(1, name("www.ubeer.org"), QuestionType::A);
+    query.questions.push(query.questions[0].clone());
+    assert_eq!(Rcode::FormErr as u16, handler.answer(&query).unwrap().rcode());
+
+    let query = DnsMessage::query(1, name("ubeer.org"), QuestionType::Axfr);
+    assert_eq!(Rcode::NotImp as u16, handler.answer(&query).unwrap().rcode());
+
+    let response = DnsMessage::response_to(&query);
+    assert!(handler.answer(&response).is_none());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Response for `host` with a single A record of `ttl`
+fn cached(host : &str, ttl : u32) -> DnsMessage {
+
+    let mut response = DnsMessage::response_to(
+        &DnsMessage::query(1, name(host), QuestionType::A));
+
+    response.answers.push(ResourceRecord::new(name(host), ttl,
+        Record::A("192.0.2.99".parse().unwrap())));
+
+    response
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Forwarder to `upstream` serving stale records, with `response` cached
+/// since `age` seconds
+fn stale_forwarder(upstream : SocketAddr, response : &DnsMessage, age : u64)
+    -> ForwardingHandler {
+
+    let mut cache = Cache::default();
+    cache.set_max_stale(Duration::from_secs(600));
+    cache.insert_at(response, Instant::now() - Duration::from_secs(age));
+
+    let mut handler = ForwardingHandler::new(Arc::new(cache));
+    handler.set_timeout(Duration::from_millis(200));
+    handler.set_stale_answer_timeout(Duration::from_millis(50));
+    handler.add_upstream(upstream);
+
+    handler
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_forwarder_stale() {
+
+    let (_socket, silent) = silent_server();
+    let handler = stale_forwarder(silent, &cached("www.ubeer.org", 60), 120);
+
+    let query = |host : &str| DnsMessage::query(1, name(host), QuestionType::A);
+
+    // Upstream takes too long, answer with what we have
+    let start = Instant::now();
+    let response = handler.answer(&query("www.ubeer.org")).unwrap();
+
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert_eq!(30, response.answers[0].ttl);
+    assert!(start.elapsed() < Duration::from_millis(200));
+
+    // Once upstream is known to fail, do not wait for it at all
+    let start = Instant::now();
+
+    while handler.is_healthy(&silent) {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        handler.answer(&query("www.ubeer.org")).unwrap();
+    }
+
+    let start = Instant::now();
+    let response = handler.answer(&query("www.ubeer.org")).unwrap();
+
+    assert_eq!(1, response.answers.len());
+    assert!(start.elapsed() < Duration::from_millis(50));
+
+    // Nothing to answer with
+    let response = handler.answer(&query("ftp.ubeer.org")).unwrap();
+    assert_eq!(Rcode::ServFail as u16, response.rcode());
+
+    // Upstream answering in time is preferred
+    let (public, _) = start_upstreams();
+
+    let mut handler = stale_forwarder(public, &cached("www.ubeer.org", 60), 120);
+    handler.set_stale_answer_timeout(Duration::from_secs(2));
+
+    let response = handler.answer(&query("www.ubeer.org")).unwrap();
+    assert_eq!(Record::A("192.0.2.2".parse().unwrap()), response.answers[0].record);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_forwarder_stale_refresh() {
+
+    let (socket, silent) = silent_server();
+    let handler = stale_forwarder(silent, &cached("www.ubeer.org", 60), 120);
+
+    let mut query = DnsMessage::query(1, name("www.ubeer.org"), QuestionType::A);
+    query.edns = Some(Edns::new(1232));
+
+    // Stale answers are marked as such (RFC 8767, 6)
+    for _ in 0 .. 3 {
+        let response = handler.answer(&query).unwrap();
+        let errors = response.edns.as_ref().unwrap().extended_errors();
+        assert_eq!(1, errors.len());
+        assert_eq!(Some(ExtendedErrorCode::StaleAnswer), errors[0].code());
+    }
+
+    // Upstream is asked once only while the first refresh is pending
+    let mut buffer = [0u8; 512];
+    socket.set_read_timeout(Some(Duration::from_millis(300))).unwrap();
+
+    assert!(socket.recv(&mut buffer).is_ok());
+    assert!(socket.recv(&mut buffer).is_err());
+
+    // Without EDNS in the query, there is none in the response
+    query.edns = None;
+    assert!(handler.answer(&query).unwrap().edns.is_none());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_forwarder_prefetch() {
+
+    let (public, _) = start_upstreams();
+    let handler = stale_forwarder(public, &cached("www.ubeer.org", 100), 95);
+
+    let query = DnsMessage::query(1, name("www.ubeer.org"), QuestionType::A);
+    let cached_record = Record::A("192.0.2.99".parse().unwrap());
+
+    // Popular and about to expire, thus refreshed in the background
+    for _ in 0 .. 2 {
+        let response = handler.answer(&query).unwrap();
+        assert_eq!(cached_record, response.answers[0].record);
+    }
+
+    let start = Instant::now();
+
+    loop {
+
+        let response = handler.cache().lookup(&name("www.ubeer.org"), QuestionType::A)
+            .unwrap();
+
+        if cached_record != response.answers[0].record {
+            assert_eq!(Record::A("192.0.2.2".parse().unwrap()), response.answers[0].record);
+            break;
+        }
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(10));
+
+    }
+
+    assert_eq!(1, handler.cache().stats().prefetches);
+
+}
+
+/*----------------------------------------------------------------------------*/
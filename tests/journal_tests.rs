@@ -0,0 +1,266 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::fs::{self, OpenOptions};
+use ::std::io::Write;
+use ::std::net::IpAddr;
+use ::std::path::PathBuf;
+use ::std::process;
+use ::std::str::FromStr;
+use ::std::sync::Arc;
+use rustydns::{parse_zone, AuthoritativeHandler, Diff, DnsMessage, Journal, Network};
+use rustydns::{QuestionType, Record, ResourceRecord, Zone, ZoneCatalog};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+/// Version `serial` of the zone: www changes its address with every
+/// version, tmp only exists in version 2
+fn zone(serial : u32) -> Zone {
+
+    let mut text = format!("
+@     SOA   ns1 hostmaster {} 7200 3600 1209600 300
+      NS    ns1
+ns1   A     192.0.2.1
+www   A     192.0.2.{}
+", serial, 10 + serial);
+
+    if 2 == serial {
+        text.push_str("tmp TXT \"temporary\"\n");
+    }
+
+    parse_zone(&name("ubeer.org"), &text).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn diff(from : u32, to : u32) -> Diff {
+    Diff::between(&name("ubeer.org"), &zone(from), &zone(to)).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn rr(s : &str) -> ResourceRecord {
+
+    let mut parts = s.splitn(2, ' ');
+    let owner = name(parts.next().unwrap());
+
+    ResourceRecord::new(owner, 3600, Record::from_str(parts.next().unwrap()).unwrap())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn sorted(mut records : Vec<ResourceRecord>) -> Vec<String> {
+
+    let mut strings : Vec<String> = records.drain(..).map(|rr| rr.to_string()).collect();
+    strings.sort();
+    strings
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn journal_path(test : &str) -> PathBuf {
+
+    let path = ::std::env::temp_dir()
+        .join(format!("rustydns-{}-{}.jnl", process::id(), test));
+
+    fs::remove_file(&path).ok();
+    path
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_diff() {
+
+    let diff = diff(1, 2);
+
+    assert_eq!(1, diff.from_serial());
+    assert_eq!(2, diff.to_serial());
+    assert!(diff.increases_serial());
+    assert_eq!(vec![rr("www.ubeer.org A 192.0.2.11")], diff.deleted);
+    assert_eq!(sorted(vec![rr("www.ubeer.org A 192.0.2.12"), rr("tmp.ubeer.org TXT \"temporary\"")]),
+               sorted(diff.added.clone()));
+
+    let mut records = zone(1).records();
+    diff.apply(&mut records).unwrap();
+    assert_eq!(sorted(zone(2).records()), sorted(records));
+
+    let mut records = zone(1).records();
+    assert!(self::diff(2, 3).apply(&mut records).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_journal_persistence() {
+
+    let path = journal_path("persistence");
+
+    let mut journal = Journal::open(&path).unwrap();
+    assert!(journal.is_empty());
+
+    journal.append(diff(1, 2)).unwrap();
+    journal.append(diff(2, 3)).unwrap();
+
+    let reopened = Journal::open(&path).unwrap();
+    assert_eq!(journal.diffs(), reopened.diffs());
+    assert_eq!(Some(3), reopened.last_serial());
+
+    // Condensed: tmp was added and deleted again
+    let changes = reopened.changes_since(1).unwrap();
+    assert_eq!(1, changes.from_serial());
+    assert_eq!(3, changes.to_serial());
+    assert_eq!(vec![rr("www.ubeer.org A 192.0.2.11")], changes.deleted);
+    assert_eq!(vec![rr("www.ubeer.org A 192.0.2.13")], changes.added);
+
+    assert!(reopened.changes_since(3).is_none());
+    assert!(reopened.changes_since(7).is_none());
+
+    // Cut short while writing
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"- ubeer.org 3600 SOA ns1.ubeer.org hostmaster.ubeer.org 3 7200 3600 1209600 300\n+ ubeer").unwrap();
+
+    let mut reopened = Journal::open(&path).unwrap();
+    assert_eq!(journal.diffs(), reopened.diffs());
+
+    reopened.append(diff(3, 4)).unwrap();
+    assert_eq!(3, Journal::open(&path).unwrap().len());
+
+    // Not continuing the journal starts it anew
+    reopened.append(diff(1, 2)).unwrap();
+    assert_eq!(vec![diff(1, 2)], Journal::open(&path).unwrap().diffs());
+
+    fs::remove_file(&path).ok();
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_journal_compaction() {
+
+    let path = journal_path("compaction");
+
+    let mut journal = Journal::open(&path).unwrap();
+    journal.set_max_len(2);
+
+    journal.append(diff(1, 2)).unwrap();
+    journal.append(diff(2, 3)).unwrap();
+    journal.append(diff(3, 4)).unwrap();
+    assert_eq!(3, journal.len());
+
+    journal.append(diff(4, 5)).unwrap();
+    assert_eq!(2, journal.len());
+
+    let reopened = Journal::open(&path).unwrap();
+    assert_eq!(journal.diffs(), reopened.diffs());
+    assert!(reopened.changes_since(1).is_none());
+    assert_eq!(5, reopened.changes_since(3).unwrap().to_serial());
+
+    fs::remove_file(&path).ok();
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn ixfr(handler : &AuthoritativeHandler, serial : u32) -> Vec<ResourceRecord> {
+
+    let mut query = DnsMessage::query(42, name("ubeer.org"), QuestionType::Ixfr);
+    query.authority.push(zone(serial).soa(&name("ubeer.org")).unwrap());
+
+    let client = IpAddr::from_str("127.0.0.1").unwrap();
+
+    handler.transfer(&query, &client, None).unwrap().into_iter()
+        .flat_map(|msg| msg.answers)
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_ixfr_from_journal() {
+
+    let origin = name("ubeer.org");
+    let soa = |serial| zone(serial).soa(&origin).unwrap();
+
+    let mut first = zone(1);
+    first.set_journal(Journal::new());
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    catalog.add(origin.clone(), first).unwrap();
+    catalog.replace(origin.clone(), zone(2)).unwrap();
+    catalog.replace(origin.clone(), zone(3)).unwrap();
+
+    let mut handler = AuthoritativeHandler::with_catalog(catalog.clone());
+    handler.allow_transfer(Network::from_str("127.0.0.1").unwrap());
+
+    assert_eq!(vec![
+        soa(3),
+        soa(1), rr("www.ubeer.org A 192.0.2.11"),
+        soa(3), rr("www.ubeer.org A 192.0.2.13"),
+        soa(3)],
+        ixfr(&handler, 1));
+
+    // Up to date
+    assert_eq!(vec![soa(3)], ixfr(&handler, 3));
+
+    // Journal too short - the whole zone as by AXFR
+    let records = ixfr(&handler, 0);
+    assert_eq!(soa(3), records[0]);
+    assert_eq!(soa(3), records[records.len() - 1]);
+    assert_eq!(sorted(zone(3).records()), sorted(records[.. records.len() - 1].to_vec()));
+
+    // Serial not increased - versions cannot be told apart
+    catalog.replace(origin.clone(), zone(3)).unwrap();
+    assert_eq!(soa(3), ixfr(&handler, 1)[0]);
+    assert_eq!(5, ixfr(&handler, 1).len());
+
+    // IXFR requires the SOA of the client
+    let query = DnsMessage::query(42, origin.clone(), QuestionType::Ixfr);
+    let response = handler.transfer(&query, &IpAddr::from_str("127.0.0.1").unwrap(), None).unwrap();
+    assert_eq!(rustydns::Rcode::FormErr as u16, response[0].rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
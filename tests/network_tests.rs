@@ -0,0 +1,88 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+
+use ::std::net::IpAddr;
+use ::std::str::FromStr;
+use rustydns::Network;
+
+/*----------------------------------------------------------------------------*/
+
+fn ip(s : &str) -> IpAddr {
+    IpAddr::from_str(s).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_network_from_str() {
+
+    let net = Network::from_str("10.1.2.3/8").unwrap();
+    assert_eq!(ip("10.0.0.0"), net.address());
+    assert_eq!(8, net.prefix_len());
+    assert_eq!("10.0.0.0/8", net.to_string());
+
+    assert_eq!(32, Network::from_str("10.1.2.3").unwrap().prefix_len());
+    assert_eq!("2001:db8::/29", Network::from_str("2001:db9::1/29").unwrap().to_string());
+
+    assert!(Network::from_str("10.0.0.0/33").is_err());
+    assert!(Network::from_str("2001:db8::/129").is_err());
+    assert!(Network::from_str("10.0.0/8").is_err());
+    assert!(Network::from_str("10.0.0.0/a").is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_network_contains() {
+
+    let net = Network::from_str("192.0.2.128/25").unwrap();
+    assert!(net.contains(&ip("192.0.2.129")));
+    assert!(net.contains(&ip("192.0.2.255")));
+    assert!(! net.contains(&ip("192.0.2.127")));
+    assert!(! net.contains(&ip("::ffff:192.0.2.129")));
+
+    let all = Network::from_str("0.0.0.0/0").unwrap();
+    assert!(all.contains(&ip("8.8.8.8")));
+    assert!(! all.contains(&ip("::1")));
+
+    assert!(all.covers(&net));
+    assert!(! net.covers(&all));
+    assert!(net.covers(&net));
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,242 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::{IpAddr, UdpSocket};
+use ::std::str::FromStr;
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::Duration;
+use rustydns::{parse_zone, send_notify, AuthoritativeHandler, DnsMessage, Network};
+use rustydns::{Notifier, Opcode, Rcode, RefreshTrigger, ResourceRecord, Secondary};
+use rustydns::{WireReader, Zone, ZoneCatalog};
+use testhelpers::{name, start_server};
+
+/*----------------------------------------------------------------------------*/
+
+fn localhost() -> IpAddr {
+    IpAddr::from_str("127.0.0.1").unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Version `serial` of the zone, to be refreshed rarely
+fn zone(serial : u32) -> Zone {
+
+    let text = format!("
+@     SOA   ns1 hostmaster {} 3600 3600 1209600 300
+      NS    ns1
+ns1   A     192.0.2.1
+www   A     192.0.2.{}
+", serial, serial);
+
+    parse_zone(&name("ubeer.org"), &text).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn soa(serial : u32) -> ResourceRecord {
+    zone(serial).soa(&name("ubeer.org")).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_notify_accepted() {
+
+    let trigger = RefreshTrigger::new();
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.accept_notify(name("ubeer.org"), localhost(), None, trigger.clone());
+    let addr = start_server(handler);
+
+    let interval = Duration::from_secs(1);
+
+    assert!(send_notify(&addr, &name("ubeer.org"), &soa(2), 0, interval, None).is_ok());
+    assert!(trigger.wait(Duration::from_secs(5)));
+    assert!(! trigger.wait(Duration::from_millis(10)));
+
+    // Not secondary for that zone
+    assert_eq!(Err("NOTIFY refused"),
+               send_notify(&addr, &name("ubeer.net"), &soa(2), 0, interval, None));
+    assert!(! trigger.wait(Duration::from_millis(10)));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_notify_from_unknown_primary() {
+
+    let trigger = RefreshTrigger::new();
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.accept_notify(name("ubeer.org"), IpAddr::from_str("192.0.2.1").unwrap(), None,
+                          trigger.clone());
+
+    let mut notify = DnsMessage::query(7, name("ubeer.org"), rustydns::QuestionType::Soa);
+    notify.set_opcode(Opcode::Notify);
+
+    let response = handler.notified(&notify, &localhost(), None).unwrap();
+
+    assert_eq!(Rcode::Refused as u16, response.rcode());
+    assert_eq!(Opcode::Notify as u16, response.opcode());
+    assert!(! trigger.wait(Duration::from_millis(10)));
+
+    let primary = IpAddr::from_str("192.0.2.1").unwrap();
+    assert_eq!(Rcode::NoError as u16, handler.notified(&notify, &primary, None).unwrap().rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_notify_requires_key() {
+
+    let trigger = RefreshTrigger::new();
+    let key = name("transfer.ubeer.org");
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.accept_notify(name("ubeer.org"), localhost(), Some(key.clone()), trigger.clone());
+
+    let mut notify = DnsMessage::query(7, name("ubeer.org"), rustydns::QuestionType::Soa);
+    notify.set_opcode(Opcode::Notify);
+
+    // Unsigned
+    let response = handler.notified(&notify, &localhost(), None).unwrap();
+    assert_eq!(Rcode::NotAuth as u16, response.rcode());
+    assert!(! trigger.wait(Duration::from_millis(10)));
+
+    // Signed with some other key
+    let response = handler.notified(&notify, &localhost(), Some(&name("other.ubeer.org")))
+        .unwrap();
+    assert_eq!(Rcode::NotAuth as u16, response.rcode());
+    assert!(! trigger.wait(Duration::from_millis(10)));
+
+    let response = handler.notified(&notify, &localhost(), Some(&key)).unwrap();
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(trigger.wait(Duration::from_secs(1)));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_notify_retries() {
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    // Drop the first NOTIFY, answer the second one
+    let secondary = thread::spawn(move || {
+
+        let mut buffer = [0u8; 512];
+
+        socket.recv_from(&mut buffer).unwrap();
+        let (len, from) = socket.recv_from(&mut buffer).unwrap();
+
+        let notify = DnsMessage::read(&mut WireReader::new(&buffer[.. len])).unwrap();
+        assert_eq!(Opcode::Notify as u16, notify.opcode());
+        assert_eq!(vec![soa(2)], notify.answers);
+
+        socket.send_to(&DnsMessage::response_to(&notify).to_vec(), from).unwrap();
+
+    });
+
+    let interval = Duration::from_millis(200);
+
+    assert!(send_notify(&addr, &name("ubeer.org"), &soa(2), 1, interval, None).is_ok());
+    secondary.join().unwrap();
+
+    // Nobody answers
+    let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = silent.local_addr().unwrap();
+
+    assert_eq!(Err("No answer to NOTIFY"),
+               send_notify(&addr, &name("ubeer.org"), &soa(2), 2, interval, None));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_notify_triggers_transfer() {
+
+    let origin = name("ubeer.org");
+
+    // Primary
+    let primary_catalog = Arc::new(ZoneCatalog::new());
+    primary_catalog.add(origin.clone(), zone(1)).unwrap();
+
+    let mut handler = AuthoritativeHandler::with_catalog(primary_catalog.clone());
+    handler.allow_transfer(Network::from_str("127.0.0.1").unwrap());
+    let primary = start_server(handler);
+
+    // Secondary
+    let catalog = Arc::new(ZoneCatalog::new());
+    let secondary = Secondary::new(catalog.clone(), origin.clone(), primary);
+
+    let mut handler = AuthoritativeHandler::with_catalog(catalog.clone());
+    handler.accept_notify(origin.clone(), localhost(), None, secondary.trigger());
+    let secondary_addr = start_server(handler);
+
+    secondary.spawn();
+
+    let wait_for_serial = |serial : u32| {
+        for _ in 0 .. 50 {
+            if catalog.get(&origin).and_then(|z| z.soa(&origin)) == Some(soa(serial)) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        false
+    };
+
+    assert!(wait_for_serial(1));
+
+    let mut notifier = Notifier::new();
+    notifier.add_target(origin.clone(), secondary_addr);
+    primary_catalog.add_observer(Arc::new(notifier));
+
+    // Refresh is due in an hour, NOTIFY makes the secondary transfer now
+    primary_catalog.replace(origin.clone(), zone(2)).unwrap();
+
+    assert!(wait_for_serial(2));
+
+}
+
+/*----------------------------------------------------------------------------*/
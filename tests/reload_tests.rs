@@ -0,0 +1,189 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+extern crate signal_hook;
+
+use ::std::fs::{self, File};
+use ::std::path::PathBuf;
+use ::std::process;
+use ::std::str::FromStr;
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::{Duration, SystemTime};
+use rustydns::{QuestionType, Record, ZoneCatalog, ZoneReloader};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+fn zone_path(test : &str) -> PathBuf {
+
+    ::std::env::temp_dir().join(format!("rustydns-{}-{}.zone", process::id(), test))
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Write zone file and make its modification time differ from the last one
+fn write_zone(path : &PathBuf, serial : u32, address : &str) {
+
+    let text = format!(
+        "@ SOA ns1 hostmaster {} 7200 3600 1209600 300\n@ NS ns1\nwww A {}\n",
+        serial, address);
+
+    fs::write(path, text).unwrap();
+    bump_modification_time(path);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn bump_modification_time(path : &PathBuf) {
+
+    let modified = fs::metadata(path).unwrap().modified().unwrap();
+    let file = File::options().write(true).open(path).unwrap();
+    file.set_modified(modified.max(SystemTime::now()) + Duration::from_secs(1)).unwrap();
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn served_address(catalog : &ZoneCatalog) -> Record {
+
+    let (_, zone) = catalog.find(&name("www.ubeer.org")).unwrap();
+    zone.rrset(&name("www.ubeer.org"), QuestionType::A.code())[0].record.clone()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn a(s : &str) -> Record {
+    Record::from_str(&format!("A {}", s)).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_reload_changed() {
+
+    let path = zone_path("changed");
+    write_zone(&path, 1, "192.0.2.1");
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    let mut reloader = ZoneReloader::new(catalog.clone());
+
+    assert!(reloader.add(name("ubeer.org"), path.to_str().unwrap()).is_ok());
+    assert_eq!(a("192.0.2.1"), served_address(&catalog));
+
+    assert_eq!(0, reloader.reload_changed());
+
+    write_zone(&path, 2, "192.0.2.2");
+    assert_eq!(1, reloader.reload_changed());
+    assert_eq!(a("192.0.2.2"), served_address(&catalog));
+
+    assert_eq!(0, reloader.reload_changed());
+
+    fs::remove_file(&path).ok();
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_reload_keeps_old_zone() {
+
+    let path = zone_path("keep");
+    write_zone(&path, 4294967295, "192.0.2.1");
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    let mut reloader = ZoneReloader::new(catalog.clone());
+    reloader.add(name("ubeer.org"), path.to_str().unwrap()).unwrap();
+
+    // Serial not increased
+    write_zone(&path, 4294967295, "192.0.2.2");
+    assert_eq!(0, reloader.reload_changed());
+    assert_eq!(a("192.0.2.1"), served_address(&catalog));
+
+    // Broken file
+    fs::write(&path, "www A 192.0.2.").unwrap();
+    bump_modification_time(&path);
+    assert_eq!(0, reloader.reload_changed());
+    assert_eq!(a("192.0.2.1"), served_address(&catalog));
+
+    // SOA missing
+    fs::write(&path, "www A 192.0.2.3").unwrap();
+    bump_modification_time(&path);
+    assert_eq!(0, reloader.reload_all());
+    assert_eq!(a("192.0.2.1"), served_address(&catalog));
+
+    // Serial wraps around
+    write_zone(&path, 1, "192.0.2.4");
+    assert_eq!(1, reloader.reload_all());
+    assert_eq!(a("192.0.2.4"), served_address(&catalog));
+
+    fs::remove_file(&path).ok();
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_reload_on_sighup() {
+
+    let path = zone_path("sighup");
+    write_zone(&path, 1, "192.0.2.1");
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    let mut reloader = ZoneReloader::new(catalog.clone());
+    reloader.add(name("ubeer.org"), path.to_str().unwrap()).unwrap();
+    reloader.spawn(Duration::from_secs(3600)).unwrap();
+
+    write_zone(&path, 2, "192.0.2.2");
+    signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+
+    for _ in 0 .. 50 {
+        if a("192.0.2.2") == served_address(&catalog) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    assert_eq!(a("192.0.2.2"), served_address(&catalog));
+
+    fs::remove_file(&path).ok();
+
+}
+
+/*----------------------------------------------------------------------------*/
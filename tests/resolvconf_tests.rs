@@ -0,0 +1,155 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::{IpAddr, SocketAddr};
+use ::std::str::FromStr;
+use ::std::time::Duration;
+use rustydns::{reverse_name, Hosts, QuestionType, Record, ResolvConf};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+const FIXTURES : &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/client");
+
+/*----------------------------------------------------------------------------*/
+
+fn fixture(file : &str) -> String {
+    format!("{}/{}", FIXTURES, file)
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn addr(s : &str) -> IpAddr {
+    IpAddr::from_str(s).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolv_conf() {
+
+    let conf = ResolvConf::load(fixture("resolv.conf")).unwrap();
+
+    // The zone index makes fe80::1 unusable, 192.0.2.55 is one too many
+    assert_eq!(vec![
+            SocketAddr::new(addr("192.0.2.53"), 53),
+            SocketAddr::new(addr("2001:db8::53"), 53),
+            SocketAddr::new(addr("192.0.2.54"), 53)],
+        conf.nameservers);
+
+    assert_eq!(vec![name("ubeer.org"), name("example.org")], conf.search);
+    assert_eq!(2, conf.ndots);
+    assert_eq!(Duration::from_secs(1), conf.timeout);
+    assert_eq!(5, conf.attempts);
+    assert!(conf.rotate);
+    assert!(conf.edns0);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolv_conf_defaults() {
+
+    let conf = ResolvConf::load(fixture("missing")).unwrap();
+    assert_eq!(ResolvConf::new(), conf);
+
+    assert_eq!(vec![SocketAddr::new(addr("127.0.0.1"), 53)], conf.nameservers);
+    assert!(conf.search.is_empty());
+    assert_eq!(1, conf.ndots);
+    assert_eq!(Duration::from_secs(5), conf.timeout);
+    assert_eq!(2, conf.attempts);
+    assert!(! conf.rotate);
+    assert!(! conf.edns0);
+
+    // The last of search and domain wins
+    let conf = ResolvConf::from_str("search a.org b.org\ndomain c.org").unwrap();
+    assert_eq!(vec![name("c.org")], conf.search);
+
+    let conf = ResolvConf::from_str("options ndots:99 timeout:0 attempts:0").unwrap();
+    assert_eq!(15, conf.ndots);
+    assert_eq!(Duration::from_secs(1), conf.timeout);
+    assert_eq!(1, conf.attempts);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_hosts() {
+
+    let hosts = Hosts::load(fixture("hosts")).unwrap();
+    assert_eq!(4, hosts.len());
+
+    assert_eq!(vec![addr("192.0.2.77"), addr("2001:db8::77")],
+        hosts.addresses(&name("www.ubeer.org")));
+    assert_eq!(vec![addr("192.0.2.77")], hosts.addresses(&name("WWW")));
+    assert!(hosts.addresses(&name("nobody")).is_empty());
+
+    let records = hosts.lookup(&name("localhost"), QuestionType::A);
+    assert_eq!(1, records.len());
+    assert_eq!(name("localhost"), records[0].name);
+    assert_eq!(Record::A("127.0.0.1".parse().unwrap()), records[0].record);
+
+    let records = hosts.lookup(&name("ip6-localhost"), QuestionType::Aaaa);
+    assert_eq!(vec![Record::AAAA("::1".parse().unwrap())],
+        records.into_iter().map(|r| r.record).collect::<Vec<Record>>());
+
+    assert!(hosts.lookup(&name("ip6-localhost"), QuestionType::A).is_empty());
+    assert!(hosts.lookup(&name("www"), QuestionType::Mx).is_empty());
+
+    let records = hosts.lookup(&name("77.2.0.192.in-addr.arpa"), QuestionType::Ptr);
+    assert_eq!(Record::PTR(name("www.ubeer.org")), records[0].record);
+
+    assert!(Hosts::load(fixture("missing")).unwrap().is_empty());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_reverse_name() {
+
+    assert_eq!(name("1.2.0.192.in-addr.arpa"), reverse_name(&addr("192.0.2.1")));
+
+    assert_eq!(
+        name("1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa"),
+        reverse_name(&addr("2001:db8::1")));
+
+}
+
+/*----------------------------------------------------------------------------*/
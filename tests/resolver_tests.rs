@@ -0,0 +1,375 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::{IpAddr, UdpSocket};
+use ::std::str::FromStr;
+use ::std::sync::Arc;
+use ::std::time::{Duration, Instant};
+use rustydns::{parse_zone, AuthoritativeHandler, Cache, Name, QuestionType, Rcode, Record};
+use rustydns::Resolver;
+use testhelpers::{name, start_server_at};
+
+/*----------------------------------------------------------------------------*/
+
+/// Fake hierarchy: Server address, and the zones it serves
+const HIERARCHY : &[(&str, &[(&str, &str)])] = &[
+
+    ("127.0.0.1", &[(".", "
+@                   SOA   a.root-servers.net. hostmaster 1 1800 900 604800 86400
+@                   NS    a.root-servers.net.
+a.root-servers.net  A     127.0.0.1
+org                 NS    ns1.org.
+ns1.org             A     127.0.0.2
+; Glueless: The server lies within another TLD
+test                NS    ns.ubeer.org.
+")]),
+
+    ("127.0.0.2", &[("org", "
+@                   SOA   ns1 hostmaster 1 1800 900 604800 300
+@                   NS    ns1
+ns1                 A     127.0.0.2
+ubeer               NS    ns1.ubeer
+ns1.ubeer           A     127.0.0.3
+; Delegations depending on each other
+cycle               NS    ns.cyclic.test.
+")]),
+
+    ("127.0.0.3", &[("ubeer.org", "
+@                   SOA   ns1 hostmaster 1 1800 900 604800 300
+@                   NS    ns1
+ns1                 A     127.0.0.3
+ns                  A     127.0.0.4
+www                 A     192.0.2.1
+ftp                 CNAME www
+alias               CNAME www.example.test.
+dangling            CNAME nope.example.test.
+")]),
+
+    ("127.0.0.4", &[("test", "
+@                   SOA   ns.ubeer.org. hostmaster 1 1800 900 604800 300
+@                   NS    ns.ubeer.org.
+www.example         A     192.0.2.4
+cyclic              NS    ns.cycle.org.
+")]),
+
+];
+
+/*----------------------------------------------------------------------------*/
+
+fn addr(s : &str) -> IpAddr {
+    IpAddr::from_str(s).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Start the servers of the fake hierarchy, all listening on the port
+/// returned
+fn start_hierarchy() -> u16 {
+
+    loop {
+
+        let port = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let started = HIERARCHY.iter().all(|(server, zones)| {
+
+            let mut handler = AuthoritativeHandler::new();
+
+            for (origin, text) in zones.iter() {
+                let zone = parse_zone(&name(origin), text).unwrap();
+                handler.add_zone(name(origin), zone).unwrap();
+            }
+
+            start_server_at(&format!("{}:{}", server, port), handler, None).is_some()
+
+        });
+
+        if started {
+            return port;
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn hints(root_server : &str) -> Vec<rustydns::ResourceRecord> {
+
+    let text = format!("
+@                   NS    a.root-servers.net.
+a.root-servers.net  A     {}
+", root_server);
+
+    parse_zone(&Name::root(), &text).unwrap().records()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn resolver() -> Resolver {
+
+    let mut resolver = Resolver::new();
+    resolver.set_root_hints(&hints("127.0.0.1")).unwrap();
+    resolver.set_port(start_hierarchy());
+    resolver.set_timeout(Duration::from_millis(500));
+
+    resolver
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn records(response : &rustydns::DnsMessage) -> Vec<(Name, Record)> {
+
+    response.answers.iter()
+        .map(|rr| (rr.name.clone(), rr.record.clone()))
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolver_hints() {
+
+    assert_eq!(13, Resolver::new().root_hints().len());
+
+    let mut resolver = Resolver::new();
+    resolver.set_root_hints(&hints("192.0.2.1")).unwrap();
+    assert_eq!(vec![addr("192.0.2.1")], resolver.root_hints());
+
+    let unrelated = parse_zone(&name("ubeer.org"), "www A 192.0.2.1").unwrap().records();
+    assert!(resolver.set_root_hints(&unrelated).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolver_referrals() {
+
+    let resolver = resolver();
+
+    let response = resolver.resolve(&name("www.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert_eq!(vec![(name("www.ubeer.org"), Record::A("192.0.2.1".parse().unwrap()))],
+        records(&response));
+
+    // Every server on the way has been asked
+    for server in &["127.0.0.1", "127.0.0.2", "127.0.0.3"] {
+        assert!(resolver.rtt(&addr(server)).is_some());
+    }
+
+    assert!(resolver.rtt(&addr("127.0.0.4")).is_none());
+
+    // Glueless delegation of test
+    let response = resolver.resolve(&name("www.example.test"), QuestionType::A).unwrap();
+    assert_eq!(vec![(name("www.example.test"), Record::A("192.0.2.4".parse().unwrap()))],
+        records(&response));
+
+    assert!(resolver.rtt(&addr("127.0.0.4")).is_some());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolver_negative() {
+
+    let resolver = resolver();
+
+    let response = resolver.resolve(&name("nope.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+    assert!(response.answers.is_empty());
+    assert_eq!(1, response.authority.len());
+    assert_eq!(name("ubeer.org"), response.authority[0].name);
+
+    let response = resolver.resolve(&name("www.ubeer.org"), QuestionType::Mx).unwrap();
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert!(response.answers.is_empty());
+    assert_eq!(name("ubeer.org"), response.authority[0].name);
+
+    // Denied by the root
+    let response = resolver.resolve(&name("www.ubeer.nope"), QuestionType::A).unwrap();
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+    assert!(response.authority[0].name.is_root());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolver_cname() {
+
+    let resolver = resolver();
+
+    // Within the zone
+    let response = resolver.resolve(&name("ftp.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(vec![
+            (name("ftp.ubeer.org"), Record::CNAME(name("www.ubeer.org"))),
+            (name("www.ubeer.org"), Record::A("192.0.2.1".parse().unwrap()))],
+        records(&response));
+
+    // Across zones
+    let response = resolver.resolve(&name("alias.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(vec![
+            (name("alias.ubeer.org"), Record::CNAME(name("www.example.test"))),
+            (name("www.example.test"), Record::A("192.0.2.4".parse().unwrap()))],
+        records(&response));
+
+    let response = resolver.resolve(&name("dangling.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+    assert_eq!(vec![(name("dangling.ubeer.org"), Record::CNAME(name("nope.example.test")))],
+        records(&response));
+    assert_eq!(name("test"), response.authority[0].name);
+
+    // The alias itself
+    let response = resolver.resolve(&name("alias.ubeer.org"), QuestionType::Cname).unwrap();
+    assert_eq!(1, response.answers.len());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolver_limits() {
+
+    let mut resolver = resolver();
+
+    resolver.set_limits(1, 64);
+    assert_eq!(Err("Too many referrals"),
+        resolver.resolve(&name("www.ubeer.org"), QuestionType::A).map(|_| ()));
+    assert!(resolver.resolve(&name("ns1.org"), QuestionType::A).is_ok());
+
+    resolver.set_limits(16, 2);
+    assert_eq!(Err("Too many queries"),
+        resolver.resolve(&name("www.ubeer.org"), QuestionType::A).map(|_| ()));
+
+    resolver.set_limits(16, 64);
+    assert!(resolver.resolve(&name("www.ubeer.org"), QuestionType::A).is_ok());
+
+    // Name servers of cycle.org and cyclic.test depend on each other
+    let start = Instant::now();
+    assert!(resolver.resolve(&name("www.cycle.org"), QuestionType::A).is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolver_server_selection() {
+
+    let port = start_hierarchy();
+    let timeout = Duration::from_millis(500);
+
+    // Root server never answering
+    let dead = "127.0.0.5";
+    let _silent = UdpSocket::bind(format!("{}:{}", dead, port)).unwrap();
+
+    let mut resolver = Resolver::new();
+    resolver.set_root_hints(&hints(dead)).unwrap();
+    resolver.set_port(port);
+    resolver.set_timeout(timeout);
+
+    assert_eq!(Err("No answer"),
+        resolver.resolve(&name("www.ubeer.org"), QuestionType::A).map(|_| ()));
+    assert!(resolver.rtt(&addr(dead)).unwrap() >= timeout);
+
+    let both = parse_zone(&Name::root(), &format!("
+@                   NS    a.root-servers.net.
+@                   NS    b.root-servers.net.
+a.root-servers.net  A     {}
+b.root-servers.net  A     127.0.0.1
+", dead)).unwrap().records();
+
+    resolver.set_root_hints(&both).unwrap();
+    assert_eq!(2, resolver.root_hints().len());
+
+    // The dead server is avoided from now on
+    for _ in 0 .. 5 {
+        let start = Instant::now();
+        assert!(resolver.resolve(&name("www.ubeer.org"), QuestionType::A).is_ok());
+        assert!(start.elapsed() < timeout);
+    }
+
+    assert!(resolver.rtt(&addr("127.0.0.1")).unwrap() < timeout);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_resolver_cache() {
+
+    let mut cache = Cache::default();
+    cache.set_max_stale(Duration::from_secs(600));
+
+    let cache = Arc::new(cache);
+
+    let mut resolver = resolver();
+    resolver.set_cache(cache.clone());
+
+    // Resolving again takes no queries
+    let response = resolver.resolve(&name("alias.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(2, response.answers.len());
+
+    resolver.set_limits(16, 0);
+
+    let cached = resolver.resolve(&name("alias.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(records(&response), records(&cached));
+
+    assert!(resolver.resolve(&name("www.example.test"), QuestionType::A).is_ok());
+    assert!(resolver.resolve(&name("www.ubeer.org"), QuestionType::A).is_err());
+
+    assert_eq!(2, cache.stats().hits);
+
+    // Expired records are better than none
+    let mut stale = rustydns::DnsMessage::response_to(
+        &rustydns::DnsMessage::query(1, name("ftp.ubeer.org"), QuestionType::A));
+    stale.answers.push(rustydns::ResourceRecord::new(name("ftp.ubeer.org"), 60,
+        Record::A("192.0.2.99".parse().unwrap())));
+
+    cache.insert_at(&stale, Instant::now() - Duration::from_secs(120));
+
+    let response = resolver.resolve(&name("ftp.ubeer.org"), QuestionType::A).unwrap();
+    assert_eq!(30, response.answers[0].ttl);
+    assert_eq!(1, cache.stats().stale_hits);
+
+}
+
+/*----------------------------------------------------------------------------*/
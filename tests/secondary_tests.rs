@@ -0,0 +1,292 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::SocketAddr;
+use ::std::str::FromStr;
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::Duration;
+use rustydns::{parse_zone, transfer_zone, AuthoritativeHandler, ContinueState, DnsMessage};
+use rustydns::{Handler, Journal, Message, Network, QuestionType, Record, ResourceRecord};
+use rustydns::{Responder, Secondary, WireReader, Zone, ZoneCatalog};
+use testhelpers::{name, start_server};
+
+/*----------------------------------------------------------------------------*/
+
+const ZONE_V1 : &str = "
+@        SOA   ns1 hostmaster 1 1 1 2 300
+         NS    ns1
+ns1      A     192.0.2.1
+www      A     192.0.2.10
+";
+
+const ZONE_V2 : &str = "
+@        SOA   ns1 hostmaster 2 1 1 2 300
+         NS    ns1
+ns1      A     192.0.2.1
+www      A     192.0.2.20
+mail     A     192.0.2.25
+";
+
+/*----------------------------------------------------------------------------*/
+
+fn zone(text : &str) -> Zone {
+    parse_zone(&name("ubeer.org"), text).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn rr(s : &str) -> ResourceRecord {
+
+    let mut parts = s.splitn(2, ' ');
+    let owner = name(parts.next().unwrap());
+
+    ResourceRecord::new(owner, 3600, Record::from_str(parts.next().unwrap()).unwrap())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn soa(serial : u32) -> ResourceRecord {
+    rr(&format!("ubeer.org SOA ns1.ubeer.org hostmaster.ubeer.org {} 1 1 2 300", serial))
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn sorted(zone : &Zone) -> Vec<String> {
+
+    let mut records : Vec<String> = zone.records().iter()
+        .map(|rr| format!("{} {} {}", rr.name, rr.ttl, rr.record))
+        .collect();
+
+    records.sort();
+    records
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn rustydns_primary(catalog : Arc<ZoneCatalog>) -> SocketAddr {
+
+    let mut handler = AuthoritativeHandler::with_catalog(catalog);
+    handler.allow_transfer(Network::from_str("127.0.0.1").unwrap());
+
+    start_server(handler)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Serves version 3 of the zone, answering IXFR with the changes
+/// from version 1 split into two messages
+struct IxfrPrimary {}
+
+impl Handler for IxfrPrimary {
+
+    fn handle(&self, msg : Message, responder : Responder) -> ContinueState {
+
+        let query = DnsMessage::read(&mut WireReader::new(&msg.buffer[.. msg.num_bytes])).unwrap();
+        let mut first = DnsMessage::response_to(&query);
+        let mut second = DnsMessage::response_to(&query);
+
+        match query.questions[0].qtype {
+
+            QuestionType::Soa => first.answers.push(soa(3)),
+
+            QuestionType::Ixfr if Record::SOA(soa_data(3)) == query.authority[0].record => {
+                first.answers.push(soa(3));
+            },
+
+            QuestionType::Ixfr => {
+                first.answers.extend(vec![
+                    soa(3),
+                    soa(1), rr("www.ubeer.org A 192.0.2.10"),
+                    soa(2), rr("www.ubeer.org A 192.0.2.20"), rr("mail.ubeer.org A 192.0.2.25"),
+                ]);
+                second.answers.extend(vec![
+                    soa(2), rr("mail.ubeer.org A 192.0.2.25"),
+                    soa(3), rr("mail.ubeer.org A 192.0.2.30"),
+                    soa(3),
+                ]);
+            },
+
+            _ => panic!("Unexpected query"),
+
+        };
+
+        let mut responses = vec![first.to_vec()];
+
+        if ! second.answers.is_empty() {
+            responses.push(second.to_vec());
+        }
+
+        responder.respond_all(responses);
+        ContinueState::Continue
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn soa_data(serial : u32) -> rustydns::Soa {
+
+    match soa(serial).record {
+        Record::SOA(soa) => soa,
+        _ => unreachable!(),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_secondary_refresh() {
+
+    let primary_catalog = Arc::new(ZoneCatalog::new());
+    primary_catalog.add(name("ubeer.org"), zone(ZONE_V1)).unwrap();
+
+    let primary = rustydns_primary(primary_catalog.clone());
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    let secondary = Secondary::new(catalog.clone(), name("ubeer.org"), primary);
+
+    assert_eq!(Ok(true), secondary.refresh());
+    assert_eq!(sorted(&zone(ZONE_V1)), sorted(&catalog.get(&name("ubeer.org")).unwrap()));
+
+    assert_eq!(Ok(false), secondary.refresh());
+
+    // Without journal, the primary answers IXFR with the whole zone
+    primary_catalog.replace(name("ubeer.org"), zone(ZONE_V2)).unwrap();
+
+    assert_eq!(Ok(true), secondary.refresh());
+    assert_eq!(sorted(&zone(ZONE_V2)), sorted(&catalog.get(&name("ubeer.org")).unwrap()));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_secondary_journal() {
+
+    let mut first = zone(ZONE_V1);
+    first.set_journal(Journal::new());
+
+    let primary_catalog = Arc::new(ZoneCatalog::new());
+    primary_catalog.add(name("ubeer.org"), first).unwrap();
+
+    let primary = rustydns_primary(primary_catalog.clone());
+
+    let path = ::std::env::temp_dir()
+        .join(format!("rustydns-{}-secondary.jnl", ::std::process::id()));
+    ::std::fs::remove_file(&path).ok();
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    let mut secondary = Secondary::new(catalog.clone(), name("ubeer.org"), primary);
+    secondary.set_journal_path(&path);
+
+    assert_eq!(Ok(true), secondary.refresh());
+
+    primary_catalog.replace(name("ubeer.org"), zone(ZONE_V2)).unwrap();
+
+    assert_eq!(Ok(true), secondary.refresh());
+    assert_eq!(sorted(&zone(ZONE_V2)), sorted(&catalog.get(&name("ubeer.org")).unwrap()));
+
+    // Changes are recorded for secondaries of the secondary
+    let journal = Journal::open(&path).unwrap();
+    assert_eq!(Some(2), journal.last_serial());
+    assert_eq!(primary_catalog.get(&name("ubeer.org")).unwrap().journal().unwrap().diffs(),
+               journal.diffs());
+
+    ::std::fs::remove_file(&path).ok();
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_secondary_ixfr() {
+
+    let primary = start_server(IxfrPrimary {});
+
+    let v3 = transfer_zone(&primary, &name("ubeer.org"), Some(&zone(ZONE_V1)), None)
+        .unwrap()
+        .unwrap();
+
+    let expected = zone(&ZONE_V2
+        .replace("hostmaster 2", "hostmaster 3")
+        .replace("192.0.2.25", "192.0.2.30"));
+
+    assert_eq!(sorted(&expected), sorted(&v3));
+
+    assert!(transfer_zone(&primary, &name("ubeer.org"), Some(&v3), None).unwrap().is_none());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_secondary_expire() {
+
+    let primary_catalog = Arc::new(ZoneCatalog::new());
+    primary_catalog.add(name("ubeer.org"), zone(ZONE_V1)).unwrap();
+
+    let primary = rustydns_primary(primary_catalog.clone());
+
+    let catalog = Arc::new(ZoneCatalog::new());
+    Secondary::new(catalog.clone(), name("ubeer.org"), primary).spawn();
+
+    let wait_for = |served : bool| {
+        for _ in 0 .. 100 {
+            if served == catalog.get(&name("ubeer.org")).is_some() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        false
+    };
+
+    assert!(wait_for(true));
+
+    // Primary refuses from now on, the zone expires after 2 seconds
+    primary_catalog.remove(&name("ubeer.org"));
+
+    assert!(wait_for(false));
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,130 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+
+use ::std::io::{Read, Write};
+use ::std::net::{SocketAddr, TcpStream, UdpSocket};
+use ::std::sync::Arc;
+use ::std::sync::mpsc::channel;
+use ::std::thread;
+use ::std::time::Duration;
+use rustydns::{ContinueState, Handler, Message, Responder, Server, TcpServer, Threadpool, Transport, UdpServer};
+
+/*----------------------------------------------------------------------------*/
+
+/// Replies with the transport the query was received by
+struct TransportHandler {}
+
+impl Handler for TransportHandler {
+
+    fn handle(&self, msg : Message, responder : Responder) -> ContinueState {
+
+        assert_eq!(msg.transport, responder.transport());
+        assert_eq!(msg.addr, responder.addr());
+
+        let reply = match responder.transport() {
+            Transport::Udp => b"udp".to_vec(),
+            Transport::Tcp => b"tcp".to_vec(),
+        };
+
+        responder.respond(reply);
+        ContinueState::Continue
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn start_server() -> (SocketAddr, SocketAddr) {
+
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+
+        let threadpool = Threadpool::new(Arc::new(TransportHandler {}), 10);
+        threadpool.run(3);
+
+        let udp_server = UdpServer::bind_to("127.0.0.1:0", &threadpool).unwrap();
+        let tcp_server = TcpServer::bind_to("127.0.0.1:0", &threadpool).unwrap();
+
+        sender.send((
+            udp_server.local_addr().unwrap(),
+            tcp_server.local_addr().unwrap())).unwrap();
+
+        let mut server = Server::new().unwrap();
+        server.add(udp_server).unwrap();
+        server.add(tcp_server).unwrap();
+        server.run();
+
+    });
+
+    receiver.recv().unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_replies_are_routed_to_listener() {
+
+    let (udp_addr, tcp_addr) = start_server();
+
+    let udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+    udp.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    let mut tcp = TcpStream::connect(tcp_addr).unwrap();
+    tcp.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    for _ in 0 .. 10 {
+
+        udp.send_to(b"query", udp_addr).unwrap();
+        tcp.write_all(&[0, 5]).unwrap();
+        tcp.write_all(b"query").unwrap();
+
+        let mut buffer = [0u8; 16];
+        let (len, from) = udp.recv_from(&mut buffer).unwrap();
+        assert_eq!(udp_addr, from);
+        assert_eq!(b"udp", &buffer[.. len]);
+
+        let mut frame = [0u8; 5];
+        tcp.read_exact(&mut frame).unwrap();
+        assert_eq!(&[0, 3, b't', b'c', b'p'], &frame);
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
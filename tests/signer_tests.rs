@@ -0,0 +1,562 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate ring;
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::str::FromStr;
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED, ED25519};
+use rustydns::{format_zone, nsec3_hash, parse_zone, AuthoritativeHandler, DnsMessage};
+use rustydns::{Dnskey, Ds, Nsec3, QuestionType, Rcode, Record, ResourceRecord, Rrsig, Zone};
+use rustydns::{Signer, SigningAlgorithm, SigningKey, ZoneCatalog};
+use rustydns::{ALGORITHM_ED25519, DIGEST_SHA1, FLAG_AA};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+const ZONE : &str = "
+$TTL 3600
+@        SOA   ns1 hostmaster 1 7200 3600 1209600 300
+         NS    ns1
+ns1      A     192.0.2.1
+www      A     192.0.2.10
+         A     192.0.2.11
+alias    CNAME www
+sub      NS    ns.sub
+ns.sub   A     192.0.2.53
+secure   NS    ns.sub
+         DS    2371 13 2 0A0B0C0D
+a.b.c    A     192.0.2.12
+";
+
+/// 2026-10-19 00:00:00 UTC
+const NOW : u32 = 1792368000;
+
+/*----------------------------------------------------------------------------*/
+
+fn zone() -> Zone {
+    parse_zone(&name("ubeer.org"), ZONE).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn signer() -> Signer {
+
+    let mut signer = Signer::new();
+    signer.add_key(SigningKey::generate(SigningAlgorithm::EcdsaP256Sha256, true).unwrap());
+    signer.add_key(SigningKey::generate(SigningAlgorithm::Ed25519, false).unwrap());
+    signer
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn types_at(zone : &Zone, owner : &str) -> Vec<u16> {
+
+    let mut types : Vec<u16> = zone.records_at(&name(owner)).iter()
+        .map(|rr| rr.type_code())
+        .collect();
+
+    types.sort_unstable();
+    types.dedup();
+    types
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn rrsigs(zone : &Zone, owner : &str) -> Vec<Rrsig> {
+
+    zone.rrset(&name(owner), QuestionType::Rrsig.code()).into_iter()
+        .filter_map(|rr| match rr.record {
+            Record::RRSIG(rrsig) => Some(rrsig),
+            _ => None,
+        })
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Check every signature of `zone` against its DNSKEYs.
+/// Returns the number of signatures checked.
+fn verify_all(zone : &Zone) -> usize {
+
+    let keys : Vec<Dnskey> = zone.rrset(&name("ubeer.org"), QuestionType::Dnskey.code())
+        .into_iter()
+        .filter_map(|rr| match rr.record {
+            Record::DNSKEY(key) => Some(key),
+            _ => None,
+        })
+        .collect();
+
+    let mut checked = 0;
+
+    for rr in zone.records() {
+
+        let rrsig = match rr.record {
+            Record::RRSIG(ref rrsig) => rrsig,
+            _ => continue,
+        };
+
+        let key = keys.iter().find(|k| k.key_tag() == rrsig.key_tag).unwrap();
+        let rrset = zone.rrset(&rr.name, rrsig.type_covered);
+        let data = rrsig.signed_data(&rrset);
+
+        let result = match key.algorithm {
+            ALGORITHM_ED25519 => UnparsedPublicKey::new(&ED25519, &key.public_key)
+                .verify(&data, &rrsig.signature),
+            _ => {
+                let mut public_key = vec![4u8];
+                public_key.extend_from_slice(&key.public_key);
+                UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &public_key)
+                    .verify(&data, &rrsig.signature)
+            },
+        };
+
+        assert!(result.is_ok(), "Bad signature {}", rr);
+        checked += 1;
+
+    }
+
+    checked
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_signer_keys() {
+
+    // RFC 4034, 5.4
+    let key = Dnskey::from_fields(&[
+        "256", "3", "5",
+        "AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/2pHm822aJ5iI9BMzNXxeYCmZ\
+         DRD99WYwYqUSdjMmmAphXdvxegXd/M5+X7OrzKBaMbCVdFLUUh6DhweJBjEVv5f2wwjM9Xzc\
+         nOf+EPbtG9DMBmADjFDc2w/rljwvFw=="].iter().map(|s| s.to_string())
+        .collect::<Vec<String>>()).unwrap();
+
+    assert_eq!(60485, key.key_tag());
+
+    let ds = Ds::from_dnskey(&name("dskey.example.com"), &key, DIGEST_SHA1).unwrap();
+    assert_eq!(Record::from_str("DS 60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118").unwrap(),
+               Record::DS(ds.clone()));
+    assert!(ds.matches(&name("DSKEY.example.com"), &key));
+    assert!(! ds.matches(&name("example.com"), &key));
+
+    for algorithm in &[SigningAlgorithm::EcdsaP256Sha256, SigningAlgorithm::Ed25519] {
+
+        let key = SigningKey::generate(*algorithm, true).unwrap();
+        assert!(key.is_sep());
+        assert_eq!(257, key.dnskey().flags);
+        assert_eq!(64, key.sign(b"data").unwrap().len());
+
+        // Keys are stored as FLAGS ALGORITHM PKCS8
+        let parsed = SigningKey::from_str(&key.to_string()).unwrap();
+        assert_eq!(key.dnskey(), parsed.dnskey());
+        assert_eq!(key.key_tag(), parsed.key_tag());
+
+    }
+
+    assert_eq!(SigningAlgorithm::Ed25519, SigningAlgorithm::from_str("15").unwrap());
+    assert!(SigningAlgorithm::from_str("RSASHA256").is_err());
+    assert!(SigningKey::from_str("257 ED25519 AAAA").is_err());
+
+    let signer = Signer::from_str(&format!("; keys\n{}\n\n{}\n",
+        SigningKey::generate(SigningAlgorithm::Ed25519, true).unwrap(),
+        SigningKey::generate(SigningAlgorithm::Ed25519, false).unwrap())).unwrap();
+    assert_eq!(2, signer.keys().len());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_signer_nsec3_hash() {
+
+    // RFC 5155, appendix A
+    let salt = [0xaa, 0xbb, 0xcc, 0xdd];
+    let hash = |n : &str| nsec3_hash(&name(n), &salt, 12);
+
+    let expected = |s : &str| match Record::from_str(&format!("NSEC3 1 0 12 AABBCCDD {}", s)) {
+        Ok(Record::NSEC3(nsec3)) => nsec3.next_hashed,
+        _ => panic!("Could not parse NSEC3"),
+    };
+
+    assert_eq!(expected("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom"), hash("example"));
+    assert_eq!(expected("35mthgpgcu1qg68fab165klnsnk3dpvl"), hash("a.EXAMPLE"));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_signer_nsec() {
+
+    let origin = name("ubeer.org");
+    let signer = signer();
+    let signed = signer.sign_at(&origin, &zone(), None, NOW).unwrap();
+
+    // 2 DNSKEY, CDS, CDNSKEY at the apex
+    assert_eq!(2, signed.rrset(&origin, QuestionType::Dnskey.code()).len());
+    assert_eq!(1, signed.rrset(&origin, QuestionType::Cds.code()).len());
+    assert_eq!(1, signed.rrset(&origin, QuestionType::Cdnskey.code()).len());
+
+    // DNSKEY signed by the KSK, everything else by the ZSK
+    let ksk = signer.keys()[0].key_tag();
+    let zsk = signer.keys()[1].key_tag();
+
+    for rrsig in rrsigs(&signed, "ubeer.org") {
+        let key_set = [QuestionType::Dnskey, QuestionType::Cds, QuestionType::Cdnskey]
+            .iter().any(|t| t.code() == rrsig.type_covered);
+        assert_eq!(if key_set { ksk } else { zsk }, rrsig.key_tag);
+        assert_eq!(NOW + 14 * 86400, rrsig.expiration);
+        assert_eq!(NOW - 3600, rrsig.inception);
+    }
+
+    let www = rrsigs(&signed, "www.ubeer.org");
+    assert_eq!(2, www.len());
+    assert_eq!(3, www[0].labels);
+    assert_eq!(3600, www[0].original_ttl);
+
+    // Delegations: Only DS and NSEC signed, glue not at all
+    let covered = |owner : &str| rrsigs(&signed, owner).iter()
+        .map(|r| r.type_covered).collect::<Vec<u16>>();
+    assert_eq!(vec![QuestionType::Nsec.code()], covered("sub.ubeer.org"));
+    assert_eq!(2, covered("secure.ubeer.org").len());
+    assert!(types_at(&signed, "ns.sub.ubeer.org").iter().all(|t| *t == QuestionType::A.code()));
+
+    // Chain in canonical order, back to the apex
+    let mut owner = origin.clone();
+    let mut chain = Vec::new();
+
+    loop {
+        let nsec = match signed.rrset(&owner, QuestionType::Nsec.code())[0].record {
+            Record::NSEC(ref nsec) => nsec.clone(),
+            _ => panic!("Not an NSEC"),
+        };
+        assert_eq!(300, signed.rrset(&owner, QuestionType::Nsec.code())[0].ttl);
+        chain.push(owner.to_string());
+        owner = nsec.next;
+        if owner == origin {
+            break;
+        }
+    }
+
+    assert_eq!(vec!["ubeer.org", "alias.ubeer.org", "a.b.c.ubeer.org", "ns1.ubeer.org",
+                    "secure.ubeer.org", "sub.ubeer.org", "www.ubeer.org"], chain);
+
+    match signed.rrset(&name("alias.ubeer.org"), QuestionType::Nsec.code())[0].record {
+        Record::NSEC(ref nsec) => assert_eq!(vec![5, 46, 47], nsec.types),
+        _ => panic!("Not an NSEC"),
+    };
+
+    assert_eq!(17, verify_all(&signed));
+
+    // Signing again replaces everything signed before
+    let again = signer.sign_at(&origin, &signed, None, NOW).unwrap();
+    assert_eq!(signed.records().len(), again.records().len());
+    assert_eq!(17, verify_all(&again));
+
+    // Survives being written to a zone file
+    let reparsed = parse_zone(&origin, &format_zone(&signed)).unwrap();
+    assert_eq!(signed.records().len(), reparsed.records().len());
+    assert_eq!(17, verify_all(&reparsed));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn nsec3_of(zone : &Zone, owner : &str) -> Option<Nsec3> {
+
+    let hash = nsec3_hash(&name(owner), &[0xaa, 0xbb], 12);
+    let label = base32hex(&hash);
+    let hashed = name(&format!("{}.ubeer.org", label));
+
+    match zone.rrset(&hashed, QuestionType::Nsec3.code()).first() {
+        Some(&ResourceRecord { record : Record::NSEC3(ref nsec3), .. }) => Some(nsec3.clone()),
+        _ => None,
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn base32hex(data : &[u8]) -> String {
+
+    let alphabet = b"0123456789abcdefghijklmnopqrstuv";
+    let mut result = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            result.push(alphabet[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+
+    if bits > 0 {
+        result.push(alphabet[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    result
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_signer_nsec3() {
+
+    let origin = name("ubeer.org");
+    let mut signer = signer();
+    signer.set_nsec3(12, vec![0xaa, 0xbb], false);
+
+    let signed = signer.sign_at(&origin, &zone(), None, NOW).unwrap();
+
+    assert!(signed.rrset(&origin, QuestionType::Nsec.code()).is_empty());
+
+    match signed.rrset(&origin, QuestionType::Nsec3param.code())[0].record {
+        Record::NSEC3PARAM(ref param) => {
+            assert_eq!(0, param.flags);
+            assert_eq!(12, param.iterations);
+        },
+        _ => panic!("Not an NSEC3PARAM"),
+    };
+
+    // Apex, 6 names below, 2 empty non-terminals
+    let count = signed.records().iter()
+        .filter(|rr| QuestionType::Nsec3.code() == rr.type_code())
+        .count();
+    assert_eq!(9, count);
+
+    assert_eq!(vec![2, 6, 46, 48, 51, 59, 60], nsec3_of(&signed, "ubeer.org").unwrap().types);
+    assert_eq!(vec![1, 46], nsec3_of(&signed, "www.ubeer.org").unwrap().types);
+    assert_eq!(vec![2], nsec3_of(&signed, "sub.ubeer.org").unwrap().types);
+    assert_eq!(vec![2, 43, 46], nsec3_of(&signed, "secure.ubeer.org").unwrap().types);
+    assert!(nsec3_of(&signed, "b.c.ubeer.org").unwrap().types.is_empty());
+    assert!(nsec3_of(&signed, "ns.sub.ubeer.org").is_none());
+    assert!(! nsec3_of(&signed, "ubeer.org").unwrap().opt_out());
+
+    assert_eq!(20, verify_all(&signed));
+
+    // Opt-out leaves out insecure delegations
+    signer.set_nsec3(12, vec![0xaa, 0xbb], true);
+    let signed = signer.sign_at(&origin, &zone(), None, NOW).unwrap();
+
+    let count = signed.records().iter()
+        .filter(|rr| QuestionType::Nsec3.code() == rr.type_code())
+        .count();
+    assert_eq!(8, count);
+    assert!(nsec3_of(&signed, "sub.ubeer.org").is_none());
+    assert!(nsec3_of(&signed, "secure.ubeer.org").unwrap().opt_out());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_signer_resign() {
+
+    let origin = name("ubeer.org");
+    let signer = signer();
+    let signed = signer.sign_at(&origin, &zone(), None, NOW).unwrap();
+
+    assert!(! signer.needs_refresh(&signed, NOW + 86400));
+    assert!(signer.needs_refresh(&zone(), NOW));
+
+    // Unchanged RRsets keep their signatures
+    let mut changed = signed.clone();
+    changed.add(name("www.ubeer.org"), Record::from_str("A 192.0.2.13").unwrap()).unwrap();
+
+    let resigned = signer.sign_at(&origin, &changed, Some(&signed), NOW + 86400).unwrap();
+
+    assert_eq!(rrsigs(&signed, "ns1.ubeer.org"), rrsigs(&resigned, "ns1.ubeer.org"));
+    assert_eq!(rrsigs(&signed, "ubeer.org"), rrsigs(&resigned, "ubeer.org"));
+
+    let www = rrsigs(&resigned, "www.ubeer.org");
+    let a = www.iter().find(|r| QuestionType::A.code() == r.type_covered).unwrap();
+    assert_eq!(NOW + 86400 + 14 * 86400, a.expiration);
+
+    assert_eq!(17, verify_all(&resigned));
+
+    // Signatures close to expiry are renewed
+    let later = NOW + 11 * 86400;
+    assert!(signer.needs_refresh(&resigned, later));
+
+    let renewed = signer.sign_at(&origin, &resigned, Some(&resigned), later).unwrap();
+    assert!(! signer.needs_refresh(&renewed, later));
+    assert!(rrsigs(&renewed, "ns1.ubeer.org").iter().all(|r| later - 3600 == r.inception));
+
+    // Signatures of other keys are not taken over
+    let other = signer_with_other_keys();
+    let foreign = other.sign_at(&origin, &zone(), Some(&signed), NOW).unwrap();
+    assert!(rrsigs(&foreign, "ns1.ubeer.org").iter()
+            .all(|r| other.keys()[1].key_tag() == r.key_tag));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn signer_with_other_keys() -> Signer {
+
+    let mut signer = Signer::new();
+    signer.add_key(SigningKey::generate(SigningAlgorithm::Ed25519, true).unwrap());
+    signer.add_key(SigningKey::generate(SigningAlgorithm::Ed25519, false).unwrap());
+    signer
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_signer_catalog() {
+
+    let origin = name("ubeer.org");
+    let catalog = ZoneCatalog::new();
+
+    catalog.set_signer(origin.clone(), signer());
+    catalog.add(origin.clone(), zone()).unwrap();
+
+    let signed = catalog.get(&origin).unwrap();
+    assert_eq!(17, verify_all(&signed));
+
+    // Changes get signed
+    let mut changed = (*signed).clone();
+    changed.add(name("new.ubeer.org"), Record::from_str("A 192.0.2.99").unwrap()).unwrap();
+    catalog.replace(origin.clone(), changed).unwrap();
+
+    let signed = catalog.get(&origin).unwrap();
+    assert_eq!(2, rrsigs(&signed, "new.ubeer.org").len());
+    assert_eq!(19, verify_all(&signed));
+
+    // Nothing to refresh yet
+    assert_eq!(0, catalog.refresh_signatures());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn ask(handler : &AuthoritativeHandler, qname : &str, qtype : QuestionType, dnssec_ok : bool)
+    -> DnsMessage {
+
+    let mut query = DnsMessage::query(42, name(qname), qtype);
+    query.set_dnssec_ok(dnssec_ok);
+
+    handler.answer(&query).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn count(records : &[ResourceRecord], qtype : QuestionType) -> usize {
+    records.iter().filter(|rr| qtype.code() == rr.type_code()).count()
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_signer_answers() {
+
+    let origin = name("ubeer.org");
+
+    for nsec3 in &[false, true] {
+
+        let mut signer = signer();
+
+        if *nsec3 {
+            signer.set_nsec3(1, Vec::new(), false);
+        }
+
+        let mut handler = AuthoritativeHandler::new();
+        handler.catalog().set_signer(origin.clone(), signer);
+        handler.add_zone(origin.clone(), zone()).unwrap();
+
+        let denial = if *nsec3 { QuestionType::Nsec3 } else { QuestionType::Nsec };
+
+        // Without DO, no DNSSEC records at all
+        let response = ask(&handler, "www.ubeer.org", QuestionType::A, false);
+        assert_eq!(2, response.answers.len());
+
+        let response = ask(&handler, "www.ubeer.org", QuestionType::A, true);
+        assert!(response.dnssec_ok());
+        assert_eq!(2, count(&response.answers, QuestionType::A));
+        assert_eq!(1, count(&response.answers, QuestionType::Rrsig));
+
+        let response = ask(&handler, "alias.ubeer.org", QuestionType::A, true);
+        assert_eq!(2, count(&response.answers, QuestionType::Rrsig));
+
+        // NODATA
+        let response = ask(&handler, "www.ubeer.org", QuestionType::Mx, true);
+        assert!(response.answers.is_empty());
+        assert_eq!(1, count(&response.authority, QuestionType::Soa));
+        assert_eq!(1, count(&response.authority, denial));
+        assert_eq!(2, count(&response.authority, QuestionType::Rrsig));
+
+        let response = ask(&handler, "www.ubeer.org", QuestionType::Mx, false);
+        assert_eq!(1, response.authority.len());
+
+        // NXDOMAIN - name and wildcard covered, NSEC3 proves the
+        // closest encloser as well
+        let response = ask(&handler, "nothere.ubeer.org", QuestionType::A, true);
+        assert_eq!(Rcode::NxDomain as u16, response.rcode());
+        let proofs = count(&response.authority, denial);
+        assert!(if *nsec3 { (2 ..= 3).contains(&proofs) } else { (1 ..= 2).contains(&proofs) });
+        assert_eq!(proofs + 1, count(&response.authority, QuestionType::Rrsig));
+
+        // Referrals carry DS or the proof there is none
+        let response = ask(&handler, "www.secure.ubeer.org", QuestionType::A, true);
+        assert!(! response.flag(FLAG_AA));
+        assert_eq!(1, count(&response.authority, QuestionType::Ds));
+        assert_eq!(1, count(&response.authority, QuestionType::Rrsig));
+
+        let response = ask(&handler, "www.sub.ubeer.org", QuestionType::A, true);
+        assert_eq!(0, count(&response.authority, QuestionType::Ds));
+        assert_eq!(1, count(&response.authority, denial));
+
+        // DS is answered by the parent
+        let response = ask(&handler, "secure.ubeer.org", QuestionType::Ds, true);
+        assert!(response.flag(FLAG_AA));
+        assert_eq!(1, count(&response.answers, QuestionType::Ds));
+        assert_eq!(1, count(&response.answers, QuestionType::Rrsig));
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
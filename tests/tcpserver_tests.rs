@@ -0,0 +1,235 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+
+use ::std::io::{Read, Write};
+use ::std::net::{SocketAddr, TcpStream};
+use ::std::sync::mpsc::channel;
+use ::std::thread;
+use ::std::time::Duration;
+use rustydns::{ContinueState, Handler, Message, Responder, Server, TcpConfig, TcpServer};
+
+/*----------------------------------------------------------------------------*/
+
+/// Echoes every message after `delay`
+struct EchoHandler {
+    delay : Duration,
+}
+
+impl Handler for EchoHandler {
+
+    fn handle(&self, msg : Message, responder : Responder) -> ContinueState {
+
+        let echo = msg.buffer[.. msg.num_bytes].to_vec();
+        let delay = self.delay;
+
+        if delay == Duration::from_secs(0) {
+            responder.respond(echo);
+        } else {
+            thread::spawn(move || {
+                thread::sleep(delay);
+                responder.respond(echo);
+            });
+        }
+
+        ContinueState::Continue
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn start_server(config : TcpConfig, delay : Duration) -> SocketAddr {
+
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+
+        let handler = EchoHandler { delay };
+
+        let mut tcp_server = TcpServer::bind_to(
+            "127.0.0.1:0", &handler).unwrap();
+
+        tcp_server.set_config(config);
+        sender.send(tcp_server.local_addr().unwrap()).unwrap();
+
+        let mut server = Server::new().unwrap();
+        server.add(tcp_server).unwrap();
+        server.run();
+
+    });
+
+    receiver.recv().unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn connect(addr : &SocketAddr) -> TcpStream {
+
+    let stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    stream
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn read_frame(stream : &mut TcpStream) -> Vec<u8> {
+
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len).unwrap();
+
+    let mut frame = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut frame).unwrap();
+    frame
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tcp_pipelined_queries() {
+
+    let addr = start_server(TcpConfig::default(), Duration::from_secs(0));
+    let mut stream = connect(&addr);
+
+    // Two queries in one segment, the third one split
+    stream.write_all(&[0, 3, 1, 2, 3, 0, 2, 4, 5, 0]).unwrap();
+    stream.flush().unwrap();
+    thread::sleep(Duration::from_millis(50));
+    stream.write_all(&[1, 6]).unwrap();
+
+    let mut frames = vec![
+        read_frame(&mut stream),
+        read_frame(&mut stream),
+        read_frame(&mut stream)];
+    frames.sort();
+
+    assert_eq!(vec![vec![1, 2, 3], vec![4, 5], vec![6]], frames);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tcp_connection_limit() {
+
+    let config = TcpConfig {
+        max_connections_per_client : 1,
+        ..TcpConfig::default()
+    };
+
+    let addr = start_server(config, Duration::from_secs(0));
+    let mut first = connect(&addr);
+
+    first.write_all(&[0, 1, 42]).unwrap();
+    assert_eq!(vec![42], read_frame(&mut first));
+
+    // Refused connections are closed right away
+    let mut second = connect(&addr);
+    second.write_all(&[0, 1, 43]).ok();
+    let mut buffer = [0u8; 3];
+    assert!(match second.read(&mut buffer) {
+        Ok(0) | Err(_) => true,
+        Ok(_) => false,
+    });
+
+    first.write_all(&[0, 1, 44]).unwrap();
+    assert_eq!(vec![44], read_frame(&mut first));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tcp_idle_timeout() {
+
+    let config = TcpConfig {
+        idle_timeout : Duration::from_millis(200),
+        ..TcpConfig::default()
+    };
+
+    let addr = start_server(config, Duration::from_secs(0));
+    let mut stream = connect(&addr);
+
+    let mut buffer = [0u8; 1];
+    assert!(match stream.read(&mut buffer) {
+        Ok(0) | Err(_) => true,
+        Ok(_) => false,
+    });
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tcp_idle_timeout_outstanding() {
+
+    let config = TcpConfig {
+        idle_timeout : Duration::from_millis(200),
+        ..TcpConfig::default()
+    };
+
+    // Answering takes longer than the idle timeout
+    let addr = start_server(config, Duration::from_millis(600));
+    let mut stream = connect(&addr);
+
+    stream.write_all(&[0, 1, 42]).unwrap();
+    assert_eq!(vec![42], read_frame(&mut stream));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tcp_large_message() {
+
+    let addr = start_server(TcpConfig::default(), Duration::from_secs(0));
+    let mut stream = connect(&addr);
+
+    // Largest message possible, written in one go with the next one
+    let mut data = vec![0xff, 0xff];
+    data.extend(vec![7u8; u16::MAX as usize]);
+    data.extend_from_slice(&[0, 1, 42]);
+    stream.write_all(&data).unwrap();
+
+    assert_eq!(vec![7u8; u16::MAX as usize], read_frame(&mut stream));
+    assert_eq!(vec![42], read_frame(&mut stream));
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -26,6 +26,13 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //
+use ::std::net::SocketAddr;
+use ::std::str::FromStr;
+use ::std::sync::Arc;
+use ::std::sync::mpsc::channel;
+use ::std::thread;
+use rustydns::{CookieConfig, Handler, Name, Server, TcpServer, Threadpool, UdpServer};
+
 /*----------------------------------------------------------------------------*/
 
 // pub fn print_name_bytes(bytes : &[u8]) {
@@ -67,9 +74,62 @@ pub fn print_str_as_bytes(s: &str) {
 
     print!("Str is '");
     for c in s.bytes() {
-        print!("{} ", c as u8);
+        print!("{} ", c);
     }
     println!("'");
 }
 
 /*----------------------------------------------------------------------------*/
+
+pub fn name(s : &str) -> Name {
+    Name::from_str(s).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Serve `handler` via UDP and TCP on the same port of the loopback
+/// interface
+pub fn start_server<H : Handler + Send + Sync + 'static>(handler : H) -> SocketAddr {
+    start_server_at("127.0.0.1:0", handler, None).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Serve `handler` via UDP and TCP on the same port of `addr`, a port of 0
+/// picking a free one. Returns the address served on, if it could be bound.
+pub fn start_server_at<H : Handler + Send + Sync + 'static>(
+    addr : &str, handler : H, cookies : Option<CookieConfig>) -> Option<SocketAddr> {
+
+    let (sender, receiver) = channel();
+    let addr = addr.to_string();
+
+    thread::spawn(move || {
+
+        let threadpool = Threadpool::new(Arc::new(handler), 10);
+        threadpool.run(2);
+
+        let servers = UdpServer::bind_to(&addr, &threadpool)
+            .and_then(|udp| Ok((udp.local_addr()?, udp)))
+            .and_then(|(addr, udp)|
+                Ok((addr, udp, TcpServer::bind_to(&addr.to_string(), &threadpool)?)));
+
+        let (addr, mut udp_server, tcp_server) = match servers {
+            Ok(s) => s,
+            Err(_) => return sender.send(None).unwrap(),
+        };
+
+        udp_server.set_cookie_config(cookies).unwrap();
+        sender.send(Some(addr)).unwrap();
+
+        let mut server = Server::new().unwrap();
+        server.add(udp_server).unwrap();
+        server.add(tcp_server).unwrap();
+        server.run();
+
+    });
+
+    receiver.recv().unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -89,7 +89,7 @@ pub fn check_to_bytes<T: AsBytes + FromStr>(s: &str, expected: Vec<u8>) -> bool
 
     println!("length {}  : {:?}", length, &v[.. length]);
 
-    expected == (&v[.. length])
+    expected == v[.. length]
 
 }
 
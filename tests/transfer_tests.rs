@@ -0,0 +1,194 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::io::{Read, Write};
+use ::std::net::{IpAddr, TcpStream};
+use ::std::str::FromStr;
+use ::std::time::Duration;
+use rustydns::{axfr_messages, AuthoritativeHandler, DnsMessage, Network, QuestionType};
+use rustydns::{Rcode, Record, WireReader, Zone};
+use rustydns::{FLAG_AA, MAX_TRANSFER_MESSAGE_LEN};
+use testhelpers::{name, start_server};
+
+/*----------------------------------------------------------------------------*/
+
+/// Number of TXT records to make transfers span several messages
+const NUM_TXT_RECORDS : usize = 2000;
+
+/*----------------------------------------------------------------------------*/
+
+fn large_zone() -> Zone {
+
+    let mut zone = Zone::new();
+
+    zone.add_from_str("ubeer.org SOA ns1.ubeer.org hostmaster.ubeer.org 1 7200 3600 1209600 300").unwrap();
+    zone.add_from_str("ubeer.org NS ns1.ubeer.org").unwrap();
+    zone.add_from_str("ns1.ubeer.org A 192.0.2.1").unwrap();
+
+    let text = "x".repeat(100);
+
+    for i in 0 .. NUM_TXT_RECORDS {
+        zone.add_from_str(&format!("txt{}.ubeer.org TXT \"{}\"", i, text)).unwrap();
+    }
+
+    zone
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn handler(allowed : &str) -> AuthoritativeHandler {
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(name("ubeer.org"), large_zone()).unwrap();
+    handler.allow_transfer(Network::from_str(allowed).unwrap());
+    handler
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn check_transfer(messages : &[DnsMessage]) {
+
+    let soa = QuestionType::Soa.code();
+    let answers : Vec<_> = messages.iter().flat_map(|m| m.answers.iter()).collect();
+
+    assert!(1 < messages.len());
+    assert_eq!(NUM_TXT_RECORDS + 4, answers.len());
+    assert_eq!(soa, answers[0].type_code());
+    assert_eq!(soa, answers[answers.len() - 1].type_code());
+    assert_eq!(2, answers.iter().filter(|rr| soa == rr.type_code()).count());
+
+    for msg in messages {
+        assert_eq!(42, msg.id);
+        assert_eq!(Rcode::NoError as u16, msg.rcode());
+        assert!(msg.flag(FLAG_AA));
+        assert!(MAX_TRANSFER_MESSAGE_LEN >= msg.to_vec().len());
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_axfr_messages() {
+
+    let query = DnsMessage::query(42, name("ubeer.org"), QuestionType::Axfr);
+    let zone = large_zone();
+
+    let messages = axfr_messages(&query, &name("ubeer.org"), &zone).unwrap();
+    check_transfer(&messages);
+
+    let text = Record::TXT(vec!["x".repeat(100).into_bytes()]);
+    let answers : Vec<_> = messages.iter().flat_map(|m| m.answers.iter()).collect();
+
+    for i in 0 .. NUM_TXT_RECORDS {
+        let owner = name(&format!("txt{}.ubeer.org", i));
+        assert!(answers.iter().any(|rr| owner == rr.name && text == rr.record));
+    }
+
+    assert!(axfr_messages(&query, &name("ubeer.org"), &Zone::new()).is_none());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_transfer_access() {
+
+    let handler = handler("192.0.2.0/24");
+    let query = DnsMessage::query(42, name("ubeer.org"), QuestionType::Axfr);
+
+    let client = IpAddr::from_str("192.0.2.10").unwrap();
+    check_transfer(&handler.transfer(&query, &client, None).unwrap());
+
+    let refused = handler.transfer(&query, &IpAddr::from_str("198.51.100.1").unwrap(), None).unwrap();
+    assert_eq!(1, refused.len());
+    assert_eq!(Rcode::Refused as u16, refused[0].rcode());
+    assert!(refused[0].answers.is_empty());
+
+    // Not the apex of a zone of ours
+    let query = DnsMessage::query(42, name("txt1.ubeer.org"), QuestionType::Axfr);
+    let not_auth = handler.transfer(&query, &client, None).unwrap();
+    assert_eq!(1, not_auth.len());
+    assert_eq!(Rcode::NotAuth as u16, not_auth[0].rcode());
+
+    // Not via UDP
+    let response = handler.answer(&query).unwrap();
+    assert_eq!(Rcode::NotImp as u16, response.rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_axfr_via_tcp() {
+
+    let addr = start_server(handler("127.0.0.1"));
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    let query = DnsMessage::query(42, name("ubeer.org"), QuestionType::Axfr).to_vec();
+    stream.write_all(&(query.len() as u16).to_be_bytes()).unwrap();
+    stream.write_all(&query).unwrap();
+
+    let mut messages = Vec::new();
+    let mut num_soa = 0;
+
+    while 2 > num_soa {
+
+        let mut len = [0u8; 2];
+        stream.read_exact(&mut len).unwrap();
+
+        let mut frame = vec![0u8; u16::from_be_bytes(len) as usize];
+        stream.read_exact(&mut frame).unwrap();
+
+        let msg = DnsMessage::read(&mut WireReader::new(&frame)).unwrap();
+        num_soa += msg.answers.iter()
+            .filter(|rr| QuestionType::Soa.code() == rr.type_code())
+            .count();
+
+        messages.push(msg);
+
+    }
+
+    check_transfer(&messages);
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,359 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::{SocketAddr, UdpSocket};
+use ::std::str::FromStr;
+use ::std::time::Duration;
+use rustydns::{parse_zone, transfer_zone, Algorithm, AuthoritativeHandler, CookieConfig};
+use rustydns::{DnsMessage, KeyStore, FLAG_TC};
+use rustydns::{Opcode, Question, QuestionType, Rcode, Record, ResourceRecord};
+use rustydns::{TsigError, TsigKey, TsigSession, WireReader};
+use rustydns::{Zone, DEFAULT_FUDGE};
+use testhelpers::{name, start_server, start_server_at};
+
+/*----------------------------------------------------------------------------*/
+
+const KEYS : &str = "
+; Keys shared with our secondaries
+transfer.ubeer.org  hmac-sha256  c2VjcmV0IG9mIHRoZSB0cmFuc2Zlcg==
+update.ubeer.org    hmac-sha512  dXBkYXRlIHNlY3JldA==
+";
+
+const NOW : u64 = 1_760_000_000;
+
+/*----------------------------------------------------------------------------*/
+
+fn keys() -> KeyStore {
+    KeyStore::from_str(KEYS).unwrap()
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn key(s : &str) -> TsigKey {
+    keys().get(&name(s)).unwrap().clone()
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn zone() -> Zone {
+
+    let text = "
+@     SOA   ns1 hostmaster 1 3600 3600 1209600 300
+      NS    ns1
+ns1   A     192.0.2.1
+www   A     192.0.2.10
+";
+
+    parse_zone(&name("ubeer.org"), text).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn query() -> DnsMessage {
+    DnsMessage::query(4711, name("www.ubeer.org"), QuestionType::A)
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn exchange_udp(addr : &SocketAddr, msg : &DnsMessage) -> Vec<u8> {
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    socket.send_to(&msg.to_vec(), addr).unwrap();
+
+    let mut buffer = [0u8; 4096];
+    let (len, _) = socket.recv_from(&mut buffer).unwrap();
+
+    buffer[.. len].to_vec()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tsig_key_store() {
+
+    let keys = keys();
+
+    assert_eq!(2, keys.len());
+    assert_eq!(Algorithm::HmacSha256, keys.get(&name("TRANSFER.ubeer.org")).unwrap().algorithm);
+    assert_eq!(Algorithm::HmacSha512, keys.get(&name("update.ubeer.org")).unwrap().algorithm);
+    assert!(keys.get(&name("ubeer.org")).is_none());
+
+    assert_eq!(Some(Algorithm::HmacSha384), Algorithm::from_name(&name("hmac-sha384")));
+    assert!(Algorithm::from_str("hmac-md5.sig-alg.reg.int").is_err());
+
+    assert!(KeyStore::from_str("k hmac-sha256").is_err());
+    assert!(KeyStore::from_str("k hmac-md5 c2VjcmV0").is_err());
+    assert!(KeyStore::from_str("k hmac-sha256 c2VjcmV0=").is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tsig_sign_verify() {
+
+    let mut client = TsigSession::new(key("transfer.ubeer.org"));
+
+    let mut request = query();
+    client.sign_at(&mut request, NOW);
+
+    let tsig = request.tsig.clone().unwrap();
+    assert_eq!(name("transfer.ubeer.org"), tsig.key_name);
+    assert_eq!(name("hmac-sha256"), tsig.algorithm);
+    assert_eq!(32, tsig.mac.len());
+    assert_eq!(DEFAULT_FUDGE, tsig.fudge);
+
+    // TSIG survives the wire
+    let bytes = request.to_vec();
+    let parsed = DnsMessage::read(&mut WireReader::new(&bytes)).unwrap();
+    assert_eq!(request, parsed);
+
+    let mut server = TsigSession::new(key("transfer.ubeer.org"));
+    assert_eq!(Ok(()), server.verify_at(&bytes, NOW + 10));
+
+    // The response must be signed based on the MAC of the request
+    let mut response = DnsMessage::response_to(&parsed);
+    server.sign_at(&mut response, NOW + 10);
+
+    assert_eq!(Ok(()), client.clone().verify_at(&response.to_vec(), NOW + 10));
+
+    let mut unrelated = TsigSession::new(key("transfer.ubeer.org"));
+    unrelated.sign_at(&mut query(), NOW + 1);
+    assert_eq!(Err(TsigError::BadSig), unrelated.verify_at(&response.to_vec(), NOW + 10));
+
+    // Tampered message
+    let mut tampered = bytes.clone();
+    tampered[3] ^= 0x01;
+    let mut server = TsigSession::new(key("transfer.ubeer.org"));
+    assert_eq!(Err(TsigError::BadSig), server.verify_at(&tampered, NOW));
+
+    // Wrong key
+    let mut server = TsigSession::new(key("update.ubeer.org"));
+    assert_eq!(Err(TsigError::BadKey), server.verify_at(&bytes, NOW));
+
+    // Outside of the fudge
+    let mut server = TsigSession::new(key("transfer.ubeer.org"));
+    assert_eq!(Err(TsigError::BadTime), server.verify_at(&bytes, NOW + 301));
+
+    let mut server = TsigSession::new(key("transfer.ubeer.org"));
+    assert_eq!(Err(TsigError::BadTime), server.verify_at(&bytes, NOW - 301));
+
+    // Unsigned request
+    let mut server = TsigSession::new(key("transfer.ubeer.org"));
+    assert_eq!(Err(TsigError::BadSig), server.verify_at(&query().to_vec(), NOW));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tsig_stream() {
+
+    let mut client = TsigSession::new(key("transfer.ubeer.org"));
+    let mut server = TsigSession::new(key("transfer.ubeer.org"));
+
+    let mut request = DnsMessage::query(1, name("ubeer.org"), QuestionType::Axfr);
+    client.sign_at(&mut request, NOW);
+    assert_eq!(Ok(()), server.verify_at(&request.to_vec(), NOW));
+
+    let record = |i : u8| ResourceRecord::new(
+        name("www.ubeer.org"), 300, Record::from_str(&format!("A 192.0.2.{}", i)).unwrap());
+
+    let mut messages = Vec::new();
+
+    for i in 0 .. 5 {
+
+        let mut response = DnsMessage::response_to(&request);
+        response.answers.push(record(i));
+
+        // Messages in between might go without TSIG
+        match i {
+            2 => server.skip(&response),
+            _ => server.sign_at(&mut response, NOW),
+        };
+
+        messages.push(response.to_vec());
+
+    }
+
+    let mut verifier = client.clone();
+
+    for bytes in &messages {
+        assert_eq!(Ok(()), verifier.verify_at(bytes, NOW));
+    }
+
+    // Messages must not be reordered
+    let mut verifier = client.clone();
+    assert_eq!(Ok(()), verifier.verify_at(&messages[0], NOW));
+    assert_eq!(Err(TsigError::BadSig), verifier.verify_at(&messages[3], NOW));
+
+    // The first message must be signed
+    let mut unsigned = DnsMessage::response_to(&request);
+    unsigned.answers.push(record(0));
+    assert_eq!(Err(TsigError::BadSig), client.clone().verify_at(&unsigned.to_vec(), NOW));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tsig_signed_update() {
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(name("ubeer.org"), zone()).unwrap();
+    handler.set_keys(keys());
+    handler.allow_update_key(name("update.ubeer.org"));
+
+    let addr = start_server(handler);
+
+    let update = || {
+        let mut msg = DnsMessage::new(DnsMessage::random_id().unwrap());
+        msg.set_opcode(Opcode::Update);
+        msg.questions.push(Question::new(name("ubeer.org"), QuestionType::Soa));
+        msg.authority.push(ResourceRecord::new(
+                name("mail.ubeer.org"), 300, Record::from_str("A 192.0.2.25").unwrap()));
+        msg
+    };
+
+    // Unsigned updates are refused
+    let bytes = exchange_udp(&addr, &update());
+    let response = DnsMessage::read(&mut WireReader::new(&bytes)).unwrap();
+    assert_eq!(Rcode::Refused as u16, response.rcode());
+
+    // Signed with a key not allowed to update
+    let mut session = TsigSession::new(key("transfer.ubeer.org"));
+    let mut msg = update();
+    session.sign(&mut msg);
+
+    let bytes = exchange_udp(&addr, &msg);
+    assert_eq!(Ok(()), session.verify(&bytes));
+    let response = DnsMessage::read(&mut WireReader::new(&bytes)).unwrap();
+    assert_eq!(Rcode::Refused as u16, response.rcode());
+
+    // Signed with an unknown key
+    let unknown = TsigKey::new(name("unknown.ubeer.org"), Algorithm::HmacSha256, b"secret");
+    let mut session = TsigSession::new(unknown);
+    let mut msg = update();
+    session.sign(&mut msg);
+
+    let bytes = exchange_udp(&addr, &msg);
+    let response = DnsMessage::read(&mut WireReader::new(&bytes)).unwrap();
+    assert_eq!(Rcode::NotAuth as u16, response.rcode());
+    assert_eq!(TsigError::BadKey as u16, response.tsig.as_ref().unwrap().error);
+    assert_eq!(Err(TsigError::BadKey), session.verify(&bytes));
+
+    // Signed too long ago
+    let mut session = TsigSession::new(key("update.ubeer.org"));
+    let mut msg = update();
+    session.sign_at(&mut msg, NOW);
+
+    let bytes = exchange_udp(&addr, &msg);
+    let response = DnsMessage::read(&mut WireReader::new(&bytes)).unwrap();
+    assert_eq!(Rcode::NotAuth as u16, response.rcode());
+    assert_eq!(Err(TsigError::BadTime), session.verify(&bytes));
+
+    // Signed with the right key
+    let mut session = TsigSession::new(key("update.ubeer.org"));
+    let mut msg = update();
+    session.sign(&mut msg);
+
+    let bytes = exchange_udp(&addr, &msg);
+    let response = DnsMessage::read(&mut WireReader::new(&bytes)).unwrap();
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+    assert_eq!(Ok(()), session.verify(&bytes));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tsig_signed_transfer() {
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(name("ubeer.org"), zone()).unwrap();
+    handler.set_keys(keys());
+    handler.allow_transfer_key(name("transfer.ubeer.org"));
+
+    let addr = start_server(handler);
+    let origin = name("ubeer.org");
+
+    let transferred = transfer_zone(&addr, &origin, None, Some(&key("transfer.ubeer.org")))
+        .unwrap().unwrap();
+    assert_eq!(zone().records().len(), transferred.records().len());
+
+    assert!(transfer_zone(&addr, &origin, None, None).is_err());
+    assert!(transfer_zone(&addr, &origin, None, Some(&key("update.ubeer.org"))).is_err());
+
+    let unknown = TsigKey::new(name("transfer.ubeer.org"), Algorithm::HmacSha256, b"guessed");
+    assert!(transfer_zone(&addr, &origin, None, Some(&unknown)).is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_tsig_signed_truncated() {
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(name("ubeer.org"), zone()).unwrap();
+    handler.set_keys(keys());
+
+    let config = CookieConfig {
+        max_cookieless_response_len : 150,
+        ..CookieConfig::default()
+    };
+
+    let addr = start_server_at("127.0.0.1:0", handler, Some(config)).unwrap();
+
+    // Signed query without cookie, its answer exceeding the cookieless limit
+    let mut session = TsigSession::new(key("transfer.ubeer.org"));
+    let mut msg = DnsMessage::query(4712, name("ubeer.org"), QuestionType::Soa);
+    session.sign(&mut msg);
+
+    let bytes = exchange_udp(&addr, &msg);
+    assert!(150 >= bytes.len());
+
+    let response = DnsMessage::read(&mut WireReader::new(&bytes)).unwrap();
+    assert!(response.flag(FLAG_TC));
+    assert!(response.answers.is_empty());
+    assert_eq!(Ok(()), session.verify(&bytes));
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,127 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+
+use ::std::collections::HashSet;
+use ::std::net::{SocketAddr, UdpSocket};
+use ::std::sync::mpsc::channel;
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+use rustydns::{ContinueState, Handler, Message, Responder, Server, UdpServer};
+
+/*----------------------------------------------------------------------------*/
+
+const BURST_LEN : usize = 256;
+
+/*----------------------------------------------------------------------------*/
+
+struct EchoHandler {}
+
+impl Handler for EchoHandler {
+
+    fn handle(&self, msg : Message, responder : Responder) -> ContinueState {
+
+        responder.respond(msg.buffer[.. msg.num_bytes].to_vec());
+        ContinueState::Continue
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn start_server() -> SocketAddr {
+
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+
+        let handler = EchoHandler {};
+
+        let udp_server = UdpServer::bind_to(
+            "127.0.0.1:0", &handler).unwrap();
+
+        sender.send(udp_server.local_addr().unwrap()).unwrap();
+
+        let mut server = Server::new().unwrap();
+        server.add(udp_server).unwrap();
+        server.run();
+
+    });
+
+    receiver.recv().unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_udp_burst_is_not_stranded() {
+
+    let addr = start_server();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+
+    let receiver_socket = socket.try_clone().unwrap();
+
+    let receiver = thread::spawn(move || {
+
+        let mut received = HashSet::new();
+        let mut buffer = [0u8; 16];
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while received.len() < BURST_LEN && Instant::now() < deadline {
+            if let Ok(2) = receiver_socket.recv(&mut buffer) {
+                received.insert(u16::from_be_bytes([buffer[0], buffer[1]]));
+            }
+        }
+
+        received
+
+    });
+
+    for i in 0 .. BURST_LEN {
+        socket.send_to(&(i as u16).to_be_bytes(), addr).unwrap();
+    }
+
+    // No further datagram arrives that could trigger another read event
+    let received = receiver.join().unwrap();
+
+    assert_eq!(BURST_LEN, received.len());
+
+}
+
+/*----------------------------------------------------------------------------*/
@@ -0,0 +1,276 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::net::IpAddr;
+use ::std::str::FromStr;
+use rustydns::{parse_zone, apply_update, AuthoritativeHandler, DnsMessage, Network};
+use rustydns::{Opcode, Question, QuestionType, Rcode, Record, ResourceRecord, WireReader, Zone};
+use rustydns::{CLASS_ANY, CLASS_NONE};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+fn zone() -> Zone {
+
+    let text = "
+@     SOA   ns1 hostmaster 1 3600 3600 1209600 300
+      NS    ns1
+ns1   A     192.0.2.1
+www   A     192.0.2.10
+www   A     192.0.2.11
+";
+
+    parse_zone(&name("ubeer.org"), text).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn serial(zone : &Zone) -> u32 {
+
+    match zone.soa(&name("ubeer.org")).unwrap().record {
+        Record::SOA(ref soa) => soa.serial,
+        _ => panic!("No SOA"),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn rr(owner : &str, record : &str) -> ResourceRecord {
+    ResourceRecord::new(name(owner), 3600, Record::from_str(record).unwrap())
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Record without rdata of class `class`, as used for prerequisites
+/// and deletions
+fn empty(owner : &str, class : u16, type_code : u16) -> ResourceRecord {
+
+    ResourceRecord {
+        name : name(owner),
+        class,
+        ttl : 0,
+        record : Record::Unknown(type_code, Vec::new()),
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Create an UPDATE message for ubeer.org, sent through the wire once
+fn update(prerequisites : Vec<ResourceRecord>, changes : Vec<ResourceRecord>) -> DnsMessage {
+
+    let mut msg = DnsMessage::new(1);
+    msg.set_opcode(Opcode::Update);
+    msg.questions.push(Question::new(name("ubeer.org"), QuestionType::Soa));
+    msg.answers = prerequisites;
+    msg.authority = changes;
+
+    let bytes = msg.to_vec();
+    DnsMessage::read(&mut WireReader::new(&bytes)).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_update_add_record() {
+
+    let zone = zone();
+    let msg = update(vec![], vec![rr("mail.ubeer.org", "A 192.0.2.25")]);
+
+    let updated = apply_update(&msg, &name("ubeer.org"), &zone).unwrap().unwrap();
+
+    assert_eq!(1, updated.rrset(&name("mail.ubeer.org"), QuestionType::A.code()).len());
+    assert_eq!(2, serial(&updated));
+
+    // Adding a record that exists already does not change anything
+    let msg = update(vec![], vec![rr("ns1.ubeer.org", "A 192.0.2.1")]);
+    assert_eq!(None, apply_update(&msg, &name("ubeer.org"), &zone).unwrap().map(|_| ()));
+
+    // CNAME records conflicting with other data are ignored
+    let msg = update(vec![], vec![rr("www.ubeer.org", "CNAME ns1.ubeer.org")]);
+    assert_eq!(None, apply_update(&msg, &name("ubeer.org"), &zone).unwrap().map(|_| ()));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_update_data_types() {
+
+    let zone = zone();
+
+    // CAA and private types are data, even though their codes are high
+    let caa = Record::Unknown(257, b"\x00\x05issueca.example".to_vec());
+    let private = Record::Unknown(65280, vec![1, 2, 3]);
+
+    let msg = update(vec![], vec![
+        ResourceRecord::new(name("ubeer.org"), 3600, caa),
+        ResourceRecord::new(name("www.ubeer.org"), 3600, private),
+    ]);
+
+    let updated = apply_update(&msg, &name("ubeer.org"), &zone).unwrap().unwrap();
+
+    assert_eq!(1, updated.rrset(&name("ubeer.org"), 257).len());
+    assert_eq!(1, updated.rrset(&name("www.ubeer.org"), 65280).len());
+
+    // Meta types cannot be added
+    let msg = update(vec![], vec![
+        ResourceRecord::new(name("www.ubeer.org"), 3600, Record::Unknown(128, vec![1]))]);
+
+    assert_eq!(Err(Rcode::FormErr),
+        apply_update(&msg, &name("ubeer.org"), &zone).map(|_| ()));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_update_prerequisites() {
+
+    let zone = zone();
+    let origin = name("ubeer.org");
+    let any = QuestionType::Any.code();
+    let a = QuestionType::A.code();
+    let change = || vec![rr("mail.ubeer.org", "A 192.0.2.25")];
+
+    let check = |prerequisites : Vec<ResourceRecord>| {
+        apply_update(&update(prerequisites, change()), &origin, &zone).map(|_| ())
+    };
+
+    assert_eq!(Ok(()), check(vec![empty("www.ubeer.org", CLASS_ANY, any)]));
+    assert_eq!(Err(Rcode::NxDomain), check(vec![empty("ftp.ubeer.org", CLASS_ANY, any)]));
+    assert_eq!(Ok(()), check(vec![empty("www.ubeer.org", CLASS_ANY, a)]));
+    assert_eq!(Err(Rcode::NxRrset), check(vec![empty("ns1.ubeer.org", CLASS_ANY, 28)]));
+    assert_eq!(Ok(()), check(vec![empty("ftp.ubeer.org", CLASS_NONE, any)]));
+    assert_eq!(Err(Rcode::YxDomain), check(vec![empty("www.ubeer.org", CLASS_NONE, any)]));
+    assert_eq!(Err(Rcode::YxRrset), check(vec![empty("www.ubeer.org", CLASS_NONE, a)]));
+
+    // Value dependent RRsets must match exactly, regardless of order
+    let mut www = vec![rr("www.ubeer.org", "A 192.0.2.11"), rr("www.ubeer.org", "A 192.0.2.10")];
+    for r in www.iter_mut() {
+        r.ttl = 0;
+    }
+
+    assert_eq!(Ok(()), check(www.clone()));
+    assert_eq!(Err(Rcode::NxRrset), check(www[..1].to_vec()));
+
+    // Prerequisites must have a TTL of 0 and must be within the zone
+    assert_eq!(Err(Rcode::FormErr), check(vec![rr("www.ubeer.org", "A 192.0.2.10")]));
+    assert_eq!(Err(Rcode::NotZone), check(vec![empty("www.example.org", CLASS_ANY, any)]));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_update_delete() {
+
+    let zone = zone();
+    let origin = name("ubeer.org");
+    let a = QuestionType::A.code();
+
+    // Delete a single record
+    let mut record = rr("www.ubeer.org", "A 192.0.2.10");
+    record.class = CLASS_NONE;
+    record.ttl = 0;
+
+    let updated = apply_update(&update(vec![], vec![record]), &origin, &zone).unwrap().unwrap();
+    let www = updated.rrset(&name("www.ubeer.org"), a);
+    assert_eq!(vec![rr("www.ubeer.org", "A 192.0.2.11")], www);
+    assert_eq!(2, serial(&updated));
+
+    // Delete an RRset
+    let msg = update(vec![], vec![empty("www.ubeer.org", CLASS_ANY, a)]);
+    let updated = apply_update(&msg, &origin, &zone).unwrap().unwrap();
+    assert!(updated.records_at(&name("www.ubeer.org")).is_empty());
+
+    // Deleting all records at the apex keeps SOA and NS
+    let msg = update(vec![], vec![empty("ubeer.org", CLASS_ANY, QuestionType::Any.code())]);
+    assert_eq!(None, apply_update(&msg, &origin, &zone).unwrap().map(|_| ()));
+
+    // The last NS record of the apex is never deleted
+    let mut ns = rr("ubeer.org", "NS ns1.ubeer.org");
+    ns.class = CLASS_NONE;
+    ns.ttl = 0;
+
+    let msg = update(vec![], vec![ns]);
+    assert_eq!(None, apply_update(&msg, &origin, &zone).unwrap().map(|_| ()));
+
+    // Changes outside of the zone are rejected, and nothing is applied
+    let msg = update(vec![], vec![
+        rr("mail.ubeer.org", "A 192.0.2.25"),
+        rr("www.example.org", "A 192.0.2.26")]);
+    assert_eq!(Err(Rcode::NotZone), apply_update(&msg, &origin, &zone).map(|_| ()));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_update_handler() {
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(name("ubeer.org"), zone()).unwrap();
+    handler.allow_update(Network::from_str("192.0.2.0/24").unwrap());
+
+    let allowed = IpAddr::from_str("192.0.2.53").unwrap();
+    let denied = IpAddr::from_str("198.51.100.53").unwrap();
+
+    let msg = update(vec![], vec![rr("mail.ubeer.org", "A 192.0.2.25")]);
+
+    let response = handler.update(&msg, &denied, None).unwrap();
+    assert_eq!(Rcode::Refused as u16, response.rcode());
+
+    let response = handler.update(&msg, &allowed, None).unwrap();
+    assert_eq!(Rcode::NoError as u16, response.rcode());
+
+    let zone = handler.catalog().get(&name("ubeer.org")).unwrap();
+    assert_eq!(2, serial(&zone));
+    assert_eq!(1, zone.rrset(&name("mail.ubeer.org"), QuestionType::A.code()).len());
+
+    // Zones not served are rejected
+    let mut other = update(vec![], vec![]);
+    other.questions[0].name = name("example.org");
+
+    let response = handler.update(&other, &allowed, None).unwrap();
+    assert_eq!(Rcode::NotAuth as u16, response.rcode());
+
+}
+
+/*----------------------------------------------------------------------------*/
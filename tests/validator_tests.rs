@@ -0,0 +1,852 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::cell::RefCell;
+use ::std::collections::HashMap;
+use ::std::fs;
+use ::std::net::IpAddr;
+use ::std::str::FromStr;
+use rustydns::{parse_zone, verify_rrsig, AuthoritativeHandler, ClientSubnet, DnsMessage, Name};
+use rustydns::{Edns, EdnsOption, Network};
+use rustydns::{Ds, QuestionType, Rcode, Record, ResourceRecord, WireReader};
+use rustydns::{QuerySource, Security, Signer, SigningAlgorithm, SigningKey, Validation, Validator};
+use rustydns::DIGEST_SHA256;
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+/// Time the fixtures were recorded at, 2026-10-19 00:00:00 UTC.
+/// Their signatures are valid from an hour before for 14 days.
+const NOW : u32 = 1792368000;
+
+const FIXTURES : &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/validator");
+
+/*----------------------------------------------------------------------------*/
+
+/// Responses recorded by `record_fixtures`, keyed by question
+struct Recorded {
+
+    responses : HashMap<(Name, QuestionType), DnsMessage>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl Recorded {
+
+    fn load() -> Recorded {
+
+        let text = fs::read_to_string(format!("{}/responses.txt", FIXTURES)).unwrap();
+        let mut responses = HashMap::new();
+
+        for line in text.lines().filter(|l| ! l.is_empty() && ! l.starts_with(';')) {
+
+            let fields : Vec<&str> = line.split_whitespace().collect();
+            let bytes = from_hex(fields[2]);
+            let msg = DnsMessage::read(&mut WireReader::new(&bytes)).unwrap();
+
+            responses.insert((name(fields[0]), QuestionType::from_str(fields[1]).unwrap()), msg);
+
+        }
+
+        Recorded { responses }
+
+    }
+
+    /*------------------------------------------------------------------------*/
+
+    fn response(&self, qname : &str, qtype : QuestionType) -> DnsMessage {
+
+        self.query(&name(qname), qtype).unwrap()
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl QuerySource for Recorded {
+
+    fn query(&self, name : &Name, qtype : QuestionType) -> Result<DnsMessage, &'static str> {
+
+        self.responses.get(&(name.clone(), qtype)).cloned().ok_or("No recorded response")
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Source failing for one question
+struct Failing<'a> {
+
+    inner : &'a Recorded,
+    name : Name,
+    qtype : QuestionType,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl<'a> QuerySource for Failing<'a> {
+
+    fn query(&self, name : &Name, qtype : QuestionType) -> Result<DnsMessage, &'static str> {
+
+        match *name == self.name && qtype == self.qtype {
+            true => Err("Timeout"),
+            false => self.inner.query(name, qtype),
+        }
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn rr(s : &str) -> ResourceRecord {
+
+    let fields : Vec<&str> = s.splitn(2, ' ').collect();
+    ResourceRecord::new(name(fields[0]), 3600, Record::from_str(fields[1]).unwrap())
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn from_hex(s : &str) -> Vec<u8> {
+
+    (0 .. s.len() / 2)
+        .map(|i| u8::from_str_radix(&s[2 * i .. 2 * i + 2], 16).unwrap())
+        .collect()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn validator() -> Validator {
+
+    Validator::load(format!("{}/anchors.txt", FIXTURES)).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn validate(qname : &str, qtype : QuestionType) -> Validation {
+
+    let recorded = Recorded::load();
+    validator().validate_at(&recorded.response(qname, qtype), &recorded, NOW)
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn assert_validation(security : Security, reason : &str, validation : &Validation) {
+
+    assert_eq!(security, validation.security, "{}", validation);
+    assert_eq!(reason, validation.reason);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_rfc_vectors() {
+
+    // RFC 6605, 6.1
+    let key = rr("example.net DNSKEY 257 3 13 \
+        GojIhhXUN/u4v54ZQqGSnyhWJwaubCvTmeexv7bR6edbkrSqQpF64cYbcB7wNcP+e+MAnLr+Wi9xMWyQLc8NAA==");
+    let rrsig = rr("www.example.net RRSIG A 13 3 3600 20100909100439 20100812100439 55648 \
+        example.net. \
+        qx6wLYqmh+l9oCKTN6qIc+bw6ya+KJ8oMz0YP107epXAyGmt+3SNruPFKG7tZoLBLlUzGGus7ZwmwWep666VCw==");
+
+    check_vector(&key, &rrsig, &[rr("www.example.net A 192.0.2.1")]);
+
+    // RFC 8080, 6.1
+    let key = rr("example.com DNSKEY 257 3 15 l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=");
+    let rrsig = rr("example.com RRSIG MX 15 2 3600 1440021600 1438207200 3613 example.com. \
+        oL9krJun7xfBOIWcGHi7mag5/hdZrKWw15jPGrHpjQeRAvTdszaPD+QLs3fx8A4M3e23mRZ9VrbpMngwcrqNAg==");
+
+    check_vector(&key, &rrsig, &[rr("example.com MX 10 mail.example.com.")]);
+
+    // RFC 5702, 6.2
+    let key = rr("example.net DNSKEY 256 3 10 \
+        AwEAAdHoNTOW+et86KuJOWRDp1pndvwb6Y83nSVXXyLA3DLroROUkN6X0O6pnWnjJQujX/AyhqFDxj13tOnD\
+        9u/1kTg7cV6rklMrZDtJCQ5PCl/D7QNPsgVsMu1J2Q8gpMpztNFLpPBz1bWXjDtaR7ZQBlZ3PFY12ZTSncor\
+        ffcGmhOL");
+    let rrsig = rr("www.example.net RRSIG A 10 3 3600 20300101000000 20000101000000 3740 \
+        example.net. \
+        tsb4wnjRUDnB1BUi+t6TMTXThjVnG+eCkWqjvvjhzQL1d0YRoOe0CbxrVDYd0xDtsuJRaeUw1ep94PzEWzr0\
+        iGYgZBWm/zpq+9fOuagYJRfDqfReKBzMweOLDiNa8iP5g9vMhpuv6OPlvpXwm9Sa9ZXIbNl1MBGk0fthPgxd\
+        DLw=");
+
+    check_vector(&key, &rrsig, &[rr("www.example.net A 192.0.2.91")]);
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn check_vector(key : &ResourceRecord, rrsig : &ResourceRecord, rrset : &[ResourceRecord]) {
+
+    let (key, rrsig) = match (&key.record, &rrsig.record) {
+        (Record::DNSKEY(key), Record::RRSIG(rrsig)) => (key, rrsig),
+        _ => panic!("Expected DNSKEY and RRSIG"),
+    };
+
+    assert_eq!(rrsig.key_tag, key.key_tag());
+    assert_eq!(Ok(()), verify_rrsig(rrsig, rrset, key));
+
+    let mut tampered = rrset.to_vec();
+    tampered[0].ttl = 1;
+    assert_eq!(Ok(()), verify_rrsig(rrsig, &tampered, key), "TTL is not signed");
+
+    tampered[0].name = name("other.example.net");
+    assert_eq!(Err("Invalid signature"), verify_rrsig(rrsig, &tampered, key));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_secure() {
+
+    // NSEC
+    assert!(validate("www.ubeer.org", QuestionType::A).is_secure());
+    assert!(validate("www.ubeer.org", QuestionType::Mx).is_secure());
+    assert!(validate("nothere.ubeer.org", QuestionType::A).is_secure());
+    assert!(validate("c.ubeer.org", QuestionType::Txt).is_secure());
+    assert!(validate("host.wild.ubeer.org", QuestionType::A).is_secure());
+    assert!(validate("host.wild.ubeer.org", QuestionType::Mx).is_secure());
+
+    // NSEC3
+    assert!(validate("www.example", QuestionType::A).is_secure());
+    assert!(validate("www.example", QuestionType::Mx).is_secure());
+    assert!(validate("nothere.example", QuestionType::A).is_secure());
+    assert!(validate("b.example", QuestionType::Txt).is_secure());
+    assert!(validate("host.wild.example", QuestionType::Txt).is_secure());
+    assert!(validate("host.wild.example", QuestionType::A).is_secure());
+
+    // Keys themselves, single key signing everything
+    assert!(validate("org", QuestionType::Dnskey).is_secure());
+    assert!(validate("ubeer.org", QuestionType::Ds).is_secure());
+    assert!(validate("www.ubeer.org", QuestionType::Ds).is_secure());
+
+    assert_eq!("Secure", validate("www.ubeer.org", QuestionType::A).to_string());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_insecure() {
+
+    // No DS, proven by NSEC, NSEC3 and an NSEC3 opt-out span
+    assert_validation(Security::Insecure, "Delegation without DS",
+                      &validate("www.nods.ubeer.org", QuestionType::A));
+    assert_validation(Security::Insecure, "Delegation without DS",
+                      &validate("www.sub.example", QuestionType::A));
+    assert_validation(Security::Insecure, "Delegation without DS",
+                      &validate("www.insecure.org", QuestionType::A));
+
+    // CNAME from a secure into an insecure zone
+    let validation = validate("alias.ubeer.org", QuestionType::A);
+    assert_validation(Security::Insecure, "Delegation without DS", &validation);
+    assert_eq!("Insecure: Delegation without DS", validation.to_string());
+
+    // The name might lie below an insecure delegation left out
+    assert_validation(Security::Insecure, "Name lies within an opt-out span",
+                      &validate("nothere.org", QuestionType::A));
+
+    // DS with unknown algorithm
+    let recorded = Recorded::load();
+    let response = recorded.response("www.ubeer.org", QuestionType::A);
+
+    let validator = Validator::from_str("org. DS 12345 253 2 AABBCCDD").unwrap();
+    assert_validation(Security::Insecure, "No trust anchor with supported algorithm",
+                      &validator.validate_at(&response, &recorded, NOW));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_bogus() {
+
+    let recorded = Recorded::load();
+    let validator = validator();
+
+    assert_validation(Security::Bogus, "No DNSKEY matches the trust anchor",
+                      &validate("www.bogus.org", QuestionType::A));
+
+    let response = recorded.response("www.ubeer.org", QuestionType::A);
+
+    assert_validation(Security::Bogus, "Signature expired",
+                      &validator.validate_at(&response, &recorded, NOW + 15 * 86400));
+    assert_validation(Security::Bogus, "Signature not yet valid",
+                      &validator.validate_at(&response, &recorded, NOW - 7200));
+
+    // Tampered data
+    let mut tampered = response.clone();
+    tampered.answers[0].record = Record::from_str("A 192.0.2.66").unwrap();
+    assert_validation(Security::Bogus, "Invalid signature",
+                      &validator.validate_at(&tampered, &recorded, NOW));
+
+    // Signatures stripped
+    let mut stripped = response.clone();
+    stripped.remove_dnssec_records();
+    assert_validation(Security::Bogus, "Missing signature",
+                      &validator.validate_at(&stripped, &recorded, NOW));
+
+    // Proofs stripped
+    let mut response = recorded.response("nothere.ubeer.org", QuestionType::A);
+    assert_eq!(Rcode::NxDomain as u16, response.rcode());
+    response.authority.retain(|rr| QuestionType::Nsec.code() != rr.type_code());
+    assert_validation(Security::Bogus, "Missing proof of non-existence",
+                      &validator.validate_at(&response, &recorded, NOW));
+
+    let mut response = recorded.response("www.example", QuestionType::Mx);
+    response.authority.retain(|rr| QuestionType::Nsec3.code() != rr.type_code());
+    assert_validation(Security::Bogus, "Missing proof of no data",
+                      &validator.validate_at(&response, &recorded, NOW));
+
+    // Wildcard answer for a name that exists
+    let mut response = recorded.response("host.wild.ubeer.org", QuestionType::A);
+    response.authority.clear();
+    assert_validation(Security::Bogus, "Missing proof for wildcard expansion",
+                      &validator.validate_at(&response, &recorded, NOW));
+
+    // NXDOMAIN turned into NODATA
+    let mut response = recorded.response("nothere.example", QuestionType::A);
+    response.set_rcode(Rcode::NoError);
+    assert_validation(Security::Bogus, "Missing proof of no data",
+                      &validator.validate_at(&response, &recorded, NOW));
+
+    // Trust anchor does not match
+    let anchors = fs::read_to_string(format!("{}/anchors.txt", FIXTURES)).unwrap();
+    let mut anchor = anchors.lines().find(|l| l.starts_with("org.")).unwrap().to_string();
+    let last = if anchor.ends_with('0') { "1" } else { "0" };
+    anchor.pop();
+    anchor.push_str(last);
+
+    let validator = Validator::from_str(&anchor).unwrap();
+    assert_validation(Security::Bogus, "No DNSKEY matches the trust anchor",
+                      &validator.validate_at(&recorded.response("www.ubeer.org", QuestionType::A),
+                                             &recorded, NOW));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_indeterminate() {
+
+    let recorded = Recorded::load();
+    let response = recorded.response("www.ubeer.org", QuestionType::A);
+
+    assert_validation(Security::Indeterminate, "No trust anchor",
+                      &Validator::new().validate_at(&response, &recorded, NOW));
+
+    let failing = Failing {
+        inner : &recorded,
+        name : name("org"),
+        qtype : QuestionType::Dnskey,
+    };
+
+    assert_validation(Security::Indeterminate, "Could not fetch DNSKEY",
+                      &validator().validate_at(&response, &failing, NOW));
+
+    let failing = Failing {
+        inner : &recorded,
+        name : name("ubeer.org"),
+        qtype : QuestionType::Ds,
+    };
+
+    assert_validation(Security::Indeterminate, "Could not fetch DS",
+                      &validator().validate_at(&response, &failing, NOW));
+
+    let mut error = response.clone();
+    error.set_rcode(Rcode::ServFail);
+    assert_validation(Security::Indeterminate, "Response indicates an error",
+                      &validator().validate_at(&error, &recorded, NOW));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_anchors() {
+
+    let recorded = Recorded::load();
+    let response = recorded.response("www.ubeer.org", QuestionType::A);
+
+    // Anchor below the root, given as DNSKEY
+    let keys = recorded.response("ubeer.org", QuestionType::Dnskey);
+
+    let mut validator = Validator::new();
+
+    for key in keys.answers.iter().filter(|rr| QuestionType::Dnskey.code() == rr.type_code()) {
+        validator.add_anchor(key.clone()).unwrap();
+    }
+
+    assert!(validator.validate_at(&response, &recorded, NOW).is_secure());
+
+    // Outside of the anchored zone
+    let response = recorded.response("www.example", QuestionType::A);
+    assert_validation(Security::Indeterminate, "No trust anchor",
+                      &validator.validate_at(&response, &recorded, NOW));
+
+    assert!(validator.add_anchor(rr("ubeer.org A 192.0.2.1")).is_err());
+
+    let root = Validator::with_root_anchors();
+    let tags : Vec<String> = root.anchors().iter()
+        .filter(|anchor| anchor.name.is_root())
+        .map(|anchor| anchor.record.to_string())
+        .map(|ds| ds.split(' ').nth(1).unwrap().to_string())
+        .collect();
+
+    assert_eq!(vec!["20326", "38696"], tags);
+    assert!(Validator::from_str("; anchors\nubeer.org. MX 10 mail.ubeer.org.").is_err());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_nsec3_iterations() {
+
+    // Zone signed with NSEC3 of `iterations`, and the response for a name
+    // that does not exist
+    let validate_with = |iterations : u16| {
+
+        let mut signer = signer(SigningAlgorithm::Ed25519, false);
+        signer.set_nsec3(iterations, vec![0xaa, 0xbb], false);
+
+        let zone = parse_zone(&name("example"), EXAMPLE_ZONE).unwrap();
+        let zone = signer.sign_at(&name("example"), &zone, None, NOW).unwrap();
+
+        let mut handler = AuthoritativeHandler::new();
+        handler.add_zone(name("example"), zone).unwrap();
+
+        let servers = Servers { handlers : vec![(name("example"), handler)] };
+        let response = servers.query(&name("nothere.example"), QuestionType::A).unwrap();
+        assert_eq!(Rcode::NxDomain as u16, response.rcode());
+
+        let anchor = format!("example. DS {}", ds("example", &signer));
+        Validator::from_str(&anchor).unwrap().validate_at(&response, &servers, NOW)
+
+    };
+
+    assert!(validate_with(150).is_secure());
+
+    // Too costly to check (RFC 9276, 3.2)
+    assert_validation(Security::Insecure, "Too many NSEC3 iterations", &validate_with(500));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_wildcard() {
+
+    // Answers the server synthesizes from `*.wild` of a zone signed
+    // with NSEC or NSEC3
+    let validate_with = |origin : &str, text : &str, nsec3 : bool, qname : &str,
+                         qtype : QuestionType| {
+
+        let mut signer = signer(SigningAlgorithm::Ed25519, false);
+
+        if nsec3 {
+            signer.set_nsec3(1, vec![0xaa, 0xbb], false);
+        }
+
+        let zone = parse_zone(&name(origin), text).unwrap();
+        let zone = signer.sign_at(&name(origin), &zone, None, NOW).unwrap();
+
+        let mut handler = AuthoritativeHandler::new();
+        handler.add_zone(name(origin), zone).unwrap();
+
+        let servers = Servers { handlers : vec![(name(origin), handler)] };
+        let response = servers.query(&name(qname), qtype).unwrap();
+
+        assert_eq!(Rcode::NoError as u16, response.rcode());
+        assert!(response.answers.iter().all(|rr| name(qname) == rr.name));
+
+        let anchor = format!("{}. DS {}", origin, ds(origin, &signer));
+        Validator::from_str(&anchor).unwrap().validate_at(&response, &servers, NOW)
+
+    };
+
+    let ubeer = |qname, qtype| validate_with("ubeer.org", UBEER_ZONE, false, qname, qtype);
+    let example = |qname, qtype| validate_with("example", EXAMPLE_ZONE, true, qname, qtype);
+
+    assert!(ubeer("host.wild.ubeer.org", QuestionType::A).is_secure());
+    assert!(ubeer("a.host.wild.ubeer.org", QuestionType::A).is_secure());
+    assert!(ubeer("host.wild.ubeer.org", QuestionType::Mx).is_secure());
+    assert!(example("host.wild.example", QuestionType::Txt).is_secure());
+    assert!(example("a.host.wild.example", QuestionType::Txt).is_secure());
+    assert!(example("host.wild.example", QuestionType::A).is_secure());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_validator_client_subnet() {
+
+    let signer = signer(SigningAlgorithm::Ed25519, false);
+
+    let mut zone = parse_zone(&name("ubeer.org"), UBEER_ZONE).unwrap();
+    zone.add_for_subnet(&name("www.ubeer.org"), Network::from_str("10.0.0.0/8").unwrap(),
+                        Record::from_str("A 10.0.0.10").unwrap()).unwrap();
+    let zone = signer.sign_at(&name("ubeer.org"), &zone, None, NOW).unwrap();
+
+    let mut handler = AuthoritativeHandler::new();
+    handler.add_zone(name("ubeer.org"), zone).unwrap();
+
+    let servers = Servers { handlers : vec![(name("ubeer.org"), handler)] };
+
+    let query = |dnssec_ok : bool| {
+        let subnet = ClientSubnet::new(IpAddr::from_str("10.1.2.0").unwrap(), 24).unwrap();
+        let mut query = DnsMessage::query(1, name("www.ubeer.org"), QuestionType::A);
+        let mut edns = Edns::new(1232);
+        edns.set_option(EdnsOption::ClientSubnet(subnet));
+        query.edns = Some(edns);
+        query.set_dnssec_ok(dnssec_ok);
+        servers.handlers[0].1.answer(&query).unwrap()
+    };
+
+    let scope = |response : &DnsMessage| response.edns.as_ref().unwrap()
+        .client_subnet().unwrap().scope_prefix_len;
+
+    // Unsigned variant without DNSSEC
+    let response = query(false);
+    assert_eq!(Record::from_str("A 10.0.0.10").unwrap(), response.answers[0].record);
+    assert_eq!(8, scope(&response));
+
+    // The signed default records otherwise, valid for every client
+    let response = query(true);
+    assert_eq!(Record::from_str("A 192.0.2.10").unwrap(), response.answers[0].record);
+    assert_eq!(0, scope(&response));
+
+    let anchor = format!("ubeer.org. DS {}", ds("ubeer.org", &signer));
+    assert!(Validator::from_str(&anchor).unwrap().validate_at(&response, &servers, NOW)
+        .is_secure());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Authoritative servers for the zones of the fixtures
+struct Servers {
+
+    handlers : Vec<(Name, AuthoritativeHandler)>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl QuerySource for Servers {
+
+    /// Ask the server of the closest zone, DS queries the parent
+    fn query(&self, qname : &Name, qtype : QuestionType) -> Result<DnsMessage, &'static str> {
+
+        let (_, handler) = self.handlers.iter()
+            .filter(|(origin, _)| qname.is_subdomain_of(origin))
+            .filter(|(origin, _)| QuestionType::Ds != qtype || origin != qname)
+            .max_by_key(|(origin, _)| origin.label_count())
+            .ok_or("No server")?;
+
+        let mut query = DnsMessage::query(1, qname.clone(), qtype);
+        query.set_dnssec_ok(true);
+
+        handler.answer(&query).ok_or("No response")
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Records the queries passed on to `inner`
+struct Recorder<'a> {
+
+    inner : &'a dyn QuerySource,
+    log : RefCell<Vec<(Name, QuestionType, DnsMessage)>>,
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+impl<'a> QuerySource for Recorder<'a> {
+
+    fn query(&self, qname : &Name, qtype : QuestionType) -> Result<DnsMessage, &'static str> {
+
+        let response = self.inner.query(qname, qtype)?;
+        self.log.borrow_mut().push((qname.clone(), qtype, response.clone()));
+        Ok(response)
+
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+const ORG_ZONE : &str = "
+$TTL 3600
+@        SOA   a0.org.afilias-nst.info. noc.afilias-nst.info. 1 1800 900 604800 3600
+         NS    a0.org.afilias-nst.info.
+ubeer    NS    ns.ubeer
+ns.ubeer A     192.0.2.1
+insecure NS    ns.insecure
+ns.insecure A  192.0.2.2
+bogus    NS    ns.bogus
+ns.bogus A     192.0.2.3
+";
+
+const UBEER_ZONE : &str = "
+$TTL 3600
+@        SOA   ns hostmaster 1 7200 3600 1209600 300
+         NS    ns
+ns       A     192.0.2.1
+www      A     192.0.2.10
+         AAAA  2001:db8::10
+*.wild   A     192.0.2.20
+alias    CNAME www.insecure.org.
+nods     NS    ns.nods
+ns.nods  A     192.0.2.4
+a.b.c    TXT   \"empty non-terminals above\"
+";
+
+const EXAMPLE_ZONE : &str = "
+$TTL 3600
+@        SOA   ns hostmaster 1 7200 3600 1209600 300
+         NS    ns
+ns       A     192.0.2.53
+www      A     192.0.2.80
+*.wild   TXT   \"wildcard\"
+sub      NS    ns.sub
+ns.sub   A     192.0.2.54
+a.b      TXT   \"empty non-terminal above\"
+";
+
+const SUB_EXAMPLE_ZONE : &str = "
+@        SOA   ns hostmaster 1 7200 3600 1209600 300
+         NS    ns
+ns       A     192.0.2.54
+www      A     192.0.2.81
+";
+
+const NODS_ZONE : &str = "
+@        SOA   ns hostmaster 1 7200 3600 1209600 300
+         NS    ns
+ns       A     192.0.2.4
+www      A     192.0.2.92
+";
+
+const INSECURE_ZONE : &str = "
+@        SOA   ns hostmaster 1 7200 3600 1209600 300
+         NS    ns
+ns       A     192.0.2.2
+www      A     192.0.2.90
+";
+
+const BOGUS_ZONE : &str = "
+@        SOA   ns hostmaster 1 7200 3600 1209600 300
+         NS    ns
+ns       A     192.0.2.3
+www      A     192.0.2.91
+";
+
+/*----------------------------------------------------------------------------*/
+
+fn signer(algorithm : SigningAlgorithm, split : bool) -> Signer {
+
+    let mut signer = Signer::new();
+    signer.add_key(SigningKey::generate(algorithm, true).unwrap());
+
+    if split {
+        signer.add_key(SigningKey::generate(algorithm, false).unwrap());
+    }
+
+    signer
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+fn ds(owner : &str, signer : &Signer) -> Ds {
+
+    Ds::from_dnskey(&name(owner), signer.keys()[0].dnskey(), DIGEST_SHA256).unwrap()
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Resolve `qname` like a resolver would, following CNAMEs into other zones
+fn resolve(servers : &Servers, qname : &str, qtype : QuestionType) -> DnsMessage {
+
+    let mut response = servers.query(&name(qname), qtype).unwrap();
+
+    let target = response.answers.iter()
+        .filter_map(|rr| match rr.record {
+            Record::CNAME(ref target) => Some(target.clone()),
+            _ => None,
+        })
+        .next_back();
+
+    if let Some(target) = target {
+
+        let other = servers.query(&target, qtype).unwrap();
+
+        response.set_rcode(match other.rcode() {
+            r if Rcode::NxDomain as u16 == r => Rcode::NxDomain,
+            _ => Rcode::NoError,
+        });
+        response.answers.extend(other.answers);
+        response.authority = other.authority;
+
+    }
+
+    response
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+/// Sign the zones below the trust anchors org and example, then record the responses of the tests above along with
+/// the queries the validator makes for them.
+/// Run via `cargo test --test validator_tests -- --ignored` to renew.
+#[test]
+#[ignore]
+fn record_fixtures() {
+
+    let mut org = signer(SigningAlgorithm::EcdsaP256Sha256, false);
+    let ubeer = signer(SigningAlgorithm::Ed25519, true);
+    let mut example = signer(SigningAlgorithm::Ed25519, true);
+    let bogus = signer(SigningAlgorithm::EcdsaP256Sha256, false);
+    let unrelated = signer(SigningAlgorithm::EcdsaP256Sha256, false);
+
+    org.set_nsec3(0, Vec::new(), true);
+    example.set_nsec3(5, vec![0xaa, 0xbb, 0xcc, 0xdd], false);
+
+    let mut zones = vec![
+        ("org", ORG_ZONE, Some(&org)),
+        ("ubeer.org", UBEER_ZONE, Some(&ubeer)),
+        ("example", EXAMPLE_ZONE, Some(&example)),
+        ("sub.example", SUB_EXAMPLE_ZONE, None),
+        ("nods.ubeer.org", NODS_ZONE, None),
+        ("insecure.org", INSECURE_ZONE, None),
+        ("bogus.org", BOGUS_ZONE, Some(&bogus)),
+    ];
+
+    let delegations = [("org", "ubeer.org", &ubeer), ("org", "bogus.org", &unrelated)];
+
+    let mut handlers = Vec::new();
+
+    for (origin, text, signer) in zones.drain(..) {
+
+        let mut zone = parse_zone(&name(origin), text).unwrap();
+
+        for (parent, child, signer) in delegations.iter() {
+            if *parent == origin {
+                zone.add(name(child), Record::DS(ds(child, signer))).unwrap();
+            }
+        }
+
+        if let Some(signer) = signer {
+            zone = signer.sign_at(&name(origin), &zone, None, NOW).unwrap();
+        }
+
+        let mut handler = AuthoritativeHandler::new();
+        handler.add_zone(name(origin), zone).unwrap();
+        handlers.push((name(origin), handler));
+
+    }
+
+    let servers = Servers { handlers };
+    let recorder = Recorder { inner : &servers, log : RefCell::new(Vec::new()) };
+    let anchor = format!("org. DS {}\nexample. DS {}\n", ds("org", &org), ds("example", &example));
+    let validator = Validator::from_str(&anchor).unwrap();
+
+    let questions = [
+        ("www.ubeer.org", QuestionType::A), ("www.ubeer.org", QuestionType::Mx),
+        ("nothere.ubeer.org", QuestionType::A), ("c.ubeer.org", QuestionType::Txt),
+        ("host.wild.ubeer.org", QuestionType::A), ("host.wild.ubeer.org", QuestionType::Mx),
+        ("www.example", QuestionType::A),
+        ("www.example", QuestionType::Mx), ("nothere.example", QuestionType::A),
+        ("b.example", QuestionType::Txt), ("host.wild.example", QuestionType::Txt),
+        ("host.wild.example", QuestionType::A), ("org", QuestionType::Dnskey),
+        ("ubeer.org", QuestionType::Ds), ("ubeer.org", QuestionType::Dnskey),
+        ("www.ubeer.org", QuestionType::Ds),
+        ("www.nods.ubeer.org", QuestionType::A), ("www.sub.example", QuestionType::A),
+        ("www.insecure.org", QuestionType::A), ("alias.ubeer.org", QuestionType::A),
+        ("nothere.org", QuestionType::A), ("www.bogus.org", QuestionType::A),
+    ];
+
+    let mut text = String::from("; Recorded by record_fixtures in validator_tests.rs\n");
+    let mut recorded = Vec::new();
+
+    for (qname, qtype) in questions.iter() {
+
+        let response = resolve(&servers, qname, *qtype);
+        let validation = validator.validate_at(&response, &recorder, NOW);
+        println!("{} {}: {}", qname, qtype, validation);
+
+        recorded.push((name(qname), *qtype, response));
+        recorded.extend(recorder.log.borrow_mut().drain(..));
+
+    }
+
+    for (qname, qtype, response) in recorded {
+
+        let question = format!("{}. {} ", qname, qtype);
+
+        if text.contains(&format!("\n{}", question)) {
+            continue;
+        }
+
+        let hex : String = response.to_vec().iter().map(|b| format!("{:02x}", b)).collect();
+        text.push_str(&format!("{}{}\n", question, hex));
+
+    }
+
+    fs::create_dir_all(FIXTURES).unwrap();
+    fs::write(format!("{}/responses.txt", FIXTURES), text).unwrap();
+    fs::write(format!("{}/anchors.txt", FIXTURES),
+              format!("; Trust anchors of the fixtures\n{}", anchor)).unwrap();
+
+}
+
+/*----------------------------------------------------------------------------*/
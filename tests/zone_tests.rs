@@ -37,7 +37,10 @@ extern crate rustydns;
 mod testhelpers;
 
 use ::std::str::FromStr;
-use rustydns::{Name,Record,Zone};
+use ::std::net::IpAddr;
+use rustydns::{ClientSubnet,Lookup,Name,Network,QuestionType,Record,ResourceRecord,Zone};
+use rustydns::{verify_rrsig, Signer, SigningAlgorithm, SigningKey, DEFAULT_TTL};
+
 /*----------------------------------------------------------------------------*/
 
 fn check_zone_add(zone: &mut Zone, name_str: &str, record_str: &str) -> bool {
@@ -99,7 +102,7 @@ fn test_zone_add_from_str() {
     let name = Name::from_str("ubeer.org").unwrap();
     let record = Record::from_str("A 1.2.3.4").unwrap();
 
-    let entry_string = format!("{} {}", name.to_string() , record.to_string());
+    let entry_string = format!("{} {}", name, record);
 
     assert!(zone.add_from_str(&entry_string).is_ok());
     assert_eq!(zone.lookup(&name), Option::Some(&record));
@@ -109,10 +112,11 @@ fn test_zone_add_from_str() {
     let name = Name::from_str("org").unwrap();
     let record = Record::from_str("PTR ubeer.org").unwrap();
 
-    let entry_string = format!("{} {}", name.to_string(), record.to_string());
+    let entry_string = format!("{} {}", name, record);
 
-    // Should not be possible since 'org' is already a sub-zone
-    assert!(zone.add_from_str(&entry_string).is_err());
+    // Names might carry records as well as names below them, e.g. the apex
+    assert!(zone.add_from_str(&entry_string).is_ok());
+    assert_eq!(zone.lookup(&name), Option::Some(&record));
 
     assert!(zone.add_from_str("nordri.alfheim.yggdrasil A 1.2.3.4").is_ok());
     assert!(zone.add_from_str("1.2.3.4 PTR sudri.alfheim.yggdrasil").is_ok());
@@ -139,11 +143,7 @@ fn check_zone_lookup(zone : &Zone, entry : &str, expected : Option<&Record>) ->
     let n = Name::from_str(entry).unwrap();
     let r = zone.lookup(&n);
 
-    if expected != r {
-        return false;
-    }
-
-    return true;
+    expected == r
 
 }
 
@@ -197,3 +197,238 @@ fn test_zone_lookup() {
 }
 
 /*----------------------------------------------------------------------------*/
+
+fn check_subnet_lookup(zone : &Zone, client : Option<(&str, u8)>,
+                       expected : Option<(&str, u8)>) -> bool {
+
+    let name = Name::from_str("www.ubeer.org").unwrap();
+
+    let subnet = client.map(|(a, p)|
+        ClientSubnet::new(IpAddr::from_str(a).unwrap(), p).unwrap());
+
+    let expected = expected.map(|(r, p)| (Record::from_str(r).unwrap(), p));
+
+    match zone.lookup_for_subnet(&name, subnet.as_ref()) {
+        None => expected.is_none(),
+        Some((record, scope)) => Some((record.clone(), scope)) == expected,
+    }
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_zone_lookup_for_subnet() {
+
+    let mut zone = Zone::new();
+    let name = Name::from_str("www.ubeer.org").unwrap();
+    let a = |s: &str| Record::from_str(s).unwrap();
+    let net = |s: &str| Network::from_str(s).unwrap();
+
+    // Variants require a default
+    assert!(zone.add_for_subnet(&name, net("10.0.0.0/8"), a("A 1.1.1.1")).is_err());
+
+    assert!(zone.add_from_str("www.ubeer.org A 1.2.3.4").is_ok());
+
+    // No variants: Answer valid for everyone
+    assert!(check_subnet_lookup(&zone, Some(("10.1.2.0", 24)), Some(("A 1.2.3.4", 0))));
+
+    assert!(zone.add_for_subnet(&name, net("10.0.0.0/8"), a("A 10.0.0.1")).is_ok());
+    assert!(zone.add_for_subnet(&name, net("10.1.0.0/16"), a("A 10.1.0.1")).is_ok());
+    assert!(zone.add_for_subnet(&name, net("2001:db8::/32"), a("A 10.2.0.1")).is_ok());
+    assert!(zone.add_for_subnet(&name, net("10.1.0.0/16"), a("A 10.1.0.2")).is_err());
+
+    assert!(check_subnet_lookup(&zone, None, Some(("A 1.2.3.4", 0))));
+    assert!(check_subnet_lookup(&zone, Some(("10.1.2.0", 24)), Some(("A 10.1.0.1", 16))));
+    assert!(check_subnet_lookup(&zone, Some(("10.2.2.0", 24)), Some(("A 10.0.0.1", 8))));
+    assert!(check_subnet_lookup(&zone, Some(("2001:db8:1::", 48)), Some(("A 10.2.0.1", 32))));
+
+    // Client subnet is less specific than a variant: Scoped to the
+    // variant's prefix, since the answer depends on it (RFC 7871, 7.2.1)
+    assert!(check_subnet_lookup(&zone, Some(("10.0.0.0", 12)), Some(("A 10.0.0.1", 16))));
+    assert!(check_subnet_lookup(&zone, Some(("10.0.0.0", 4)), Some(("A 1.2.3.4", 16))));
+
+    // No match: default, scoped to client network
+    assert!(check_subnet_lookup(&zone, Some(("192.0.2.0", 24)), Some(("A 1.2.3.4", 24))));
+
+    assert!(zone.add_for_subnet(&name, net("172.16.5.0/24"), a("A 172.16.5.1")).is_ok());
+    assert!(check_subnet_lookup(&zone, Some(("172.16.0.0", 16)), Some(("A 1.2.3.4", 24))));
+    assert!(check_subnet_lookup(&zone, Some(("172.16.5.0", 24)), Some(("A 172.16.5.1", 24))));
+
+    assert_eq!(Some(&a("A 1.2.3.4")), zone.lookup(&name));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_zone_subnet_variants_signed() {
+
+    let origin = Name::from_str("ubeer.org").unwrap();
+    let name = Name::from_str("www.ubeer.org").unwrap();
+    let a = |s: &str| Record::from_str(s).unwrap();
+
+    let mut zone = Zone::new();
+    assert!(zone.add_from_str("ubeer.org SOA ns hostmaster 1 7200 3600 1209600 300").is_ok());
+    assert!(zone.add_from_str("www.ubeer.org A 1.2.3.4").is_ok());
+    assert!(zone.add_for_subnet(&name, Network::from_str("10.0.0.0/8").unwrap(),
+                                a("A 10.0.0.1")).is_ok());
+
+    let mut signer = Signer::new();
+    signer.add_key(SigningKey::generate(SigningAlgorithm::Ed25519, true).unwrap());
+    let zone = signer.sign_at(&origin, &zone, None, 1792368000).unwrap();
+
+    let key = match zone.rrset(&origin, QuestionType::Dnskey.code())[0].record {
+        Record::DNSKEY(ref key) => key.clone(),
+        _ => panic!("Expected DNSKEY"),
+    };
+
+    let answer = |subnet : Option<(&str, u8)>| {
+        let subnet = subnet.map(|(a, p)|
+            ClientSubnet::new(IpAddr::from_str(a).unwrap(), p).unwrap());
+        match zone.find(&origin, &name, QuestionType::A.code(), subnet.as_ref()) {
+            Lookup::Answer(records, scope) => (records, scope),
+            other => panic!("Expected answer, got {:?}", other),
+        }
+    };
+
+    let signed = |rrset : &[ResourceRecord]| zone.signatures(&name, QuestionType::A.code())
+        .iter().any(|rr| match rr.record {
+            Record::RRSIG(ref rrsig) => verify_rrsig(rrsig, rrset, &key).is_ok(),
+            _ => false,
+        });
+
+    // Variants survive signing, scoped as before
+    let (records, scope) = answer(Some(("10.1.2.0", 24)));
+    assert_eq!(a("A 10.0.0.1"), records[0].record);
+    assert_eq!(8, scope);
+
+    // ... but are not signed: Only the records found without subnet,
+    // valid for everyone, match the signatures
+    assert!(! signed(&records));
+
+    let (records, scope) = answer(None);
+    assert_eq!(a("A 1.2.3.4"), records[0].record);
+    assert_eq!(0, scope);
+    assert!(signed(&records));
+
+    let (records, scope) = answer(Some(("192.0.2.0", 24)));
+    assert_eq!(24, scope);
+    assert!(signed(&records));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_zone_root() {
+
+    let zone = &mut Zone::new();
+    let root = Name::root();
+    let org = Name::from_str("org").unwrap();
+
+    assert!(zone.add_from_str(". SOA a.root-servers.net. hostmaster 1 1800 900 604800 86400").is_ok());
+    assert!(zone.add_from_str(". NS a.root-servers.net.").is_ok());
+    assert!(zone.add_from_str("org NS ns1.org.").is_ok());
+    assert!(zone.add_from_str("ns1.org A 192.0.2.1").is_ok());
+
+    assert!(zone.soa(&root).is_some());
+    assert_eq!(Some(&Record::from_str("NS a.root-servers.net").unwrap()),
+        zone.rrset(&root, QuestionType::Ns.code()).first().map(|rr| &rr.record));
+
+    // Records of the root come first
+    let records = zone.records();
+    assert_eq!(4, records.len());
+    assert!(records[0].name.is_root());
+    assert!(records[1].name.is_root());
+
+    match zone.find(&root, &Name::from_str("www.ubeer.org").unwrap(),
+                    QuestionType::A.code(), None) {
+        Lookup::Referral(ns) => assert_eq!(org, ns[0].name),
+        other => panic!("Expected referral, got {:?}", other),
+    };
+
+    assert_eq!(Lookup::NoData, zone.find(&root, &root, QuestionType::Mx.code(), None));
+
+    assert!(zone.remove(&root, &Record::from_str("NS a.root-servers.net").unwrap()));
+    assert_eq!(1, zone.remove_rrset(&root, QuestionType::Soa.code()));
+    assert!(zone.soa(&root).is_none());
+    assert_eq!(2, zone.records().len());
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_zone_find_wildcard() {
+
+    let zone = &mut Zone::new();
+    let origin = Name::from_str("ubeer.org").unwrap();
+    let qname = Name::from_str("host.wild.ubeer.org").unwrap();
+    let wildcard = Name::from_str("*.wild.ubeer.org").unwrap();
+
+    assert!(zone.add_from_str("ubeer.org SOA ns hostmaster 1 7200 3600 1209600 300").is_ok());
+    assert!(zone.add_from_str("*.wild.ubeer.org A 192.0.2.20").is_ok());
+
+    let a = ResourceRecord::new(qname.clone(), DEFAULT_TTL,
+                                Record::from_str("A 192.0.2.20").unwrap());
+
+    assert_eq!(Lookup::Wildcard(wildcard.clone(), Box::new(Lookup::Answer(vec![a], 0))),
+               zone.find(&origin, &qname, QuestionType::A.code(), None));
+    assert_eq!(Lookup::Wildcard(wildcard.clone(), Box::new(Lookup::NoData)),
+               zone.find(&origin, &qname, QuestionType::Mx.code(), None));
+
+    // The wildcard itself and its parent exist
+    assert!(match zone.find(&origin, &wildcard, QuestionType::A.code(), None) {
+        Lookup::Answer(records, _) => wildcard == records[0].name,
+        _ => false,
+    });
+    assert_eq!(Lookup::NoData,
+               zone.find(&origin, &wildcard.parent().unwrap(), QuestionType::A.code(), None));
+
+    // No wildcard at the closest encloser
+    assert_eq!(Lookup::NxDomain, zone.find(&origin, &Name::from_str("www.ubeer.org").unwrap(),
+                                           QuestionType::A.code(), None));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_zone_denial_chain() {
+
+    let zone = &mut Zone::new();
+    let origin = Name::from_str("ubeer.org").unwrap();
+
+    for entry in &["ubeer.org SOA ns1.ubeer.org. hostmaster 1 1800 900 604800 300",
+                   "ubeer.org NSEC a.ubeer.org. SOA NSEC",
+                   "a.ubeer.org A 192.0.2.1",
+                   "a.ubeer.org NSEC m.ubeer.org. A NSEC",
+                   "m.ubeer.org A 192.0.2.2",
+                   "m.ubeer.org NSEC ubeer.org. A NSEC"] {
+        assert!(zone.add_from_str(entry).is_ok());
+    }
+
+    let owners = |zone : &Zone, name : &str| -> Vec<String> {
+        zone.denial(&origin, &Name::from_str(name).unwrap(), true).iter()
+            .map(|rr| rr.name.to_string())
+            .collect()
+    };
+
+    // The name itself and the wildcard at the apex
+    assert_eq!(vec!["a.ubeer.org", "ubeer.org"], owners(zone, "c.ubeer.org"));
+    assert_eq!(vec!["m.ubeer.org", "ubeer.org"], owners(zone, "z.ubeer.org"));
+
+    // Changes are reflected
+    let nsec = Record::from_str("NSEC m.ubeer.org. A NSEC").unwrap();
+    assert!(zone.remove(&Name::from_str("a.ubeer.org").unwrap(), &nsec));
+    assert!(zone.add_from_str("a.ubeer.org NSEC d.ubeer.org. A NSEC").is_ok());
+    assert!(zone.add_from_str("d.ubeer.org A 192.0.2.3").is_ok());
+    assert!(zone.add_from_str("d.ubeer.org NSEC m.ubeer.org. A NSEC").is_ok());
+
+    assert_eq!(vec!["a.ubeer.org", "ubeer.org"], owners(zone, "c.ubeer.org"));
+    assert_eq!(vec!["d.ubeer.org", "ubeer.org"], owners(zone, "e.ubeer.org"));
+
+}
+
+/*----------------------------------------------------------------------------*/
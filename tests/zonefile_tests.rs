@@ -0,0 +1,114 @@
+/*
+ * (C) 2026 Michael J. Beer
+ * All rights reserved.
+ *
+ * Redistribution  and use in source and binary forms, with or with‐
+ * out modification, are permitted provided that the following  con‐
+ * ditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright
+ * notice, this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above  copy‐
+ * right  notice,  this  list  of  conditions and the following dis‐
+ * claimer in the documentation and/or other materials provided with
+ * the distribution.
+ *
+ * 3.  Neither the name of the copyright holder nor the names of its
+ * contributors may be used to endorse or promote  products  derived
+ * from this software without specific prior written permission.
+ *
+ * THIS  SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBU‐
+ * TORS "AS IS" AND ANY EXPRESS OR  IMPLIED  WARRANTIES,  INCLUDING,
+ * BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND
+ * FITNESS FOR A PARTICULAR PURPOSE  ARE  DISCLAIMED.  IN  NO  EVENT
+ * SHALL  THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DI‐
+ * RECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR  CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE
+ * GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS IN‐
+ * TERRUPTION)  HOWEVER  CAUSED  AND  ON  ANY  THEORY  OF LIABILITY,
+ * WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING  NEGLI‐
+ * GENCE  OR  OTHERWISE)  ARISING  IN ANY WAY OUT OF THE USE OF THIS
+ * SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+extern crate rustydns;
+mod testhelpers;
+
+use ::std::str::FromStr;
+use rustydns::{parse_zone, QuestionType, Record, ResourceRecord};
+use testhelpers::name;
+
+/*----------------------------------------------------------------------------*/
+
+const ZONE : &str = "
+$TTL 600
+; Zone apex
+@       IN  SOA ns1 hostmaster.ubeer.org. (
+                2026101901 ; serial
+                7200 3600 1209600 300 )
+        IN  NS  ns1
+        86400 NS ns2.example.net.
+ns1     A   192.0.2.1
+www     300 IN A 192.0.2.10
+        IN  AAAA 2001:db8::10
+$ORIGIN mail.ubeer.org.
+@       MX  10 mx1
+mx1     A   192.0.2.25
+";
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_parse_zone() {
+
+    let origin = name("ubeer.org");
+    let zone = parse_zone(&origin, ZONE).unwrap();
+
+    let soa = zone.soa(&origin).unwrap();
+    assert_eq!(600, soa.ttl);
+    assert_eq!(Record::from_str(
+            "SOA ns1.ubeer.org hostmaster.ubeer.org 2026101901 7200 3600 1209600 300").unwrap(),
+            soa.record);
+
+    assert_eq!(vec![
+        ResourceRecord::new(origin.clone(), 600, Record::from_str("NS ns1.ubeer.org").unwrap()),
+        ResourceRecord::new(origin.clone(), 86400, Record::from_str("NS ns2.example.net").unwrap()),
+    ], zone.rrset(&origin, QuestionType::Ns.code()));
+
+    let www = name("www.ubeer.org");
+    assert_eq!(300, zone.rrset(&www, QuestionType::A.code())[0].ttl);
+    assert_eq!(Record::from_str("AAAA 2001:db8::10").unwrap(),
+               zone.rrset(&www, QuestionType::Aaaa.code())[0].record);
+
+    assert_eq!(Some(&Record::from_str("MX 10 mx1.mail.ubeer.org").unwrap()),
+               zone.lookup(&name("mail.ubeer.org")));
+    assert_eq!(Some(&Record::from_str("A 192.0.2.25").unwrap()),
+               zone.lookup(&name("mx1.mail.ubeer.org")));
+
+}
+
+/*----------------------------------------------------------------------------*/
+
+#[test]
+fn test_parse_zone_errors() {
+
+    let origin = name("ubeer.org");
+
+    let check = |text : &str, line : usize| match parse_zone(&origin, text) {
+        Ok(_) => false,
+        Err((l, _)) => l == line,
+    };
+
+    assert!(check("www A 1.2.3.4\n\n   A 1.2.3", 3));
+    assert!(check("  A 1.2.3.4", 1));
+    assert!(check("@ SOA ns1 hostmaster ( 1 2 3 4 5", 1));
+    assert!(check("www A 1.2.3.4 )", 1));
+    assert!(check("www.example.com. A 1.2.3.4", 1));
+    assert!(check("www CH A 1.2.3.4", 1));
+    assert!(check("$INCLUDE other.zone", 1));
+    assert!(check("www CNAME ubeer.org.\nwww A 1.2.3.4", 2));
+
+}
+
+/*----------------------------------------------------------------------------*/